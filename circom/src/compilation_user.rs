@@ -1,9 +1,10 @@
 use ansi_term::Colour;
 use compiler::compiler_interface;
 use compiler::compiler_interface::{Config, VCP};
-use program_structure::error_definition::Report;
 use program_structure::error_code::ReportCode;
+use program_structure::error_definition::Report;
 use program_structure::file_definition::FileLibrary;
+use program_structure::progress::{Phase, PhaseTimer};
 use crate::VERSION;
 
 
@@ -21,6 +22,7 @@ pub struct CompilerConfig {
     pub c_flag: bool,
     pub debug_output: bool,
     pub produce_input_log: bool,
+    pub check_tags: bool,
     pub vcp: VCP,
 }
 
@@ -28,9 +30,15 @@ pub fn compile(config: CompilerConfig) -> Result<(), ()> {
 
 
     if config.c_flag || config.wat_flag || config.wasm_flag{
+        let timer = PhaseTimer::start(Phase::CodeGeneration);
         let circuit = compiler_interface::run_compiler(
             config.vcp,
-            Config { debug_output: config.debug_output, produce_input_log: config.produce_input_log, wat_flag: config.wat_flag },
+            Config {
+                debug_output: config.debug_output,
+                produce_input_log: config.produce_input_log,
+                wat_flag: config.wat_flag,
+                check_tags: config.check_tags,
+            },
             VERSION
         )?;
     
@@ -57,89 +65,176 @@ pub fn compile(config: CompilerConfig) -> Result<(), ()> {
             );
         }
     
-        match (config.wat_flag, config.wasm_flag) {
-            (true, true) => {
-                compiler_interface::write_wasm(&circuit, &config.js_folder, &config.wasm_name, &config.wat_file)?;
-                println!("{} {}", Colour::Green.paint("Written successfully:"), config.wat_file);
-                let result = wat_to_wasm(&config.wat_file, &config.wasm_file);
-                match result {
-                    Result::Err(report) => {
-                        Report::print_reports(&[report], &FileLibrary::new());
-                        return Err(());
-                    }
-                    Result::Ok(()) => {
-                        println!("{} {}", Colour::Green.paint("Written successfully:"), config.wasm_file);
-                    }
+        if config.wat_flag || config.wasm_flag {
+            let wasm_file = if config.wasm_flag { Some(config.wasm_file.as_str()) } else { None };
+            let wat_file = if config.wat_flag { Some(config.wat_file.as_str()) } else { None };
+            let result = compiler_interface::write_wasm(&circuit, &config.js_folder, &config.wasm_name, wasm_file, wat_file);
+            match result {
+                Result::Err(msg) => {
+                    let report = Report::error(
+                        format!("Error translating the circuit to wasm.\n\n{}", msg),
+                        ReportCode::ErrorWat2Wasm,
+                    );
+                    Report::print_reports(&[report], &FileLibrary::new());
+                    return Err(());
                 }
-            }
-            (false, true) => {
-                compiler_interface::write_wasm(&circuit,  &config.js_folder, &config.wasm_name, &config.wat_file)?;
-                let result = wat_to_wasm(&config.wat_file, &config.wasm_file);
-                std::fs::remove_file(&config.wat_file).unwrap();
-                match result {
-                    Result::Err(report) => {
-                        Report::print_reports(&[report], &FileLibrary::new());
-                        return Err(());
+                Result::Ok(()) => {
+                    if config.wat_flag {
+                        println!("{} {}", Colour::Green.paint("Written successfully:"), config.wat_file);
                     }
-                    Result::Ok(()) => {
+                    if config.wasm_flag {
                         println!("{} {}", Colour::Green.paint("Written successfully:"), config.wasm_file);
                     }
                 }
             }
-            (true, false) => {
-                compiler_interface::write_wasm(&circuit,  &config.js_folder, &config.wasm_name, &config.wat_file)?;
-                println!("{} {}", Colour::Green.paint("Written successfully:"), config.wat_file);
-            }
-            (false, false) => {}
         }
+        timer.finish();
     }
     
 
     Ok(())
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution_user::execute_project;
+    use crate::execution_user::tests::{program_and_config, CIRCUIT};
 
-fn wat_to_wasm(wat_file: &str, wasm_file: &str) -> Result<(), Report> {
-    use std::fs::read_to_string;
-    use std::fs::File;
-    use std::io::BufWriter;
-    use std::io::Write;
-    use wast::Wat;
-    use wast::parser::{self, ParseBuffer};
+    #[test]
+    fn wasm_is_the_assembled_wat() {
+        let folder = std::env::temp_dir().join(format!("circom_wasm_{}", std::process::id()));
+        let js_folder = folder.join("circuit_js");
+        std::fs::create_dir_all(&js_folder).unwrap();
+        std::fs::write(folder.join("circuit.circom"), CIRCUIT).unwrap();
+        let (program, config) = program_and_config(&folder, "wasm");
+        let vcp = execute_project(program, config).unwrap();
+        let output = |name: &str| js_folder.join(name).to_str().unwrap().to_string();
+        let config = CompilerConfig {
+            js_folder: js_folder.to_str().unwrap().to_string(),
+            wasm_name: "circuit".to_string(),
+            wat_file: output("circuit.wat"),
+            wasm_file: output("circuit.wasm"),
+            c_folder: String::new(),
+            c_run_name: String::new(),
+            c_file: String::new(),
+            dat_file: String::new(),
+            wat_flag: true,
+            wasm_flag: true,
+            c_flag: false,
+            debug_output: false,
+            produce_input_log: false,
+            check_tags: false,
+            vcp,
+        };
+        compile(config).unwrap();
+        let wat = std::fs::read_to_string(output("circuit.wat")).unwrap();
+        let wasm = std::fs::read(output("circuit.wasm")).unwrap();
+        std::fs::remove_dir_all(&folder).unwrap();
+        let buf = wast::parser::ParseBuffer::new(&wat).unwrap();
+        let mut module = wast::parser::parse::<wast::Wat>(&buf).unwrap();
+        assert!(wasm == module.module.encode().unwrap());
+    }
 
-    let wat_contents = read_to_string(wat_file).unwrap();
-    let buf = ParseBuffer::new(&wat_contents).unwrap();
-    let result_wasm_contents = parser::parse::<Wat>(&buf);
-    match result_wasm_contents {
-        Result::Err(error) => {
-            Result::Err(Report::error(
-                format!("Error translating the circuit from wat to wasm.\n\nException encountered when parsing WAT: {}", error),
-                ReportCode::ErrorWat2Wasm,
-            ))
-        }
-        Result::Ok(mut wat) => {
-            let wasm_contents = wat.module.encode();
-            match wasm_contents {
-                Result::Err(error) => {
-                    Result::Err(Report::error(
-                        format!("Error translating the circuit from wat to wasm.\n\nException encountered when encoding WASM: {}", error),
-                        ReportCode::ErrorWat2Wasm,
-                    ))
+    // Contents of the custom section of a wasm module with the given name
+    fn custom_section<'a>(wasm: &'a [u8], name: &str) -> Option<&'a [u8]> {
+        fn leb128(bytes: &[u8], at: &mut usize) -> usize {
+            let mut value = 0;
+            let mut shift = 0;
+            loop {
+                let byte = bytes[*at];
+                *at += 1;
+                value |= ((byte & 0x7f) as usize) << shift;
+                if byte & 0x80 == 0 {
+                    return value;
                 }
-                Result::Ok(wasm_contents) => {
-                    let file = File::create(wasm_file).unwrap();
-                    let mut writer = BufWriter::new(file);
-                    writer.write_all(&wasm_contents).map_err(|_err| Report::error(
-                        format!("Error writing the circuit. Exception generated: {}", _err),
-                        ReportCode::ErrorWat2Wasm,
-                    ))?;
-                    writer.flush().map_err(|_err| Report::error(
-                        format!("Error writing the circuit. Exception generated: {}", _err),
-                        ReportCode::ErrorWat2Wasm,
-                    ))?;
-                    Ok(())
+                shift += 7;
+            }
+        }
+        // the sections follow the magic number and the version
+        let mut at = 8;
+        while at < wasm.len() {
+            let id = wasm[at];
+            at += 1;
+            let size = leb128(wasm, &mut at);
+            let end = at + size;
+            if id == 0 {
+                let mut start = at;
+                let length = leb128(wasm, &mut start);
+                if &wasm[start..start + length] == name.as_bytes() {
+                    return Some(&wasm[start + length..end]);
                 }
             }
+            at = end;
         }
+        None
+    }
+
+    #[test]
+    fn main_outputs_are_described_by_name() {
+        let folder = std::env::temp_dir().join(format!("circom_outputs_{}", std::process::id()));
+        let (js_folder, c_folder) = (folder.join("circuit_js"), folder.join("circuit_cpp"));
+        std::fs::create_dir_all(&js_folder).unwrap();
+        std::fs::create_dir_all(&c_folder).unwrap();
+        std::fs::write(folder.join("circuit.circom"), CIRCUIT).unwrap();
+        let (program, config) = program_and_config(&folder, "outputs");
+        let sym = config.sym.clone();
+        let vcp = execute_project(program, config).unwrap();
+        let config = Config { debug_output: false, produce_input_log: false, wat_flag: false, check_tags: false };
+        let circuit = compiler_interface::run_compiler(vcp, config, VERSION).unwrap();
+        let path = |folder: &std::path::Path, name: &str| folder.join(name).to_str().unwrap().to_string();
+        let wasm_file = path(&js_folder, "circuit.wasm");
+        compiler_interface::write_wasm(&circuit, &path(&folder, "circuit_js"), "circuit", Some(&wasm_file), None).unwrap();
+        let (c_file, dat_file) = (path(&c_folder, "circuit.cpp"), path(&c_folder, "circuit.dat"));
+        compiler_interface::write_c(&circuit, &path(&folder, "circuit_cpp"), "circuit", &c_file, &dat_file).unwrap();
+        let sym = std::fs::read_to_string(sym).unwrap();
+        let wasm = std::fs::read(wasm_file).unwrap();
+        let c_code = std::fs::read_to_string(c_file).unwrap();
+        std::fs::remove_dir_all(&folder).unwrap();
+
+        // the first output of main gives where the array starts in the signals and in the witness
+        let first: Vec<&str> = sym.lines().find(|line| line.ends_with(",main.out[0]")).unwrap().split(',').collect();
+        let outputs: serde_json::Value = serde_json::from_slice(custom_section(&wasm, "circom_outputs").unwrap()).unwrap();
+        assert_eq!(
+            outputs,
+            serde_json::json!([{ "name": "out", "dimensions": [6], "witness": first[1].parse::<usize>().unwrap(), "size": 6 }])
+        );
+        assert!(c_code.contains("u32 mainOutputLengths0[] = {6};"));
+        assert!(c_code.contains(&format!("MainOutputInfo mainOutputList[1] = {{{{\"out\",{},6,1,mainOutputLengths0}}}};", first[0])));
+    }
+
+    #[test]
+    fn checked_tags_report_the_signals_that_do_not_satisfy_them() {
+        use compiler::interpreter::Interpreter;
+        use compiler::num_bigint::BigInt;
+        let folder = std::env::temp_dir().join(format!("circom_check_tags_{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        let circuit = "pragma circom 2.1.0;
+template Double() {
+    signal input in;
+    signal output {binary} high;
+    signal output {maxbit} out;
+    out.maxbit = 4;
+    high <-- in \\ 8;
+    high * (high - 1) === 0;
+    out <-- in * 2;
+    out === in * 2;
+}
+component main = Double();
+";
+        std::fs::write(folder.join("circuit.circom"), circuit).unwrap();
+        let (program, config) = program_and_config(&folder, "check_tags");
+        let vcp = execute_project(program, config).unwrap();
+        std::fs::remove_dir_all(&folder).unwrap();
+        let config = Config { debug_output: false, produce_input_log: false, wat_flag: false, check_tags: true };
+        let circuit = compiler_interface::run_compiler(vcp, config, VERSION).unwrap();
+        let witness_error = |input: u32| {
+            let mut interpreter = Interpreter::new(&circuit);
+            interpreter.set_input("in", &[BigInt::from(input)]).unwrap();
+            interpreter.run().err().map(|error| error.cause_message())
+        };
+        assert_eq!(witness_error(7), None);
+        assert_eq!(witness_error(16).unwrap(), "Error in template Double_0: signal high does not satisfy tag binary");
+        assert_eq!(witness_error(8).unwrap(), "Error in template Double_0: signal out does not satisfy tag maxbit = 4");
     }
 }
@@ -0,0 +1,507 @@
+// Debug Adapter Protocol server of `circom debug`, it lets editors drive the debugger.
+// Requests are only read while the execution is stopped, there is a single thread.
+use super::debug_user::{component_to_string, lookup, Breakpoint, Frontend, Resume, Session, StopReason};
+use compiler::interpreter::{ErrorCause, ExecutionError, Interpreter, SymbolValue};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::Path;
+
+const THREAD_ID: u64 = 1;
+
+// Expandable entries of the variables view, they are valid while the execution is stopped
+enum Handle {
+    Variables(usize),
+    Signals(usize),
+    Subcomponents(usize),
+    Component(usize),
+}
+
+pub struct DebugAdapter {
+    reader: Box<dyn BufRead>,
+    writer: Box<dyn Write>,
+    seq: u64,
+    // logs of the circuit already sent to the client
+    logs: usize,
+    handles: Vec<Handle>,
+    stop_on_entry: bool,
+    disconnected: bool,
+}
+
+// Serves the protocol in the standard input and output, or in a local port
+pub fn connect(port: Option<u16>) -> Result<DebugAdapter, ()> {
+    let port = match port {
+        Some(port) => port,
+        None => {
+            let reader = BufReader::new(std::io::stdin());
+            return Result::Ok(DebugAdapter::new(Box::new(reader), Box::new(std::io::stdout())));
+        }
+    };
+    let listener = TcpListener::bind(("127.0.0.1", port)).map_err(|error| eprintln!("{}", error))?;
+    eprintln!("Waiting for a debugger client in port {}", port);
+    let (stream, _) = listener.accept().map_err(|error| eprintln!("{}", error))?;
+    let reader = BufReader::new(stream.try_clone().map_err(|error| eprintln!("{}", error))?);
+    Result::Ok(DebugAdapter::new(Box::new(reader), Box::new(stream)))
+}
+
+impl DebugAdapter {
+    fn new(reader: Box<dyn BufRead>, writer: Box<dyn Write>) -> DebugAdapter {
+        DebugAdapter { reader, writer, seq: 0, logs: 0, handles: vec![], stop_on_entry: false, disconnected: false }
+    }
+
+    // Messages are a header with their length followed by their json content
+    fn read_message(&mut self) -> Option<Value> {
+        let mut length = None;
+        loop {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line).ok()? == 0 {
+                return None;
+            }
+            let line = line.trim();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(value) = line.strip_prefix("Content-Length:") {
+                length = value.trim().parse().ok();
+            }
+        }
+        let mut content = vec![0; length?];
+        self.reader.read_exact(&mut content).ok()?;
+        serde_json::from_slice(&content).ok()
+    }
+
+    fn send(&mut self, mut message: Value) {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        let content = message.to_string();
+        let _ = write!(self.writer, "Content-Length: {}\r\n\r\n{}", content.len(), content);
+        let _ = self.writer.flush();
+    }
+
+    fn respond(&mut self, request: &Value, body: Value) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "success": true,
+            "command": request["command"],
+            "body": body,
+        }));
+    }
+
+    fn fail(&mut self, request: &Value, message: &str) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "success": false,
+            "command": request["command"],
+            "message": message,
+        }));
+    }
+
+    fn event(&mut self, event: &str, body: Value) {
+        self.send(json!({ "type": "event", "event": event, "body": body }));
+    }
+
+    fn send_logs(&mut self, interpreter: &Interpreter) {
+        for log in &interpreter.logs()[self.logs..] {
+            let body = json!({ "category": "stdout", "output": format!("{}\n", log) });
+            self.send(json!({ "type": "event", "event": "output", "body": body }));
+        }
+        self.logs = interpreter.logs().len();
+    }
+
+    fn handle(&mut self, session: &mut Session, interpreter: Option<&Interpreter>, request: &Value) -> Option<Resume> {
+        let arguments = &request["arguments"];
+        let command = request["command"].as_str().unwrap_or("");
+        match (command, interpreter) {
+            ("initialize", _) => {
+                let capabilities = json!({
+                    "supportsConfigurationDoneRequest": true,
+                    "supportsFunctionBreakpoints": true,
+                    "supportsEvaluateForHovers": true,
+                });
+                self.respond(request, capabilities);
+                self.event("initialized", json!({}));
+            }
+            ("launch", _) | ("attach", _) => {
+                self.stop_on_entry = arguments["stopOnEntry"].as_bool().unwrap_or(false);
+                self.respond(request, json!({}));
+            }
+            ("setBreakpoints", _) => {
+                let path = arguments["source"]["path"].as_str().unwrap_or("");
+                let file_id = session.file_library.find_file(path);
+                session.breakpoints.retain(|b| !matches!(b, Breakpoint::Line(Some(id), _) if Some(*id) == file_id));
+                let mut breakpoints = vec![];
+                for line in arguments["breakpoints"].as_array().into_iter().flatten().filter_map(|b| b["line"].as_u64()) {
+                    if let Some(file_id) = file_id {
+                        session.breakpoints.push(Breakpoint::Line(Some(file_id), line as usize));
+                    }
+                    breakpoints.push(json!({ "verified": file_id.is_some(), "line": line }));
+                }
+                self.respond(request, json!({ "breakpoints": breakpoints }));
+            }
+            ("setFunctionBreakpoints", _) => {
+                session.breakpoints.retain(|b| !matches!(b, Breakpoint::Symbol(_)));
+                let mut breakpoints = vec![];
+                for name in arguments["breakpoints"].as_array().into_iter().flatten().filter_map(|b| b["name"].as_str()) {
+                    let breakpoint = session.parse_breakpoint(name);
+                    breakpoints.push(json!({ "verified": breakpoint.is_ok() }));
+                    if let Result::Ok(breakpoint @ Breakpoint::Symbol(_)) = breakpoint {
+                        session.breakpoints.push(breakpoint);
+                    }
+                }
+                self.respond(request, json!({ "breakpoints": breakpoints }));
+            }
+            ("setExceptionBreakpoints", _) => self.respond(request, json!({ "breakpoints": [] })),
+            ("configurationDone", _) => {
+                self.respond(request, json!({}));
+                return Some(if self.stop_on_entry { Resume::StepIn } else { Resume::Continue });
+            }
+            ("threads", _) => self.respond(request, json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] })),
+            ("stackTrace", Some(interpreter)) => {
+                let stack = interpreter.call_stack();
+                let mut frames = vec![];
+                for (id, frame) in stack.iter().enumerate().rev() {
+                    let path = session.path_of(frame).map(|p| Path::new(p).canonicalize().unwrap_or_else(|_| p.into()));
+                    let name = format!("{} ({})", frame.symbol, interpreter.component_path(frame.component));
+                    let source = path.map(|p| json!({ "name": p.file_name().map(|n| n.to_string_lossy()), "path": p }));
+                    frames.push(json!({ "id": id + 1, "name": name, "source": source, "line": frame.line, "column": 1 }));
+                }
+                self.respond(request, json!({ "stackFrames": frames, "totalFrames": stack.len() }));
+            }
+            ("scopes", Some(interpreter)) => {
+                let frame = (arguments["frameId"].as_u64().unwrap_or(1) as usize).saturating_sub(1);
+                let stack = interpreter.call_stack();
+                let mut scopes = vec![json!({ "name": "Variables", "variablesReference": self.add_handle(Handle::Variables(frame)), "expensive": false })];
+                if let Some(frame) = stack.get(frame).filter(|f| !f.is_function) {
+                    let signals = self.add_handle(Handle::Signals(frame.component));
+                    let subcomponents = self.add_handle(Handle::Subcomponents(frame.component));
+                    scopes.push(json!({ "name": "Signals", "variablesReference": signals, "expensive": false }));
+                    scopes.push(json!({ "name": "Subcomponents", "variablesReference": subcomponents, "expensive": false }));
+                }
+                self.respond(request, json!({ "scopes": scopes }));
+            }
+            ("variables", Some(interpreter)) => {
+                let reference = arguments["variablesReference"].as_u64().unwrap_or(0) as usize;
+                let variables = self.variables(interpreter, reference);
+                self.respond(request, json!({ "variables": variables }));
+            }
+            ("evaluate", Some(interpreter)) => {
+                let stack = interpreter.call_stack();
+                let frame = match arguments["frameId"].as_u64() {
+                    Some(id) => (id as usize).saturating_sub(1),
+                    None => stack.len().saturating_sub(1),
+                };
+                let expression = arguments["expression"].as_str().unwrap_or("");
+                match (stack.get(frame), lookup(interpreter, frame, expression)) {
+                    (Some(_), Some(value)) => self.respond(request, json!({ "result": value, "variablesReference": 0 })),
+                    _ => self.fail(request, &format!("There is no symbol named {}", expression)),
+                }
+            }
+            ("continue", _) => {
+                self.respond(request, json!({ "allThreadsContinued": true }));
+                return Some(Resume::Continue);
+            }
+            ("next", _) => {
+                self.respond(request, json!({}));
+                return Some(Resume::StepOver);
+            }
+            ("stepIn", _) => {
+                self.respond(request, json!({}));
+                return Some(Resume::StepIn);
+            }
+            ("stepOut", _) => {
+                self.respond(request, json!({}));
+                return Some(Resume::StepOut);
+            }
+            ("disconnect", _) | ("terminate", _) => {
+                self.respond(request, json!({}));
+                self.disconnected = true;
+                return Some(Resume::Stop);
+            }
+            _ => self.fail(request, &format!("The request {} is not supported", command)),
+        }
+        None
+    }
+
+    fn add_handle(&mut self, handle: Handle) -> usize {
+        self.handles.push(handle);
+        self.handles.len()
+    }
+
+    fn variables(&mut self, interpreter: &Interpreter, reference: usize) -> Vec<Value> {
+        fn symbols(symbols: Vec<SymbolValue>) -> Vec<Value> {
+            symbols
+                .into_iter()
+                .map(|s| json!({ "name": s.name, "value": s.values_to_string(), "variablesReference": 0 }))
+                .collect()
+        }
+        let (signals, subcomponents) = match reference.checked_sub(1).and_then(|i| self.handles.get(i)) {
+            Some(Handle::Variables(frame)) => return symbols(interpreter.variables(*frame)),
+            Some(Handle::Signals(component)) => (Some(*component), None),
+            Some(Handle::Subcomponents(component)) => (None, Some(*component)),
+            Some(Handle::Component(component)) => (Some(*component), Some(*component)),
+            None => return vec![],
+        };
+        let mut variables = signals.map_or(vec![], |component| symbols(interpreter.signals(component)));
+        for subcomponent in subcomponents.map_or(vec![], |component| interpreter.subcomponents(component)) {
+            let (value, reference) = match subcomponent.component {
+                Some(id) => (component_to_string(interpreter, id), self.add_handle(Handle::Component(id))),
+                None => ("not created".to_string(), 0),
+            };
+            variables.push(json!({ "name": subcomponent.name, "value": value, "variablesReference": reference }));
+        }
+        variables
+    }
+
+    // Answers the requests of the client until it resumes the execution
+    fn wait(&mut self, session: &mut Session, interpreter: Option<&Interpreter>) -> Resume {
+        while let Some(request) = self.read_message() {
+            if let Some(resume) = self.handle(session, interpreter, &request) {
+                return resume;
+            }
+        }
+        self.disconnected = true;
+        Resume::Stop
+    }
+}
+
+impl Frontend for DebugAdapter {
+    fn start(&mut self, session: &mut Session) -> Resume {
+        self.wait(session, None)
+    }
+
+    fn stopped(&mut self, session: &mut Session, interpreter: &Interpreter, reason: StopReason) -> Resume {
+        self.send_logs(interpreter);
+        self.handles.clear();
+        let reason = match reason {
+            StopReason::Entry => "entry",
+            StopReason::Step => "step",
+            StopReason::Breakpoint(_) => "breakpoint",
+        };
+        self.event("stopped", json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true }));
+        self.wait(session, Some(interpreter))
+    }
+
+    fn finished(&mut self, session: &mut Session, interpreter: &Interpreter, error: Option<&ExecutionError>) {
+        self.send_logs(interpreter);
+        if self.disconnected {
+            return;
+        }
+        let exit_code = match error {
+            None => 0,
+            Some(error) if matches!(error.cause, ErrorCause::Interrupted) => 1,
+            Some(error) => {
+                // the state of the failure can be inspected until the client resumes
+                self.handles.clear();
+                self.event("output", json!({ "category": "stderr", "output": format!("{}\n", error.message()) }));
+                let body = json!({
+                    "reason": "exception",
+                    "description": error.cause_message(),
+                    "text": error.message(),
+                    "threadId": THREAD_ID,
+                    "allThreadsStopped": true,
+                });
+                self.event("stopped", body);
+                if self.wait(session, Some(interpreter)) == Resume::Stop && self.disconnected {
+                    return;
+                }
+                1
+            }
+        };
+        self.event("terminated", json!({}));
+        self.event("exited", json!({ "exitCode": exit_code }));
+        while !self.disconnected {
+            self.wait(session, None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input_user::DebugInput;
+    use std::cell::RefCell;
+    use std::io::Cursor;
+    use std::rc::Rc;
+
+    const CIRCUIT: &str = "pragma circom 2.0.0;
+template Square() {
+    signal input in;
+    signal output out;
+    out <== in * in;
+}
+template Main() {
+    signal input a;
+    signal output b;
+    component s = Square();
+    s.in <== a;
+    assert(s.out != 16);
+    b <== s.out;
+}
+component main = Main();
+";
+
+    // Messages written by the adapter, shared with the test
+    #[derive(Clone, Default)]
+    struct Output(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Result::Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Result::Ok(())
+        }
+    }
+
+    fn encode(requests: &[(&str, Value)]) -> Vec<u8> {
+        let mut content = String::new();
+        for (seq, (command, arguments)) in requests.iter().enumerate() {
+            let request = json!({ "seq": seq + 1, "type": "request", "command": command, "arguments": arguments });
+            let request = request.to_string();
+            content.push_str(&format!("Content-Length: {}\r\n\r\n{}", request.len(), request));
+        }
+        content.into_bytes()
+    }
+
+    fn decode(output: &[u8]) -> Vec<Value> {
+        let mut adapter = DebugAdapter::new(Box::new(Cursor::new(output.to_vec())), Box::new(std::io::sink()));
+        std::iter::from_fn(|| adapter.read_message()).collect()
+    }
+
+    // Debugs the circuit with the given input for a client that sends the requests in order
+    fn debug_session(name: &str, a: u64, requests: &[(&str, Value)]) -> (bool, Vec<Value>) {
+        let folder = std::env::temp_dir().join(format!("circom_dap_{}_{}", std::process::id(), name));
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("circuit.circom"), CIRCUIT).unwrap();
+        std::fs::write(folder.join("input.json"), format!("{{\"a\": \"{}\"}}", a)).unwrap();
+        let input = DebugInput {
+            input_path: folder.join("circuit.circom"),
+            inputs_path: Some(folder.join("input.json")),
+            breakpoints: vec![],
+            dap_flag: true,
+            port: None,
+            prime: "bn128".to_string(),
+            link_libraries: vec![],
+        };
+        let output = Output::default();
+        let writer = output.clone();
+        let requests = encode(requests);
+        let connect = move || -> Result<Box<dyn Frontend>, ()> {
+            Result::Ok(Box::new(DebugAdapter::new(Box::new(Cursor::new(requests)), Box::new(writer))))
+        };
+        let result = crate::debug_user::debug(&input, connect).unwrap();
+        std::fs::remove_dir_all(&folder).unwrap();
+        let messages = decode(&output.0.borrow());
+        (result.is_ok(), messages)
+    }
+
+    fn find<'a>(messages: &'a [Value], kind: &str, name: &str) -> Vec<&'a Value> {
+        let key = if kind == "event" { "event" } else { "command" };
+        messages.iter().filter(|m| m["type"] == kind && m[key] == name).collect()
+    }
+
+    #[test]
+    fn session_stops_on_entry_and_on_function_breakpoints() {
+        let requests = [
+            ("initialize", json!({ "adapterID": "circom" })),
+            ("launch", json!({ "stopOnEntry": true })),
+            ("setFunctionBreakpoints", json!({ "breakpoints": [{ "name": "Square" }, { "name": "Missing" }] })),
+            ("configurationDone", json!({})),
+            ("stackTrace", json!({ "threadId": 1 })),
+            ("continue", json!({ "threadId": 1 })),
+            ("stackTrace", json!({ "threadId": 1 })),
+            ("evaluate", json!({ "expression": "in" })),
+            ("evaluate", json!({ "expression": "s.in", "frameId": 1 })),
+            ("continue", json!({ "threadId": 1 })),
+            ("disconnect", json!({})),
+        ];
+        let (ok, messages) = debug_session("breakpoints", 3, &requests);
+        assert!(ok);
+        // every message is numbered and every request is answered once, in order
+        for (i, message) in messages.iter().enumerate() {
+            assert_eq!(message["seq"], json!(i + 1));
+        }
+        let answered: Vec<_> = messages.iter().filter_map(|m| m["request_seq"].as_u64()).collect();
+        assert_eq!(answered, (1..=requests.len() as u64).collect::<Vec<_>>());
+        assert_eq!(find(&messages, "event", "initialized").len(), 1);
+        let breakpoints = &find(&messages, "response", "setFunctionBreakpoints")[0]["body"]["breakpoints"];
+        assert_eq!(breakpoints, &json!([{ "verified": true }, { "verified": false }]));
+
+        let stops: Vec<_> = find(&messages, "event", "stopped").iter().map(|m| m["body"]["reason"].clone()).collect();
+        assert_eq!(stops, vec![json!("entry"), json!("breakpoint")]);
+        let traces = find(&messages, "response", "stackTrace");
+        let entry = &traces[0]["body"]["stackFrames"];
+        assert_eq!(entry.as_array().unwrap().len(), 1);
+        assert_eq!(entry[0]["name"], "Main (main)");
+        assert_eq!(entry[0]["source"]["name"], "circuit.circom");
+        let frames = &traces[1]["body"]["stackFrames"];
+        assert_eq!(frames[0]["name"], "Square (main.s)");
+        assert_eq!(frames[0]["line"], 5);
+        assert_eq!(frames[1]["name"], "Main (main)");
+
+        let values: Vec<_> = find(&messages, "response", "evaluate").iter().map(|m| m["body"]["result"].clone()).collect();
+        assert_eq!(values, vec![json!("3"), json!("3")]);
+        let exited = find(&messages, "event", "exited");
+        assert_eq!(exited[0]["body"]["exitCode"], 0);
+        assert!(messages.last().unwrap()["command"] == "disconnect");
+    }
+
+    #[test]
+    fn session_stops_on_failed_asserts_and_line_breakpoints() {
+        let requests = [
+            ("initialize", json!({})),
+            ("launch", json!({})),
+            ("setBreakpoints", json!({ "source": { "path": "unknown.circom" }, "breakpoints": [{ "line": 2 }] })),
+            ("configurationDone", json!({})),
+            ("scopes", json!({ "frameId": 1 })),
+            ("variables", json!({ "variablesReference": 2 })),
+            ("evaluate", json!({ "expression": "missing" })),
+            ("stepIn", json!({ "threadId": 1 })),
+            ("disconnect", json!({})),
+        ];
+        let (ok, messages) = debug_session("assert", 4, &requests);
+        assert!(!ok);
+        let breakpoints = &find(&messages, "response", "setBreakpoints")[0]["body"]["breakpoints"];
+        assert_eq!(breakpoints, &json!([{ "verified": false, "line": 2 }]));
+        let stopped = find(&messages, "event", "stopped");
+        assert_eq!(stopped.len(), 1);
+        assert_eq!(stopped[0]["body"]["reason"], "exception");
+        let names: Vec<_> = find(&messages, "response", "scopes")[0]["body"]["scopes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|scope| scope["name"].clone())
+            .collect();
+        assert_eq!(names, vec![json!("Variables"), json!("Signals"), json!("Subcomponents")]);
+        let signals = &find(&messages, "response", "variables")[0]["body"]["variables"];
+        assert!(signals.as_array().unwrap().contains(&json!({ "name": "a", "value": "4", "variablesReference": 0 })));
+        let evaluate = find(&messages, "response", "evaluate");
+        assert_eq!(evaluate[0]["success"], false);
+        assert_eq!(evaluate[0]["message"], "There is no symbol named missing");
+        assert_eq!(find(&messages, "event", "exited")[0]["body"]["exitCode"], 1);
+    }
+
+    #[test]
+    fn messages_are_read_after_their_headers() {
+        let content = r#"{"seq":1,"command":"threads"}"#;
+        let input = format!(
+            "Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{}Content-Length: 40\r\n\r\n{{}}",
+            content.len(),
+            content
+        );
+        let output = Output::default();
+        let mut adapter = DebugAdapter::new(Box::new(Cursor::new(input.into_bytes())), Box::new(output.clone()));
+        let request = adapter.read_message().unwrap();
+        assert_eq!(request["command"], "threads");
+        // the second message is shorter than its header says
+        assert!(adapter.read_message().is_none());
+        let mut session = Session::default();
+        assert!(adapter.handle(&mut session, None, &request).is_none());
+        let answer = decode(&output.0.borrow());
+        assert_eq!(answer[0]["body"], json!({ "threads": [{ "id": 1, "name": "main" }] }));
+        assert_eq!(answer[0]["request_seq"], 1);
+    }
+}
@@ -0,0 +1,561 @@
+use super::input_user::DebugInput;
+use crate::VERSION;
+use ansi_term::Colour;
+use compiler::compiler_interface::{run_compiler, Config};
+use compiler::interpreter::{ErrorCause, ExecutionError, ExecutionObserver, Interpreter, StackFrame, SymbolValue};
+use compiler::num_bigint::BigInt;
+use program_structure::constants::UsefulConstants;
+use program_structure::error_definition::Report;
+use program_structure::file_definition::{FileID, FileLibrary};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+pub enum Breakpoint {
+    // a line of a file, or of any file if none is given
+    Line(Option<FileID>, usize),
+    // the start of a template or function
+    Symbol(String),
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum Resume {
+    Continue,
+    StepIn,
+    StepOver,
+    StepOut,
+    Stop,
+}
+
+#[derive(Copy, Clone)]
+pub enum StopReason {
+    Entry,
+    Step,
+    Breakpoint(usize),
+}
+
+// Source of the program being debugged and the breakpoints set by the user
+#[derive(Default)]
+pub struct Session {
+    pub file_library: FileLibrary,
+    // file in which each template and function is declared
+    files: HashMap<String, FileID>,
+    pub breakpoints: Vec<Breakpoint>,
+}
+
+impl Session {
+    pub fn file_of(&self, symbol: &str) -> Option<FileID> {
+        self.files.get(symbol).cloned()
+    }
+
+    // A breakpoint is written as line, file:line or the name of a template or function
+    pub fn parse_breakpoint(&self, spec: &str) -> Result<Breakpoint, String> {
+        if let Result::Ok(line) = spec.parse() {
+            return Result::Ok(Breakpoint::Line(None, line));
+        }
+        if let Some((file, line)) = spec.rsplit_once(':') {
+            let line = line.parse().map_err(|_| format!("Invalid line in breakpoint {}", spec))?;
+            return match self.file_library.find_file(file) {
+                Some(file_id) => Result::Ok(Breakpoint::Line(Some(file_id), line)),
+                None => Result::Err(format!("The file {} is not part of the circuit", file)),
+            };
+        }
+        if self.files.contains_key(spec) {
+            Result::Ok(Breakpoint::Symbol(spec.to_string()))
+        } else {
+            Result::Err(format!("There is no template or function named {}", spec))
+        }
+    }
+
+    pub fn breakpoint_to_string(&self, breakpoint: &Breakpoint) -> String {
+        match breakpoint {
+            Breakpoint::Line(Some(file_id), line) => {
+                format!("{}:{}", self.file_library.get_path(*file_id).unwrap_or_default(), line)
+            }
+            Breakpoint::Line(None, line) => format!("line {}", line),
+            Breakpoint::Symbol(symbol) => symbol.clone(),
+        }
+    }
+
+    pub fn path_of(&self, frame: &StackFrame) -> Option<&str> {
+        self.file_library.get_path(self.file_of(&frame.symbol)?)
+    }
+
+    pub fn source_line(&self, frame: &StackFrame) -> Option<String> {
+        let file_id = self.file_of(&frame.symbol)?;
+        let location = self.file_library.get_line_location(frame.line, file_id)?;
+        Some(self.file_library.get_source(file_id)?[location].to_string())
+    }
+
+    fn breakpoint_hit(&self, frame: &StackFrame, entered: bool) -> Option<usize> {
+        self.breakpoints.iter().position(|breakpoint| match breakpoint {
+            Breakpoint::Line(file_id, line) => {
+                *line == frame.line && file_id.is_none_or(|file_id| self.file_of(&frame.symbol) == Some(file_id))
+            }
+            Breakpoint::Symbol(symbol) => entered && *symbol == frame.symbol,
+        })
+    }
+}
+
+// Interacts with the user when the execution starts, stops and finishes
+pub trait Frontend {
+    fn start(&mut self, session: &mut Session) -> Resume;
+    fn stopped(&mut self, session: &mut Session, interpreter: &Interpreter, reason: StopReason) -> Resume;
+    fn finished(&mut self, session: &mut Session, interpreter: &Interpreter, error: Option<&ExecutionError>);
+}
+
+struct Debugger {
+    session: Session,
+    frontend: Box<dyn Frontend>,
+    resume: Resume,
+    // depth of the call stack and line in which the execution was resumed
+    origin: (usize, usize),
+    // depth and line of the last step
+    previous: (usize, usize),
+}
+
+impl ExecutionObserver for Debugger {
+    fn on_step(&mut self, interpreter: &Interpreter) -> bool {
+        let stack = interpreter.call_stack();
+        let frame = stack.last().unwrap();
+        let position = (stack.len(), frame.line);
+        let previous = std::mem::replace(&mut self.previous, position);
+        let (depth, line) = self.origin;
+        let reason = match self.resume {
+            Resume::StepIn if depth == 0 => Some(StopReason::Entry),
+            Resume::StepIn if position != self.origin => Some(StopReason::Step),
+            Resume::StepOver if position.0 < depth || (position.0 == depth && position.1 != line) => {
+                Some(StopReason::Step)
+            }
+            Resume::StepOut if position.0 < depth => Some(StopReason::Step),
+            // a line with several statements only stops once
+            _ if position != previous => {
+                self.session.breakpoint_hit(frame, position.0 > previous.0).map(StopReason::Breakpoint)
+            }
+            _ => None,
+        };
+        if let Some(reason) = reason {
+            self.resume = self.frontend.stopped(&mut self.session, interpreter, reason);
+            self.origin = position;
+        }
+        self.resume != Resume::Stop
+    }
+
+    fn on_finish(&mut self, interpreter: &Interpreter, error: Option<&ExecutionError>) {
+        self.frontend.finished(&mut self.session, interpreter, error);
+    }
+}
+
+pub fn run_debugger(input: &DebugInput) -> Result<(), ()> {
+    let connect = || -> Result<Box<dyn Frontend>, ()> {
+        if input.dap_flag {
+            Result::Ok(Box::new(super::dap_user::connect(input.port)?))
+        } else {
+            Result::Ok(Box::new(CommandLine::default()))
+        }
+    };
+    let result = debug(input, connect)?;
+    if input.dap_flag && input.port.is_none() {
+        // the standard output belongs to the client of the adapter
+        std::process::exit(if result.is_ok() { 0 } else { 1 });
+    }
+    match result {
+        Result::Err(error) if !matches!(error.cause, ErrorCause::Interrupted) => Result::Err(()),
+        _ => Result::Ok(()),
+    }
+}
+
+// Compiles the circuit and executes it with the frontend given by connect, which is only called
+// once the circuit is ready, and gives the result of the execution
+pub fn debug<F>(input: &DebugInput, connect: F) -> Result<Result<(), Box<ExecutionError>>, ()>
+where
+    F: FnOnce() -> Result<Box<dyn Frontend>, ()>,
+{
+    use constraint_generation::build_unsimplified_circuit;
+    let prime = UsefulConstants::new(&input.prime()).get_p().clone();
+    let file = input.input_path.to_str().unwrap().to_string();
+    let mut program = match parser::run_parser(file, VERSION, input.get_link_libraries().to_vec(), &prime) {
+        Result::Err((file_library, reports)) => {
+            Report::print_reports(&reports, &file_library);
+            return Result::Err(());
+        }
+        Result::Ok((program, warnings)) => {
+            Report::print_reports(&warnings, &program.file_library);
+            program
+        }
+    };
+    super::type_analysis_user::analyse_project(&mut program, false, false)?;
+    let mut files = HashMap::new();
+    for (name, data) in program.get_templates() {
+        files.insert(name.clone(), data.get_file_id());
+    }
+    for (name, data) in program.get_functions() {
+        files.insert(name.clone(), data.get_file_id());
+    }
+    let file_library = program.file_library.clone();
+    let vcp = match build_unsimplified_circuit(program, &input.prime()) {
+        Result::Ok((_, vcp)) => vcp,
+        Result::Err(reports) => {
+            Report::print_reports(&reports, &file_library);
+            return Result::Err(());
+        }
+    };
+    let config = Config { debug_output: false, produce_input_log: false, wat_flag: false, check_tags: false };
+    let circuit = run_compiler(vcp, config, VERSION)?;
+    let inputs = match &input.inputs_path {
+        Some(path) => read_inputs(path).map_err(|error| eprintln!("{}", Colour::Red.paint(error)))?,
+        None => vec![],
+    };
+    let mut interpreter = Interpreter::new(&circuit);
+    for (name, values) in &inputs {
+        interpreter.set_input(name, values).map_err(|error| eprintln!("{}", Colour::Red.paint(error)))?;
+    }
+    let mut session = Session { file_library, files, breakpoints: vec![] };
+    for spec in &input.breakpoints {
+        let breakpoint = session.parse_breakpoint(spec).map_err(|error| eprintln!("{}", Colour::Red.paint(error)))?;
+        session.breakpoints.push(breakpoint);
+    }
+    let mut frontend = connect()?;
+    let resume = frontend.start(&mut session);
+    if resume == Resume::Stop {
+        return Result::Ok(Result::Ok(()));
+    }
+    interpreter.set_observer(Box::new(Debugger { session, frontend, resume, origin: (0, 0), previous: (0, 0) }));
+    Result::Ok(interpreter.run())
+}
+
+// Reads the values of the inputs of the main component. Buses and arrays of buses are given
+// as objects, so their fields are named as in the input list of the circuit: p[0].x
+fn read_inputs(path: &Path) -> Result<Vec<(String, Vec<BigInt>)>, String> {
+    use serde_json::Value;
+    fn has_objects(value: &Value) -> bool {
+        match value {
+            Value::Object(_) => true,
+            Value::Array(values) => values.iter().any(has_objects),
+            _ => false,
+        }
+    }
+    fn collect(value: &Value, values: &mut Vec<BigInt>) -> Result<(), String> {
+        match value {
+            Value::Array(elements) => elements.iter().try_for_each(|element| collect(element, values)),
+            Value::Number(number) => to_bigint(&number.to_string()).map(|n| values.push(n)),
+            Value::String(number) => to_bigint(number).map(|n| values.push(n)),
+            _ => Result::Err(format!("Invalid input value {}", value)),
+        }
+    }
+    fn flatten(value: &Value, name: String, inputs: &mut Vec<(String, Vec<BigInt>)>) -> Result<(), String> {
+        match value {
+            Value::Object(fields) => {
+                for (field, value) in fields {
+                    let field = if name.is_empty() { field.clone() } else { format!("{}.{}", name, field) };
+                    flatten(value, field, inputs)?;
+                }
+            }
+            Value::Array(elements) if has_objects(value) => {
+                for (i, element) in elements.iter().enumerate() {
+                    flatten(element, format!("{}[{}]", name, i), inputs)?;
+                }
+            }
+            _ => {
+                let mut values = vec![];
+                collect(value, &mut values)?;
+                inputs.push((name, values));
+            }
+        }
+        Result::Ok(())
+    }
+    fn to_bigint(number: &str) -> Result<BigInt, String> {
+        let parsed = match number.strip_prefix("0x") {
+            Some(hex) => BigInt::parse_bytes(hex.as_bytes(), 16),
+            None => BigInt::parse_bytes(number.as_bytes(), 10),
+        };
+        parsed.ok_or_else(|| format!("Invalid input value {}", number))
+    }
+    let content = std::fs::read_to_string(path).map_err(|_| format!("Could not read {}", path.display()))?;
+    let value: Value = serde_json::from_str(&content).map_err(|error| format!("Invalid input values: {}", error))?;
+    let mut inputs = vec![];
+    flatten(&value, String::new(), &mut inputs)?;
+    Result::Ok(inputs)
+}
+
+// Looks for a variable of a frame, a signal of its component or one of its subcomponents.
+// Signals of subcomponents are written as in the source: c[1].out
+pub fn lookup(interpreter: &Interpreter, frame: usize, name: &str) -> Option<String> {
+    let stack = interpreter.call_stack();
+    if let Some(variable) = interpreter.variables(frame).into_iter().find(|v| v.name == name) {
+        return Some(variable.values_to_string());
+    }
+    let mut component = stack[frame].component;
+    let mut parts = name.split('.').peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            if let Some(signal) = interpreter.signals(component).into_iter().find(|s| s.name == part) {
+                return Some(signal.values_to_string());
+            }
+        }
+        let subcomponent = interpreter.subcomponents(component).into_iter().find(|s| s.name == part)?;
+        match subcomponent.component {
+            Some(id) if parts.peek().is_none() => return Some(component_to_string(interpreter, id)),
+            Some(id) => component = id,
+            None => return Some("not created".to_string()),
+        }
+    }
+    None
+}
+
+pub fn component_to_string(interpreter: &Interpreter, component: usize) -> String {
+    match interpreter.component_state(component) {
+        Some((template, 0)) => format!("{} {}", template, interpreter.component_path(component)),
+        Some((template, inputs)) => {
+            format!("{} {} (waiting for {} inputs)", template, interpreter.component_path(component), inputs)
+        }
+        None => "not created".to_string(),
+    }
+}
+
+fn symbols_to_string(symbols: &[SymbolValue]) -> String {
+    let lines: Vec<String> = symbols.iter().map(|s| format!("  {} = {}", s.name, s.values_to_string())).collect();
+    lines.join("\n")
+}
+
+const HELP: &str = "Commands:
+  continue (c)         runs until the next breakpoint
+  step (s)             runs until the next line, entering calls and subcomponents
+  next (n)             runs until the next line of the current template or function
+  finish (f)           runs until the current template or function ends
+  break (b) [spec]     sets a breakpoint at a line, file:line, template or function, lists them without argument
+  delete (d) [n]       removes the breakpoint n, or all of them
+  print (p) name       shows a variable, a signal or a subcomponent
+  vars                 shows the variables of the current frame
+  signals              shows the signals of the current component
+  components           shows the subcomponents of the current component
+  backtrace (bt)       shows the frames being executed
+  frame n              selects the frame n of the backtrace
+  quit (q)             interrupts the execution";
+
+#[derive(Default)]
+struct CommandLine {
+    // logs of the circuit already printed
+    logs: usize,
+    // selected frame, counting from the innermost one
+    frame: usize,
+}
+
+impl CommandLine {
+    fn print_logs(&mut self, interpreter: &Interpreter) {
+        for log in &interpreter.logs()[self.logs..] {
+            println!("{}", log);
+        }
+        self.logs = interpreter.logs().len();
+    }
+
+    fn print_frame(&self, session: &Session, interpreter: &Interpreter) {
+        let stack = interpreter.call_stack();
+        let frame = &stack[stack.len() - 1 - self.frame];
+        let kind = if frame.is_function { "function" } else { "template" };
+        let path = session.path_of(frame).unwrap_or_default();
+        println!("{} {} ({}) at {}:{}", kind, frame.symbol, interpreter.component_path(frame.component), path, frame.line);
+        if let Some(source) = session.source_line(frame) {
+            println!("{:>5} | {}", frame.line, source);
+        }
+    }
+
+    // Reads commands until the execution is resumed
+    fn command_loop(&mut self, session: &mut Session, interpreter: &Interpreter) -> Resume {
+        let stack = interpreter.call_stack();
+        let stdin = std::io::stdin();
+        loop {
+            print!("(debug) ");
+            let _ = std::io::stdout().flush();
+            let mut line = String::new();
+            if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+                return Resume::Stop;
+            }
+            let mut words = line.split_whitespace();
+            let command = words.next().unwrap_or("");
+            let argument = words.next();
+            let frame = stack.len() - 1 - self.frame;
+            match (command, argument) {
+                ("", _) => {}
+                ("continue", _) | ("c", _) => return Resume::Continue,
+                ("step", _) | ("s", _) => return Resume::StepIn,
+                ("next", _) | ("n", _) => return Resume::StepOver,
+                ("finish", _) | ("f", _) => return Resume::StepOut,
+                ("quit", _) | ("q", _) => return Resume::Stop,
+                ("help", _) | ("h", _) => println!("{}", HELP),
+                ("break", None) | ("b", None) => {
+                    for (i, breakpoint) in session.breakpoints.iter().enumerate() {
+                        println!("  {}: {}", i, session.breakpoint_to_string(breakpoint));
+                    }
+                }
+                ("break", Some(spec)) | ("b", Some(spec)) => match session.parse_breakpoint(spec) {
+                    Result::Ok(breakpoint) => {
+                        println!("breakpoint {} at {}", session.breakpoints.len(), session.breakpoint_to_string(&breakpoint));
+                        session.breakpoints.push(breakpoint);
+                    }
+                    Result::Err(error) => println!("{}", error),
+                },
+                ("delete", None) | ("d", None) => session.breakpoints.clear(),
+                ("delete", Some(n)) | ("d", Some(n)) => match n.parse::<usize>() {
+                    Result::Ok(n) if n < session.breakpoints.len() => {
+                        session.breakpoints.remove(n);
+                    }
+                    _ => println!("There is no breakpoint {}", n),
+                },
+                ("print", Some(name)) | ("p", Some(name)) => match lookup(interpreter, frame, name) {
+                    Some(value) => println!("{} = {}", name, value),
+                    None => println!("There is no symbol named {}", name),
+                },
+                ("vars", _) => println!("{}", symbols_to_string(&interpreter.variables(frame))),
+                ("signals", _) => println!("{}", symbols_to_string(&interpreter.signals(stack[frame].component))),
+                ("components", _) => {
+                    for subcomponent in interpreter.subcomponents(stack[frame].component) {
+                        let state = match subcomponent.component {
+                            Some(id) => component_to_string(interpreter, id),
+                            None => "not created".to_string(),
+                        };
+                        println!("  {} = {}", subcomponent.name, state);
+                    }
+                }
+                ("backtrace", _) | ("bt", _) => {
+                    for (i, frame) in stack.iter().rev().enumerate() {
+                        let path = session.path_of(frame).unwrap_or_default();
+                        println!("  #{} {} ({}) at {}:{}", i, frame.symbol, interpreter.component_path(frame.component), path, frame.line);
+                    }
+                }
+                ("frame", Some(n)) => match n.parse::<usize>() {
+                    Result::Ok(n) if n < stack.len() => {
+                        self.frame = n;
+                        self.print_frame(session, interpreter);
+                    }
+                    _ => println!("There is no frame {}", n),
+                },
+                _ => println!("Unknown command, type help to see the available ones"),
+            }
+        }
+    }
+}
+
+impl Frontend for CommandLine {
+    fn start(&mut self, session: &mut Session) -> Resume {
+        println!("Type help to see the commands of the debugger");
+        if session.breakpoints.is_empty() {
+            Resume::StepIn
+        } else {
+            Resume::Continue
+        }
+    }
+
+    fn stopped(&mut self, session: &mut Session, interpreter: &Interpreter, reason: StopReason) -> Resume {
+        self.print_logs(interpreter);
+        self.frame = 0;
+        if let StopReason::Breakpoint(n) = reason {
+            println!("breakpoint {} at {}", n, session.breakpoint_to_string(&session.breakpoints[n]));
+        }
+        self.print_frame(session, interpreter);
+        self.command_loop(session, interpreter)
+    }
+
+    fn finished(&mut self, session: &mut Session, interpreter: &Interpreter, error: Option<&ExecutionError>) {
+        self.print_logs(interpreter);
+        match error {
+            None => println!("{}", Colour::Green.paint("The witness was computed")),
+            Some(error) if matches!(error.cause, ErrorCause::Interrupted) => {}
+            Some(error) => {
+                // the state of the failure can be inspected until the user resumes
+                println!("{}", Colour::Red.paint(error.message()));
+                self.frame = 0;
+                self.print_frame(session, interpreter);
+                self.command_loop(session, interpreter);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    const CIRCUIT: &str = "pragma circom 2.0.0;
+function double(x) {
+    var y = x + x;
+    return y;
+}
+template Main() {
+    signal input a;
+    signal output b;
+    signal c;
+    c <-- double(a);
+    b <== a * c + 1;
+}
+component main = Main();
+";
+
+    // Resumes the execution as the script says and records where it stopped
+    struct Script {
+        resumes: Vec<Resume>,
+        stops: Rc<RefCell<Vec<(String, usize)>>>,
+    }
+
+    impl Frontend for Script {
+        fn start(&mut self, _session: &mut Session) -> Resume {
+            Resume::StepIn
+        }
+        fn stopped(&mut self, _session: &mut Session, interpreter: &Interpreter, _reason: StopReason) -> Resume {
+            let stack = interpreter.call_stack();
+            let frame = stack.last().unwrap();
+            self.stops.borrow_mut().push((frame.symbol.clone(), frame.line));
+            if self.resumes.is_empty() {
+                Resume::Continue
+            } else {
+                self.resumes.remove(0)
+            }
+        }
+        fn finished(&mut self, _session: &mut Session, _interpreter: &Interpreter, _error: Option<&ExecutionError>) {}
+    }
+
+    fn stops(name: &str, breakpoints: &[&str], resumes: Vec<Resume>) -> Vec<(String, usize)> {
+        let folder = std::env::temp_dir().join(format!("circom_debug_{}_{}", std::process::id(), name));
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("circuit.circom"), CIRCUIT).unwrap();
+        std::fs::write(folder.join("input.json"), "{\"a\": 2}").unwrap();
+        let input = DebugInput {
+            input_path: folder.join("circuit.circom"),
+            inputs_path: Some(folder.join("input.json")),
+            breakpoints: breakpoints.iter().map(|b| b.to_string()).collect(),
+            dap_flag: false,
+            port: None,
+            prime: "bn128".to_string(),
+            link_libraries: vec![],
+        };
+        let stops = Rc::new(RefCell::new(vec![]));
+        let script = Script { resumes, stops: stops.clone() };
+        let result = debug(&input, move || -> Result<Box<dyn Frontend>, ()> { Result::Ok(Box::new(script)) });
+        std::fs::remove_dir_all(&folder).unwrap();
+        if let Result::Err(error) = result.unwrap() {
+            assert!(matches!(error.cause, ErrorCause::Interrupted));
+        }
+        let stops = stops.borrow().clone();
+        stops
+    }
+
+    #[test]
+    fn steps_enter_skip_and_leave_functions() {
+        let main = |line| ("Main".to_string(), line);
+        let double = |line| ("double".to_string(), line);
+        let stepped_in = stops("in", &[], vec![Resume::StepIn, Resume::StepIn, Resume::StepIn, Resume::Stop]);
+        assert_eq!(stepped_in, vec![main(10), double(3), double(4), main(11)]);
+        let stepped_over = stops("over", &[], vec![Resume::StepOver, Resume::Stop]);
+        assert_eq!(stepped_over, vec![main(10), main(11)]);
+        let stepped_out = stops("out", &[], vec![Resume::StepIn, Resume::StepOut, Resume::Stop]);
+        assert_eq!(stepped_out, vec![main(10), double(3), main(11)]);
+    }
+
+    #[test]
+    fn breakpoints_stop_once_per_line() {
+        let hits = stops("breakpoints", &["double", "11"], vec![Resume::Continue, Resume::Continue, Resume::Continue]);
+        assert_eq!(hits, vec![("Main".to_string(), 10), ("double".to_string(), 3), ("Main".to_string(), 11)]);
+    }
+}
@@ -0,0 +1,485 @@
+use super::input_user::DiffInput;
+use crate::VERSION;
+use ansi_term::Colour;
+use circom_algebra::algebra::Constraint as CConstraint;
+use circom_algebra::modular_arithmetic;
+use circom_algebra::num_bigint::BigInt;
+use constraint_generation::build_unsimplified_circuit;
+use constraint_writers::r1cs_reader::read_r1cs;
+use constraint_writers::sym_writer::read_sym_file;
+use dag::{Tree, DAG};
+use program_structure::constants::UsefulConstants;
+use program_structure::error_definition::Report;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+type LinearCombination = HashMap<usize, BigInt>;
+type Constraint = (LinearCombination, LinearCombination, LinearCombination);
+
+// What is compared of a circuit, the signals are identified by their names as in the .sym file
+struct CircuitSummary {
+    field: BigInt,
+    outputs: Vec<String>,
+    public_inputs: Vec<String>,
+    private_inputs: Vec<String>,
+    // signals that remain in the witness
+    signals: BTreeSet<String>,
+    constraints: usize,
+    // instances of each template and constraints generated by them, only known when the
+    // circuit is compiled from its source
+    templates: Option<BTreeMap<String, (usize, usize)>>,
+    // canonical form of the constraints of each component instance
+    instances: BTreeMap<String, Vec<String>>,
+}
+
+// Number of signals of each kind at the beginning of the witness, after the constant one
+struct Interface {
+    outputs: usize,
+    public_inputs: usize,
+    private_inputs: usize,
+}
+
+// Whether the circuits are equal, main turns it into the exit code as the diff tool does
+pub fn diff(input: &DiffInput) -> Result<bool, ()> {
+    let old = load(&input.old_path, input)?;
+    let new = load(&input.new_path, input)?;
+    let diff = compare(&old, &new, input.constraints_flag);
+    print_diff(&diff);
+    if let Some(file) = &input.json_path {
+        let contents = serde_json::to_string_pretty(&diff).unwrap();
+        if std::fs::write(file, contents + "\n").is_err() {
+            eprintln!("{}", Colour::Red.paint(format!("Could not write the diff in {}", file.display())));
+            return Result::Err(());
+        }
+        println!("{} {}", Colour::Green.paint("Written successfully:"), file.display());
+    }
+    Result::Ok(diff["equal"].as_bool().unwrap())
+}
+
+// A side of the diff is a circuit file, compiled with the given options, or a build: a .r1cs
+// file, or a folder with a single one, together with the .sym file of the same name
+fn load(path: &Path, input: &DiffInput) -> Result<CircuitSummary, ()> {
+    if path.extension().is_some_and(|e| e == "circom") {
+        return compile(path, input);
+    }
+    read_build(&find_r1cs(path)?)
+}
+
+// The .r1cs file of a build, given by its path or by the folder that only contains it
+pub fn find_r1cs(path: &Path) -> Result<PathBuf, ()> {
+    if path.is_dir() {
+        let entries = std::fs::read_dir(path).map_err(|_| eprintln!("{}", Colour::Red.paint(format!("Could not read {}", path.display()))))?;
+        let mut found: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|e| e == "r1cs"))
+            .collect();
+        if found.len() != 1 {
+            let message = format!("The folder {} must contain exactly one .r1cs file, it contains {}", path.display(), found.len());
+            eprintln!("{}", Colour::Red.paint(message));
+            return Result::Err(());
+        }
+        Result::Ok(found.pop().unwrap())
+    } else {
+        Result::Ok(path.to_path_buf())
+    }
+}
+
+fn read_build(r1cs: &Path) -> Result<CircuitSummary, ()> {
+    let sym = r1cs.with_extension("sym");
+    let data = read_r1cs(r1cs.to_str().unwrap()).map_err(|err| {
+        eprintln!("{}", Colour::Red.paint(format!("Could not read the r1cs file {}: {}", r1cs.display(), err)))
+    })?;
+    let elems = read_sym_file(sym.to_str().unwrap()).map_err(|err| {
+        eprintln!("{}", Colour::Red.paint(format!("Could not read the sym file {}: {}", sym.display(), err)))
+    })?;
+    let mut names = HashMap::new();
+    for elem in elems.into_iter().filter(|elem| elem.witness >= 0) {
+        names.insert(elem.witness as usize, elem.symbol);
+    }
+    let interface = Interface {
+        outputs: data.public_outputs,
+        public_inputs: data.public_inputs,
+        private_inputs: data.private_inputs,
+    };
+    Result::Ok(summarize(data.field, interface, &data.constraints, &names, None))
+}
+
+fn compile(path: &Path, input: &DiffInput) -> Result<CircuitSummary, ()> {
+    let field = UsefulConstants::new(&input.prime()).get_p().clone();
+    let file = path.to_str().unwrap().to_string();
+    let mut program = match parser::run_parser(file, VERSION, input.get_link_libraries().to_vec(), &field) {
+        Result::Err((file_library, reports)) => {
+            Report::print_reports(&reports, &file_library);
+            return Result::Err(());
+        }
+        Result::Ok((program, warnings)) => {
+            Report::print_reports(&warnings, &program.file_library);
+            program
+        }
+    };
+    super::type_analysis_user::analyse_project(&mut program, false, false)?;
+    let file_library = program.file_library.clone();
+    let dag = match build_unsimplified_circuit(program, &input.prime()) {
+        Result::Ok((dag, _)) => dag,
+        Result::Err(reports) => {
+            Report::print_reports(&reports, &file_library);
+            return Result::Err(());
+        }
+    };
+    let mut names = HashMap::new();
+    let mut generated = Vec::new();
+    let mut templates = BTreeMap::new();
+    visit(&Tree::new(&dag), &dag, &mut names, &mut generated, &mut templates).map_err(|error| eprintln!("{}", error))?;
+    if input.no_simplification {
+        let interface = Interface {
+            outputs: dag.public_outputs(),
+            public_inputs: dag.public_inputs(),
+            private_inputs: dag.private_inputs(),
+        };
+        return Result::Ok(summarize(field, interface, &generated, &names, Some(templates)));
+    }
+    let list = super::explain_user::simplify(dag, input.reduced_simplification, input.no_rounds, input.prime())?;
+    let interface = Interface {
+        outputs: list.no_public_outputs,
+        public_inputs: list.no_public_inputs,
+        private_inputs: list.no_private_inputs_witness,
+    };
+    let mut constraints = Vec::new();
+    for id in list.constraints.get_ids() {
+        let constraint = list.constraints.read_constraint(id).unwrap();
+        let constraint = CConstraint::apply_correspondence(&constraint, &list.signal_map);
+        constraints.push((constraint.a().clone(), constraint.b().clone(), constraint.c().clone()));
+    }
+    let names = list.signal_map.iter().filter_map(|(label, wire)| Some((*wire, names.get(label)?.clone()))).collect();
+    Result::Ok(summarize(field, interface, &constraints, &names, Some(templates)))
+}
+
+fn visit(
+    tree: &Tree,
+    dag: &DAG,
+    names: &mut HashMap<usize, String>,
+    generated: &mut Vec<Constraint>,
+    templates: &mut BTreeMap<String, (usize, usize)>,
+) -> std::io::Result<()> {
+    for (signal, name) in &tree.id_to_name {
+        names.insert(*signal, format!("{}.{}", tree.path, name));
+    }
+    let template = templates.entry(dag.nodes[tree.node_id].template_name().to_string()).or_insert((0, 0));
+    template.0 += 1;
+    for constraint in tree.constraints() {
+        let constraint = constraint?;
+        template.1 += 1;
+        generated.push((constraint.a().clone(), constraint.b().clone(), constraint.c().clone()));
+    }
+    for edge in Tree::get_edges(tree) {
+        let subtree = Tree::go_to_subtree(tree, edge);
+        visit(&subtree, dag, names, generated, templates)?;
+    }
+    Result::Ok(())
+}
+
+fn summarize(
+    field: BigInt,
+    interface: Interface,
+    constraints: &[Constraint],
+    names: &HashMap<usize, String>,
+    templates: Option<BTreeMap<String, (usize, usize)>>,
+) -> CircuitSummary {
+    let name = |wire: usize| names.get(&wire).cloned().unwrap_or_else(|| format!("signal_{}", wire));
+    let outputs_end = 1 + interface.outputs;
+    let public_end = outputs_end + interface.public_inputs;
+    let private_end = public_end + interface.private_inputs;
+    let mut instances: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for constraint in constraints {
+        let (instance, canonical) = canonical_form(constraint, names, &field);
+        instances.entry(instance).or_default().push(canonical);
+    }
+    for constraints in instances.values_mut() {
+        constraints.sort();
+    }
+    CircuitSummary {
+        outputs: (1..outputs_end).map(name).collect(),
+        public_inputs: (outputs_end..public_end).map(name).collect(),
+        private_inputs: (public_end..private_end).map(name).collect(),
+        signals: names.values().cloned().collect(),
+        constraints: constraints.len(),
+        templates,
+        instances,
+        field,
+    }
+}
+
+// The constraint is attributed to the deepest component containing all its signals and it is
+// written with their names relative to that component. A * B = C is scaled so that the first
+// coefficients of A and B are 1, the linear constraints so that their first coefficient is 1,
+// so that the same constraint gets the same form in both circuits.
+fn canonical_form(constraint: &Constraint, names: &HashMap<usize, String>, field: &BigInt) -> (String, String) {
+    let (a, b, c) = constraint;
+    let mut instance: Option<Vec<&str>> = None;
+    for wire in a.keys().chain(b.keys()).chain(c.keys()).filter(|w| **w != 0) {
+        let name = names.get(wire).map_or("main", |n| n.as_str());
+        let component: Vec<_> = name.rsplit_once('.').map_or(name, |(component, _)| component).split('.').collect();
+        instance = Some(match instance {
+            None => component,
+            Some(current) => current.iter().zip(component.iter()).take_while(|(x, y)| x == y).map(|(x, _)| *x).collect(),
+        });
+    }
+    let instance = instance.map_or("main".to_string(), |segments| segments.join("."));
+    let prefix = format!("{}.", instance);
+    let relative = |wire: &usize| match names.get(wire) {
+        Some(name) => name.strip_prefix(&prefix).unwrap_or(name).to_string(),
+        None => format!("signal_{}", wire),
+    };
+    let canonical = if a.is_empty() || b.is_empty() {
+        let (c, _) = normalize(c, &relative, field);
+        format!("{} = 0", c)
+    } else {
+        let (a, factor_a) = normalize(a, &relative, field);
+        let (b, factor_b) = normalize(b, &relative, field);
+        let factor = modular_arithmetic::mul(&factor_a, &factor_b, field);
+        let inverse = inverse(&factor, field);
+        let c: LinearCombination = c.iter().map(|(w, v)| (*w, modular_arithmetic::mul(v, &inverse, field))).collect();
+        let (c, _) = terms(&c, &relative, field);
+        let (first, second) = if a <= b { (a, b) } else { (b, a) };
+        format!("({}) * ({}) = {}", first, second, c)
+    };
+    (instance, canonical)
+}
+
+// Writes the linear combination divided by its first coefficient, which is returned
+fn normalize(lc: &LinearCombination, relative: &dyn Fn(&usize) -> String, field: &BigInt) -> (String, BigInt) {
+    let (_, first) = terms(lc, relative, field);
+    let factor = first.unwrap_or_else(|| BigInt::from(1));
+    let inverse = inverse(&factor, field);
+    let lc: LinearCombination = lc.iter().map(|(w, v)| (*w, modular_arithmetic::mul(v, &inverse, field))).collect();
+    (terms(&lc, relative, field).0, factor)
+}
+
+// The first coefficients of the linear combinations are never zero
+fn inverse(value: &BigInt, field: &BigInt) -> BigInt {
+    modular_arithmetic::div(&BigInt::from(1), value, field).unwrap_or_else(|_| BigInt::from(1))
+}
+
+// Terms sorted by the names of their signals with the constant at the end, and the coefficient
+// of the first one. The coefficients greater than p/2 are written as negative numbers.
+fn terms(lc: &LinearCombination, relative: &dyn Fn(&usize) -> String, field: &BigInt) -> (String, Option<BigInt>) {
+    let mut terms: Vec<_> = lc
+        .iter()
+        .filter(|(_, v)| **v != BigInt::from(0))
+        .map(|(w, v)| (*w == 0, if *w == 0 { String::new() } else { relative(w) }, v.clone()))
+        .collect();
+    terms.sort();
+    let first = terms.first().map(|(_, _, v)| v.clone());
+    let mut out = String::new();
+    for (constant, name, value) in terms {
+        let value = if value.clone() * 2 > *field { value - field } else { value };
+        let negative = value < BigInt::from(0);
+        let magnitude = if negative { -value } else { value };
+        out.push_str(match (out.is_empty(), negative) {
+            (true, true) => "-",
+            (true, false) => "",
+            (false, true) => " - ",
+            (false, false) => " + ",
+        });
+        if constant {
+            out.push_str(&magnitude.to_string());
+        } else if magnitude == BigInt::from(1) {
+            out.push_str(&name);
+        } else {
+            out.push_str(&format!("{}*{}", magnitude, name));
+        }
+    }
+    if out.is_empty() {
+        out.push('0');
+    }
+    (out, first)
+}
+
+fn compare(old: &CircuitSummary, new: &CircuitSummary, constraints_flag: bool) -> Value {
+    let mut equal = old.field == new.field;
+    let mut interface = serde_json::Map::new();
+    let kinds = [
+        ("outputs", &old.outputs, &new.outputs),
+        ("public_inputs", &old.public_inputs, &new.public_inputs),
+        ("private_inputs", &old.private_inputs, &new.private_inputs),
+    ];
+    for (kind, old_signals, new_signals) in kinds {
+        if old_signals != new_signals {
+            equal = false;
+            interface.insert(kind.to_string(), json!({ "old": old_signals, "new": new_signals }));
+        }
+    }
+    let removed: Vec<_> = old.signals.difference(&new.signals).collect();
+    let added: Vec<_> = new.signals.difference(&old.signals).collect();
+    equal &= removed.is_empty() && added.is_empty() && old.constraints == new.constraints;
+    let templates = match (&old.templates, &new.templates) {
+        (Some(old_templates), Some(new_templates)) => {
+            let mut changed = serde_json::Map::new();
+            let names: BTreeSet<_> = old_templates.keys().chain(new_templates.keys()).collect();
+            for name in names {
+                let (old_count, new_count) = (old_templates.get(name), new_templates.get(name));
+                if old_count != new_count {
+                    let count = |c: Option<&(usize, usize)>| c.map(|(i, n)| json!({ "instances": i, "constraints": n }));
+                    changed.insert(name.clone(), json!({ "old": count(old_count), "new": count(new_count) }));
+                }
+            }
+            equal &= changed.is_empty();
+            Value::Object(changed)
+        }
+        _ => Value::Null,
+    };
+    let instances = if constraints_flag {
+        let mut changed = serde_json::Map::new();
+        let paths: BTreeSet<_> = old.instances.keys().chain(new.instances.keys()).collect();
+        let empty = Vec::new();
+        for path in paths {
+            let old_constraints = old.instances.get(path).unwrap_or(&empty);
+            let new_constraints = new.instances.get(path).unwrap_or(&empty);
+            if old_constraints != new_constraints {
+                let (removed, added) = multiset_difference(old_constraints, new_constraints);
+                changed.insert(path.clone(), json!({
+                    "old": old_constraints.len(),
+                    "new": new_constraints.len(),
+                    "removed": removed,
+                    "added": added,
+                }));
+            }
+        }
+        equal &= changed.is_empty();
+        Value::Object(changed)
+    } else {
+        Value::Null
+    };
+    json!({
+        "equal": equal,
+        "prime": { "old": old.field.to_string(), "new": new.field.to_string() },
+        "interface": interface,
+        "signals": { "old": old.signals.len(), "new": new.signals.len(), "removed": removed, "added": added },
+        "constraints": { "old": old.constraints, "new": new.constraints },
+        "templates": templates,
+        "instances": instances,
+    })
+}
+
+// Constraints of the sorted list old that are not in new and the other way around
+fn multiset_difference<'a>(old: &'a [String], new: &'a [String]) -> (Vec<&'a String>, Vec<&'a String>) {
+    let (mut removed, mut added) = (Vec::new(), Vec::new());
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if j == new.len() || (i < old.len() && old[i] < new[j]) {
+            removed.push(&old[i]);
+            i += 1;
+        } else if i == old.len() || new[j] < old[i] {
+            added.push(&new[j]);
+            j += 1;
+        } else {
+            i += 1;
+            j += 1;
+        }
+    }
+    (removed, added)
+}
+
+fn print_diff(diff: &Value) {
+    let count = |v: &Value| v.as_u64().unwrap_or(0);
+    if diff["prime"]["old"] != diff["prime"]["new"] {
+        println!("Prime: {} -> {}", diff["prime"]["old"].as_str().unwrap(), diff["prime"]["new"].as_str().unwrap());
+    }
+    let interface = diff["interface"].as_object().unwrap();
+    if interface.is_empty() {
+        println!("Public interface: unchanged");
+    } else {
+        println!("Public interface:");
+        for (kind, signals) in interface {
+            let list = |v: &Value| v.as_array().unwrap().iter().map(|s| s.as_str().unwrap()).collect::<Vec<_>>().join(", ");
+            println!("  {}: [{}] -> [{}]", kind.replace('_', " "), list(&signals["old"]), list(&signals["new"]));
+        }
+    }
+    let signals = &diff["signals"];
+    println!("Signals in the witness: {} -> {}", count(&signals["old"]), count(&signals["new"]));
+    for signal in signals["removed"].as_array().unwrap() {
+        println!("  {} {}", Colour::Red.paint("-"), signal.as_str().unwrap());
+    }
+    for signal in signals["added"].as_array().unwrap() {
+        println!("  {} {}", Colour::Green.paint("+"), signal.as_str().unwrap());
+    }
+    println!("Constraints: {} -> {}", count(&diff["constraints"]["old"]), count(&diff["constraints"]["new"]));
+    match diff["templates"].as_object() {
+        None => println!("Constraints generated by each template: unknown, the circuit files are needed"),
+        Some(templates) if templates.is_empty() => println!("Constraints generated by each template: unchanged"),
+        Some(templates) => {
+            println!("Constraints generated by each template:");
+            let show = |v: &Value| {
+                if v.is_null() {
+                    "none".to_string()
+                } else {
+                    let instances = count(&v["instances"]);
+                    let plural = if instances == 1 { "" } else { "s" };
+                    format!("{} instance{}, {} constraints", instances, plural, count(&v["constraints"]))
+                }
+            };
+            for (name, change) in templates {
+                println!("  {}: {} -> {}", name, show(&change["old"]), show(&change["new"]));
+            }
+        }
+    }
+    if let Some(instances) = diff["instances"].as_object() {
+        if instances.is_empty() {
+            println!("Constraints of each component instance: unchanged");
+        } else {
+            println!("Constraints of each component instance:");
+        }
+        for (path, change) in instances {
+            println!("  {}: {} -> {} constraints", path, count(&change["old"]), count(&change["new"]));
+            for constraint in change["removed"].as_array().unwrap() {
+                println!("    {} {}", Colour::Red.paint("-"), constraint.as_str().unwrap());
+            }
+            for constraint in change["added"].as_array().unwrap() {
+                println!("    {} {}", Colour::Green.paint("+"), constraint.as_str().unwrap());
+            }
+        }
+    }
+    if diff["equal"].as_bool().unwrap() {
+        println!("{}", Colour::Green.paint("The circuits are structurally equal"));
+    } else {
+        println!("{}", Colour::Yellow.paint("The circuits are different"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution_user::tests::CIRCUIT;
+
+    fn input(folder: &Path, old: &str, new: &str) -> DiffInput {
+        DiffInput {
+            old_path: folder.join(old),
+            new_path: folder.join(new),
+            json_path: Option::None,
+            constraints_flag: true,
+            no_simplification: true,
+            reduced_simplification: false,
+            no_rounds: usize::MAX,
+            prime: "bn128".to_string(),
+            link_libraries: vec![],
+        }
+    }
+
+    #[test]
+    fn diff_tells_equal_different_and_failed_comparisons_apart() {
+        let folder = std::env::temp_dir().join(format!("circom_diff_{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("old.circom"), CIRCUIT).unwrap();
+        std::fs::write(folder.join("same.circom"), CIRCUIT).unwrap();
+        std::fs::write(folder.join("new.circom"), CIRCUIT.replace("Main(6)", "Main(5)")).unwrap();
+        let equal = diff(&input(&folder, "old.circom", "same.circom"));
+        let different = diff(&input(&folder, "old.circom", "new.circom"));
+        let failed = diff(&input(&folder, "old.circom", "missing.circom"));
+        std::fs::remove_dir_all(&folder).unwrap();
+        assert_eq!(equal, Result::Ok(true));
+        assert_eq!(different, Result::Ok(false));
+        assert_eq!(failed, Result::Err(()));
+    }
+}
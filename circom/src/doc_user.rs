@@ -0,0 +1,373 @@
+use super::input_user::{DocFormat, DocInput};
+use crate::VERSION;
+use ansi_term::Colour;
+use program_structure::ast::{Statement, VariableType};
+use program_structure::constants::UsefulConstants;
+use program_structure::error_definition::Report;
+use program_structure::file_definition::FileID;
+use program_structure::program_archive::ProgramArchive;
+use program_structure::wire_data::{WireDeclarationOrder, WireInfo, WireType};
+use std::collections::HashMap;
+
+// Documentation of a template, function or bus
+struct DefinitionDoc {
+    kind: &'static str,
+    name: String,
+    params: Vec<String>,
+    file_id: FileID,
+    start: usize,
+    docs: Vec<String>,
+    // inputs and outputs of a template, fields of a bus
+    wires: Vec<(&'static str, Vec<WireDoc>)>,
+}
+
+struct WireDoc {
+    // name with the dimensions of its declaration, like in[n]
+    name: String,
+    wire_type: String,
+    tags: Vec<String>,
+}
+
+pub fn write_documentation(input: &DocInput) -> Result<(), ()> {
+    let prime = UsefulConstants::new(&input.prime()).get_p().clone();
+    let file_name = input.input_path.to_str().unwrap().to_string();
+    let result = parser::run_doc_parser(file_name.clone(), VERSION, input.get_link_libraries().to_vec(), &prime);
+    let program = match result {
+        Result::Err((file_library, reports)) => {
+            Report::print_reports(&reports, &file_library);
+            return Result::Err(());
+        }
+        Result::Ok((program, warnings)) => {
+            Report::print_reports(&warnings, program.get_file_library());
+            program
+        }
+    };
+    let definitions = collect_definitions(&program);
+    let title = input.input_path.file_name().unwrap().to_str().unwrap().to_string();
+    let (contents, extension) = match input.format {
+        DocFormat::Markdown => (to_markdown(&title, &definitions, &program), "md"),
+        DocFormat::Html => (to_html(&title, &definitions, &program), "html"),
+    };
+    let stem = input.input_path.file_stem().unwrap().to_str().unwrap();
+    let output = input.output_path.join(format!("{}.{}", stem, extension));
+    if std::fs::write(&output, contents).is_err() {
+        eprintln!("{}", Colour::Red.paint(format!("Could not write the documentation in {}", output.display())));
+        return Result::Err(());
+    }
+    println!("{} {}", Colour::Green.paint("Written successfully:"), output.display());
+    Result::Ok(())
+}
+
+// Definitions sorted by file, in the order in which the files are included, and by position
+fn collect_definitions(program: &ProgramArchive) -> Vec<DefinitionDoc> {
+    let mut definitions = Vec::new();
+    for (name, data) in program.get_templates() {
+        let source = program.get_file_library().get_source(data.get_file_id()).unwrap_or("");
+        let dimensions = declared_dimensions(data.get_body(), source);
+        let kind = if data.is_custom_gate() {
+            "custom template"
+        } else if data.is_parallel() {
+            "parallel template"
+        } else {
+            "template"
+        };
+        definitions.push(DefinitionDoc {
+            kind,
+            name: name.clone(),
+            params: data.get_name_of_params().clone(),
+            file_id: data.get_file_id(),
+            start: data.get_param_location().start,
+            docs: data.get_docs().clone(),
+            wires: vec![
+                ("Inputs", wires(data.get_declaration_inputs(), data.get_inputs(), &dimensions)),
+                ("Outputs", wires(data.get_declaration_outputs(), data.get_outputs(), &dimensions)),
+            ],
+        });
+    }
+    for (name, data) in program.get_functions() {
+        definitions.push(DefinitionDoc {
+            kind: "function",
+            name: name.clone(),
+            params: data.get_name_of_params().clone(),
+            file_id: data.get_file_id(),
+            start: data.get_param_location().start,
+            docs: data.get_docs().clone(),
+            wires: vec![],
+        });
+    }
+    for (name, data) in program.get_buses() {
+        let source = program.get_file_library().get_source(data.get_file_id()).unwrap_or("");
+        let dimensions = declared_dimensions(data.get_body(), source);
+        definitions.push(DefinitionDoc {
+            kind: "bus",
+            name: name.clone(),
+            params: data.get_name_of_params().clone(),
+            file_id: data.get_file_id(),
+            start: data.get_param_location().start,
+            docs: data.get_docs().clone(),
+            wires: vec![("Fields", wires(data.get_declaration_fields(), data.get_fields(), &dimensions))],
+        });
+    }
+    definitions.sort_by_key(|definition| (definition.file_id, definition.start));
+    definitions
+}
+
+fn wires(order: &WireDeclarationOrder, info: &WireInfo, dimensions: &HashMap<String, String>) -> Vec<WireDoc> {
+    let mut wires = Vec::new();
+    for (name, _) in order {
+        let data = info.get(name).unwrap();
+        let wire_type = match data.get_type() {
+            WireType::Signal => "signal".to_string(),
+            WireType::Bus(bus_name) => bus_name,
+        };
+        let mut tags: Vec<_> = data.get_tags().iter().cloned().collect();
+        tags.sort();
+        let name = format!("{}{}", name, dimensions.get(name).map_or("", |d| d.as_str()));
+        wires.push(WireDoc { name, wire_type, tags });
+    }
+    wires
+}
+
+// Dimensions of the signals and buses as they are written in their declarations
+fn declared_dimensions(body: &Statement, source: &str) -> HashMap<String, String> {
+    fn visit(stmt: &Statement, source: &str, dimensions: &mut HashMap<String, String>) {
+        use Statement::*;
+        match stmt {
+            IfThenElse { if_case, else_case, .. } => {
+                visit(if_case, source, dimensions);
+                if let Some(else_case) = else_case {
+                    visit(else_case, source, dimensions);
+                }
+            }
+            While { stmt, .. } => visit(stmt, source, dimensions),
+            Block { stmts, .. } | InitializationBlock { initializations: stmts, .. } => {
+                for stmt in stmts {
+                    visit(stmt, source, dimensions);
+                }
+            }
+            Declaration { xtype: VariableType::Signal(..) | VariableType::Bus(..), name, dimensions: dims, .. } => {
+                let text: String = dims
+                    .iter()
+                    .map(|dim| format!("[{}]", source.get(dim.get_meta().start..dim.get_meta().end).unwrap_or("?")))
+                    .collect();
+                dimensions.entry(name.clone()).or_insert(text);
+            }
+            _ => {}
+        }
+    }
+    let mut dimensions = HashMap::new();
+    visit(body, source, &mut dimensions);
+    dimensions
+}
+
+fn signature(definition: &DefinitionDoc) -> String {
+    format!("{}({})", definition.name, definition.params.join(", "))
+}
+
+fn file_path(program: &ProgramArchive, file_id: FileID) -> &str {
+    program.get_file_library().get_path(file_id).unwrap_or_default()
+}
+
+fn to_markdown(title: &str, definitions: &[DefinitionDoc], program: &ProgramArchive) -> String {
+    let mut out = format!("# {}\n", title);
+    let mut current_file = None;
+    for definition in definitions {
+        if current_file != Some(definition.file_id) {
+            current_file = Some(definition.file_id);
+            out.push_str(&format!("\n## `{}`\n", file_path(program, definition.file_id)));
+        }
+        out.push_str(&format!("\n### {} `{}`\n", definition.kind, signature(definition)));
+        if !definition.docs.is_empty() {
+            out.push_str(&format!("\n{}\n", definition.docs.join("\n")));
+        }
+        for (section, wires) in &definition.wires {
+            if wires.is_empty() {
+                continue;
+            }
+            out.push_str(&format!("\n{}:\n\n| Name | Type | Tags |\n| --- | --- | --- |\n", section));
+            for wire in wires {
+                out.push_str(&format!("| `{}` | {} | {} |\n", wire.name, wire.wire_type, wire.tags.join(", ")));
+            }
+        }
+    }
+    out
+}
+
+fn to_html(title: &str, definitions: &[DefinitionDoc], program: &ProgramArchive) -> String {
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n</head>\n<body>\n<h1>{0}</h1>\n",
+        escape(title)
+    );
+    let mut current_file = None;
+    for definition in definitions {
+        if current_file != Some(definition.file_id) {
+            current_file = Some(definition.file_id);
+            out.push_str(&format!("<h2><code>{}</code></h2>\n", escape(file_path(program, definition.file_id))));
+        }
+        out.push_str(&format!(
+            "<h3 id=\"{}\">{} <code>{}</code></h3>\n",
+            escape(&definition.name),
+            definition.kind,
+            escape(&signature(definition))
+        ));
+        // empty lines separate the paragraphs of the doc comments
+        for paragraph in definition.docs.split(|line| line.trim().is_empty()).filter(|p| !p.is_empty()) {
+            out.push_str(&format!("<p>{}</p>\n", escape(&paragraph.join("\n"))));
+        }
+        for (section, wires) in &definition.wires {
+            if wires.is_empty() {
+                continue;
+            }
+            out.push_str(&format!("<p>{}:</p>\n<table>\n<tr><th>Name</th><th>Type</th><th>Tags</th></tr>\n", section));
+            for wire in wires {
+                out.push_str(&format!(
+                    "<tr><td><code>{}</code></td><td>{}</td><td>{}</td></tr>\n",
+                    escape(&wire.name),
+                    escape(&wire.wire_type),
+                    escape(&wire.tags.join(", "))
+                ));
+            }
+            out.push_str("</table>\n");
+        }
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIBRARY: &str = "pragma circom 2.2.0;
+
+/// A point of the curve.
+bus Point() {
+    signal x;
+    signal y;
+}
+
+/// Binary decomposition of a number.
+///
+/// The bits are given in little endian.
+template Num2Bits(n) {
+    signal input in;
+    signal output {binary} out[n];
+    var sum = 0;
+    for (var i = 0; i < n; i++) {
+        out[i] <-- (in >> i) & 1;
+        out[i] * (out[i] - 1) === 0;
+        sum += out[i] * 2 ** i;
+    }
+    sum === in;
+}
+
+//////// Not a doc comment
+function double(x) {
+    return 2 * x;
+}
+";
+
+    const CIRCUIT: &str = "pragma circom 2.2.0;
+include \"lib.circom\" as lib;
+
+/// Moves a point.
+template Move(n) {
+    input lib.Point() p;
+    output lib.Point() q[2];
+    /// not attached to a definition
+    q[0].x <== p.x;
+    q[0].y <== lib.double(p.y);
+    q[1] <== p;
+}
+";
+
+    #[test]
+    fn definitions_are_documented_with_their_comments_and_wires() {
+        let folder = std::env::temp_dir().join(format!("circom_doc_{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("lib.circom"), LIBRARY).unwrap();
+        std::fs::write(folder.join("circuit.circom"), CIRCUIT).unwrap();
+        let document = |format: DocFormat, extension: &str| {
+            let input = DocInput {
+                input_path: folder.join("circuit.circom"),
+                output_path: folder.clone(),
+                format,
+                prime: "bn128".to_string(),
+                link_libraries: vec![],
+            };
+            write_documentation(&input).unwrap();
+            let contents = std::fs::read_to_string(folder.join(format!("circuit.{}", extension))).unwrap();
+            contents.replace(&format!("{}/", folder.display()), "")
+        };
+        let markdown = document(DocFormat::Markdown, "md");
+        let html = document(DocFormat::Html, "html");
+        std::fs::remove_dir_all(&folder).unwrap();
+        // the comments that are not right before a definition are not documentation
+        assert_eq!(
+            markdown,
+            "# circuit.circom
+
+## `circuit.circom`
+
+### template `Move(n)`
+
+Moves a point.
+
+Inputs:
+
+| Name | Type | Tags |
+| --- | --- | --- |
+| `p` | lib.Point |  |
+
+Outputs:
+
+| Name | Type | Tags |
+| --- | --- | --- |
+| `q[2]` | lib.Point |  |
+
+## `lib.circom`
+
+### bus `lib.Point()`
+
+A point of the curve.
+
+Fields:
+
+| Name | Type | Tags |
+| --- | --- | --- |
+| `x` | signal |  |
+| `y` | signal |  |
+
+### template `lib.Num2Bits(n)`
+
+Binary decomposition of a number.
+
+The bits are given in little endian.
+
+Inputs:
+
+| Name | Type | Tags |
+| --- | --- | --- |
+| `in` | signal |  |
+
+Outputs:
+
+| Name | Type | Tags |
+| --- | --- | --- |
+| `out[n]` | signal | binary |
+
+### function `lib.double(x)`
+"
+        );
+        assert!(html.contains(
+            "<h3 id=\"lib.Num2Bits\">template <code>lib.Num2Bits(n)</code></h3>
+<p>Binary decomposition of a number.</p>
+<p>The bits are given in little endian.</p>
+"
+        ));
+    }
+}
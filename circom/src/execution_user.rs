@@ -2,7 +2,9 @@ use ansi_term::Colour;
 use compiler::hir::very_concrete_program::VCP;
 use constraint_writers::debug_writer::DebugWriter;
 use constraint_writers::ConstraintExporter;
+use constraint_generation::ExecutionBudgets;
 use program_structure::program_archive::ProgramArchive;
+use program_structure::progress::{Phase, PhaseTimer};
 
 
 pub struct ExecutionConfig {
@@ -10,6 +12,12 @@ pub struct ExecutionConfig {
     pub sym: String,
     pub json_constraints: String,
     pub json_substitutions: String,
+    pub hierarchy: Option<String>,
+    pub hierarchy_dot: Option<String>,
+    pub simplification_report: Option<String>,
+    pub substitution_map: String,
+    pub json_substitution_map: String,
+    pub hierarchy_depth: usize,
     pub no_rounds: usize,
     pub flag_s: bool,
     pub flag_f: bool,
@@ -17,11 +25,15 @@ pub struct ExecutionConfig {
     pub flag_old_heuristics:bool,
     pub flag_verbose: bool,
     pub inspect_constraints_flag: bool,
+    pub stream_flag: bool,
     pub sym_flag: bool,
     pub r1cs_flag: bool,
     pub json_substitution_flag: bool,
     pub json_constraint_flag: bool,
+    pub substitution_map_flag: bool,
     pub prime: String,
+    pub budgets: ExecutionBudgets,
+    pub threads: usize,
 }
 
 pub fn execute_project(
@@ -39,11 +51,16 @@ pub fn execute_project(
         flag_p: config.flag_p,
         flag_verbose: config.flag_verbose,
         inspect_constraints: config.inspect_constraints_flag,
+        stream_flag: config.stream_flag,
+        keep_substitutions: config.simplification_report.is_some() || config.substitution_map_flag,
         flag_old_heuristics: config.flag_old_heuristics,
         prime : config.prime,
+        budgets: config.budgets,
+        threads: config.threads,
     };
     let custom_gates = program_archive.custom_gates;
     let (exporter, vcp) = build_circuit(program_archive, build_config)?;
+    let timer = PhaseTimer::start(Phase::ConstraintOutputs);
     if config.r1cs_flag {
         generate_output_r1cs(&config.r1cs, exporter.as_ref(), custom_gates)?;
     }
@@ -53,9 +70,62 @@ pub fn execute_project(
     if config.json_constraint_flag {
         generate_json_constraints(&debug, exporter.as_ref())?;
     }
+    if config.hierarchy.is_some() || config.hierarchy_dot.is_some() {
+        let depth = config.hierarchy_depth;
+        generate_output_hierarchy(&config.hierarchy, &config.hierarchy_dot, depth, exporter.as_ref())?;
+    }
+    if let Some(file) = &config.simplification_report {
+        use constraint_writers::simplification_writer::write_json;
+        write_output_file(file, write_json(&exporter.simplification_report(), file))?;
+    }
+    if config.substitution_map_flag {
+        generate_output_substitution_map(&config.substitution_map, &config.json_substitution_map, exporter.as_ref())?;
+    }
+    timer.finish();
     Result::Ok(vcp)
 }
 
+fn generate_output_hierarchy(
+    json_file: &Option<String>,
+    dot_file: &Option<String>,
+    depth: usize,
+    exporter: &dyn ConstraintExporter,
+) -> Result<(), ()> {
+    use constraint_writers::hierarchy_writer::{write_dot, write_json};
+    let root = exporter.hierarchy();
+    if let Some(file) = json_file {
+        write_output_file(file, write_json(&root, file))?;
+    }
+    if let Some(file) = dot_file {
+        write_output_file(file, write_dot(&root, file, depth))?;
+    }
+    Result::Ok(())
+}
+
+fn generate_output_substitution_map(
+    file: &str,
+    json_file: &str,
+    exporter: &dyn ConstraintExporter,
+) -> Result<(), ()> {
+    use constraint_writers::substitution_map_writer::{write_binary, write_json};
+    let map = exporter.substitution_map();
+    write_output_file(file, write_binary(&map, file))?;
+    write_output_file(json_file, write_json(&map, json_file))
+}
+
+fn write_output_file(file: &str, result: std::io::Result<()>) -> Result<(), ()> {
+    match result {
+        Result::Ok(()) => {
+            println!("{} {}", Colour::Green.paint("Written successfully:"), file);
+            Result::Ok(())
+        }
+        Result::Err(error) => {
+            eprintln!("{}", Colour::Red.paint(format!("Could not write the output in {}: {}", file, error)));
+            Result::Err(())
+        }
+    }
+}
+
 fn generate_output_r1cs(file: &str, exporter: &dyn ConstraintExporter, custom_gates: bool) -> Result<(), ()> {
     if let Result::Ok(()) = exporter.r1cs(file, custom_gates) {
         println!("{} {}", Colour::Green.paint("Written successfully:"), file);
@@ -88,3 +158,353 @@ fn generate_json_constraints(
         Result::Err(())
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use program_structure::constants::UsefulConstants;
+    use std::path::Path;
+
+    pub const CIRCUIT: &str = "pragma circom 2.1.0;
+template Square() {
+    signal input in;
+    signal output out;
+    out <== in * in;
+}
+template Power(n) {
+    signal input in;
+    signal output out;
+    component squares[n];
+    signal acc[n + 1];
+    acc[0] <== in;
+    for (var i = 0; i < n; i++) {
+        squares[i] = Square();
+        squares[i].in <== acc[i];
+        acc[i + 1] <== squares[i].out;
+    }
+    out <== acc[n];
+}
+template Bits(n) {
+    signal input in;
+    signal output {binary} out[n];
+    var sum = 0;
+    for (var i = 0; i < n; i++) {
+        out[i] <-- (in >> i) & 1;
+        out[i] * (out[i] - 1) === 0;
+        sum += out[i] * 2 ** i;
+    }
+    sum === in;
+}
+template Branch(n) {
+    signal input in;
+    signal output out;
+    component power = Power(n);
+    power.in <== in;
+    signal bits[8] <== Bits(8)(power.out);
+    signal squared <== Power(2)(in);
+    out <== bits[1] + squared * bits[0];
+}
+template Main(branches) {
+    signal input in;
+    signal output out[branches];
+    signal squares[branches];
+    component branch[branches];
+    for (var i = 0; i < branches; i++) {
+        branch[i] = Branch(i + 3);
+        branch[i].in <== in + i;
+        squares[i] <== Square()(branch[i].out);
+        out[i] <== Power(i % 2 + 1)(squares[i]);
+    }
+}
+component main = Main(6);
+";
+
+    // Parses and analyses the circuit.circom of folder and gives the configuration that writes
+    // the r1cs, sym and json files of the circuit in folder, named after run, without simplification
+    pub fn program_and_config(folder: &Path, run: &str) -> (ProgramArchive, ExecutionConfig) {
+        let prime = UsefulConstants::new(&"bn128".to_string()).get_p().clone();
+        let file = folder.join("circuit.circom").to_str().unwrap().to_string();
+        let (mut program, _) = parser::run_parser(file, crate::VERSION, vec![], &prime).ok().unwrap();
+        crate::type_analysis_user::analyse_project(&mut program, false, false).unwrap();
+        let output = |name: &str| folder.join(format!("{}_{}", run, name)).to_str().unwrap().to_string();
+        let config = ExecutionConfig {
+            r1cs: output("circuit.r1cs"),
+            sym: output("circuit.sym"),
+            json_constraints: output("constraints.json"),
+            json_substitutions: output("substitutions.json"),
+            hierarchy: Option::None,
+            hierarchy_dot: Option::None,
+            simplification_report: Option::None,
+            substitution_map: output("circuit.smap"),
+            json_substitution_map: output("smap.json"),
+            hierarchy_depth: 3,
+            no_rounds: usize::MAX,
+            flag_s: false,
+            flag_f: true,
+            flag_p: false,
+            flag_old_heuristics: false,
+            flag_verbose: false,
+            inspect_constraints_flag: false,
+            stream_flag: false,
+            sym_flag: true,
+            r1cs_flag: true,
+            json_substitution_flag: false,
+            json_constraint_flag: true,
+            substitution_map_flag: false,
+            prime: "bn128".to_string(),
+            budgets: ExecutionBudgets::default(),
+            threads: 1,
+        };
+        (program, config)
+    }
+
+    // Generates the constraints of the circuit in folder with the given threads, simplified
+    // with --O2 unless flag_f, and returns the r1cs, sym and json files, named after run
+    fn outputs(folder: &Path, run: &str, threads: usize, flag_f: bool) -> Vec<Vec<u8>> {
+        let (program, mut config) = program_and_config(folder, run);
+        config.threads = threads;
+        config.flag_f = flag_f;
+        let output = |name: &str| folder.join(format!("{}_{}", run, name)).to_str().unwrap().to_string();
+        execute_project(program, config).unwrap();
+        ["circuit.r1cs", "circuit.sym", "constraints.json"].iter().map(|name| std::fs::read(output(name)).unwrap()).collect()
+    }
+
+    #[test]
+    fn threads_do_not_change_the_outputs() {
+        let folder = std::env::temp_dir().join(format!("circom_threads_{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("circuit.circom"), CIRCUIT).unwrap();
+        let sequential = outputs(&folder, "sequential", 1, true);
+        let parallel = outputs(&folder, "parallel", 4, true);
+        std::fs::remove_dir_all(&folder).unwrap();
+        assert!(sequential == parallel);
+    }
+
+    const LINEAR_CIRCUIT: &str = "pragma circom 2.1.0;
+template Mix(t) {
+    signal input in[t];
+    signal output out[t];
+    signal squares[t];
+    signal mixed[t];
+    for (var i = 0; i < t; i++) {
+        squares[i] <== in[i] * in[i];
+    }
+    for (var i = 0; i < t; i++) {
+        var acc = 0;
+        for (var j = 0; j < t; j++) {
+            acc += ((i + 1) * (j + 3) % 11 + 1) * squares[j];
+        }
+        mixed[i] <== acc + i;
+    }
+    for (var i = 0; i < t; i++) {
+        var acc = 0;
+        for (var j = 0; j < t; j++) {
+            acc += ((i + 2) * (j + 5) % 7 + 1) * mixed[j];
+        }
+        out[i] <== acc;
+    }
+}
+template Main(t, rounds) {
+    signal input in[t];
+    signal output out;
+    component mix[rounds];
+    for (var r = 0; r < rounds; r++) {
+        mix[r] = Mix(t);
+        for (var i = 0; i < t; i++) {
+            mix[r].in[i] <== r == 0 ? in[i] : mix[r - 1].out[i] + mix[r - 1].in[i];
+        }
+    }
+    var acc = 0;
+    for (var i = 0; i < t; i++) {
+        acc += mix[rounds - 1].out[i];
+    }
+    out <== acc;
+}
+component main = Main(8, 6);
+";
+
+    // The hash maps of every compilation are seeded differently, so an order taken from one of
+    // them shows up as a difference between the two outputs
+    #[test]
+    fn compilations_give_the_same_outputs() {
+        let folder = std::env::temp_dir().join(format!("circom_reproducible_{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("circuit.circom"), LINEAR_CIRCUIT).unwrap();
+        let first = outputs(&folder, "first", 1, false);
+        let second = outputs(&folder, "second", 1, false);
+        std::fs::remove_dir_all(&folder).unwrap();
+        assert!(first == second);
+    }
+
+    // The first round finds that x is 3, which makes the product of the second one linear
+    const ROUNDS_CIRCUIT: &str = "pragma circom 2.1.0;
+template Main() {
+    signal input in;
+    signal output out;
+    signal x;
+    signal y;
+    x <-- 3;
+    y <-- 2;
+    x + y === 5;
+    x - y === 1;
+    out <== x * in;
+}
+component main = Main();
+";
+
+    const REPORT_CIRCUIT: &str = "pragma circom 2.1.0;
+template Mult() {
+    signal input a;
+    signal input b;
+    signal output c;
+    c <== a * b;
+}
+template Main(n) {
+    signal input xs[n];
+    signal input k;
+    signal output out[n];
+    component m[n];
+    for (var i = 0; i < n; i++) {
+        m[i] = Mult();
+        m[i].a <== xs[i];
+        m[i].b <== k;
+        out[i] <== m[i].c;
+    }
+    k === 3;
+}
+component main = Main(2);
+";
+
+    // The report of the circuit of the documentation of the simplification report, simplified
+    // with --O1, or with --O2 when the redundant constraint is added
+    fn simplification_report(folder: &Path, run: &str, redundant: bool) -> serde_json::Value {
+        let circuit = if redundant {
+            REPORT_CIRCUIT.replace("    k === 3;", "    k === 3;\n    2 * m[0].c === 2 * xs[0] * k;")
+        } else {
+            REPORT_CIRCUIT.to_string()
+        };
+        std::fs::write(folder.join("circuit.circom"), circuit).unwrap();
+        let (program, mut config) = program_and_config(folder, run);
+        let report = folder.join(format!("{}_report.json", run));
+        config.flag_f = false;
+        config.flag_s = !redundant;
+        config.simplification_report = Option::Some(report.to_str().unwrap().to_string());
+        execute_project(program, config).unwrap();
+        serde_json::from_slice(&std::fs::read(&report).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn simplification_report_tells_how_every_signal_was_removed() {
+        use serde_json::json;
+        let folder = std::env::temp_dir().join(format!("circom_report_{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        let o1 = simplification_report(&folder, "o1", false);
+        let o2 = simplification_report(&folder, "o2", true);
+        std::fs::remove_dir_all(&folder).unwrap();
+        let equality = |signal: &str, replaced_by: &str| {
+            json!({"signal": signal, "template": "Mult", "rule": "equality", "round": null, "replaced_by": replaced_by})
+        };
+        assert_eq!(
+            o1,
+            json!({
+                "eliminated": [
+                    {"signal": "main.k", "template": "Main", "rule": "constant equality", "round": null, "replaced_by": "3"},
+                    equality("main.m[0].c", "main.out[0]"),
+                    equality("main.m[0].a", "main.xs[0]"),
+                    equality("main.m[0].b", "main.k"),
+                    equality("main.m[1].c", "main.out[1]"),
+                    equality("main.m[1].a", "main.xs[1]"),
+                    equality("main.m[1].b", "main.k")
+                ],
+                "templates": [
+                    {"template": "Main", "signals": 5, "eliminated": 1, "rules": {"constant equality": 1, "equality": 0, "linear": 0, "unused": 0}},
+                    {"template": "Mult", "signals": 6, "eliminated": 6, "rules": {"constant equality": 0, "equality": 6, "linear": 0, "unused": 0}}
+                ],
+                "linear_rounds": []
+            })
+        );
+        assert_eq!(
+            o2["linear_rounds"],
+            json!([
+                {"round": 1, "constraints": 0, "rank": 0, "redundant": 0},
+                {"round": 2, "constraints": 3, "rank": 2, "redundant": 1}
+            ])
+        );
+        // the Gauss elimination only removes signals in the second round
+        let linear: Vec<_> =
+            o2["eliminated"].as_array().unwrap().iter().filter(|signal| signal["rule"] == "linear").collect();
+        assert!(!linear.is_empty() && linear.iter().all(|signal| signal["round"] == 2));
+    }
+
+    #[test]
+    fn progress_rounds_are_the_rounds_of_the_report() {
+        use program_structure::progress::{self, ProgressEvent};
+        use std::sync::{Arc, Mutex};
+        let folder = std::env::temp_dir().join(format!("circom_rounds_{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("circuit.circom"), ROUNDS_CIRCUIT).unwrap();
+        let (program, mut config) = program_and_config(&folder, "rounds");
+        let report = folder.join("report.json");
+        config.flag_f = false;
+        config.simplification_report = Option::Some(report.to_str().unwrap().to_string());
+        // the simplification runs in this thread, the events of other tests are left out
+        let rounds = Arc::new(Mutex::new(Vec::new()));
+        let (events, test_thread) = (Arc::clone(&rounds), std::thread::current().id());
+        progress::set_reporter(Box::new(move |event| {
+            if let ProgressEvent::SimplificationRound { round, linear, .. } = event {
+                if std::thread::current().id() == test_thread {
+                    events.lock().unwrap().push((*round, *linear));
+                }
+            }
+        }));
+        execute_project(program, config).unwrap();
+        progress::remove_reporter();
+        let report: serde_json::Value = serde_json::from_slice(&std::fs::read(&report).unwrap()).unwrap();
+        std::fs::remove_dir_all(&folder).unwrap();
+        let linear_rounds: Vec<_> = report["linear_rounds"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|round| (round["round"].as_u64().unwrap() as usize, round["constraints"].as_u64().unwrap() as usize))
+            .collect();
+        assert!(linear_rounds.len() > 1);
+        assert_eq!(*rounds.lock().unwrap(), linear_rounds);
+    }
+
+    // The instances of the hierarchy are the components of the DAG, with the constraints generated
+    // by each of them when the circuit is not simplified
+    #[test]
+    fn hierarchy_follows_the_components_of_the_dag() {
+        use dag::Tree;
+        use serde_json::Value;
+        fn check(tree: &Tree, instance: &Value) {
+            assert_eq!(instance["path"], tree.path.as_str());
+            assert_eq!(instance["signal_offset"], tree.offset);
+            assert_eq!(instance["constraints"], tree.constraints().count());
+            let edges = Tree::get_edges(tree);
+            let subcomponents = instance["subcomponents"].as_array().unwrap();
+            assert_eq!(subcomponents.len(), edges.len());
+            let mut total = tree.constraints().count();
+            for (edge, subcomponent) in edges.iter().zip(subcomponents) {
+                check(&Tree::go_to_subtree(tree, edge), subcomponent);
+                total += subcomponent["total_constraints"].as_u64().unwrap() as usize;
+            }
+            assert_eq!(instance["total_constraints"], total);
+        }
+        let folder = std::env::temp_dir().join(format!("circom_hierarchy_{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("circuit.circom"), CIRCUIT).unwrap();
+        let (program, mut config) = program_and_config(&folder, "hierarchy");
+        let hierarchy = folder.join("hierarchy.json");
+        config.hierarchy = Option::Some(hierarchy.to_str().unwrap().to_string());
+        execute_project(program, config).unwrap();
+        let contents = std::fs::read_to_string(&hierarchy).unwrap();
+        let (program, _) = program_and_config(&folder, "dag");
+        std::fs::remove_dir_all(&folder).unwrap();
+        let (dag, _) = constraint_generation::build_unsimplified_circuit(program, &"bn128".to_string()).ok().unwrap();
+        let root: Value = serde_json::from_str(&contents).unwrap();
+        // Main(6) with its branches, their powers and bits, and the squares
+        assert!(root["subcomponents"].as_array().unwrap().len() > 6);
+        check(&Tree::new(&dag), &root);
+    }
+}
@@ -0,0 +1,475 @@
+use super::input_user::ExplainInput;
+use crate::VERSION;
+use ansi_term::Colour;
+use circom_algebra::algebra::{ArithmeticExpression, Substitution};
+use circom_algebra::num_bigint::BigInt;
+use constraint_generation::build_unsimplified_circuit;
+use constraint_list::ConstraintList;
+use constraint_writers::r1cs_reader::read_r1cs;
+use constraint_writers::substitution_map_writer::read_substitution_map;
+use constraint_writers::sym_writer::read_sym_file;
+use dag::{ConstraintLocation, SimplificationFlags, Tree, DAG};
+use program_structure::constants::UsefulConstants;
+use program_structure::error_definition::Report;
+use program_structure::file_definition::FileLibrary;
+use std::collections::HashMap;
+use std::path::Path;
+
+type LinearCombination = HashMap<usize, BigInt>;
+type C = (LinearCombination, LinearCombination, LinearCombination);
+type S = Substitution<usize>;
+
+// Constraints generated by the execution of the circuit, before any simplification
+struct Generated {
+    constraint: C,
+    location: ConstraintLocation,
+    component: String,
+}
+
+// Constraints generated by the circuit, only known when it is compiled from its source
+struct Source {
+    generated: Vec<Generated>,
+    file_library: FileLibrary,
+}
+
+// Constraints written in the r1cs file, with the witness position of every remaining signal.
+// The signals are identified by their labels, the numbers of the .sym file.
+struct Final {
+    field: BigInt,
+    names: HashMap<usize, String>,
+    constraints: Vec<C>,
+    witness: HashMap<usize, usize>,
+    // not known for the builds without a substitution map
+    substitutions: Option<HashMap<usize, S>>,
+    simplified: bool,
+}
+
+// The input is a circuit, compiled with the given options, or a build: a .r1cs file, or a
+// folder with a single one, together with the .sym file and, if it was written, the .smap
+// file of the same name
+pub fn explain(input: &ExplainInput) -> Result<(), ()> {
+    let (source, circuit) = if input.input_path.extension().is_some_and(|e| e == "circom") {
+        let (source, circuit) = compile(input)?;
+        (Some(source), circuit)
+    } else {
+        let r1cs = super::diff_user::find_r1cs(&input.input_path)?;
+        (None, read_build(&r1cs)?)
+    };
+    let printer = Printer { names: &circuit.names, field: &circuit.field };
+    if let Some(signal) = &input.signal {
+        let name = if signal == "main" || signal.starts_with("main.") {
+            signal.clone()
+        } else {
+            format!("main.{}", signal)
+        };
+        let label = match circuit.names.iter().find(|(_, n)| **n == name) {
+            Some((label, _)) => *label,
+            None => {
+                eprintln!("{}", Colour::Red.paint(format!("The circuit has no signal {}", name)));
+                return Result::Err(());
+            }
+        };
+        println!("{} {}", Colour::Green.paint("Signal"), printer.name(label));
+        print!("{}", explain_signal(label, source.as_ref(), &circuit, &printer));
+    }
+    if let Some(index) = input.constraint {
+        match circuit.constraints.get(index) {
+            Some(constraint) => {
+                println!("{} {}", Colour::Green.paint("Constraint"), index);
+                print!("{}", explain_constraint(constraint, &circuit, &printer));
+            }
+            None => {
+                let message = format!(
+                    "The circuit has {} constraints, there is no constraint {}",
+                    circuit.constraints.len(),
+                    index
+                );
+                eprintln!("{}", Colour::Red.paint(message));
+                return Result::Err(());
+            }
+        }
+    }
+    Result::Ok(())
+}
+
+fn compile(input: &ExplainInput) -> Result<(Source, Final), ()> {
+    let field = UsefulConstants::new(&input.prime()).get_p().clone();
+    let file = input.input_path.to_str().unwrap().to_string();
+    let mut program = match parser::run_parser(file, VERSION, input.get_link_libraries().to_vec(), &field) {
+        Result::Err((file_library, reports)) => {
+            Report::print_reports(&reports, &file_library);
+            return Result::Err(());
+        }
+        Result::Ok((program, warnings)) => {
+            Report::print_reports(&warnings, &program.file_library);
+            program
+        }
+    };
+    super::type_analysis_user::analyse_project(&mut program, false, false)?;
+    let file_library = program.file_library.clone();
+    let dag = match build_unsimplified_circuit(program, &input.prime()) {
+        Result::Ok((dag, _)) => dag,
+        Result::Err(reports) => {
+            Report::print_reports(&reports, &file_library);
+            return Result::Err(());
+        }
+    };
+    let mut names = HashMap::new();
+    let mut generated = Vec::new();
+    collect(&Tree::new(&dag), &mut names, &mut generated).map_err(|error| eprintln!("{}", error))?;
+    let circuit = if input.no_simplification {
+        Final {
+            field,
+            constraints: generated.iter().map(|g| g.constraint.clone()).collect(),
+            witness: names.keys().map(|signal| (*signal, *signal)).collect(),
+            names,
+            substitutions: Some(HashMap::new()),
+            simplified: false,
+        }
+    } else {
+        let list = simplify(dag, input.reduced_simplification, input.no_rounds, input.prime())?;
+        let constraints = list
+            .constraints
+            .get_ids()
+            .into_iter()
+            .map(|id| list.constraints.read_constraint(id).unwrap())
+            .map(|c| (c.a().clone(), c.b().clone(), c.c().clone()))
+            .collect();
+        let substitutions = list.substitutions.into_iter().map(|s| (*s.substitution.from(), s.substitution)).collect();
+        Final { field, names, constraints, witness: list.signal_map, substitutions: Some(substitutions), simplified: true }
+    };
+    Result::Ok((Source { generated, file_library }, circuit))
+}
+
+// The constraints and the substitution map of a build are written with the wires of the
+// witness, they are read back with the labels of the signals
+fn read_build(r1cs: &Path) -> Result<Final, ()> {
+    let sym = r1cs.with_extension("sym");
+    let smap = r1cs.with_extension("smap");
+    let data = read_r1cs(r1cs.to_str().unwrap()).map_err(|err| {
+        eprintln!("{}", Colour::Red.paint(format!("Could not read the r1cs file {}: {}", r1cs.display(), err)))
+    })?;
+    let elems = read_sym_file(sym.to_str().unwrap()).map_err(|err| {
+        eprintln!("{}", Colour::Red.paint(format!("Could not read the sym file {}: {}", sym.display(), err)))
+    })?;
+    let wire_to_label = &data.wire_to_label;
+    let labels = |lc: LinearCombination| -> LinearCombination {
+        lc.into_iter().map(|(wire, value)| (wire_to_label[wire], value)).collect()
+    };
+    let substitutions = if smap.is_file() {
+        let map = read_substitution_map(smap.to_str().unwrap()).map_err(|err| {
+            eprintln!("{}", Colour::Red.paint(format!("Could not read the substitution map {}: {}", smap.display(), err)))
+        })?;
+        let mut substitutions = HashMap::new();
+        for signal in map.signals {
+            let label = signal.label;
+            let coefficients = labels(signal.terms.into_iter().collect());
+            substitutions.extend(S::new(label, ArithmeticExpression::Linear { coefficients }).map(|s| (label, s)));
+        }
+        Some(substitutions)
+    } else {
+        None
+    };
+    let names = elems.into_iter().map(|elem| (elem.original as usize, elem.symbol)).collect();
+    let witness = wire_to_label.iter().enumerate().map(|(wire, label)| (*label, wire)).collect();
+    let constraints = data.constraints.into_iter().map(|(a, b, c)| (labels(a), labels(b), labels(c))).collect();
+    Result::Ok(Final { field: data.field, names, constraints, witness, substitutions, simplified: true })
+}
+
+fn collect(tree: &Tree, names: &mut HashMap<usize, String>, generated: &mut Vec<Generated>) -> std::io::Result<()> {
+    // the names of the main component are not shifted, its offset is 0
+    for (signal, name) in &tree.id_to_name {
+        names.insert(*signal, format!("{}.{}", tree.path, name));
+    }
+    for located in tree.located_constraints() {
+        let (constraint, location) = located?;
+        let component = tree.path.clone();
+        let constraint = (constraint.a().clone(), constraint.b().clone(), constraint.c().clone());
+        generated.push(Generated { constraint, location: location.clone(), component });
+    }
+    for edge in Tree::get_edges(tree) {
+        let subtree = Tree::go_to_subtree(tree, edge);
+        collect(&subtree, names, generated)?;
+    }
+    Result::Ok(())
+}
+
+// Simplification of the circuit as in the compilation, keeping the substitutions applied
+pub fn simplify(dag: DAG, reduced_simplification: bool, no_rounds: usize, prime: String) -> Result<ConstraintList, ()> {
+    let flags = SimplificationFlags {
+        flag_s: reduced_simplification,
+        parallel_flag: false,
+        port_substitution: false,
+        json_substitutions: String::new(),
+        keep_substitutions: true,
+        stream_flag: false,
+        no_rounds,
+        flag_old_heuristics: false,
+        prime,
+    };
+    DAG::map_to_list(dag, flags).map_err(|error| eprintln!("{}", error))
+}
+
+fn explain_signal(label: usize, source: Option<&Source>, circuit: &Final, printer: &Printer) -> String {
+    let mut out = String::new();
+    if let Some(source) = source {
+        out.push_str("\nGenerated constraints:\n");
+        let mut found = false;
+        for g in source.generated.iter().filter(|g| involves(&g.constraint, label)) {
+            let (file_id, location) = &g.location;
+            let path = source.file_library.get_path(*file_id).unwrap_or_default();
+            let line = source.file_library.get_line(location.start, *file_id).unwrap_or(0);
+            out.push_str(&format!("  {}\n", printer.constraint(&g.constraint)));
+            out.push_str(&format!("      in {} at {}:{}\n", g.component, path, line));
+            found = true;
+        }
+        if !found {
+            out.push_str("  none\n");
+        }
+    }
+    if !circuit.simplified {
+        out.push_str(&format!("\nThe constraints are not simplified, the signal is in position {} of the witness\n", label));
+    } else if let Some(position) = circuit.witness.get(&label) {
+        out.push_str(&format!("\nThe signal is in position {} of the witness\n", position));
+    } else {
+        match &circuit.substitutions {
+            Some(substitutions) if substitutions.contains_key(&label) => {
+                let value = resolve(label, substitutions, printer.field);
+                out.push_str("\nThe signal is removed by the simplification, it is replaced by\n");
+                out.push_str(&format!("  {} = {}\n", printer.name(label), printer.linear(value.to())));
+            }
+            Some(_) => out.push_str("\nThe signal is removed by the simplification, it is not used by any constraint\n"),
+            None => out.push_str(
+                "\nThe signal is removed by the simplification, the build has no substitution map to know its value\n",
+            ),
+        }
+    }
+    out.push_str("\nConstraints of the circuit:\n");
+    let mut found = false;
+    for (index, constraint) in circuit.constraints.iter().enumerate() {
+        if involves(constraint, label) {
+            out.push_str(&format!("  [{}] {}\n", index, printer.constraint(constraint)));
+            found = true;
+        }
+    }
+    if !found {
+        out.push_str("  none\n");
+    }
+    out
+}
+
+fn explain_constraint(constraint: &C, circuit: &Final, printer: &Printer) -> String {
+    let mut out = format!("  {}\n", printer.constraint(constraint));
+    out.push_str("\nSignals:\n");
+    let (a, b, c) = constraint;
+    let mut signals: Vec<_> = a.keys().chain(b.keys()).chain(c.keys()).filter(|s| **s != 0).cloned().collect();
+    signals.sort_unstable();
+    signals.dedup();
+    for signal in signals {
+        let position = circuit.witness.get(&signal).map_or("?".to_string(), |p| p.to_string());
+        out.push_str(&format!("  {} (witness position {})\n", printer.name(signal), position));
+    }
+    out
+}
+
+fn involves((a, b, c): &C, label: usize) -> bool {
+    a.contains_key(&label) || b.contains_key(&label) || c.contains_key(&label)
+}
+
+// The substitutions are found in several rounds: the value of a signal may use signals
+// that are replaced afterwards, they are applied until only remaining signals are left
+fn resolve(label: usize, substitutions: &HashMap<usize, S>, field: &BigInt) -> S {
+    let mut value = substitutions[&label].clone();
+    for _ in 0..substitutions.len() {
+        let pending: Vec<_> = value.to().keys().filter(|s| substitutions.contains_key(s)).cloned().collect();
+        if pending.is_empty() {
+            break;
+        }
+        for signal in pending {
+            S::apply_substitution(&mut value, &substitutions[&signal], field);
+        }
+    }
+    value
+}
+
+struct Printer<'a> {
+    names: &'a HashMap<usize, String>,
+    field: &'a BigInt,
+}
+
+impl Printer<'_> {
+    fn name(&self, signal: usize) -> String {
+        self.names.get(&signal).cloned().unwrap_or_else(|| format!("signal_{}", signal))
+    }
+
+    // The coefficients greater than p/2 are shown as negative numbers
+    fn coefficient(&self, value: &BigInt) -> BigInt {
+        if value * 2 > *self.field {
+            value - self.field
+        } else {
+            value.clone()
+        }
+    }
+
+    fn linear(&self, lc: &HashMap<usize, BigInt>) -> String {
+        let mut terms: Vec<_> = lc.iter().filter(|(_, v)| **v != BigInt::from(0)).collect();
+        // the constant is written at the end
+        terms.sort_by_key(|(s, _)| (**s == 0, **s));
+        let mut out = String::new();
+        for (signal, value) in terms {
+            let value = self.coefficient(value);
+            let negative = value < BigInt::from(0);
+            let magnitude = if negative { -value } else { value };
+            if out.is_empty() {
+                out.push_str(if negative { "-" } else { "" });
+            } else {
+                out.push_str(if negative { " - " } else { " + " });
+            }
+            if *signal == 0 {
+                out.push_str(&magnitude.to_string());
+            } else if magnitude == BigInt::from(1) {
+                out.push_str(&self.name(*signal));
+            } else {
+                out.push_str(&format!("{}*{}", magnitude, self.name(*signal)));
+            }
+        }
+        if out.is_empty() {
+            "0".to_string()
+        } else {
+            out
+        }
+    }
+
+    // A * B - C = 0 is shown as (A) * (B) = C, and the linear constraints as C = 0
+    fn constraint(&self, (a, b, c): &C) -> String {
+        if a.is_empty() && b.is_empty() {
+            format!("{} = 0", self.linear(c))
+        } else {
+            format!("({}) * ({}) = {}", self.linear(a), self.linear(b), self.linear(c))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CIRCUIT: &str = "pragma circom 2.0.0;
+template Double() {
+    signal input in;
+    signal output out;
+    out <== 2 * in;
+}
+template Main() {
+    signal input a;
+    signal input b;
+    signal output c;
+    component d = Double();
+    d.in <== a;
+    c <== d.out * b;
+}
+component main = Main();
+";
+
+    #[test]
+    fn signals_are_explained_before_and_after_the_simplification() {
+        let folder = std::env::temp_dir().join(format!("circom_explain_{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("circuit.circom"), CIRCUIT).unwrap();
+        let input = ExplainInput {
+            input_path: folder.join("circuit.circom"),
+            signal: None,
+            constraint: None,
+            no_simplification: false,
+            reduced_simplification: false,
+            no_rounds: usize::MAX,
+            prime: "bn128".to_string(),
+            link_libraries: vec![],
+        };
+        let (source, circuit) = compile(&input).unwrap();
+        let printer = Printer { names: &circuit.names, field: &circuit.field };
+        let label = |name: &str| *circuit.names.iter().find(|(_, n)| *n == name).unwrap().0;
+        let explanation = explain_signal(label("main.d.out"), Some(&source), &circuit, &printer);
+        let constraint = explain_constraint(&circuit.constraints[0], &circuit, &printer);
+        std::fs::remove_dir_all(&folder).unwrap();
+        // the value of main.d.out is given by main.d.in, which is replaced by main.a
+        assert_eq!(
+            explanation.replace(&format!("{}/", folder.display()), ""),
+            "
+Generated constraints:
+  (-main.d.out) * (main.b) = -main.c
+      in main at circuit.circom:13
+  -main.d.out + 2*main.d.in = 0
+      in main.d at circuit.circom:5
+
+The signal is removed by the simplification, it is replaced by
+  main.d.out = 2*main.a
+
+Constraints of the circuit:
+  none
+"
+        );
+        assert_eq!(
+            constraint,
+            "  (-2*main.a) * (main.b) = -main.c
+
+Signals:
+  main.c (witness position 1)
+  main.a (witness position 2)
+  main.b (witness position 3)
+"
+        );
+    }
+
+    #[test]
+    fn builds_are_explained_from_their_files() {
+        use crate::execution_user::execute_project;
+        use crate::execution_user::tests::program_and_config;
+        let folder = std::env::temp_dir().join(format!("circom_explain_build_{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("circuit.circom"), CIRCUIT).unwrap();
+        let (program, mut config) = program_and_config(&folder, "build");
+        config.flag_f = false;
+        config.substitution_map_flag = true;
+        let r1cs = config.r1cs.clone();
+        execute_project(program, config).unwrap();
+        let explanations = |circuit: &Final| {
+            let printer = Printer { names: &circuit.names, field: &circuit.field };
+            let label = |name: &str| *circuit.names.iter().find(|(_, n)| *n == name).unwrap().0;
+            ["main.d.out", "main.b"].map(|name| explain_signal(label(name), None, circuit, &printer))
+        };
+        let with_map = explanations(&read_build(Path::new(&r1cs)).unwrap());
+        std::fs::remove_file(Path::new(&r1cs).with_extension("smap")).unwrap();
+        let without_map = explanations(&read_build(Path::new(&r1cs)).unwrap());
+        std::fs::remove_dir_all(&folder).unwrap();
+        assert_eq!(
+            with_map[0],
+            "
+The signal is removed by the simplification, it is replaced by
+  main.d.out = 2*main.a
+
+Constraints of the circuit:
+  none
+"
+        );
+        assert_eq!(
+            with_map[1],
+            "
+The signal is in position 3 of the witness
+
+Constraints of the circuit:
+  [0] (-2*main.a) * (main.b) = -main.c
+"
+        );
+        assert_eq!(
+            without_map[0],
+            "
+The signal is removed by the simplification, the build has no substitution map to know its value
+
+Constraints of the circuit:
+  none
+"
+        );
+        assert_eq!(with_map[1], without_map[1]);
+    }
+}
@@ -1,10 +1,13 @@
 use std::path::PathBuf;
+use constraint_generation::ExecutionBudgets;
 
 pub struct Input {
     pub input_program: PathBuf,
     pub out_r1cs: PathBuf,
     pub out_json_constraints: PathBuf,
     pub out_json_substitutions: PathBuf,
+    pub out_substitution_map: PathBuf,
+    pub out_json_substitution_map: PathBuf,
     pub out_wat_code: PathBuf,
     pub out_wasm_code: PathBuf,
     pub out_wasm_name: String,
@@ -14,6 +17,12 @@ pub struct Input {
     pub out_c_code: PathBuf,
     pub out_c_dat: PathBuf,
     pub out_sym: PathBuf,
+    pub out_hierarchy: Option<PathBuf>,
+    pub out_hierarchy_dot: Option<PathBuf>,
+    pub out_simplification_report: Option<PathBuf>,
+    pub out_manifest: Option<PathBuf>,
+    pub out_progress_json: Option<PathBuf>,
+    pub hierarchy_depth: usize,
     //pub field: &'static str,
     pub c_flag: bool,
     pub wasm_flag: bool,
@@ -22,6 +31,7 @@ pub struct Input {
     pub sym_flag: bool,
     pub json_constraint_flag: bool,
     pub json_substitution_flag: bool,
+    pub substitution_map_flag: bool,
     pub main_inputs_flag: bool,
     pub print_ir_flag: bool,
     pub fast_flag: bool,
@@ -29,6 +39,12 @@ pub struct Input {
     pub parallel_simplification_flag: bool,
     pub flag_old_heuristics: bool,
     pub inspect_constraints_flag: bool,
+    pub stream_flag: bool,
+    pub check_tags_flag: bool,
+    pub r1cs_metadata_flag: bool,
+    pub reproducible_flag: bool,
+    pub budgets: ExecutionBudgets,
+    pub threads: usize,
     pub no_rounds: usize,
     pub flag_verbose: bool,
     pub prime: String,
@@ -44,18 +60,241 @@ const JS: &'static str = "js";
 const DAT: &'static str = "dat";
 const SYM: &'static str = "sym";
 const JSON: &'static str = "json";
+const SMAP: &str = "smap";
 
 
+// Tests of the circuits found in a file or in a folder, run with `circom test`
+pub struct TestInput {
+    pub input_path: PathBuf,
+    pub filter: Option<String>,
+    pub prime: String,
+    pub link_libraries: Vec<PathBuf>,
+}
+
+// Execution of the main component of a file step by step, run with `circom debug`
+pub struct DebugInput {
+    pub input_path: PathBuf,
+    pub inputs_path: Option<PathBuf>,
+    pub breakpoints: Vec<String>,
+    pub dap_flag: bool,
+    pub port: Option<u16>,
+    pub prime: String,
+    pub link_libraries: Vec<PathBuf>,
+}
+
+// Documentation of the definitions of a file and of the files it includes, run with `circom doc`
+pub struct DocInput {
+    pub input_path: PathBuf,
+    pub output_path: PathBuf,
+    pub format: DocFormat,
+    pub prime: String,
+    pub link_libraries: Vec<PathBuf>,
+}
+
+// Constraints of a signal before and after the simplification, of a circuit or of a build of
+// it, run with `circom explain`
+pub struct ExplainInput {
+    pub input_path: PathBuf,
+    pub signal: Option<String>,
+    pub constraint: Option<usize>,
+    pub no_simplification: bool,
+    pub reduced_simplification: bool,
+    pub no_rounds: usize,
+    pub prime: String,
+    pub link_libraries: Vec<PathBuf>,
+}
+
+// Comparison of two circuits or of two builds of them, run with `circom diff`
+pub struct DiffInput {
+    pub old_path: PathBuf,
+    pub new_path: PathBuf,
+    pub json_path: Option<PathBuf>,
+    pub constraints_flag: bool,
+    pub no_simplification: bool,
+    pub reduced_simplification: bool,
+    pub no_rounds: usize,
+    pub prime: String,
+    pub link_libraries: Vec<PathBuf>,
+}
+
+// Values of every signal of a build computed from its witness, run with `circom reconstruct`
+pub struct ReconstructInput {
+    pub witness_path: PathBuf,
+    pub map_path: PathBuf,
+    pub sym_path: PathBuf,
+    pub json_path: Option<PathBuf>,
+}
+
+#[derive(Copy, Clone)]
+pub enum DocFormat {
+    Markdown,
+    Html,
+}
+
+pub enum Command {
+    Compile(Box<Input>),
+    Test(TestInput),
+    Debug(DebugInput),
+    Doc(DocInput),
+    Explain(ExplainInput),
+    Diff(DiffInput),
+    Reconstruct(ReconstructInput),
+}
+
+pub fn read_command() -> Result<Command, ()> {
+    let matches = input_processing::view();
+    if let Some(test_matches) = matches.subcommand_matches("test") {
+        Result::Ok(Command::Test(TestInput::new(test_matches)?))
+    } else if let Some(debug_matches) = matches.subcommand_matches("debug") {
+        Result::Ok(Command::Debug(DebugInput::new(debug_matches)?))
+    } else if let Some(doc_matches) = matches.subcommand_matches("doc") {
+        Result::Ok(Command::Doc(DocInput::new(doc_matches)?))
+    } else if let Some(explain_matches) = matches.subcommand_matches("explain") {
+        Result::Ok(Command::Explain(ExplainInput::new(explain_matches)?))
+    } else if let Some(diff_matches) = matches.subcommand_matches("diff") {
+        Result::Ok(Command::Diff(DiffInput::new(diff_matches)?))
+    } else if let Some(reconstruct_matches) = matches.subcommand_matches("reconstruct") {
+        Result::Ok(Command::Reconstruct(ReconstructInput::new(reconstruct_matches)?))
+    } else {
+        Result::Ok(Command::Compile(Box::new(Input::new(&matches)?)))
+    }
+}
+
+impl TestInput {
+    fn new(matches: &clap::ArgMatches) -> Result<TestInput, ()> {
+        Result::Ok(TestInput {
+            input_path: input_processing::get_test_input(matches)?,
+            filter: matches.value_of("filter").map(|f| f.to_string()),
+            prime: input_processing::get_prime(matches)?,
+            link_libraries: input_processing::get_link_libraries(matches),
+        })
+    }
+
+    pub fn get_link_libraries(&self) -> &Vec<PathBuf> {
+        &self.link_libraries
+    }
+    pub fn prime(&self) -> String {
+        self.prime.clone()
+    }
+}
+
+impl DebugInput {
+    fn new(matches: &clap::ArgMatches) -> Result<DebugInput, ()> {
+        let breakpoints = match matches.values_of("breakpoints") {
+            Some(values) => values.map(|b| b.to_string()).collect(),
+            None => vec![],
+        };
+        Result::Ok(DebugInput {
+            input_path: input_processing::get_input(matches)?,
+            inputs_path: input_processing::get_inputs_file(matches)?,
+            breakpoints,
+            dap_flag: matches.is_present("dap") || matches.is_present("port"),
+            port: input_processing::get_port(matches)?,
+            prime: input_processing::get_prime(matches)?,
+            link_libraries: input_processing::get_link_libraries(matches),
+        })
+    }
+
+    pub fn get_link_libraries(&self) -> &Vec<PathBuf> {
+        &self.link_libraries
+    }
+    pub fn prime(&self) -> String {
+        self.prime.clone()
+    }
+}
+
+impl DocInput {
+    fn new(matches: &clap::ArgMatches) -> Result<DocInput, ()> {
+        let format = match matches.value_of("format") {
+            Some("html") => DocFormat::Html,
+            _ => DocFormat::Markdown,
+        };
+        Result::Ok(DocInput {
+            input_path: input_processing::get_input(matches)?,
+            output_path: input_processing::get_output_path(matches)?,
+            format,
+            prime: input_processing::get_prime(matches)?,
+            link_libraries: input_processing::get_link_libraries(matches),
+        })
+    }
+
+    pub fn get_link_libraries(&self) -> &Vec<PathBuf> {
+        &self.link_libraries
+    }
+    pub fn prime(&self) -> String {
+        self.prime.clone()
+    }
+}
+
+impl ExplainInput {
+    fn new(matches: &clap::ArgMatches) -> Result<ExplainInput, ()> {
+        use input_processing::SimplificationStyle;
+        let o_style = input_processing::get_simplification_style(matches)?;
+        Result::Ok(ExplainInput {
+            input_path: input_processing::get_existing_path(matches, "input")?,
+            signal: matches.value_of("signal").map(|s| s.to_string()),
+            constraint: input_processing::get_constraint_index(matches)?,
+            no_simplification: o_style == SimplificationStyle::O0,
+            reduced_simplification: o_style == SimplificationStyle::O1,
+            no_rounds: if let SimplificationStyle::O2(r) = o_style { r } else { 0 },
+            prime: input_processing::get_prime(matches)?,
+            link_libraries: input_processing::get_link_libraries(matches),
+        })
+    }
+
+    pub fn get_link_libraries(&self) -> &Vec<PathBuf> {
+        &self.link_libraries
+    }
+    pub fn prime(&self) -> String {
+        self.prime.clone()
+    }
+}
+
+impl DiffInput {
+    fn new(matches: &clap::ArgMatches) -> Result<DiffInput, ()> {
+        use input_processing::SimplificationStyle;
+        let o_style = input_processing::get_simplification_style(matches)?;
+        Result::Ok(DiffInput {
+            old_path: input_processing::get_existing_path(matches, "old")?,
+            new_path: input_processing::get_existing_path(matches, "new")?,
+            json_path: matches.value_of("json").map(PathBuf::from),
+            constraints_flag: matches.is_present("constraints"),
+            no_simplification: o_style == SimplificationStyle::O0,
+            reduced_simplification: o_style == SimplificationStyle::O1,
+            no_rounds: if let SimplificationStyle::O2(r) = o_style { r } else { 0 },
+            prime: input_processing::get_prime(matches)?,
+            link_libraries: input_processing::get_link_libraries(matches),
+        })
+    }
+
+    pub fn get_link_libraries(&self) -> &Vec<PathBuf> {
+        &self.link_libraries
+    }
+    pub fn prime(&self) -> String {
+        self.prime.clone()
+    }
+}
+
+impl ReconstructInput {
+    fn new(matches: &clap::ArgMatches) -> Result<ReconstructInput, ()> {
+        Result::Ok(ReconstructInput {
+            witness_path: input_processing::get_existing_path(matches, "witness")?,
+            map_path: input_processing::get_existing_path(matches, "map")?,
+            sym_path: input_processing::get_existing_path(matches, "sym")?,
+            json_path: matches.value_of("json").map(PathBuf::from),
+        })
+    }
+}
+
 impl Input {
-    pub fn new() -> Result<Input, ()> {
+    fn new(matches: &clap::ArgMatches) -> Result<Input, ()> {
         use ansi_term::Colour;
         use input_processing::SimplificationStyle;
-        let matches = input_processing::view();
-        let input = input_processing::get_input(&matches)?;
+        let input = input_processing::get_input(matches)?;
         let mut file_name = input.file_stem().unwrap().to_str().unwrap().to_string();
-        let output_path = input_processing::get_output_path(&matches)?;
+        let output_path = input_processing::get_output_path(matches)?;
 
-        let c_flag = input_processing::get_c(&matches);
+        let c_flag = input_processing::get_c(matches);
 
         if c_flag && (file_name == "main" || file_name == "fr" || file_name == "calcwit"){
             println!("{}", Colour::Yellow.paint(format!("The name {} is reserved in Circom when using de --c flag. The files generated for your circuit will use the name {}_c instead of {}.", file_name, file_name, file_name)));
@@ -63,8 +302,8 @@ impl Input {
         };
         let output_c_path = Input::build_folder(&output_path, &file_name, CPP);
         let output_js_path = Input::build_folder(&output_path, &file_name, JS);
-        let o_style = input_processing::get_simplification_style(&matches)?;
-        let link_libraries = input_processing::get_link_libraries(&matches);
+        let o_style = input_processing::get_simplification_style(matches)?;
+        let link_libraries = input_processing::get_link_libraries(matches);
         Result::Ok(Input {
             //field: P_BN128,
             input_program: input,
@@ -78,6 +317,12 @@ impl Input {
             out_c_code: Input::build_output(&output_c_path, &file_name, CPP),
             out_c_dat: Input::build_output(&output_c_path, &file_name, DAT),
             out_sym: Input::build_output(&output_path, &file_name, SYM),
+            out_hierarchy: matches.value_of("hierarchy").map(PathBuf::from),
+            out_hierarchy_dot: matches.value_of("hierarchy_dot").map(PathBuf::from),
+            out_simplification_report: matches.value_of("simplification_report").map(PathBuf::from),
+            out_manifest: matches.value_of("manifest").map(PathBuf::from),
+            out_progress_json: matches.value_of("progress_json").map(PathBuf::from),
+            hierarchy_depth: input_processing::get_hierarchy_depth(matches)?,
             out_json_constraints: Input::build_output(
                 &output_path,
                 &format!("{}_constraints", file_name),
@@ -88,23 +333,36 @@ impl Input {
                 &format!("{}_substitutions", file_name),
                 JSON,
             ),
-            wat_flag:input_processing::get_wat(&matches),
-            wasm_flag: input_processing::get_wasm(&matches),
+            out_substitution_map: Input::build_output(&output_path, &file_name, SMAP),
+            out_json_substitution_map: Input::build_output(
+                &output_path,
+                &format!("{}_smap", file_name),
+                JSON,
+            ),
+            wat_flag:input_processing::get_wat(matches),
+            wasm_flag: input_processing::get_wasm(matches),
             c_flag: c_flag,
-            r1cs_flag: input_processing::get_r1cs(&matches),
-            sym_flag: input_processing::get_sym(&matches),
-            main_inputs_flag: input_processing::get_main_inputs_log(&matches),
-            json_constraint_flag: input_processing::get_json_constraints(&matches),
-            json_substitution_flag: input_processing::get_json_substitutions(&matches),
-            print_ir_flag: input_processing::get_ir(&matches),
+            r1cs_flag: input_processing::get_r1cs(matches),
+            sym_flag: input_processing::get_sym(matches),
+            main_inputs_flag: input_processing::get_main_inputs_log(matches),
+            json_constraint_flag: input_processing::get_json_constraints(matches),
+            json_substitution_flag: input_processing::get_json_substitutions(matches),
+            substitution_map_flag: matches.is_present("substitution_map"),
+            print_ir_flag: input_processing::get_ir(matches),
             no_rounds: if let SimplificationStyle::O2(r) = o_style { r } else { 0 },
             fast_flag: o_style == SimplificationStyle::O0,
             reduced_simplification_flag: o_style == SimplificationStyle::O1,
-            parallel_simplification_flag: input_processing::get_parallel_simplification(&matches),
-            inspect_constraints_flag: input_processing::get_inspect_constraints(&matches),
-            flag_old_heuristics: input_processing::get_flag_old_heuristics(&matches),
-            flag_verbose: input_processing::get_flag_verbose(&matches), 
-            prime: input_processing::get_prime(&matches)?,
+            parallel_simplification_flag: input_processing::get_parallel_simplification(matches),
+            inspect_constraints_flag: input_processing::get_inspect_constraints(matches),
+            stream_flag: input_processing::get_stream(matches, o_style)?,
+            check_tags_flag: input_processing::get_check_tags(matches),
+            r1cs_metadata_flag: matches.is_present("r1cs_metadata"),
+            reproducible_flag: matches.is_present("reproducible"),
+            budgets: input_processing::get_budgets(matches)?,
+            threads: input_processing::get_threads(matches)?,
+            flag_old_heuristics: input_processing::get_flag_old_heuristics(matches),
+            flag_verbose: input_processing::get_flag_verbose(matches), 
+            prime: input_processing::get_prime(matches)?,
             link_libraries
         })
     }
@@ -167,6 +425,30 @@ impl Input {
     pub fn json_substitutions_file(&self) -> &str {
         self.out_json_substitutions.to_str().unwrap()
     }
+    pub fn substitution_map_file(&self) -> &str {
+        self.out_substitution_map.to_str().unwrap()
+    }
+    pub fn json_substitution_map_file(&self) -> &str {
+        self.out_json_substitution_map.to_str().unwrap()
+    }
+    pub fn hierarchy_file(&self) -> Option<String> {
+        self.out_hierarchy.as_ref().map(|file| file.to_str().unwrap().to_string())
+    }
+    pub fn hierarchy_dot_file(&self) -> Option<String> {
+        self.out_hierarchy_dot.as_ref().map(|file| file.to_str().unwrap().to_string())
+    }
+    pub fn simplification_report_file(&self) -> Option<String> {
+        self.out_simplification_report.as_ref().map(|file| file.to_str().unwrap().to_string())
+    }
+    pub fn manifest_file(&self) -> Option<String> {
+        self.out_manifest.as_ref().map(|file| file.to_str().unwrap().to_string())
+    }
+    pub fn progress_json_file(&self) -> Option<String> {
+        self.out_progress_json.as_ref().map(|file| file.to_str().unwrap().to_string())
+    }
+    pub fn hierarchy_depth(&self) -> usize {
+        self.hierarchy_depth
+    }
     pub fn wasm_flag(&self) -> bool {
         self.wasm_flag
     }
@@ -188,6 +470,9 @@ impl Input {
     pub fn json_substitutions_flag(&self) -> bool {
         self.json_substitution_flag
     }
+    pub fn substitution_map_flag(&self) -> bool {
+        self.substitution_map_flag
+    }
     pub fn main_inputs_flag(&self) -> bool {
         self.main_inputs_flag
     }
@@ -200,9 +485,27 @@ impl Input {
     pub fn inspect_constraints_flag(&self) -> bool {
         self.inspect_constraints_flag
     }
+    pub fn stream_flag(&self) -> bool {
+        self.stream_flag
+    }
     pub fn flag_verbose(&self) -> bool {
         self.flag_verbose
     }
+    pub fn check_tags_flag(&self) -> bool {
+        self.check_tags_flag
+    }
+    pub fn r1cs_metadata_flag(&self) -> bool {
+        self.r1cs_metadata_flag
+    }
+    pub fn reproducible_flag(&self) -> bool {
+        self.reproducible_flag
+    }
+    pub fn budgets(&self) -> ExecutionBudgets {
+        self.budgets
+    }
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
     pub fn reduced_simplification_flag(&self) -> bool {
         self.reduced_simplification_flag
     }
@@ -221,9 +524,10 @@ impl Input {
 }
 mod input_processing {
     use ansi_term::Colour;
-    use clap::{App, Arg, ArgMatches};
+    use clap::{App, Arg, ArgGroup, ArgMatches, SubCommand};
     use std::path::{Path, PathBuf};
     use crate::VERSION;
+    use constraint_generation::ExecutionBudgets;
 
     pub fn get_input(matches: &ArgMatches) -> Result<PathBuf, ()> {
         let route = Path::new(matches.value_of("input").unwrap()).to_path_buf();
@@ -235,6 +539,106 @@ mod input_processing {
         }
     }
 
+    pub fn get_test_input(matches: &ArgMatches) -> Result<PathBuf, ()> {
+        let route = Path::new(matches.value_of("input").unwrap()).to_path_buf();
+        if route.exists() {
+            Result::Ok(route)
+        } else {
+            let route = if route.to_str().is_some() { ": ".to_owned() + route.to_str().unwrap()} else { "".to_owned() };
+            eprintln!("{}", Colour::Red.paint("Input file or folder does not exist".to_owned() + &route));
+            Result::Err(())
+        }
+    }
+
+    pub fn get_inputs_file(matches: &ArgMatches) -> Result<Option<PathBuf>, ()> {
+        match matches.value_of("inputs") {
+            Some(route) if Path::new(route).is_file() => Result::Ok(Some(Path::new(route).to_path_buf())),
+            Some(route) => {
+                eprintln!("{}", Colour::Red.paint(format!("Input values file does not exist: {}", route)));
+                Result::Err(())
+            }
+            None => Result::Ok(None),
+        }
+    }
+
+    pub fn get_port(matches: &ArgMatches) -> Result<Option<u16>, ()> {
+        match matches.value_of("port") {
+            Some(port) => match port.parse() {
+                Result::Ok(port) => Result::Ok(Some(port)),
+                Result::Err(_) => {
+                    eprintln!("{}", Colour::Red.paint(format!("invalid port: {}", port)));
+                    Result::Err(())
+                }
+            },
+            None => Result::Ok(None),
+        }
+    }
+
+    // A budget of 0 removes the limit
+    pub fn get_budgets(matches: &ArgMatches) -> Result<ExecutionBudgets, ()> {
+        let get_budget = |name: &str, default: Option<usize>| match matches.value_of(name) {
+            Some(value) => match value.parse::<usize>() {
+                Result::Ok(0) => Result::Ok(None),
+                Result::Ok(budget) => Result::Ok(Some(budget)),
+                Result::Err(_) => {
+                    eprintln!("{}", Colour::Red.paint(format!("invalid value of --{}: {}", name, value)));
+                    Result::Err(())
+                }
+            },
+            None => Result::Ok(default),
+        };
+        let default = ExecutionBudgets::default();
+        Result::Ok(ExecutionBudgets {
+            max_iterations: get_budget("max-iterations", default.max_iterations)?,
+            max_recursion_depth: get_budget("max-recursion-depth", default.max_recursion_depth)?,
+            max_instances: get_budget("max-instances", default.max_instances)?,
+            max_constraints: get_budget("max-constraints", default.max_constraints)?,
+        })
+    }
+
+    // 0 stands for one thread per core
+    pub fn get_threads(matches: &ArgMatches) -> Result<usize, ()> {
+        let value = matches.value_of("threads").unwrap();
+        match value.parse::<usize>() {
+            Result::Ok(0) => Result::Ok(std::thread::available_parallelism().map_or(1, |n| n.get())),
+            Result::Ok(threads) => Result::Ok(threads),
+            Result::Err(_) => {
+                eprintln!("{}", Colour::Red.paint(format!("invalid value of --threads: {}", value)));
+                Result::Err(())
+            }
+        }
+    }
+
+    pub fn get_hierarchy_depth(matches: &ArgMatches) -> Result<usize, ()> {
+        let value = matches.value_of("hierarchy_depth").unwrap();
+        value.parse::<usize>().map_err(|_| {
+            eprintln!("{}", Colour::Red.paint(format!("invalid value of --hierarchy-depth: {}", value)))
+        })
+    }
+
+    pub fn get_existing_path(matches: &ArgMatches, name: &str) -> Result<PathBuf, ()> {
+        let path = PathBuf::from(matches.value_of(name).unwrap());
+        if path.exists() {
+            Result::Ok(path)
+        } else {
+            eprintln!("{}", Colour::Red.paint(format!("{} does not exist", path.display())));
+            Result::Err(())
+        }
+    }
+
+    pub fn get_constraint_index(matches: &ArgMatches) -> Result<Option<usize>, ()> {
+        match matches.value_of("constraint") {
+            Some(index) => match index.parse::<usize>() {
+                Result::Ok(index) => Result::Ok(Some(index)),
+                Result::Err(_) => {
+                    eprintln!("{}", Colour::Red.paint("invalid constraint index"));
+                    Result::Err(())
+                }
+            },
+            None => Result::Ok(None),
+        }
+    }
+
     pub fn get_output_path(matches: &ArgMatches) -> Result<PathBuf, ()> {
         let route = Path::new(matches.value_of("output").unwrap()).to_path_buf();
         if route.is_dir() {
@@ -268,6 +672,17 @@ mod input_processing {
         }
     }
 
+    // The linear rounds of --O2 need every constraint at hand, so there is nothing to stream
+    pub fn get_stream(matches: &ArgMatches, o_style: SimplificationStyle) -> Result<bool, ()> {
+        let stream = matches.is_present("stream");
+        if let (true, SimplificationStyle::O2(_)) = (stream, o_style) {
+            eprintln!("{}", Colour::Red.paint("--stream can only be used with --O0 or --O1"));
+            Result::Err(())
+        } else {
+            Result::Ok(stream)
+        }
+    }
+
     pub fn get_json_constraints(matches: &ArgMatches) -> bool {
         matches.is_present("print_json_c")
     }
@@ -311,6 +726,10 @@ mod input_processing {
         matches.is_present("inspect_constraints")
     }
 
+    pub fn get_check_tags(matches: &ArgMatches) -> bool {
+        matches.is_present("check_tags")
+    }
+
     pub fn get_flag_verbose(matches: &ArgMatches) -> bool {
         matches.is_present("flag_verbose")
     }
@@ -344,6 +763,11 @@ mod input_processing {
     }
 
     pub fn view() -> ArgMatches<'static> {
+        app().get_matches()
+    }
+
+    // Flags are written in kebab-case, the old snake_case ones are kept as aliases
+    pub fn app() -> App<'static, 'static> {
         App::new("circom compiler")
             .version(VERSION)
             .author("IDEN3")
@@ -386,6 +810,99 @@ mod input_processing {
                     .help("Maximum number of rounds of the simplification process")
                     .display_order(500)
             )
+            .arg(
+                Arg::with_name("stream")
+                    .long("stream")
+                    .takes_value(false)
+                    .display_order(510)
+                    .help("Keeps the generated constraints in temporary files instead of in memory, only with --O0 or --O1")
+            )
+            .arg(
+                Arg::with_name("max-iterations")
+                    .long("max-iterations")
+                    .takes_value(true)
+                    .display_order(810)
+                    .help("Maximum number of iterations of a loop during the constraint generation, 0 for no limit [default: 10000000]"),
+            )
+            .arg(
+                Arg::with_name("max-recursion-depth")
+                    .long("max-recursion-depth")
+                    .takes_value(true)
+                    .display_order(811)
+                    .help("Maximum number of nested calls during the constraint generation, 0 for no limit [default: 10000]"),
+            )
+            .arg(
+                Arg::with_name("max-instances")
+                    .long("max-instances")
+                    .takes_value(true)
+                    .display_order(812)
+                    .help("Maximum number of template instances, 0 for no limit [default: 0]"),
+            )
+            .arg(
+                Arg::with_name("max-constraints")
+                    .long("max-constraints")
+                    .takes_value(true)
+                    .display_order(813)
+                    .help("Maximum number of constraints generated before the simplification, 0 for no limit [default: 0]"),
+            )
+            .arg(
+                Arg::with_name("threads")
+                    .long("threads")
+                    .takes_value(true)
+                    .default_value("1")
+                    .display_order(820)
+                    .help("Number of threads used to generate the constraints, 0 for one per core"),
+            )
+            .arg(
+                Arg::with_name("hierarchy")
+                    .long("hierarchy")
+                    .takes_value(true)
+                    .display_order(130)
+                    .help("Outputs the tree of template instances in json format in the given file"),
+            )
+            .arg(
+                Arg::with_name("hierarchy_dot")
+                    .long("hierarchy-dot")
+                    .takes_value(true)
+                    .display_order(131)
+                    .help("Outputs the tree of template instances in Graphviz DOT format in the given file"),
+            )
+            .arg(
+                Arg::with_name("simplification_report")
+                    .long("simplification-report")
+                    .takes_value(true)
+                    .display_order(985)
+                    .help("Outputs in json format the signals removed by the simplification and why, in the given file"),
+            )
+            .arg(
+                Arg::with_name("substitution_map")
+                    .long("substitution-map")
+                    .takes_value(false)
+                    .display_order(986)
+                    .help("Outputs in binary and json format the linear combination of the witness equal to every signal removed by the simplification"),
+            )
+            .arg(
+                Arg::with_name("manifest")
+                    .long("manifest")
+                    .takes_value(true)
+                    .display_order(990)
+                    .help("Outputs in json format the compiler, prime, flags and hashes of the sources and outputs of the build, in the given file"),
+            )
+            .arg(
+                Arg::with_name("reproducible")
+                    .long("reproducible")
+                    .takes_value(false)
+                    .display_order(991)
+                    .help("Records the build metadata without absolute paths nor the command line, so it is the same on every machine"),
+            )
+            .arg(
+                Arg::with_name("hierarchy_depth")
+                    .long("hierarchy-depth")
+                    .takes_value(true)
+                    .default_value("3")
+                    .display_order(132)
+                    .help("Depth of the instances shown in the DOT graph, 0 for the main component only"),
+            )
             .arg(
                 Arg::with_name("output")
                     .short("o")
@@ -417,9 +934,17 @@ mod input_processing {
                     .display_order(801)
                     .help("Does an additional check over the constraints produced"),
             )
+            .arg(
+                Arg::with_name("check_tags")
+                    .long("check-tags")
+                    .takes_value(false)
+                    .display_order(802)
+                    .help("Checks the values of the tagged main inputs and signals assigned with <-- in the witness generator"),
+            )
             .arg(
                 Arg::with_name("print_json_sub")
-                    .long("simplification_substitution")
+                    .long("simplification-substitution")
+                    .alias("simplification_substitution")
                     .takes_value(false)
                     .display_order(980)
                     .help("Outputs the substitution applied in the simplification phase in json format"),
@@ -438,6 +963,14 @@ mod input_processing {
                     .display_order(30)
                     .help("Outputs the constraints in r1cs format"),
             )
+            .arg(
+                Arg::with_name("r1cs_metadata")
+                    .long("r1cs-metadata")
+                    .takes_value(false)
+                    .requires("print_r1cs")
+                    .display_order(31)
+                    .help("Adds to the r1cs file a section with the compiler, prime, flags and hashes of the sources"),
+            )
             .arg(
                 Arg::with_name("print_wasm")
                     .long("wasm")
@@ -490,11 +1023,19 @@ mod input_processing {
                     .long("verbose")
                     .takes_value(false)
                     .display_order(800)
-                    .help("Shows logs during compilation"),
+                    .help("Shows the progress, time and peak memory of every phase of the compilation"),
+            )
+            .arg(
+                Arg::with_name("progress_json")
+                    .long("progress-json")
+                    .takes_value(true)
+                    .display_order(803)
+                    .help("Writes the progress events of the compilation as json lines in the given file, or in the standard error with -"),
             )
             .arg(
                 Arg::with_name("flag_old_heuristics")
-                    .long("use_old_simplification_heuristics")
+                    .long("use-old-simplification-heuristics")
+                    .alias("use_old_simplification_heuristics")
                     .takes_value(false)
                     .display_order(980)
                     .help("Applies the old version of the heuristics when performing linear simplification"),
@@ -508,7 +1049,285 @@ mod input_processing {
                     .display_order(300)
                     .help("To choose the prime number to use to generate the circuit. Receives the name of the curve (bn128, bls12381, goldilocks, grumpkin, pallas, vesta, secq256r1)"),
             )
-            .get_matches()
+            .subcommand(
+                SubCommand::with_name("test")
+                    .about("Runs the tests declared in a circuit file or in the circuit files of a folder")
+                    .arg(
+                        Arg::with_name("input")
+                            .multiple(false)
+                            .default_value(".")
+                            .help("Path to a file or to a folder with files containing tests"),
+                    )
+                    .arg(
+                        Arg::with_name("filter")
+                            .long("filter")
+                            .takes_value(true)
+                            .help("Only runs the tests whose name contains the given string"),
+                    )
+                    .arg(
+                        Arg::with_name("link_libraries")
+                            .short("l")
+                            .takes_value(true)
+                            .multiple(true)
+                            .number_of_values(1)
+                            .help("Adds directory to library search path"),
+                    )
+                    .arg(
+                        Arg::with_name("prime")
+                            .long("prime")
+                            .takes_value(true)
+                            .default_value("bn128")
+                            .help("To choose the prime number to use to run the tests. Receives the name of the curve (bn128, bls12381, goldilocks, grumpkin, pallas, vesta, secq256r1)"),
+                    ),
+            )
+            .subcommand(
+                SubCommand::with_name("debug")
+                    .about("Computes the witness of the main component of a circuit step by step")
+                    .arg(
+                        Arg::with_name("input")
+                            .multiple(false)
+                            .required(true)
+                            .help("Path to a circuit with a main component"),
+                    )
+                    .arg(
+                        Arg::with_name("inputs")
+                            .long("inputs")
+                            .takes_value(true)
+                            .help("Json file with the values of the inputs of the main component"),
+                    )
+                    .arg(
+                        Arg::with_name("breakpoints")
+                            .long("break")
+                            .takes_value(true)
+                            .multiple(true)
+                            .number_of_values(1)
+                            .help("Stops the execution at a line, at a file:line or when a template or function starts"),
+                    )
+                    .arg(
+                        Arg::with_name("dap")
+                            .long("dap")
+                            .takes_value(false)
+                            .help("Serves the Debug Adapter Protocol through the standard input and output instead of using the command line"),
+                    )
+                    .arg(
+                        Arg::with_name("port")
+                            .long("port")
+                            .takes_value(true)
+                            .help("Serves the Debug Adapter Protocol in the given local port"),
+                    )
+                    .arg(
+                        Arg::with_name("link_libraries")
+                            .short("l")
+                            .takes_value(true)
+                            .multiple(true)
+                            .number_of_values(1)
+                            .help("Adds directory to library search path"),
+                    )
+                    .arg(
+                        Arg::with_name("prime")
+                            .long("prime")
+                            .takes_value(true)
+                            .default_value("bn128")
+                            .help("To choose the prime number to use to run the circuit. Receives the name of the curve (bn128, bls12381, goldilocks, grumpkin, pallas, vesta, secq256r1)"),
+                    ),
+            )
+            .subcommand(
+                SubCommand::with_name("diff")
+                    .about("Compares the public interface, the signals and the constraints of two circuits, given as circuit files or as folders or .r1cs files of their builds")
+                    .arg(
+                        Arg::with_name("old")
+                            .required(true)
+                            .help("Circuit file, or .r1cs file or folder of a build with its .sym file"),
+                    )
+                    .arg(
+                        Arg::with_name("new")
+                            .required(true)
+                            .help("Circuit file, or .r1cs file or folder of a build with its .sym file"),
+                    )
+                    .arg(
+                        Arg::with_name("constraints")
+                            .long("constraints")
+                            .takes_value(false)
+                            .help("Compares the constraints of every component instance in a canonical form"),
+                    )
+                    .arg(
+                        Arg::with_name("json")
+                            .long("json")
+                            .takes_value(true)
+                            .help("Writes the differences in the given json file"),
+                    )
+                    .arg(
+                        Arg::with_name("no_simplification")
+                            .long("O0")
+                            .takes_value(false)
+                            .help("No simplification is applied to the circuit files"),
+                    )
+                    .arg(
+                        Arg::with_name("reduced_simplification")
+                            .long("O1")
+                            .takes_value(false)
+                            .help("Only applies signal to signal and signal to constant simplification to the circuit files. This is the default option"),
+                    )
+                    .arg(
+                        Arg::with_name("full_simplification")
+                            .long("O2")
+                            .takes_value(false)
+                            .help("Full constraint simplification of the circuit files"),
+                    )
+                    .arg(
+                        Arg::with_name("simplification_rounds")
+                            .long("O2round")
+                            .takes_value(true)
+                            .help("Maximum number of rounds of the simplification process"),
+                    )
+                    .arg(
+                        Arg::with_name("link_libraries")
+                            .short("l")
+                            .takes_value(true)
+                            .multiple(true)
+                            .number_of_values(1)
+                            .help("Adds directory to library search path"),
+                    )
+                    .arg(
+                        Arg::with_name("prime")
+                            .long("prime")
+                            .takes_value(true)
+                            .default_value("bn128")
+                            .help("To choose the prime number to use to generate the circuit files. Receives the name of the curve (bn128, bls12381, goldilocks, grumpkin, pallas, vesta, secq256r1)"),
+                    ),
+            )
+            .subcommand(
+                SubCommand::with_name("reconstruct")
+                    .about("Computes the value of every signal of a circuit, including the ones removed by the simplification, from a witness of its build")
+                    .arg(
+                        Arg::with_name("witness")
+                            .required(true)
+                            .help("Path to a .wtns file computed by the witness generator of the build"),
+                    )
+                    .arg(
+                        Arg::with_name("map")
+                            .long("map")
+                            .takes_value(true)
+                            .required(true)
+                            .help("Path to the .smap file of the build, written with --substitution-map"),
+                    )
+                    .arg(
+                        Arg::with_name("sym")
+                            .long("sym")
+                            .takes_value(true)
+                            .required(true)
+                            .help("Path to the .sym file of the build"),
+                    )
+                    .arg(
+                        Arg::with_name("json")
+                            .long("json")
+                            .takes_value(true)
+                            .help("Writes the values in the given json file instead of showing them"),
+                    ),
+            )
+            .subcommand(
+                SubCommand::with_name("explain")
+                    .about("Shows the constraints of a signal before and after the simplification, or a constraint of the simplified circuit with the names of its signals")
+                    .arg(
+                        Arg::with_name("input")
+                            .multiple(false)
+                            .required(true)
+                            .help("Path to a circuit with a main component, or to the .r1cs file of a build, or a folder with it, together with its .sym and .smap files"),
+                    )
+                    .arg(
+                        Arg::with_name("signal")
+                            .long("signal")
+                            .takes_value(true)
+                            .help("Name of the signal, as in the .sym file, like main.c.in[0]"),
+                    )
+                    .arg(
+                        Arg::with_name("constraint")
+                            .long("constraint")
+                            .takes_value(true)
+                            .help("Index of a constraint of the simplified circuit, as in the .r1cs file"),
+                    )
+                    .group(ArgGroup::with_name("target").args(&["signal", "constraint"]).multiple(true).required(true))
+                    .arg(
+                        Arg::with_name("no_simplification")
+                            .long("O0")
+                            .takes_value(false)
+                            .help("No simplification is applied"),
+                    )
+                    .arg(
+                        Arg::with_name("reduced_simplification")
+                            .long("O1")
+                            .takes_value(false)
+                            .help("Only applies signal to signal and signal to constant simplification. This is the default option"),
+                    )
+                    .arg(
+                        Arg::with_name("full_simplification")
+                            .long("O2")
+                            .takes_value(false)
+                            .help("Full constraint simplification"),
+                    )
+                    .arg(
+                        Arg::with_name("simplification_rounds")
+                            .long("O2round")
+                            .takes_value(true)
+                            .help("Maximum number of rounds of the simplification process"),
+                    )
+                    .arg(
+                        Arg::with_name("link_libraries")
+                            .short("l")
+                            .takes_value(true)
+                            .multiple(true)
+                            .number_of_values(1)
+                            .help("Adds directory to library search path"),
+                    )
+                    .arg(
+                        Arg::with_name("prime")
+                            .long("prime")
+                            .takes_value(true)
+                            .default_value("bn128")
+                            .help("To choose the prime number to use to generate the circuit. Receives the name of the curve (bn128, bls12381, goldilocks, grumpkin, pallas, vesta, secq256r1)"),
+                    ),
+            )
+            .subcommand(
+                SubCommand::with_name("doc")
+                    .about("Writes the documentation of the templates, functions and buses of a circuit file and of the files it includes")
+                    .arg(
+                        Arg::with_name("input")
+                            .multiple(false)
+                            .required(true)
+                            .help("Path to a circuit file"),
+                    )
+                    .arg(
+                        Arg::with_name("output")
+                            .short("o")
+                            .long("output")
+                            .takes_value(true)
+                            .default_value(".")
+                            .help("Path to the directory where the documentation will be written"),
+                    )
+                    .arg(
+                        Arg::with_name("format")
+                            .long("format")
+                            .takes_value(true)
+                            .possible_values(&["markdown", "html"])
+                            .default_value("markdown")
+                            .help("Format of the documentation"),
+                    )
+                    .arg(
+                        Arg::with_name("link_libraries")
+                            .short("l")
+                            .takes_value(true)
+                            .multiple(true)
+                            .number_of_values(1)
+                            .help("Adds directory to library search path"),
+                    )
+                    .arg(
+                        Arg::with_name("prime")
+                            .long("prime")
+                            .takes_value(true)
+                            .default_value("bn128")
+                            .help("To choose the prime number to use to parse the circuit. Receives the name of the curve (bn128, bls12381, goldilocks, grumpkin, pallas, vesta, secq256r1)"),
+                    ),
+            )
     }
 
     pub fn get_link_libraries(matches: &ArgMatches) -> Vec<PathBuf> {
@@ -522,3 +1341,49 @@ mod input_processing {
         link_libraries
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<clap::ArgMatches<'static>, clap::Error> {
+        input_processing::app().get_matches_from_safe(std::iter::once("circom").chain(args.iter().cloned()))
+    }
+
+    #[test]
+    fn flags_are_written_in_kebab_case() {
+        for command in &[None, Some("test"), Some("debug"), Some("doc"), Some("explain"), Some("diff"), Some("reconstruct")] {
+            let args: Vec<_> = command.iter().cloned().chain(Some("--help")).collect();
+            let help = parse(&args).err().unwrap();
+            assert_eq!(help.kind, clap::ErrorKind::HelpDisplayed);
+            let flags: Vec<_> = help.message
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|word| word.starts_with("--"))
+                .collect();
+            assert!(!flags.is_empty());
+            assert!(flags.iter().all(|flag| !flag.contains('_')), "{:?}", flags);
+        }
+    }
+
+    #[test]
+    fn old_flags_are_aliases_of_the_new_ones() {
+        let folder = std::env::temp_dir().join(format!("circom_input_{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        let file = folder.join("circuit.circom");
+        std::fs::write(&file, "").unwrap();
+        let file = file.to_str().unwrap();
+        for flags in &[
+            ["--simplification-substitution", "--use-old-simplification-heuristics"],
+            ["--simplification_substitution", "--use_old_simplification_heuristics"],
+        ] {
+            let matches = parse(&[file, flags[0], flags[1], "--check-tags", "--simplification-report", "report.json", "--substitution-map"]).unwrap();
+            let input = Input::new(&matches).unwrap();
+            assert!(input.json_substitutions_flag() && input.flag_old_heuristics());
+            assert!(input.check_tags_flag() && input.substitution_map_flag());
+            assert_eq!(input.simplification_report_file(), Some("report.json".to_string()));
+        }
+        // the flags added in kebab-case have no snake_case spelling
+        assert!(parse(&[file, "--check_tags"]).is_err());
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+}
@@ -1,16 +1,34 @@
 mod compilation_user;
+mod dap_user;
+mod debug_user;
+mod diff_user;
+mod doc_user;
 mod execution_user;
+mod explain_user;
 mod input_user;
+mod manifest_user;
 mod parser_user;
+mod progress_user;
+mod reconstruct_user;
+mod test_user;
 mod type_analysis_user;
 
 const VERSION: &'static str = env!("CARGO_PKG_VERSION");
 
 
 use ansi_term::Colour;
-use input_user::Input;
+use input_user::{Command, Input};
 fn main() {
-    let result = start();
+    let result = match input_user::read_command() {
+        Result::Ok(Command::Compile(input)) => start(&input),
+        Result::Ok(Command::Test(input)) => test_user::run_tests(&input),
+        Result::Ok(Command::Debug(input)) => debug_user::run_debugger(&input),
+        Result::Ok(Command::Doc(input)) => doc_user::write_documentation(&input),
+        Result::Ok(Command::Explain(input)) => explain_user::explain(&input),
+        Result::Ok(Command::Diff(input)) => exit_diff(diff_user::diff(&input)),
+        Result::Ok(Command::Reconstruct(input)) => reconstruct_user::reconstruct(&input),
+        Result::Err(()) => Result::Err(()),
+    };
     if result.is_err() {
         eprintln!("{}", Colour::Red.paint("previous errors were found"));
         std::process::exit(1);
@@ -20,12 +38,37 @@ fn main() {
     }
 }
 
-fn start() -> Result<(), ()> {
+// As the diff tool: 0 when the circuits are equal, 1 when they are different and 2 on errors
+fn exit_diff(result: Result<bool, ()>) -> ! {
+    let code = match result {
+        Result::Ok(true) => {
+            println!("{}", Colour::Green.paint("Everything went okay"));
+            0
+        }
+        Result::Ok(false) => 1,
+        Result::Err(()) => {
+            eprintln!("{}", Colour::Red.paint("previous errors were found"));
+            2
+        }
+    };
+    std::process::exit(code)
+}
+
+fn start(user_input: &Input) -> Result<(), ()> {
     use compilation_user::CompilerConfig;
     use execution_user::ExecutionConfig;
-    let user_input = Input::new()?;
-    let mut program_archive = parser_user::parse_project(&user_input)?;
-    type_analysis_user::analyse_project(&mut program_archive)?;
+    progress_user::report_progress(user_input)?;
+    let mut program_archive = parser_user::parse_project(user_input)?;
+    type_analysis_user::analyse_project(
+        &mut program_archive,
+        user_input.check_tags_flag(),
+        user_input.inspect_constraints_flag(),
+    )?;
+    let build_info = if user_input.r1cs_metadata_flag() || user_input.manifest_file().is_some() {
+        Some(manifest_user::build_info(user_input, program_archive.get_file_library()))
+    } else {
+        None
+    };
 
     let config = ExecutionConfig {
         no_rounds: user_input.no_rounds(),
@@ -35,6 +78,7 @@ fn start() -> Result<(), ()> {
         flag_old_heuristics: user_input.flag_old_heuristics(),
         flag_verbose: user_input.flag_verbose(),
         inspect_constraints_flag: user_input.inspect_constraints_flag(),
+        stream_flag: user_input.stream_flag(),
         r1cs_flag: user_input.r1cs_flag(),
         json_constraint_flag: user_input.json_constraints_flag(),
         json_substitution_flag: user_input.json_substitutions_flag(),
@@ -43,9 +87,21 @@ fn start() -> Result<(), ()> {
         r1cs: user_input.r1cs_file().to_string(),
         json_constraints: user_input.json_constraints_file().to_string(),
         json_substitutions: user_input.json_substitutions_file().to_string(),
+        hierarchy: user_input.hierarchy_file(),
+        hierarchy_dot: user_input.hierarchy_dot_file(),
+        simplification_report: user_input.simplification_report_file(),
+        substitution_map_flag: user_input.substitution_map_flag(),
+        substitution_map: user_input.substitution_map_file().to_string(),
+        json_substitution_map: user_input.json_substitution_map_file().to_string(),
+        hierarchy_depth: user_input.hierarchy_depth(),
         prime: user_input.prime(),        
+        budgets: user_input.budgets(),
+        threads: user_input.threads(),
     };
     let circuit = execution_user::execute_project(program_archive, config)?;
+    if let (true, Some(info)) = (user_input.r1cs_metadata_flag(), &build_info) {
+        manifest_user::add_r1cs_metadata(info, user_input.r1cs_file())?;
+    }
     let compilation_config = CompilerConfig {
         vcp: circuit,
         debug_output: user_input.print_ir_flag(),
@@ -61,7 +117,11 @@ fn start() -> Result<(), ()> {
         wat_file: user_input.wat_file().to_string(),
         wasm_file: user_input.wasm_file().to_string(),
         produce_input_log: user_input.main_inputs_flag(),
+        check_tags: user_input.check_tags_flag(),
     };
     compilation_user::compile(compilation_config)?;
+    if let (Some(file), Some(info)) = (user_input.manifest_file(), &build_info) {
+        manifest_user::write_manifest(info, user_input, &file)?;
+    }
     Result::Ok(())
 }
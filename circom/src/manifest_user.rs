@@ -0,0 +1,173 @@
+use super::input_user::Input;
+use crate::VERSION;
+use ansi_term::Colour;
+use program_structure::file_definition::FileLibrary;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+// Compiler, prime, flags and sources of a build. It is written in the metadata section of the
+// .r1cs file and, together with the outputs, in the manifest. In reproducible mode the paths
+// are relative and the command line is left out, so that it only depends on the sources and
+// on the flags that change the outputs.
+pub struct BuildInfo {
+    metadata: Value,
+    reproducible: bool,
+}
+
+pub fn build_info(input: &Input, file_library: &FileLibrary) -> BuildInfo {
+    let reproducible = input.reproducible_flag();
+    let main_folder = canonical(&input.input_program).parent().map(Path::to_path_buf).unwrap_or_default();
+    let mut sources = Vec::new();
+    let mut file_id = 0;
+    while let (Some(path), Some(source)) = (file_library.get_path(file_id), file_library.get_source(file_id)) {
+        let path = canonical(Path::new(path));
+        let path = if reproducible { relative_path(&path, &main_folder) } else { path.display().to_string() };
+        sources.push((path, sha256(source.as_bytes())));
+        file_id += 1;
+    }
+    sources.sort();
+    sources.dedup();
+    let mut metadata = json!({
+        "compiler": format!("circom {}", VERSION),
+        "prime": input.prime(),
+        "flags": flags(input),
+        "sources": sources.into_iter().map(|(path, hash)| json!({"path": path, "sha256": hash})).collect::<Vec<_>>(),
+    });
+    if !reproducible {
+        metadata["command_line"] = json!(std::env::args().collect::<Vec<_>>());
+    }
+    BuildInfo { metadata, reproducible }
+}
+
+pub fn add_r1cs_metadata(info: &BuildInfo, r1cs: &str) -> Result<(), ()> {
+    use constraint_writers::r1cs_writer::add_metadata_section;
+    if add_metadata_section(r1cs, &info.metadata.to_string()).is_err() {
+        eprintln!("{}", Colour::Red.paint(format!("Could not add the build metadata to {}", r1cs)));
+        return Result::Err(());
+    }
+    Result::Ok(())
+}
+
+// The outputs are the files written by the compilation, including every file of the folders
+// of the witness generators
+pub fn write_manifest(info: &BuildInfo, input: &Input, file: &str) -> Result<(), ()> {
+    // the manifest is not written yet, so only its folder can be canonicalized
+    let folder = match Path::new(file).parent() {
+        Some(folder) if !folder.as_os_str().is_empty() => canonical(folder),
+        _ => canonical(Path::new(".")),
+    };
+    let mut outputs = Vec::new();
+    for path in output_files(input) {
+        if let Result::Ok(bytes) = std::fs::read(&path) {
+            let path = canonical(&path);
+            let path = if info.reproducible { relative_path(&path, &folder) } else { path.display().to_string() };
+            outputs.push((path, sha256(&bytes)));
+        }
+    }
+    outputs.sort();
+    let mut manifest = info.metadata.clone();
+    manifest["outputs"] = json!(outputs.into_iter().map(|(path, hash)| json!({"path": path, "sha256": hash})).collect::<Vec<_>>());
+    let contents = serde_json::to_string_pretty(&manifest).unwrap();
+    if std::fs::write(file, contents + "\n").is_err() {
+        eprintln!("{}", Colour::Red.paint(format!("Could not write the manifest in {}", file)));
+        return Result::Err(());
+    }
+    println!("{} {}", Colour::Green.paint("Written successfully:"), file);
+    Result::Ok(())
+}
+
+// The flags that change the outputs, in a fixed order whatever the order of the command line
+fn flags(input: &Input) -> Vec<String> {
+    let mut flags = Vec::new();
+    if input.unsimplified_flag() {
+        flags.push("--O0".to_string());
+    } else if input.reduced_simplification_flag() {
+        flags.push("--O1".to_string());
+    } else if input.no_rounds() == usize::MAX {
+        flags.push("--O2".to_string());
+    } else {
+        flags.push(format!("--O2round {}", input.no_rounds()));
+    }
+    let options = [
+        (input.r1cs_flag(), "--r1cs"),
+        (input.r1cs_metadata_flag(), "--r1cs-metadata"),
+        (input.sym_flag(), "--sym"),
+        (input.json_constraints_flag(), "--json"),
+        (input.json_substitutions_flag(), "--simplification-substitution"),
+        (input.substitution_map_flag(), "--substitution-map"),
+        (input.wasm_flag(), "--wasm"),
+        (input.wat_flag(), "--wat"),
+        (input.c_flag(), "--c"),
+        (input.check_tags_flag(), "--check-tags"),
+        (input.main_inputs_flag(), "--inputs"),
+        (input.flag_old_heuristics(), "--use-old-simplification-heuristics"),
+    ];
+    for (present, flag) in options.iter() {
+        if *present {
+            flags.push(flag.to_string());
+        }
+    }
+    flags
+}
+
+fn output_files(input: &Input) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if input.r1cs_flag() {
+        files.push(input.out_r1cs.clone());
+    }
+    if input.sym_flag() {
+        files.push(input.out_sym.clone());
+    }
+    if input.json_constraints_flag() {
+        files.push(input.out_json_constraints.clone());
+    }
+    if input.json_substitutions_flag() {
+        files.push(input.out_json_substitutions.clone());
+    }
+    if input.substitution_map_flag() {
+        files.push(input.out_substitution_map.clone());
+        files.push(input.out_json_substitution_map.clone());
+    }
+    let reports = [&input.out_hierarchy, &input.out_hierarchy_dot, &input.out_simplification_report];
+    files.extend(reports.iter().filter_map(|file| file.as_ref().cloned()));
+    if input.wasm_flag() || input.wat_flag() {
+        folder_files(&input.out_js_folder, &mut files);
+    }
+    if input.c_flag() {
+        folder_files(&input.out_c_folder, &mut files);
+    }
+    files
+}
+
+fn folder_files(folder: &Path, files: &mut Vec<PathBuf>) {
+    if let Result::Ok(entries) = std::fs::read_dir(folder) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                folder_files(&path, files);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+// Path of a file from the given folder, going up with .. when it is outside of it. The
+// components are separated with / on every system.
+fn relative_path(path: &Path, folder: &Path) -> String {
+    let path: Vec<_> = path.components().collect();
+    let folder: Vec<_> = folder.components().collect();
+    let common = path.iter().zip(&folder).take_while(|(a, b)| a == b).count();
+    let mut components = vec!["..".to_string(); folder.len() - common];
+    components.extend(path[common..].iter().map(|c| c.as_os_str().to_string_lossy().to_string()));
+    components.join("/")
+}
+
+fn sha256(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
@@ -0,0 +1,133 @@
+use super::input_user::Input;
+use ansi_term::Colour;
+use program_structure::progress::{self, ProgressEvent};
+use serde_json::{json, Value};
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Big circuits execute thousands of instances and translate thousands of templates, so their
+// lines are shown at most once per interval. The last one skipped is shown before the next
+// line that is not limited, like the last template, so the final counts are always printed.
+const INTERVAL: Duration = Duration::from_secs(1);
+
+struct Printer {
+    last_line: Option<Instant>,
+    skipped: Option<String>,
+}
+
+impl Printer {
+    fn print(&mut self, event: &ProgressEvent) {
+        let (line, limited) = match event {
+            ProgressEvent::PhaseStarted { phase } => (format!("{}...", Colour::Blue.paint(phase.name())), false),
+            ProgressEvent::PhaseFinished { phase, millis, peak_memory } => {
+                let memory = match peak_memory {
+                    Some(bytes) => format!(", peak memory {} MB", bytes / (1024 * 1024)),
+                    None => String::new(),
+                };
+                (format!("{} finished in {} ms{}", Colour::Blue.paint(phase.name()), millis, memory), false)
+            }
+            ProgressEvent::FileParsed { file } => (format!("  parsed {}", file), false),
+            ProgressEvent::InstanceExecuted { instance, constraints, instances } => {
+                let line = format!("  instances executed: {}, last {} with {} constraints", instances, instance, constraints);
+                (line, true)
+            }
+            ProgressEvent::ConstraintsGenerated { constraints } => (format!("  constraints generated: {}", constraints), false),
+            ProgressEvent::SimplificationRound { round, linear, substitutions, remaining, millis } => {
+                let line = format!(
+                    "  round {}: {} linear constraints, {} substitutions, {} remaining in {} ms",
+                    round, linear, substitutions, remaining, millis
+                );
+                (line, false)
+            }
+            ProgressEvent::TemplateGenerated { template, index, templates } => {
+                (format!("  templates generated: {} of {}, last {}", index, templates, template), index < templates)
+            }
+        };
+        let now = Instant::now();
+        if limited && self.last_line.is_some_and(|last| now.duration_since(last) < INTERVAL) {
+            self.skipped = Some(line);
+            return;
+        }
+        // a template line always comes after the skipped ones of the previous templates
+        let supersedes = limited || matches!(event, ProgressEvent::TemplateGenerated { .. });
+        if let Some(skipped) = self.skipped.take() {
+            if !supersedes {
+                println!("{}", skipped);
+            }
+        }
+        println!("{}", line);
+        self.last_line = Some(now);
+    }
+}
+
+fn to_json(event: &ProgressEvent) -> Value {
+    match event {
+        ProgressEvent::PhaseStarted { phase } => json!({"event": "phase_started", "phase": phase.name()}),
+        ProgressEvent::PhaseFinished { phase, millis, peak_memory } => json!({
+            "event": "phase_finished",
+            "phase": phase.name(),
+            "millis": *millis as u64,
+            "peak_memory": peak_memory
+        }),
+        ProgressEvent::FileParsed { file } => json!({"event": "file_parsed", "file": file}),
+        ProgressEvent::InstanceExecuted { instance, constraints, instances } => json!({
+            "event": "instance_executed",
+            "instance": instance,
+            "constraints": constraints,
+            "instances": instances
+        }),
+        ProgressEvent::ConstraintsGenerated { constraints } => {
+            json!({"event": "constraints_generated", "constraints": constraints})
+        }
+        ProgressEvent::SimplificationRound { round, linear, substitutions, remaining, millis } => json!({
+            "event": "simplification_round",
+            "round": round,
+            "linear": linear,
+            "substitutions": substitutions,
+            "remaining": remaining,
+            "millis": *millis as u64
+        }),
+        ProgressEvent::TemplateGenerated { template, index, templates } => json!({
+            "event": "template_generated",
+            "template": template,
+            "index": index,
+            "templates": templates
+        }),
+    }
+}
+
+// With --verbose the events are shown as they come, and with --progress-json every event is
+// written as a json object in its own line, with the milliseconds since the compilation started
+pub fn report_progress(input: &Input) -> Result<(), ()> {
+    let verbose = input.flag_verbose();
+    let json_file = input.progress_json_file();
+    if !verbose && json_file.is_none() {
+        return Result::Ok(());
+    }
+    let json_output: Option<Mutex<Box<dyn Write + Send>>> = match &json_file {
+        Some(file) if file == "-" => Some(Mutex::new(Box::new(std::io::stderr()))),
+        Some(file) => match std::fs::File::create(file) {
+            Result::Ok(output) => Some(Mutex::new(Box::new(output))),
+            Result::Err(_) => {
+                eprintln!("{}", Colour::Red.paint(format!("Could not write the progress events in {}", file)));
+                return Result::Err(());
+            }
+        },
+        None => None,
+    };
+    let printer = Mutex::new(Printer { last_line: None, skipped: None });
+    let start = Instant::now();
+    progress::set_reporter(Box::new(move |event| {
+        if verbose {
+            printer.lock().unwrap().print(event);
+        }
+        if let Some(output) = &json_output {
+            let mut line = to_json(event);
+            line["time_ms"] = json!(start.elapsed().as_millis() as u64);
+            let mut output = output.lock().unwrap();
+            let _ = writeln!(output, "{}", line).and_then(|_| output.flush());
+        }
+    }));
+    Result::Ok(())
+}
@@ -0,0 +1,92 @@
+use super::input_user::ReconstructInput;
+use ansi_term::Colour;
+use circom_algebra::modular_arithmetic;
+use circom_algebra::num_bigint::BigInt;
+use constraint_writers::substitution_map_writer::read_substitution_map;
+use constraint_writers::sym_writer::read_sym_file;
+use constraint_writers::wtns_reader::read_wtns;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+// The signals in the witness take their value from the .wtns file and the ones removed by a
+// substitution are computed with the map. The rest were removed without one, like the unused
+// signals, and have no value.
+pub fn reconstruct(input: &ReconstructInput) -> Result<(), ()> {
+    let witness = read_wtns(path(&input.witness_path)).map_err(|err| report(&input.witness_path, err))?;
+    let map = read_substitution_map(path(&input.map_path)).map_err(|err| report(&input.map_path, err))?;
+    let symbols = read_sym_file(path(&input.sym_path)).map_err(|err| report(&input.sym_path, err))?;
+    let mismatch = if map.field != witness.field {
+        Some("The prime of the witness is not the one of the build of the substitution map".to_string())
+    } else if map.witness_wires != witness.values.len() {
+        Some(format!("The witness has {} wires and the build of the substitution map has {}", witness.values.len(), map.witness_wires))
+    } else {
+        None
+    };
+    if let Some(message) = mismatch {
+        eprintln!("{}", Colour::Red.paint(message));
+        return Result::Err(());
+    }
+    let removed: HashMap<_, _> =
+        map.signals.iter().map(|signal| (signal.label, evaluate(&signal.terms, &witness.values, &map.field))).collect();
+    let mut values = Vec::with_capacity(symbols.len());
+    let (mut in_witness, mut reconstructed) = (0, 0);
+    for symbol in &symbols {
+        let value = if symbol.witness >= 0 {
+            in_witness += 1;
+            witness.values.get(symbol.witness as usize)
+        } else {
+            let value = removed.get(&(symbol.original as usize));
+            reconstructed += value.is_some() as usize;
+            value
+        };
+        values.push((symbol.symbol.as_str(), value));
+    }
+    match &input.json_path {
+        Some(file) => {
+            let entries: Vec<Value> = values
+                .iter()
+                .map(|(signal, value)| json!({"signal": signal, "value": value.map(|v| v.to_string())}))
+                .collect();
+            let contents = serde_json::to_string_pretty(&entries).unwrap();
+            if std::fs::write(file, contents + "\n").is_err() {
+                eprintln!("{}", Colour::Red.paint(format!("Could not write the values in {}", file.display())));
+                return Result::Err(());
+            }
+            println!("{} {}", Colour::Green.paint("Written successfully:"), file.display());
+        }
+        None => {
+            for (signal, value) in &values {
+                match value {
+                    Some(value) => println!("{} = {}", signal, value),
+                    None => println!("{} = {}", signal, Colour::Yellow.paint("unknown")),
+                }
+            }
+        }
+    }
+    println!(
+        "Signals: {}, in the witness: {}, reconstructed: {}, without value: {}",
+        values.len(),
+        in_witness,
+        reconstructed,
+        values.len() - in_witness - reconstructed
+    );
+    Result::Ok(())
+}
+
+fn evaluate(terms: &BTreeMap<usize, BigInt>, witness: &[BigInt], field: &BigInt) -> BigInt {
+    let mut value = BigInt::from(0);
+    for (wire, coefficient) in terms {
+        let term = modular_arithmetic::mul(coefficient, &witness[*wire], field);
+        value = modular_arithmetic::add(&value, &term, field);
+    }
+    value
+}
+
+fn path(file: &Path) -> &str {
+    file.to_str().unwrap()
+}
+
+fn report(file: &Path, err: std::io::Error) {
+    eprintln!("{}", Colour::Red.paint(format!("Could not read {}: {}", file.display(), err)));
+}
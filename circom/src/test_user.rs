@@ -0,0 +1,362 @@
+use super::input_user::TestInput;
+use crate::VERSION;
+use ansi_term::Colour;
+use compiler::compiler_interface::{run_compiler, Config};
+use compiler::interpreter::{ExecutionError, Interpreter};
+use compiler::num_bigint::BigInt;
+use dag::{Tree, DAG};
+use program_structure::ast::TestCase;
+use program_structure::constants::UsefulConstants;
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use program_structure::file_definition::{FileID, FileLibrary};
+use program_structure::program_archive::ProgramArchive;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+enum Outcome {
+    // the witness was computed and every constraint holds
+    Satisfied,
+    // the circuit could not be built, its witness could not be computed or a constraint does not hold
+    Failed(ReportCollection),
+    // the test could not be compiled, it fails even if it is declared with `fails`
+    Error(ReportCollection),
+}
+
+pub fn run_tests(input: &TestInput) -> Result<(), ()> {
+    let prime = UsefulConstants::new(&input.prime()).get_p().clone();
+    let mut passed = 0;
+    let mut failed = 0;
+    for file in collect_files(&input.input_path) {
+        let file_name = file.to_str().unwrap().to_string();
+        let result = parser::run_test_parser(file_name.clone(), VERSION, input.get_link_libraries().to_vec(), &prime);
+        let (tests, warnings) = match result {
+            Result::Err((file_library, reports)) => {
+                Report::print_reports(&reports, &file_library);
+                eprintln!("{}", Colour::Red.paint(format!("The tests of {} could not be parsed", file_name)));
+                failed += 1;
+                continue;
+            }
+            Result::Ok(parsed) => parsed,
+        };
+        let tests: Vec<_> = match &input.filter {
+            Some(filter) => tests.into_iter().filter(|(test, _)| test.name.contains(filter.as_str())).collect(),
+            None => tests,
+        };
+        if tests.is_empty() {
+            continue;
+        }
+        if let Some((_, program)) = tests.first() {
+            Report::print_reports(&warnings, &program.file_library);
+        }
+        println!("running {} tests from {}", tests.len(), file_name);
+        for (test, program) in tests {
+            if run_test(&test, program, &input.prime()) {
+                passed += 1;
+            } else {
+                failed += 1;
+            }
+        }
+    }
+    let summary = format!("test result: {} passed; {} failed", passed, failed);
+    if failed == 0 {
+        println!("{}", Colour::Green.paint(summary));
+        Result::Ok(())
+    } else {
+        println!("{}", Colour::Red.paint(summary));
+        Result::Err(())
+    }
+}
+
+// A test passes if its constraints are satisfied, or if they are not and the test is declared with `fails`
+fn run_test(test: &TestCase, program: ProgramArchive, prime: &String) -> bool {
+    let file_library = program.file_library.clone();
+    let outcome = execute_test(program, prime);
+    let (success, reports) = match outcome {
+        Outcome::Satisfied if test.should_fail => {
+            let mut report = Report::error(
+                "The test was expected to fail but all its constraints are satisfied".to_string(),
+                ReportCode::FailedTestConstraint,
+            );
+            report.add_primary(test.meta.location.clone(), test.meta.get_file_id(), "test declared here".to_string());
+            (false, vec![report])
+        }
+        Outcome::Satisfied => (true, vec![]),
+        Outcome::Failed(_) if test.should_fail => (true, vec![]),
+        Outcome::Failed(reports) | Outcome::Error(reports) => (false, reports),
+    };
+    if success {
+        println!("test {} ... {}", test.name, Colour::Green.paint("ok"));
+    } else {
+        println!("test {} ... {}", test.name, Colour::Red.paint("FAILED"));
+        Report::print_reports(&reports, &file_library);
+    }
+    success
+}
+
+fn execute_test(mut program: ProgramArchive, prime: &String) -> Outcome {
+    use constraint_generation::build_unsimplified_circuit;
+    use type_analysis::check_types::check_types;
+    if let Result::Err(errors) = check_types(&mut program, false, false) {
+        return Outcome::Error(errors);
+    }
+    let mut files: HashMap<String, FileID> = HashMap::new();
+    for (name, data) in program.get_templates() {
+        files.insert(name.clone(), data.get_file_id());
+    }
+    for (name, data) in program.get_functions() {
+        files.insert(name.clone(), data.get_file_id());
+    }
+    let file_library = program.file_library.clone();
+    let (dag, vcp) = match build_unsimplified_circuit(program, prime) {
+        Result::Ok(circuit) => circuit,
+        Result::Err(reports) => return Outcome::Failed(reports),
+    };
+    let config = Config { debug_output: false, produce_input_log: false, wat_flag: false, check_tags: false };
+    let circuit = match run_compiler(vcp, config, VERSION) {
+        Result::Ok(circuit) => circuit,
+        Result::Err(()) => {
+            let report = Report::error("The test could not be compiled".to_string(), ReportCode::FailedTestConstraint);
+            return Outcome::Error(vec![report]);
+        }
+    };
+    let mut interpreter = Interpreter::new(&circuit);
+    let execution = interpreter.run();
+    for log in interpreter.logs() {
+        println!("{}", log);
+    }
+    if let Result::Err(error) = execution {
+        return Outcome::Failed(vec![execution_error_report(&error, &files, &file_library)]);
+    }
+    match check_constraints(&dag, interpreter.signal_values(), interpreter.get_field()) {
+        Some(report) => Outcome::Failed(vec![report]),
+        None => Outcome::Satisfied,
+    }
+}
+
+fn execution_error_report(error: &ExecutionError, files: &HashMap<String, FileID>, file_library: &FileLibrary) -> Report {
+    let file_id = files.get(&error.symbol);
+    let location = file_id.and_then(|file_id| file_library.get_line_location(error.line, *file_id));
+    match (file_id, location) {
+        (Some(file_id), Some(location)) => {
+            let mut report = Report::error(error.cause_message(), ReportCode::FailedTestConstraint);
+            report.add_primary(location, *file_id, "found here".to_string());
+            report.add_note(format!("Followed trace of components: {}", error.trace));
+            report
+        }
+        _ => Report::error(error.message(), ReportCode::FailedTestConstraint),
+    }
+}
+
+// Looks for the first constraint that is not satisfied by the witness
+fn check_constraints(dag: &DAG, witness: &[Option<BigInt>], field: &BigInt) -> Option<Report> {
+    // the remainder of a negative value is negative, it is taken to the range [0, field)
+    fn normalize(value: BigInt, field: &BigInt) -> BigInt {
+        ((value % field) + field) % field
+    }
+    fn evaluate(lc: &HashMap<usize, BigInt>, witness: &[Option<BigInt>], field: &BigInt) -> Option<BigInt> {
+        let mut value = BigInt::from(0);
+        for (signal, coefficient) in lc {
+            value = normalize(value + coefficient * witness[*signal].as_ref()?, field);
+        }
+        Some(value)
+    }
+    fn visit(tree: &Tree, witness: &[Option<BigInt>], field: &BigInt) -> Option<Report> {
+        for located in tree.located_constraints() {
+            let (constraint, (file_id, location)) = match located {
+                Result::Ok(located) => located,
+                Result::Err(error) => return Some(Report::error(error.to_string(), ReportCode::FailedTestConstraint)),
+            };
+            let a = evaluate(constraint.a(), witness, field);
+            let b = evaluate(constraint.b(), witness, field);
+            let c = evaluate(constraint.c(), witness, field);
+            let message = match (a, b, c) {
+                (Some(a), Some(b), Some(c)) if normalize(&a * &b - &c, field) == BigInt::from(0) => continue,
+                (Some(a), Some(b), Some(c)) => format!("values of the constraint A*B - C = 0 are A = {}, B = {}, C = {}", a, b, c),
+                _ => "the constraint uses signals that have not been assigned".to_string(),
+            };
+            let mut report = Report::error(
+                format!("Constraint of component {} is not satisfied", tree.path),
+                ReportCode::FailedTestConstraint,
+            );
+            report.add_primary(location.clone(), *file_id, "this constraint does not hold".to_string());
+            report.add_note(message);
+            return Some(report);
+        }
+        for edge in Tree::get_edges(tree) {
+            let subtree = Tree::go_to_subtree(tree, edge);
+            if let Some(report) = visit(&subtree, witness, field) {
+                return Some(report);
+            }
+        }
+        None
+    }
+    visit(&Tree::new(dag), witness, field)
+}
+
+// The input is either a file or a folder whose circom files are searched recursively
+fn collect_files(path: &Path) -> Vec<PathBuf> {
+    if path.is_file() {
+        return vec![path.to_path_buf()];
+    }
+    let mut files = vec![];
+    let mut entries: Vec<_> = match std::fs::read_dir(path) {
+        Result::Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+        Result::Err(_) => return files,
+    };
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() {
+            files.append(&mut collect_files(&entry));
+        } else if entry.extension().is_some_and(|e| e == "circom") {
+            files.push(entry);
+        }
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TESTS: &str = "pragma circom 2.1.0;
+template Multiplier(n) {
+    signal input a[n];
+    signal output c;
+    signal aux[n];
+    aux[0] <== a[0];
+    for (var i = 1; i < n; i++) {
+        aux[i] <== aux[i - 1] * a[i];
+    }
+    c <== aux[n - 1];
+}
+test \"multiplies three numbers\" Multiplier(3) {
+    input a = [2, 3, 4];
+    output c = 24;
+}
+test \"detects a wrong product\" Multiplier(2) fails {
+    input a = [2, 3];
+    output c = 5;
+}
+test \"multiplies two numbers\" Multiplier(2) fails {
+    input a = [2, 3];
+    output c = 6;
+}
+test \"checks a wrong product\" Multiplier(2) {
+    input a = [2, 3];
+    output c = 5;
+}
+test \"gives an array to a single signal\" Multiplier(2) {
+    input a = [2, 3];
+    output c = [6, 6];
+}
+";
+
+    fn prime() -> String {
+        "bn128".to_string()
+    }
+
+    // Writes the tests in a file of their own folder, which is removed by the caller
+    fn write_tests(name: &str) -> PathBuf {
+        let folder = std::env::temp_dir().join(format!("circom_test_{}_{}", std::process::id(), name));
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("tests.circom"), TESTS).unwrap();
+        folder
+    }
+
+    fn parse_tests(name: &str) -> Vec<(TestCase, ProgramArchive)> {
+        let folder = write_tests(name);
+        let file = folder.join("tests.circom").to_str().unwrap().to_string();
+        let field = UsefulConstants::new(&prime()).get_p().clone();
+        let result = parser::run_test_parser(file, VERSION, vec![], &field);
+        std::fs::remove_dir_all(&folder).unwrap();
+        result.ok().unwrap().0
+    }
+
+    fn test(tests: &mut Vec<(TestCase, ProgramArchive)>, name: &str) -> (TestCase, ProgramArchive) {
+        let position = tests.iter().position(|(test, _)| test.name == name).unwrap();
+        tests.remove(position)
+    }
+
+    fn messages(reports: &ReportCollection) -> Vec<String> {
+        reports.iter().map(|report| report.get_message().clone()).collect()
+    }
+
+    #[test]
+    fn tests_are_satisfied_failed_or_errors() {
+        let mut tests = parse_tests("outcomes");
+        let (_, program) = test(&mut tests, "multiplies three numbers");
+        assert!(matches!(execute_test(program, &prime()), Outcome::Satisfied));
+        let (_, program) = test(&mut tests, "checks a wrong product");
+        match execute_test(program, &prime()) {
+            Outcome::Failed(reports) => assert_eq!(messages(&reports), vec!["Constraint does not hold: 6 != 5"]),
+            _ => panic!("the wrong product is not detected"),
+        }
+        let (_, program) = test(&mut tests, "gives an array to a single signal");
+        assert!(matches!(execute_test(program, &prime()), Outcome::Error(_)));
+    }
+
+    #[test]
+    fn tests_declared_with_fails_pass_only_if_they_fail() {
+        let mut tests = parse_tests("fails");
+        for (name, passes) in [
+            ("multiplies three numbers", true),
+            ("detects a wrong product", true),
+            ("multiplies two numbers", false),
+            ("checks a wrong product", false),
+            ("gives an array to a single signal", false),
+        ] {
+            let (case, program) = test(&mut tests, name);
+            assert_eq!(run_test(&case, program, &prime()), passes, "{}", name);
+        }
+    }
+
+    #[test]
+    fn the_filter_selects_the_tests_run() {
+        let folder = write_tests("filter");
+        let input = |filter: Option<&str>| TestInput {
+            input_path: folder.clone(),
+            filter: filter.map(|f| f.to_string()),
+            prime: prime(),
+            link_libraries: vec![],
+        };
+        let all = run_tests(&input(None));
+        let passing = run_tests(&input(Some("three")));
+        let failing = run_tests(&input(Some("checks")));
+        let none = run_tests(&input(Some("divides")));
+        std::fs::remove_dir_all(&folder).unwrap();
+        assert_eq!(all, Result::Err(()));
+        assert_eq!(passing, Result::Ok(()));
+        assert_eq!(failing, Result::Err(()));
+        assert_eq!(none, Result::Ok(()));
+    }
+
+    // The witness of a test that holds, together with the constraints it is checked against
+    fn witness(program: ProgramArchive) -> (DAG, Vec<Option<BigInt>>, BigInt) {
+        use constraint_generation::build_unsimplified_circuit;
+        let mut program = program;
+        type_analysis::check_types::check_types(&mut program, false, false).ok().unwrap();
+        let (dag, vcp) = build_unsimplified_circuit(program, &prime()).ok().unwrap();
+        let config = Config { debug_output: false, produce_input_log: false, wat_flag: false, check_tags: false };
+        let circuit = run_compiler(vcp, config, VERSION).unwrap();
+        let mut interpreter = Interpreter::new(&circuit);
+        interpreter.run().ok().unwrap();
+        (dag, interpreter.signal_values().to_vec(), interpreter.get_field().clone())
+    }
+
+    #[test]
+    fn unsatisfied_constraints_are_reported_with_their_values() {
+        let mut tests = parse_tests("constraints");
+        let (_, program) = test(&mut tests, "multiplies three numbers");
+        let (dag, mut values, field) = witness(program);
+        assert!(check_constraints(&dag, &values, &field).is_none());
+        // the values are compared in the field, also when they are negative
+        let negative: Vec<_> = values.iter().map(|v| v.as_ref().map(|v| v - &field)).collect();
+        assert!(check_constraints(&dag, &negative, &field).is_none());
+        let last = values.len() - 1;
+        values[last] = Some(BigInt::from(-1));
+        let report = check_constraints(&dag, &values, &field).unwrap();
+        assert_eq!(report.get_message(), "Constraint of component main.circuit is not satisfied");
+        assert!(report.get_notes()[0].starts_with("values of the constraint") && !report.get_notes()[0].contains("= -"));
+    }
+}
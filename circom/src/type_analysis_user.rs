@@ -2,8 +2,8 @@ use program_structure::error_definition::Report;
 use program_structure::program_archive::ProgramArchive;
 use type_analysis::check_types::check_types;
 
-pub fn analyse_project(program_archive: &mut ProgramArchive) -> Result<(), ()> {
-    let analysis_result = check_types(program_archive);
+pub fn analyse_project(program_archive: &mut ProgramArchive, check_tags: bool, inspect: bool) -> Result<(), ()> {
+    let analysis_result = check_types(program_archive, check_tags, inspect);
     match analysis_result {
         Err(errs) => {
             Report::print_reports(&errs, program_archive.get_file_library());
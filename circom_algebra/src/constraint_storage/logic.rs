@@ -1,10 +1,14 @@
 use super::*;
+use crate::field_element::{from_limbs, to_limbs};
 use std::collections::HashMap;
 
 pub fn code_expression(expr: HashMap<S, BigInt>, tracker: &mut FieldTracker) -> CompressedExpr {
     let mut c_expr = CompressedExpr::new();
     for (var, coeff) in expr {
-        let raw_coeff = coeff.to_signed_bytes_le();
+        let raw_coeff = match to_limbs(&coeff) {
+            Some(limbs) => RawField::Limbs(limbs),
+            None => RawField::Bytes(coeff.to_signed_bytes_le()),
+        };
         let coeff_id = tracker.insert(raw_coeff);
         c_expr.push((coeff_id, var));
     }
@@ -18,17 +22,28 @@ pub fn code_constraint(constraint: C, tracker: &mut FieldTracker) -> CompressedC
     (a, b, c)
 }
 
-pub fn decode_expr(c_expr: &CompressedExpr, tracker: &FieldTracker) -> HashMap<S, BigInt> {
-    let mut decoded_expr = HashMap::new();
+pub fn decode_expr(c_expr: &CompressedExpr, tracker: &FieldTracker, move_signal: &dyn Fn(S) -> S) -> HashMap<S, BigInt> {
+    let mut decoded_expr = HashMap::with_capacity(c_expr.len());
     for (coeff_id, var) in c_expr {
         let raw_coeff = tracker.get_constant(*coeff_id).unwrap();
-        let coeff = BigInt::from_signed_bytes_le(raw_coeff);
-        decoded_expr.insert(*var, coeff);
+        let coeff = match raw_coeff {
+            RawField::Limbs(limbs) => from_limbs(limbs),
+            RawField::Bytes(bytes) => BigInt::from_signed_bytes_le(bytes),
+        };
+        decoded_expr.insert(move_signal(*var), coeff);
     }
     decoded_expr
 }
 
 pub fn decode_constraint(constraint: &CompressedConstraint, tracker: &FieldTracker) -> C {
+    decode_constraint_with(constraint, tracker, &|signal| signal)
+}
+
+pub fn decode_constraint_with(constraint: &CompressedConstraint, tracker: &FieldTracker, move_signal: &dyn Fn(S) -> S) -> C {
     let (a, b, c) = constraint;
-    C { a: decode_expr(a, tracker), b: decode_expr(b, tracker), c: decode_expr(c, tracker) }
+    C {
+        a: decode_expr(a, tracker, move_signal),
+        b: decode_expr(b, tracker, move_signal),
+        c: decode_expr(c, tracker, move_signal),
+    }
 }
@@ -2,10 +2,16 @@ use crate::algebra::Constraint;
 use crate::num_bigint::BigInt;
 use constant_tracking::{ConstantTracker, CID};
 use std::collections::LinkedList;
+use std::ops::Range;
 
 mod logic;
 
-type RawField = Vec<u8>;
+// Coefficients are stored in fixed-width limbs, the few that do not fit keep their bytes
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum RawField {
+    Limbs([u64; 4]),
+    Bytes(Vec<u8>),
+}
 type FieldTracker = ConstantTracker<RawField>;
 type S = usize;
 type C = Constraint<usize>;
@@ -14,26 +20,74 @@ type CompressedExpr = Vec<(CID, S)>;
 type CompressedConstraint = (CompressedExpr, CompressedExpr, CompressedExpr); // A, B, C
 
 pub type ConstraintID = usize;
+pub type TemplateID = usize;
+
+// A constraint is either stored by itself or as a constraint of a template instance. The
+// instances of a template share its constraints, which are decoded and moved to the signals
+// of the instance when read.
+enum StoredConstraint {
+    Owned(Box<CompressedConstraint>),
+    Shared { instance: usize, position: usize },
+}
+
+// The signals of an instance are the ones of the template moved by offset, but for the
+// renamed ones. The renames are sorted by the signal of the template.
+struct Instance {
+    template: TemplateID,
+    offset: usize,
+    renames: Vec<(S, S)>,
+}
+
 pub struct ConstraintStorage {
     field_tracker: FieldTracker,
-    constraints: Vec<CompressedConstraint>,
+    constraints: Vec<StoredConstraint>,
+    templates: Vec<Vec<CompressedConstraint>>,
+    instances: Vec<Instance>,
 }
 
 impl ConstraintStorage {
     pub fn new() -> ConstraintStorage {
-        ConstraintStorage { field_tracker: FieldTracker::new(), constraints: Vec::new() }
+        ConstraintStorage {
+            field_tracker: FieldTracker::new(),
+            constraints: Vec::new(),
+            templates: Vec::new(),
+            instances: Vec::new(),
+        }
     }
 
     pub fn add_constraint(&mut self, constraint: C) -> ConstraintID {
         let id = self.constraints.len();
         let compressed = logic::code_constraint(constraint, &mut self.field_tracker);
-        self.constraints.push(compressed);
+        self.constraints.push(StoredConstraint::Owned(Box::new(compressed)));
+        id
+    }
+
+    pub fn add_template(&mut self, constraints: Vec<C>) -> TemplateID {
+        let id = self.templates.len();
+        let mut compressed = Vec::with_capacity(constraints.len());
+        for constraint in constraints {
+            compressed.push(logic::code_constraint(constraint, &mut self.field_tracker));
+        }
+        self.templates.push(compressed);
         id
     }
 
+    // Adds the constraints of the template with its signals moved by offset or renamed, the
+    // renames must not make two signals of the template the same. Returns the new ids.
+    pub fn add_instance(&mut self, template: TemplateID, offset: usize, mut renames: Vec<(S, S)>) -> Range<ConstraintID> {
+        renames.sort_unstable();
+        let instance = self.instances.len();
+        self.instances.push(Instance { template, offset, renames });
+        let first = self.constraints.len();
+        for position in 0..self.templates[template].len() {
+            self.constraints.push(StoredConstraint::Shared { instance, position });
+        }
+        first..self.constraints.len()
+    }
+
     pub fn read_constraint(&self, id: ConstraintID) -> Option<C> {
         if id < self.constraints.len() {
-            Some(logic::decode_constraint(&self.constraints[id], &self.field_tracker))
+            Some(self.decode(&self.constraints[id]))
         } else {
             None
         }
@@ -41,7 +95,8 @@ impl ConstraintStorage {
 
     pub fn replace(&mut self, id: ConstraintID, new: C) {
         if id < self.constraints.len() {
-            self.constraints[id] = logic::code_constraint(new, &mut self.field_tracker);
+            let compressed = logic::code_constraint(new, &mut self.field_tracker);
+            self.constraints[id] = StoredConstraint::Owned(Box::new(compressed));
         }
     }
 
@@ -49,7 +104,7 @@ impl ConstraintStorage {
         let old = std::mem::take(&mut self.constraints);
         let mut removed = LinkedList::new();
         for c in old {
-            let decoded = logic::decode_constraint(&c, &self.field_tracker);
+            let decoded = self.decode(&c);
             if filter(&decoded) {
                 removed.push_back(decoded);
             } else {
@@ -63,7 +118,92 @@ impl ConstraintStorage {
         (0..self.constraints.len()).collect()
     }
 
+    // The constraints in the order of their ids
+    pub fn constraints(&self) -> impl Iterator<Item = C> + '_ {
+        self.constraints.iter().map(move |c| self.decode(c))
+    }
+
+    // Removes the constraints and returns them in the order of their ids, the templates are
+    // kept for the instances added afterwards
+    pub fn drain(&mut self) -> Vec<C> {
+        let drained = self.constraints().collect();
+        self.constraints.clear();
+        self.instances.clear();
+        drained
+    }
+
     pub fn no_constants(&self) -> CID {
         self.field_tracker.next_id()
     }
+
+    fn decode(&self, stored: &StoredConstraint) -> C {
+        match stored {
+            StoredConstraint::Owned(compressed) => logic::decode_constraint(compressed, &self.field_tracker),
+            StoredConstraint::Shared { instance, position } => {
+                let instance = &self.instances[*instance];
+                let compressed = &self.templates[instance.template][*position];
+                instance_constraint(instance, compressed, &self.field_tracker)
+            }
+        }
+    }
+}
+
+fn instance_constraint(instance: &Instance, compressed: &CompressedConstraint, tracker: &FieldTracker) -> C {
+    let move_signal = |signal: S| {
+        if signal == C::constant_coefficient() {
+            signal
+        } else {
+            match instance.renames.binary_search_by_key(&signal, |(from, _)| *from) {
+                Ok(index) => instance.renames[index].1,
+                Err(_) => signal + instance.offset,
+            }
+        }
+    };
+    logic::decode_constraint_with(compressed, tracker, &move_signal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn expression(terms: &[(usize, u64)]) -> HashMap<usize, BigInt> {
+        terms.iter().map(|(signal, value)| (*signal, BigInt::from(*value))).collect()
+    }
+
+    #[test]
+    fn shared_instances_read_as_owned_constraints() {
+        let constraint = C { a: expression(&[(0, 3), (1, 2)]), b: expression(&[(2, 1)]), c: expression(&[(3, 5)]) };
+        let mut storage = ConstraintStorage::new();
+        let template = storage.add_template(vec![constraint.clone()]);
+        storage.add_constraint(constraint.apply_offset(7));
+        assert_eq!(storage.add_instance(template, 7, Vec::new()), 1..2);
+        storage.add_instance(template, 20, vec![(2, 4)]);
+        let owned = storage.read_constraint(0).unwrap();
+        let shared = storage.read_constraint(1).unwrap();
+        assert_eq!((owned.a(), owned.b(), owned.c()), (shared.a(), shared.b(), shared.c()));
+        let renamed = storage.read_constraint(2).unwrap();
+        assert_eq!((renamed.b(), renamed.c()), (&expression(&[(4, 1)]), &expression(&[(23, 5)])));
+
+        storage.replace(1, C::empty());
+        let removed = storage.extract_with(&|c| c.is_empty());
+        assert_eq!(removed.len(), 1);
+        assert_eq!(storage.get_ids(), vec![0, 1]);
+        assert_eq!(storage.read_constraint(1).unwrap().a(), &expression(&[(0, 3), (21, 2)]));
+    }
+
+    #[test]
+    fn drained_storages_keep_their_templates() {
+        let constraint = C { a: expression(&[(0, 3), (1, 2)]), b: expression(&[]), c: expression(&[(3, 5)]) };
+        let mut storage = ConstraintStorage::new();
+        let template = storage.add_template(vec![constraint.clone()]);
+        storage.add_constraint(constraint.clone());
+        storage.add_instance(template, 10, vec![(1, 2)]);
+        let drained = storage.drain();
+        assert_eq!(drained.len(), 2);
+        assert_eq!(drained[1].a(), &expression(&[(0, 3), (2, 2)]));
+        assert!(storage.get_ids().is_empty());
+        assert_eq!(storage.add_instance(template, 20, Vec::new()), 0..1);
+        assert_eq!(storage.read_constraint(0).unwrap().c(), &expression(&[(23, 5)]));
+    }
 }
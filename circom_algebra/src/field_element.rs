@@ -0,0 +1,351 @@
+use crate::modular_arithmetic;
+use num_bigint::{BigInt, Sign};
+
+// The fixed-width elements are only used by the sparse elimination of the simplification,
+// which converts the coefficients of a cluster once and converts the results back. The
+// operations of modular_arithmetic, ArithmeticExpression, Constraint, the substitutions and
+// the writers work on BigInt, and the ConstraintStorage only uses the limbs to store the
+// coefficients.
+const LIMBS: usize = 4;
+type Limbs = [u64; LIMBS];
+
+// Element of a PrimeField in Montgomery form: the element x is stored as x * 2^256 mod p.
+// Since the representation is always reduced, equal elements have equal limbs.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash, Debug)]
+pub struct FieldElement(pub(crate) Limbs);
+
+// Arithmetic of a prime field whose prime fits in four 64-bit limbs, which are all the
+// primes supported by circom. The elements are fixed-width and do not allocate, and the
+// products are reduced with Montgomery multiplication instead of divisions. Other primes
+// use the BigInt functions of modular_arithmetic.
+#[derive(Clone)]
+pub struct PrimeField {
+    prime: BigInt,
+    modulus: Limbs,
+    // -modulus^-1 mod 2^64
+    inv: u64,
+    // 2^256 mod modulus and 2^512 mod modulus
+    one: Limbs,
+    r2: Limbs,
+}
+
+impl PrimeField {
+    pub fn new(prime: &BigInt) -> Option<PrimeField> {
+        let modulus = to_limbs(prime)?;
+        if modulus[0] % 2 == 0 || *prime <= BigInt::from(2) {
+            return None;
+        }
+        let mut inv = 1u64;
+        for _ in 0..63 {
+            inv = inv.wrapping_mul(inv);
+            inv = inv.wrapping_mul(modulus[0]);
+        }
+        let r = BigInt::from(1) << (64 * LIMBS);
+        let one = to_limbs(&(&r % prime)).unwrap();
+        let r2 = to_limbs(&((&r * &r) % prime)).unwrap();
+        Some(PrimeField { prime: prime.clone(), modulus, inv: inv.wrapping_neg(), one, r2 })
+    }
+
+    pub fn prime(&self) -> &BigInt {
+        &self.prime
+    }
+
+    pub fn zero(&self) -> FieldElement {
+        FieldElement([0; LIMBS])
+    }
+
+    pub fn one(&self) -> FieldElement {
+        FieldElement(self.one)
+    }
+
+    pub fn from_bigint(&self, value: &BigInt) -> FieldElement {
+        let raw = match self.reduced(value) {
+            Some(raw) => raw,
+            None => {
+                let reduced = ((value % &self.prime) + &self.prime) % &self.prime;
+                FieldElement(to_limbs(&reduced).unwrap())
+            }
+        };
+        self.mul(&raw, &FieldElement(self.r2))
+    }
+
+    pub fn to_bigint(&self, element: &FieldElement) -> BigInt {
+        from_limbs(&self.out_of_montgomery(element).0)
+    }
+
+    pub fn is_zero(&self, element: &FieldElement) -> bool {
+        element.0 == [0; LIMBS]
+    }
+
+    pub fn add(&self, left: &FieldElement, right: &FieldElement) -> FieldElement {
+        let (sum, carry) = add_limbs(&left.0, &right.0);
+        if carry || !less_than(&sum, &self.modulus) {
+            FieldElement(sub_limbs(&sum, &self.modulus).0)
+        } else {
+            FieldElement(sum)
+        }
+    }
+
+    pub fn sub(&self, left: &FieldElement, right: &FieldElement) -> FieldElement {
+        let (difference, borrow) = sub_limbs(&left.0, &right.0);
+        if borrow {
+            FieldElement(add_limbs(&difference, &self.modulus).0)
+        } else {
+            FieldElement(difference)
+        }
+    }
+
+    pub fn neg(&self, element: &FieldElement) -> FieldElement {
+        self.sub(&self.zero(), element)
+    }
+
+    pub fn mul(&self, left: &FieldElement, right: &FieldElement) -> FieldElement {
+        FieldElement(montgomery_mul(&left.0, &right.0, &self.modulus, self.inv))
+    }
+
+    pub fn pow(&self, base: &FieldElement, exponent: &BigInt) -> FieldElement {
+        let mut result = self.one();
+        for i in (0..exponent.bits()).rev() {
+            result = self.mul(&result, &result);
+            if exponent.get_limb(i / 64) >> (i % 64) & 1 == 1 {
+                result = self.mul(&result, base);
+            }
+        }
+        result
+    }
+
+    // By Fermat's little theorem, x^-1 = x^(p - 2)
+    pub fn inverse(&self, element: &FieldElement) -> Option<FieldElement> {
+        if self.is_zero(element) {
+            None
+        } else {
+            Some(self.pow(element, &(&self.prime - 2)))
+        }
+    }
+
+    // Limbs of a value in (-p, p), reduced but not in Montgomery form
+    fn reduced(&self, value: &BigInt) -> Option<FieldElement> {
+        let magnitude = match value.sign() {
+            Sign::Minus => to_limbs(&-value)?,
+            _ => to_limbs(value)?,
+        };
+        if !less_than(&magnitude, &self.modulus) {
+            return None;
+        }
+        let element = FieldElement(magnitude);
+        Some(if value.sign() == Sign::Minus { self.neg(&element) } else { element })
+    }
+
+    fn out_of_montgomery(&self, element: &FieldElement) -> FieldElement {
+        self.mul(element, &FieldElement([1, 0, 0, 0]))
+    }
+}
+
+// Coefficients of the algorithms that only add, multiply and invert them, like the sparse
+// elimination of the simplification. They convert the BigInt coefficients of the constraints
+// to elements once, work on them and convert the results back.
+pub trait Field {
+    type Element: Clone;
+    fn element(&self, value: &BigInt) -> Self::Element;
+    fn value(&self, element: &Self::Element) -> BigInt;
+    fn is_zero(&self, element: &Self::Element) -> bool;
+    fn add(&self, left: &Self::Element, right: &Self::Element) -> Self::Element;
+    fn mul(&self, left: &Self::Element, right: &Self::Element) -> Self::Element;
+    fn neg(&self, element: &Self::Element) -> Self::Element;
+    fn inverse(&self, element: &Self::Element) -> Option<Self::Element>;
+}
+
+impl Field for PrimeField {
+    type Element = FieldElement;
+    fn element(&self, value: &BigInt) -> FieldElement {
+        self.from_bigint(value)
+    }
+    fn value(&self, element: &FieldElement) -> BigInt {
+        self.to_bigint(element)
+    }
+    fn is_zero(&self, element: &FieldElement) -> bool {
+        PrimeField::is_zero(self, element)
+    }
+    fn add(&self, left: &FieldElement, right: &FieldElement) -> FieldElement {
+        PrimeField::add(self, left, right)
+    }
+    fn mul(&self, left: &FieldElement, right: &FieldElement) -> FieldElement {
+        PrimeField::mul(self, left, right)
+    }
+    fn neg(&self, element: &FieldElement) -> FieldElement {
+        PrimeField::neg(self, element)
+    }
+    fn inverse(&self, element: &FieldElement) -> Option<FieldElement> {
+        PrimeField::inverse(self, element)
+    }
+}
+
+// Fallback for the primes that PrimeField does not support, the elements are reduced BigInts
+pub struct BigIntField(pub BigInt);
+
+impl Field for BigIntField {
+    type Element = BigInt;
+    fn element(&self, value: &BigInt) -> BigInt {
+        modular_arithmetic::add(value, &BigInt::from(0), &self.0)
+    }
+    fn value(&self, element: &BigInt) -> BigInt {
+        element.clone()
+    }
+    fn is_zero(&self, element: &BigInt) -> bool {
+        *element == BigInt::from(0)
+    }
+    fn add(&self, left: &BigInt, right: &BigInt) -> BigInt {
+        modular_arithmetic::add(left, right, &self.0)
+    }
+    fn mul(&self, left: &BigInt, right: &BigInt) -> BigInt {
+        modular_arithmetic::mul(left, right, &self.0)
+    }
+    fn neg(&self, element: &BigInt) -> BigInt {
+        modular_arithmetic::prefix_sub(element, &self.0)
+    }
+    fn inverse(&self, element: &BigInt) -> Option<BigInt> {
+        modular_arithmetic::div(&BigInt::from(1), element, &self.0).ok()
+    }
+}
+
+// Limbs of a non negative value that fits in them
+pub(crate) fn to_limbs(value: &BigInt) -> Option<Limbs> {
+    if value.sign() == Sign::Minus || value.bits() > 64 * LIMBS {
+        return None;
+    }
+    let mut limbs = [0; LIMBS];
+    for (i, limb) in limbs.iter_mut().enumerate().take(value.bits().div_ceil(64)) {
+        *limb = value.get_limb(i);
+    }
+    Some(limbs)
+}
+
+pub(crate) fn from_limbs(limbs: &Limbs) -> BigInt {
+    BigInt::from_slice_native(Sign::Plus, limbs)
+}
+
+fn less_than(left: &Limbs, right: &Limbs) -> bool {
+    for i in (0..LIMBS).rev() {
+        if left[i] != right[i] {
+            return left[i] < right[i];
+        }
+    }
+    false
+}
+
+fn add_limbs(left: &Limbs, right: &Limbs) -> (Limbs, bool) {
+    let mut result = [0; LIMBS];
+    let mut carry = false;
+    for i in 0..LIMBS {
+        let (sum, c0) = left[i].overflowing_add(right[i]);
+        let (sum, c1) = sum.overflowing_add(carry as u64);
+        result[i] = sum;
+        carry = c0 || c1;
+    }
+    (result, carry)
+}
+
+fn sub_limbs(left: &Limbs, right: &Limbs) -> (Limbs, bool) {
+    let mut result = [0; LIMBS];
+    let mut borrow = false;
+    for i in 0..LIMBS {
+        let (difference, b0) = left[i].overflowing_sub(right[i]);
+        let (difference, b1) = difference.overflowing_sub(borrow as u64);
+        result[i] = difference;
+        borrow = b0 || b1;
+    }
+    (result, borrow)
+}
+
+// a + b * c + carry, as the low and the high limbs
+fn mac(a: u64, b: u64, c: u64, carry: u64) -> (u64, u64) {
+    let value = a as u128 + (b as u128) * (c as u128) + carry as u128;
+    (value as u64, (value >> 64) as u64)
+}
+
+// left * right / 2^256 mod modulus with the CIOS method. The intermediate value is always
+// smaller than 2 * modulus, the extra limbs keep its bits over 2^256.
+fn montgomery_mul(left: &Limbs, right: &Limbs, modulus: &Limbs, inv: u64) -> Limbs {
+    let mut t = [0u64; LIMBS + 2];
+    for &word in right {
+        let mut carry = 0;
+        for j in 0..LIMBS {
+            let (low, high) = mac(t[j], left[j], word, carry);
+            t[j] = low;
+            carry = high;
+        }
+        let (sum, overflow) = t[LIMBS].overflowing_add(carry);
+        t[LIMBS] = sum;
+        t[LIMBS + 1] = overflow as u64;
+
+        let k = t[0].wrapping_mul(inv);
+        let (_, mut carry) = mac(t[0], k, modulus[0], 0);
+        for j in 1..LIMBS {
+            let (low, high) = mac(t[j], k, modulus[j], carry);
+            t[j - 1] = low;
+            carry = high;
+        }
+        let (sum, overflow) = t[LIMBS].overflowing_add(carry);
+        t[LIMBS - 1] = sum;
+        t[LIMBS] = t[LIMBS + 1] + overflow as u64;
+    }
+    let result = [t[0], t[1], t[2], t[3]];
+    if t[LIMBS] != 0 || !less_than(&result, modulus) {
+        sub_limbs(&result, modulus).0
+    } else {
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRIMES: [&str; 4] = [
+        "21888242871839275222246405745257275088548364400416034343698204186575808495617",
+        "52435875175126190479447740508185965837690552500527637822603658699938581184513",
+        "18446744069414584321",
+        "115792089210356248762697446949407573530086143415290314195533631308867097853951",
+    ];
+
+    fn samples(prime: &BigInt) -> Vec<BigInt> {
+        let mut state = 0x9e3779b97f4a7c15u64;
+        let mut values = vec![BigInt::from(0), BigInt::from(1), prime - 1, prime - 2, prime / 2];
+        for _ in 0..40 {
+            let mut value = BigInt::from(0);
+            for _ in 0..LIMBS {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                value = (value << 64) + state;
+            }
+            values.push(value % prime);
+        }
+        values
+    }
+
+    #[test]
+    fn operations_agree_with_bigint() {
+        for prime in PRIMES.iter() {
+            let p = BigInt::parse_bytes(prime.as_bytes(), 10).unwrap();
+            let field = PrimeField::new(&p).unwrap();
+            let values = samples(&p);
+            for a in &values {
+                let x = field.from_bigint(a);
+                assert_eq!(field.to_bigint(&x), *a);
+                assert_eq!(field.to_bigint(&field.neg(&x)), (&p - a) % &p);
+                if let Some(inverse) = field.inverse(&x) {
+                    assert_eq!(field.mul(&x, &inverse), field.one());
+                }
+                for b in values.iter().take(12) {
+                    let y = field.from_bigint(b);
+                    assert_eq!(field.to_bigint(&field.add(&x, &y)), (a + b) % &p);
+                    assert_eq!(field.to_bigint(&field.sub(&x, &y)), ((a - b) % &p + &p) % &p);
+                    assert_eq!(field.to_bigint(&field.mul(&x, &y)), (a * b) % &p);
+                }
+            }
+            assert_eq!(field.to_bigint(&field.from_bigint(&BigInt::from(-1))), &p - 1);
+            assert_eq!(field.to_bigint(&field.pow(&field.from_bigint(&BigInt::from(3)), &BigInt::from(5))), BigInt::from(243) % &p);
+        }
+    }
+}
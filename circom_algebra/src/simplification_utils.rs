@@ -538,6 +538,8 @@ pub struct Simplified {
     pub constraints: LinkedList<C>,
     pub substitutions: LinkedList<S>,
     pub removed: LinkedList<usize>,
+    // independent constraints of the cluster, the rest were dropped for depending on them
+    pub rank: usize,
 }
 
 pub fn full_simplification<T>(config: Config<T>) -> Simplified
@@ -545,31 +547,30 @@ where
     T: AsRef<HashSet<usize>>,
 {
     //debug_new_substitutions(&config);
-    let min = 350;
-    let max = 1000000;
-    let apply_less_ocurrences = 
-        config.constraints.len() >= min && 
-        config.constraints.len() < max && 
-        !config.use_old_heuristics;
-
     let field = config.field;
     let mut constraints = config.constraints;
-    let mut holder = SHNotNormalized::new();
-    let normalized_holder: SH;
     let non_overlapping: HashMap<usize, S>;
+    let rank;
 
-    if apply_less_ocurrences{
-        let mut signals = SignalDefinition4 { forbidden: config.forbidden.as_ref(), deleted_symbols: HashSet::new(),  order_signals: LinkedList::new() };
-        substitution_process_4(&mut signals, &mut constraints, &mut holder, config.num_signals, &field);
-        normalized_holder = normalize_substitutions(holder, &field);
-        non_overlapping = create_nonoverlapping_substitutions_4(normalized_holder, &signals, &field);
+    if !config.use_old_heuristics {
+        use crate::sparse_elimination::sparse_elimination;
+        let elimination = sparse_elimination(constraints, config.forbidden.as_ref(), &field);
+        constraints = elimination.constraints;
+        non_overlapping = elimination.substitutions;
+        rank = elimination.rank;
     }
     else{
+        let mut holder = SHNotNormalized::new();
         let mut signals = SignalDefinition { forbidden: config.forbidden.as_ref(), deleted_symbols: HashSet::new() };
         substitution_process_3(&mut signals, &mut constraints, &mut holder, &field);
-        normalized_holder = normalize_substitutions(holder, &field);
+        let normalized_holder = normalize_substitutions(holder, &field);
         non_overlapping = create_nonoverlapping_substitutions(normalized_holder, &field);
+        // the remaining constraints are not checked to be independent
+        rank = non_overlapping.len() + constraints.len();
     }
+    // the substitutions are given in the order of their signals, not of the hash map
+    let mut non_overlapping: Vec<_> = non_overlapping.into_iter().collect();
+    non_overlapping.sort_by_key(|(s, _)| *s);
     let mut substitutions = LinkedList::new();
     let mut removed = LinkedList::new();
     for (s, v) in non_overlapping {
@@ -577,7 +578,7 @@ where
         LinkedList::push_back(&mut substitutions, v);
     }
 
-    Simplified { constraints, substitutions, removed }
+    Simplified { constraints, substitutions, removed, rank }
 }
 
 pub fn debug_new_substitutions<T>(config: &Config<T>)
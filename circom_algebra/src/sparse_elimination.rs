@@ -0,0 +1,302 @@
+use super::num_bigint::BigInt;
+use crate::field_element::{BigIntField, Field, PrimeField};
+use std::collections::{BTreeSet, HashMap, HashSet, LinkedList};
+
+type C = crate::algebra::Constraint<usize>;
+type S = crate::algebra::Substitution<usize>;
+// linear expression of a constraint, without zero coefficients
+type Row<E> = HashMap<usize, E>;
+
+// Number of rows, in increasing number of signals, where the pivot with the
+// smallest Markowitz cost is searched
+const MARKOWITZ_ROWS: usize = 4;
+
+pub struct Elimination {
+    // non overlapping, the signals of the right hand sides are never removed
+    pub substitutions: HashMap<usize, S>,
+    // constraints left with only forbidden signals, independent between them
+    pub constraints: LinkedList<C>,
+    // number of independent constraints
+    pub rank: usize,
+}
+
+// Gauss-Jordan elimination of a system of linear constraints. Every step takes the pivot with
+// the smallest fill-in, (signals of the row - 1) * (occurrences of the signal - 1), and removes
+// the signal from the rest of the rows and from the expressions of the previous pivots, which
+// are counted as occurrences since they are the substitutions in the end. The rows that become
+// zero depend on the others and are dropped. The forbidden signals are eliminated at the end
+// between the remaining rows, only to drop the dependent ones.
+// The coefficients are fixed-width elements of a PrimeField during the elimination when the
+// prime allows it.
+pub fn sparse_elimination(constraints: LinkedList<C>, forbidden: &HashSet<usize>, field: &BigInt) -> Elimination {
+    match PrimeField::new(field) {
+        Some(prime_field) => elimination_in(&prime_field, constraints, forbidden),
+        None => elimination_in(&BigIntField(field.clone()), constraints, forbidden),
+    }
+}
+
+fn elimination_in<F: Field>(field: &F, constraints: LinkedList<C>, forbidden: &HashSet<usize>) -> Elimination {
+    let rows: Vec<_> = constraints.into_iter().map(|c| to_row(c.c, field)).collect();
+    let free = eliminate(rows, &|signal| !forbidden.contains(&signal), field);
+    let fixed = eliminate(free.rest, &|_| true, field);
+    let rank = free.pivots.len() + fixed.pivots.len() + fixed.rest.len();
+    let mut constraints = LinkedList::new();
+    let minus_one = field.neg(&field.element(&BigInt::from(1)));
+    for (signal, mut row) in fixed.pivots {
+        row.insert(signal, minus_one.clone());
+        constraints.push_back(C { a: HashMap::new(), b: HashMap::new(), c: to_expression(row, field) });
+    }
+    for row in fixed.rest {
+        constraints.push_back(C { a: HashMap::new(), b: HashMap::new(), c: to_expression(row, field) });
+    }
+    let substitutions = free
+        .pivots
+        .into_iter()
+        .map(|(from, to)| (from, S { from, to: to_expression(to, field) }))
+        .collect();
+    Elimination { substitutions, constraints, rank }
+}
+
+struct Reduction<E> {
+    // the signal of every pivot, in the order they were taken, and the expression it is equal to
+    pivots: Vec<(usize, Row<E>)>,
+    // rows without signals that can be pivots, including the ones that became a nonzero constant
+    rest: Vec<Row<E>>,
+}
+
+fn eliminate<F: Field>(rows: Vec<Row<F::Element>>, can_pivot: &dyn Fn(usize) -> bool, field: &F) -> Reduction<F::Element> {
+    let mut rows: Vec<_> = rows.into_iter().map(Some).collect();
+    // the rows of the pivots hold their expressions
+    let mut is_pivot = vec![false; rows.len()];
+    let mut columns: HashMap<usize, BTreeSet<usize>> = HashMap::new();
+    let mut active = BTreeSet::new();
+    let mut rest = Vec::new();
+    for (id, slot) in rows.iter_mut().enumerate() {
+        let row = slot.as_ref().unwrap();
+        if is_candidate(row, can_pivot) {
+            for signal in signals(row) {
+                columns.entry(signal).or_default().insert(id);
+            }
+            active.insert((no_signals(row), id));
+        } else {
+            classify_rest(slot.take().unwrap(), &mut rest);
+        }
+    }
+    let mut order = Vec::new();
+    while let Some((id, signal)) = take_pivot(&rows, &columns, &active, can_pivot) {
+        let row = rows[id].take().unwrap();
+        active.remove(&(no_signals(&row), id));
+        let expression = clear_signal(&row, signal, field);
+        let mut affected = columns.remove(&signal).unwrap_or_default();
+        affected.remove(&id);
+        for other in affected {
+            let mut current = rows[other].take().unwrap();
+            if !is_pivot[other] {
+                active.remove(&(no_signals(&current), other));
+            }
+            let coefficient = current.remove(&signal).unwrap();
+            for (s, value) in &expression {
+                let value = field.mul(&coefficient, value);
+                let updated = match current.remove(s) {
+                    Some(previous) => field.add(&previous, &value),
+                    None => value,
+                };
+                if !field.is_zero(&updated) {
+                    current.insert(*s, updated);
+                    if *s != C::constant_coefficient() {
+                        columns.entry(*s).or_default().insert(other);
+                    }
+                } else if *s != C::constant_coefficient() {
+                    columns.get_mut(s).unwrap().remove(&other);
+                }
+            }
+            if is_pivot[other] || is_candidate(&current, can_pivot) {
+                if !is_pivot[other] {
+                    active.insert((no_signals(&current), other));
+                }
+                rows[other] = Some(current);
+            } else {
+                for s in signals(&current) {
+                    columns.get_mut(&s).unwrap().remove(&other);
+                }
+                classify_rest(current, &mut rest);
+            }
+        }
+        rows[id] = Some(expression);
+        is_pivot[id] = true;
+        order.push((signal, id));
+    }
+    let pivots = order.into_iter().map(|(signal, id)| (signal, rows[id].take().unwrap())).collect();
+    Reduction { pivots, rest }
+}
+
+// Markowitz pivot among the first rows with less signals, the ties are broken taking the
+// greatest signal as the rest of the simplification does
+fn take_pivot<E>(
+    rows: &[Option<Row<E>>],
+    columns: &HashMap<usize, BTreeSet<usize>>,
+    active: &BTreeSet<(usize, usize)>,
+    can_pivot: &dyn Fn(usize) -> bool,
+) -> Option<(usize, usize)> {
+    let mut best: Option<(usize, usize, usize)> = None;
+    for (length, id) in active.iter().take(MARKOWITZ_ROWS) {
+        for signal in signals(rows[*id].as_ref().unwrap()) {
+            if !can_pivot(signal) {
+                continue;
+            }
+            let cost = (length - 1) * (columns[&signal].len() - 1);
+            let better = match best {
+                None => true,
+                Some((b_cost, b_signal, _)) => cost < b_cost || (cost == b_cost && signal > b_signal),
+            };
+            if better {
+                best = Some((cost, signal, *id));
+            }
+        }
+        if matches!(best, Some((0, _, _))) {
+            break;
+        }
+    }
+    best.map(|(_, signal, id)| (id, signal))
+}
+
+// Expression of the signal given by row = 0
+fn clear_signal<F: Field>(row: &Row<F::Element>, signal: usize, field: &F) -> Row<F::Element> {
+    let factor = field.neg(&field.inverse(&row[&signal]).unwrap());
+    let mut expression = Row::with_capacity(row.len() - 1);
+    for (s, value) in row {
+        if *s != signal {
+            expression.insert(*s, field.mul(value, &factor));
+        }
+    }
+    expression
+}
+
+// Rows left without pivots are dropped when they are zero, a nonzero constant is kept
+// since the system has no solution
+fn classify_rest<E>(row: Row<E>, rest: &mut Vec<Row<E>>) {
+    if !row.is_empty() {
+        rest.push(row);
+    }
+}
+
+fn is_candidate<E>(row: &Row<E>, can_pivot: &dyn Fn(usize) -> bool) -> bool {
+    signals(row).any(can_pivot)
+}
+
+fn signals<E>(row: &Row<E>) -> impl Iterator<Item = usize> + '_ {
+    row.keys().cloned().filter(|s| *s != C::constant_coefficient())
+}
+
+fn no_signals<E>(row: &Row<E>) -> usize {
+    signals(row).count()
+}
+
+fn to_row<F: Field>(expression: HashMap<usize, BigInt>, field: &F) -> Row<F::Element> {
+    let elements = expression.iter().map(|(s, v)| (*s, field.element(v)));
+    elements.filter(|(_, e)| !field.is_zero(e)).collect()
+}
+
+fn to_expression<F: Field>(row: Row<F::Element>, field: &F) -> HashMap<usize, BigInt> {
+    row.iter().map(|(s, e)| (*s, field.value(e))).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    const FIELD: &str = "257";
+
+    fn constraint(coefficients: &[(usize, i64)]) -> C {
+        let c = coefficients.iter().map(|(s, v)| (*s, BigInt::from(*v))).collect();
+        C { a: HashMap::new(), b: HashMap::new(), c }
+    }
+
+    fn check_elimination(field: &impl Field) {
+        // 1 and 2 are forbidden, the third constraint is the sum of the first two and
+        // the last one only has forbidden signals and it depends on the rest
+        let mut constraints = LinkedList::new();
+        constraints.push_back(constraint(&[(3, 1), (4, 1), (1, 256)]));
+        constraints.push_back(constraint(&[(4, 1), (5, 2), (2, 256)]));
+        constraints.push_back(constraint(&[(3, 1), (4, 2), (5, 2), (1, 256), (2, 256)]));
+        constraints.push_back(constraint(&[(5, 1), (0, 7)]));
+        constraints.push_back(constraint(&[(1, 2), (2, 2)]));
+        constraints.push_back(constraint(&[(1, 1), (2, 1)]));
+        let forbidden: HashSet<_> = [1, 2].iter().cloned().collect();
+        let result = elimination_in(field, constraints, &forbidden);
+        assert_eq!(result.rank, 4);
+        assert_eq!(result.substitutions.len(), 3);
+        assert_eq!(result.constraints.len(), 1);
+        for substitution in result.substitutions.values() {
+            for signal in substitution.to().keys() {
+                assert!(!result.substitutions.contains_key(signal));
+            }
+        }
+        // 5 = -7, 4 = 2 + 14 and 3 = 1 - 4
+        let expected = [(5, vec![(0, 250)]), (4, vec![(0, 14), (2, 1)]), (3, vec![(0, 243), (1, 1), (2, 256)])];
+        for (signal, to) in expected.iter() {
+            let to: HashMap<_, _> = to.iter().map(|(s, v)| (*s, BigInt::from(*v))).collect();
+            assert_eq!(result.substitutions[signal].to(), &to);
+        }
+    }
+
+    #[test]
+    fn dependent_constraints_are_dropped() {
+        let field = BigInt::parse_bytes(FIELD.as_bytes(), 10).unwrap();
+        check_elimination(&PrimeField::new(&field).unwrap());
+        check_elimination(&BigIntField(field));
+    }
+
+    // Rounds of t signals where every signal is a combination of the ones of the previous
+    // round, like the linear layers of a hash, with the first and the last rounds forbidden
+    fn mixing_rounds(t: usize, rounds: usize, field: &BigInt) -> (LinkedList<C>, HashSet<usize>) {
+        let mut state = 0x9e3779b97f4a7c15u64;
+        let mut coefficient = || {
+            let mut value = BigInt::from(0);
+            for _ in 0..4 {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                value = (value << 64) + state;
+            }
+            value % field
+        };
+        let signal = |round: usize, i: usize| round * t + i + 1;
+        let mut constraints = LinkedList::new();
+        for round in 1..=rounds {
+            for i in 0..t {
+                let mut c: HashMap<_, _> = (0..t).map(|j| (signal(round - 1, j), coefficient())).collect();
+                c.insert(signal(round, i), field - 1);
+                constraints.push_back(C { a: HashMap::new(), b: HashMap::new(), c });
+            }
+        }
+        let forbidden = (0..t).flat_map(|i| vec![signal(0, i), signal(rounds, i)]).collect();
+        (constraints, forbidden)
+    }
+
+    // Compares the elimination on fixed-width elements with the one on BigInt coefficients,
+    // which is the arithmetic the simplification used before. Run it with
+    // cargo test --release -p circom_algebra -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn elimination_benchmark() {
+        use std::time::Instant;
+        let field = BigInt::parse_bytes(
+            "21888242871839275222246405745257275088548364400416034343698204186575808495617".as_bytes(),
+            10,
+        )
+        .unwrap();
+        let (constraints, forbidden) = mixing_rounds(12, 600, &field);
+        let start = Instant::now();
+        let fixed = elimination_in(&PrimeField::new(&field).unwrap(), constraints.clone(), &forbidden);
+        let fixed_time = start.elapsed();
+        let start = Instant::now();
+        let bigint = elimination_in(&BigIntField(field), constraints, &forbidden);
+        let bigint_time = start.elapsed();
+        println!("{} constraints: {:?} with PrimeField, {:?} with BigInt", 12 * 600, fixed_time, bigint_time);
+        assert_eq!(fixed.rank, bigint.rank);
+        assert_eq!(fixed.substitutions.len(), bigint.substitutions.len());
+        for (signal, substitution) in &fixed.substitutions {
+            assert_eq!(substitution.to(), bigint.substitutions[signal].to());
+        }
+    }
+}
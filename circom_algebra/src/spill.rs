@@ -0,0 +1,230 @@
+use crate::algebra::Constraint;
+use crate::num_bigint::BigInt;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::Hash;
+use std::io::{self, BufReader, BufWriter, Read, Seek, Write};
+use tempfile::NamedTempFile;
+
+// Signals of the constraints kept in a spill, by their number or by their name
+pub trait SpillKey: Sized + Hash + Eq {
+    fn write_key(&self, bytes: &mut Vec<u8>);
+    fn read_key(reader: &mut impl Read) -> io::Result<Self>;
+}
+
+impl SpillKey for usize {
+    fn write_key(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&(*self as u64).to_le_bytes());
+    }
+    fn read_key(reader: &mut impl Read) -> io::Result<usize> {
+        let mut word = [0; 8];
+        reader.read_exact(&mut word)?;
+        Result::Ok(u64::from_le_bytes(word) as usize)
+    }
+}
+
+impl SpillKey for String {
+    fn write_key(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&(self.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(self.as_bytes());
+    }
+    fn read_key(reader: &mut impl Read) -> io::Result<String> {
+        let mut word = [0; 4];
+        reader.read_exact(&mut word)?;
+        let mut name = vec![0; u32::from_le_bytes(word) as usize];
+        reader.read_exact(&mut name)?;
+        String::from_utf8(name).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+}
+
+// Constraints written one after the other in a spill
+#[derive(Copy, Clone)]
+pub struct SpillRange {
+    start: u64,
+    end: u64,
+    count: usize,
+}
+
+// Constraints appended to a temporary file, which is created with a random name that no other
+// file has and removed when the spill is dropped, also when it fails to be opened. Every
+// expression is written as its number of terms followed by the terms, a term being the signal
+// and the length and signed little-endian bytes of its coefficient.
+pub struct Spill<K> {
+    file: NamedTempFile,
+    writer: RefCell<BufWriter<File>>,
+    // reader shared by the reads that do not overlap, it is taken while one is iterated
+    reader: RefCell<Option<BufReader<File>>>,
+    position: u64,
+    key: std::marker::PhantomData<K>,
+}
+
+impl<K: SpillKey> Spill<K> {
+    pub fn new() -> io::Result<Spill<K>> {
+        let file = tempfile::Builder::new().prefix("circom-").suffix(".constraints").tempfile()?;
+        let writer = file.reopen()?;
+        Result::Ok(Spill {
+            file,
+            writer: RefCell::new(BufWriter::new(writer)),
+            reader: RefCell::new(Option::None),
+            position: 0,
+            key: std::marker::PhantomData,
+        })
+    }
+
+    // Appends the constraint to the last range when it was the last one written
+    pub fn write(&mut self, constraint: &Constraint<K>, ranges: &mut Vec<SpillRange>) -> io::Result<()> {
+        let mut bytes = Vec::new();
+        write_expression(&mut bytes, &constraint.a);
+        write_expression(&mut bytes, &constraint.b);
+        write_expression(&mut bytes, &constraint.c);
+        self.writer.get_mut().write_all(&bytes)?;
+        let start = self.position;
+        self.position += bytes.len() as u64;
+        match ranges.last_mut() {
+            Option::Some(range) if range.end == start => {
+                range.end = self.position;
+                range.count += 1;
+            }
+            _ => ranges.push(SpillRange { start, end: self.position, count: 1 }),
+        }
+        Result::Ok(())
+    }
+
+    // The constraints of the ranges in the order they were written
+    pub fn read<'a>(&'a self, ranges: &'a [SpillRange]) -> SpillReader<'a, K> {
+        SpillReader { spill: self, reader: Option::None, ranges: ranges.iter(), left: 0 }
+    }
+
+    fn open_reader(&self) -> io::Result<BufReader<File>> {
+        self.writer.borrow_mut().flush()?;
+        match self.reader.borrow_mut().take() {
+            Option::Some(reader) => Result::Ok(reader),
+            Option::None => Result::Ok(BufReader::new(self.file.reopen()?)),
+        }
+    }
+}
+
+pub struct SpillReader<'a, K> {
+    spill: &'a Spill<K>,
+    reader: Option<BufReader<File>>,
+    ranges: std::slice::Iter<'a, SpillRange>,
+    left: usize,
+}
+
+impl<'a, K: SpillKey> SpillReader<'a, K> {
+    fn read_constraint(&mut self) -> io::Result<Option<Constraint<K>>> {
+        if self.reader.is_none() {
+            self.reader = Option::Some(self.spill.open_reader()?);
+        }
+        let reader = self.reader.as_mut().unwrap();
+        while self.left == 0 {
+            match self.ranges.next() {
+                Option::None => return Result::Ok(Option::None),
+                Option::Some(range) => {
+                    // the buffer is kept when the range starts close to the last one read
+                    let current = reader.stream_position()?;
+                    reader.seek_relative(range.start as i64 - current as i64)?;
+                    self.left = range.count;
+                }
+            }
+        }
+        self.left -= 1;
+        let a = read_expression(reader)?;
+        let b = read_expression(reader)?;
+        let c = read_expression(reader)?;
+        Result::Ok(Option::Some(Constraint { a, b, c }))
+    }
+}
+
+impl<'a, K: SpillKey> Iterator for SpillReader<'a, K> {
+    type Item = io::Result<Constraint<K>>;
+
+    fn next(&mut self) -> Option<io::Result<Constraint<K>>> {
+        match self.read_constraint() {
+            Result::Ok(constraint) => constraint.map(Result::Ok),
+            Result::Err(error) => {
+                // the reads stop after an error
+                self.left = 0;
+                self.ranges = [].iter();
+                Option::Some(Result::Err(error))
+            }
+        }
+    }
+}
+
+impl<'a, K> Drop for SpillReader<'a, K> {
+    fn drop(&mut self) {
+        if let Option::Some(reader) = self.reader.take() {
+            *self.spill.reader.borrow_mut() = Option::Some(reader);
+        }
+    }
+}
+
+fn write_expression<K: SpillKey>(bytes: &mut Vec<u8>, expression: &HashMap<K, BigInt>) {
+    bytes.extend_from_slice(&(expression.len() as u32).to_le_bytes());
+    for (signal, coefficient) in expression {
+        let coefficient = coefficient.to_signed_bytes_le();
+        signal.write_key(bytes);
+        bytes.extend_from_slice(&(coefficient.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&coefficient);
+    }
+}
+
+fn read_expression<K: SpillKey>(reader: &mut impl Read) -> io::Result<HashMap<K, BigInt>> {
+    let mut word = [0; 4];
+    reader.read_exact(&mut word)?;
+    let terms = u32::from_le_bytes(word) as usize;
+    let mut expression = HashMap::with_capacity(terms);
+    for _ in 0..terms {
+        let signal = K::read_key(reader)?;
+        reader.read_exact(&mut word)?;
+        let mut coefficient = vec![0; u32::from_le_bytes(word) as usize];
+        reader.read_exact(&mut coefficient)?;
+        expression.insert(signal, BigInt::from_signed_bytes_le(&coefficient));
+    }
+    Result::Ok(expression)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expression<K: SpillKey>(terms: Vec<(K, i64)>) -> HashMap<K, BigInt> {
+        terms.into_iter().map(|(signal, value)| (signal, BigInt::from(value))).collect()
+    }
+
+    #[test]
+    fn spilled_constraints_are_read_by_ranges() {
+        let first = Constraint { a: expression(vec![(0, 3), (1, 2)]), b: HashMap::new(), c: expression(vec![(3, -5)]) };
+        let second = Constraint { a: expression(vec![(2, 1)]), b: expression(vec![(1, 1)]), c: expression(vec![(4, 7)]) };
+        let mut spill = Spill::new().unwrap();
+        let (mut odd, mut even) = (Vec::new(), Vec::new());
+        spill.write(&first, &mut odd).unwrap();
+        spill.write(&second, &mut even).unwrap();
+        spill.write(&second, &mut even).unwrap();
+        spill.write(&first, &mut odd).unwrap();
+        assert_eq!((odd.len(), even.len()), (2, 1));
+        let mut outer = spill.read(&odd);
+        let read = outer.next().unwrap().unwrap();
+        assert_eq!((read.a(), read.b(), read.c()), (first.a(), first.b(), first.c()));
+        // a read in the middle of another one does not move it
+        let inner: Vec<_> = spill.read(&even).map(Result::unwrap).collect();
+        assert_eq!(inner.len(), 2);
+        assert_eq!((inner[1].a(), inner[1].b(), inner[1].c()), (second.a(), second.b(), second.c()));
+        assert_eq!(outer.next().unwrap().unwrap().c(), first.c());
+        assert!(outer.next().is_none());
+
+        let named = Constraint { a: expression(vec![("main.a[1]".to_string(), 1)]), b: HashMap::new(), c: HashMap::new() };
+        let mut spill = Spill::new().unwrap();
+        let mut ranges = Vec::new();
+        spill.write(&named, &mut ranges).unwrap();
+        assert_eq!(spill.read(&ranges).next().unwrap().unwrap().a(), named.a());
+        ranges[0].count = 2;
+        assert!(spill.read(&ranges).nth(1).unwrap().is_err());
+        let path = spill.file.path().to_path_buf();
+        assert!(path.exists());
+        drop(spill);
+        assert!(!path.exists());
+    }
+}
@@ -418,6 +418,13 @@ pub fn build_failed_assert_message(line: usize) -> String{
      )
 }
 
+pub fn build_failed_check_message(message: &str, line: usize) -> String {
+    format!("std::cout << \"{} line {}. \" <<  \"Followed trace of components: \" << {} << std::endl",
+        message,
+        line,
+        generate_my_trace()
+    )
+}
 
 pub fn build_conditional(
     cond: Vec<String>,
@@ -825,6 +832,38 @@ pub fn generate_message_list_def(_producer: &CProducer, message_list: &MessageLi
     instructions
 }
 
+// Names and positions of the outputs of the main component, used to retrieve them by name
+pub fn generate_main_output_list(producer: &CProducer) -> Vec<String> {
+    let mut instructions = vec![];
+    let output_list = producer.get_main_output_list();
+    let mut entries = vec![];
+    for (i, output) in output_list.iter().enumerate() {
+        let lengths = if output.dimensions.is_empty() {
+            "NULL".to_string()
+        } else {
+            let lengths: Vec<String> = output.dimensions.iter().map(|l| l.to_string()).collect();
+            instructions.push(format!("u32 mainOutputLengths{}[] = {{{}}};\n", i, lengths.join(",")));
+            format!("mainOutputLengths{}", i)
+        };
+        entries.push(format!(
+            "{{\"{}\",{},{},{},{}}}",
+            output.name,
+            output.start,
+            output.size,
+            output.dimensions.len(),
+            lengths
+        ));
+    }
+    instructions.push(format!(
+        "MainOutputInfo mainOutputList[{}] = {{{}}};\n",
+        std::cmp::max(output_list.len(), 1),
+        entries.join(",")
+    ));
+    instructions.push(format!("uint get_main_output_list_size() {{return {};}}\n", output_list.len()));
+    instructions.push("MainOutputInfo* get_main_output_list() {return mainOutputList;}\n".to_string());
+    instructions
+}
+
 pub fn generate_function_release_memory_component() -> Vec<String>{
     let mut instructions = vec![];
     instructions.push("void release_memory_component(Circom_CalcWit* ctx, uint pos) {{\n".to_string());
@@ -113,6 +113,48 @@ std::string Circom_CalcWit::getTrace(u64 id_cmp){
 
 }
 
+bool Circom_CalcWit::getOutputSignal(std::string name, std::vector<FrElement> &values){
+  bool found = false;
+  MainOutputInfo* outputs = get_main_output_list();
+  for (uint i = 0; i < get_main_output_list_size(); i++) {
+    std::string output_name(outputs[i].name);
+    u64 first = 0;
+    u64 count = outputs[i].signalsize;
+    if (output_name.compare(0, name.size(), name) == 0) {
+      // the whole output or a bus (array) containing it
+      if (output_name.size() != name.size() && output_name[name.size()] != '.' && output_name[name.size()] != '[') continue;
+    } else if (name.compare(0, output_name.size(), output_name) == 0) {
+      // positions of an array of signals: name is output_name followed by indexes
+      std::string accesses = name.substr(output_name.size());
+      uint dim = 0;
+      size_t pos = 0;
+      bool valid = true;
+      while (pos < accesses.size() && valid) {
+        size_t end = accesses.find(']', pos);
+        std::string digits = end == std::string::npos ? "" : accesses.substr(pos + 1, end - pos - 1);
+        if (accesses[pos] != '[' || digits.empty() || digits.find_first_not_of("0123456789") != std::string::npos || dim >= outputs[i].len) {
+          valid = false;
+        } else {
+          u64 index = std::stoull(digits);
+          if (index >= outputs[i].lengths[dim]) valid = false;
+          count = count / outputs[i].lengths[dim];
+          first += index * count;
+          dim++;
+          pos = end + 1;
+        }
+      }
+      if (!valid) continue;
+    } else {
+      continue;
+    }
+    for (u64 j = first; j < first + count; j++) {
+      values.push_back(signalValues[outputs[i].signalid + j]);
+    }
+    found = true;
+  }
+  return found;
+}
+
 std::string Circom_CalcWit::generate_position_array(uint* dimensions, uint size_dimensions, uint index){
   std::string positions = "";
 
@@ -6,6 +6,7 @@
 #include <functional>
 #include <atomic>
 #include <memory>
+#include <vector>
 
 #include "circom.hpp"
 #include "fr.hpp"
@@ -57,6 +58,9 @@ public:
 
   std::string getTrace(u64 id_cmp);
 
+  // Values of the main outputs whose qualified name is (or starts with) name, e.g. "out", "out[1].x"
+  bool getOutputSignal(std::string name, std::vector<FrElement> &values);
+
   std::string generate_position_array(uint* dimensions, uint size_dimensions, uint index);
 
 private:
@@ -33,6 +33,15 @@ struct IOFieldDefPair {
     IOFieldDef* defs;
 };
 
+//only for the main outputs
+struct MainOutputInfo {
+    const char* name;
+    u64 signalid;
+    u64 signalsize;
+    u32 len;
+    u32 *lengths;
+};
+
 struct Circom_Circuit {
   //  const char *P;
   HashSignalInfo* InputHashMap;
@@ -85,5 +94,7 @@ uint get_size_of_witness();
 uint get_size_of_constants();
 uint get_size_of_io_map();
 uint get_size_of_bus_field_map();
+uint get_main_output_list_size();
+MainOutputInfo* get_main_output_list();
 
 #endif  // __CIRCOM_H
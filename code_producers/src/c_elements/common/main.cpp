@@ -331,10 +331,54 @@ void writeBinWitness(Circom_CalcWit *ctx, std::string wtnsFileName) {
     fclose(write_ptr);
 }
 
+// Builds a json object with the outputs of the main component structured by name, dimensions and bus fields
+json getOutputs(Circom_CalcWit *ctx) {
+  json outputs = json::object();
+  MainOutputInfo* list = get_main_output_list();
+  for (uint i = 0; i < get_main_output_list_size(); i++) {
+    // "out[0].x" is stored in outputs["out"][0]["x"]
+    std::string name(list[i].name);
+    std::vector<std::string> keys;
+    std::string token = "";
+    for (char c : name) {
+      if (c == '.' || c == '[' || c == ']') {
+        if (!token.empty()) keys.push_back(token);
+        token = "";
+      } else {
+        token += c;
+      }
+    }
+    if (!token.empty()) keys.push_back(token);
+    json *output = &outputs;
+    for (uint k = 0; k < keys.size(); k++) {
+      bool is_index = keys[k].find_first_not_of("0123456789") == std::string::npos;
+      output = is_index ? &((*output)[std::stoul(keys[k])]) : &((*output)[keys[k]]);
+    }
+    for (u64 j = 0; j < list[i].signalsize; j++) {
+      json *position = output;
+      u64 index = j;
+      u64 elems = list[i].signalsize;
+      for (uint d = 0; d < list[i].len; d++) {
+        elems = elems / list[i].lengths[d];
+        position = &((*position)[index / elems]);
+        index = index % elems;
+      }
+      FrElement v = ctx->signalValues[list[i].signalid + j];
+      *position = std::string(Fr_element2str(&v));
+    }
+  }
+  return outputs;
+}
+
+void writeOutputs(Circom_CalcWit *ctx, std::string outputsFileName) {
+  std::ofstream outputsFile(outputsFileName);
+  outputsFile << getOutputs(ctx).dump(2) << std::endl;
+}
+
 int main (int argc, char *argv[]) {
   std::string cl(argv[0]);
-  if (argc!=3) {
-        std::cout << "Usage: " << cl << " <input.json> <output.wtns>\n";
+  if (argc!=3 && argc!=4) {
+        std::cout << "Usage: " << cl << " <input.json> <output.wtns> [<outputs.json>]\n";
   } else {
     std::string datfile = cl + ".dat";
     std::string jsonfile(argv[1]);
@@ -363,6 +407,9 @@ int main (int argc, char *argv[]) {
    //std::cout << std::chrono::duration<double, std::milli>(t_mid-t_start).count()<<std::endl;
 
    writeBinWitness(ctx,wtnsfile);
+   if (argc == 4) {
+     writeOutputs(ctx, std::string(argv[3]));
+   }
   
    //auto t_end = std::chrono::high_resolution_clock::now();
    //std::cout << std::chrono::duration<double, std::milli>(t_end-t_mid).count()<<std::endl;
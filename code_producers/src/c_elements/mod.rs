@@ -20,6 +20,8 @@ pub struct CProducer {
     pub prime: String,
     pub prime_str: String,
     pub main_input_list: InputList,
+    pub main_output_list: InputList,
+    pub main_signal_list: MainSignalList,
     pub witness_to_signal_list: SignalList,
     pub io_map: TemplateInstanceIOMap,
     pub template_instance_list: TemplateListParallel,
@@ -34,6 +36,7 @@ pub struct CProducer {
     pub num_of_bus_instances: usize,  //total number of different bus instances
     //pub size_of_bus_fields: usize,  //total number of fields in all differen bus intances
     pub busid_field_info: FieldMap, //for every busId (0..num-1) provides de offset, size, dimensions and busId of each field (0..n-1) in it
+    pub bus_name_list: BusNameList, //for every busId (0..num-1) provides the name of the bus
 }
 
 impl Default for CProducer {
@@ -89,6 +92,8 @@ impl Default for CProducer {
                     bus_id: None
                 },
             ].to_vec(),
+            main_output_list: Vec::new(),
+            main_signal_list: Vec::new(),
             signals_in_witness: 20,
             witness_to_signal_list: [
                 0, 1, 2, 3, 4, 5, 6, 12, 16, 19, 24, 27, 33, 42, 46, 50, 51, 65, 78, 79,
@@ -120,6 +125,7 @@ impl Default for CProducer {
 	        num_of_bus_instances: 0,
 //	        size_of_bus_fields: 0,
 	        busid_field_info: Vec::new(), 
+	        bus_name_list: Vec::new(),
         }
     }
 }
@@ -158,6 +164,12 @@ impl CProducer {
     pub fn get_main_input_list(&self) -> &InputList {
         &self.main_input_list
     }
+    pub fn get_main_output_list(&self) -> &InputList {
+        &self.main_output_list
+    }
+    pub fn get_main_signal_list(&self) -> &MainSignalList {
+        &self.main_signal_list
+    }
 //<<<<<<< HEAD  
 //=======
     pub fn get_input_hash_map_entry_size(&self) -> usize {
@@ -214,5 +226,9 @@ impl CProducer {
     pub fn get_busid_field_info(&self) -> &FieldMap {
         &self.busid_field_info
     }
+
+    pub fn get_bus_name_list(&self) -> &BusNameList {
+        &self.bus_name_list
+    }
     // end
 }
@@ -0,0 +1,302 @@
+use super::*;
+use serde_json::{json, Map, Value};
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+
+// Writes <name>.schema.json and <name>.d.ts in the given folder
+pub fn generate_io_description_files(
+    folder: &Path,
+    circuit_name: &str,
+    signals: &MainSignalList,
+    buses: &FieldMap,
+    bus_names: &BusNameList,
+) -> std::io::Result<()> {
+    use std::io::BufWriter;
+    let schema_path = folder.join(format!("{}.schema.json", circuit_name));
+    let mut schema_file = BufWriter::new(File::create(schema_path)?);
+    let schema = generate_input_schema(circuit_name, signals, buses, bus_names);
+    schema_file.write_all(schema.as_bytes())?;
+    schema_file.flush()?;
+
+    let typings_path = folder.join(format!("{}.d.ts", circuit_name));
+    let mut typings_file = BufWriter::new(File::create(typings_path)?);
+    let typings = generate_typescript_declarations(circuit_name, signals, buses, bus_names);
+    typings_file.write_all(typings.as_bytes())?;
+    typings_file.flush()?;
+    Ok(())
+}
+
+// Produces the JSON Schema of the input file expected by the witness calculators.
+// Outputs are described under $defs so tools can validate the values returned for them.
+pub fn generate_input_schema(
+    circuit_name: &str,
+    signals: &MainSignalList,
+    buses: &FieldMap,
+    bus_names: &BusNameList,
+) -> String {
+    let type_names = bus_type_names(bus_names);
+    let mut defs = Map::new();
+    defs.insert(
+        "FieldElement".to_string(),
+        json!({
+            "description": "Field element given as a number or as a decimal or hexadecimal string",
+            "oneOf": [
+                { "type": "integer" },
+                { "type": "string", "pattern": "^-?(0x[0-9a-fA-F]+|[0-9]+)$" }
+            ]
+        }),
+    );
+    for (bus_id, fields) in buses.iter().enumerate() {
+        let mut properties = Map::new();
+        let mut required = vec![];
+        for field in fields {
+            let field_type = schema_of_type(field.bus_id, &type_names);
+            properties.insert(field.name.clone(), schema_of_array(field_type, &field.dimensions));
+            required.push(Value::String(field.name.clone()));
+        }
+        defs.insert(
+            type_names[bus_id].clone(),
+            json!({
+                "type": "object",
+                "description": format!("Bus {}", bus_names[bus_id]),
+                "properties": properties,
+                "required": required,
+                "additionalProperties": false
+            }),
+        );
+    }
+    let (inputs, input_names) = schema_of_signals(signals, true, &type_names);
+    let (outputs, output_names) = schema_of_signals(signals, false, &type_names);
+    defs.insert(
+        "Output".to_string(),
+        json!({
+            "type": "object",
+            "description": format!("Outputs of the main component of {}", circuit_name),
+            "properties": outputs,
+            "required": output_names,
+            "additionalProperties": false
+        }),
+    );
+    let schema = json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": format!("{} input", circuit_name),
+        "type": "object",
+        "properties": inputs,
+        "required": input_names,
+        "additionalProperties": false,
+        "$defs": defs
+    });
+    serde_json::to_string_pretty(&schema).unwrap()
+}
+
+// Produces a TypeScript declaration file with the shape of the inputs and outputs of the main component.
+pub fn generate_typescript_declarations(
+    circuit_name: &str,
+    signals: &MainSignalList,
+    buses: &FieldMap,
+    bus_names: &BusNameList,
+) -> String {
+    let type_names = bus_type_names(bus_names);
+    let mut code = vec![];
+    code.push(format!("// Input and output types of the main component of {}", circuit_name));
+    code.push("// This file has been generated by circom. Do not edit it.".to_string());
+    code.push("".to_string());
+    code.push("export type FieldElement = bigint | number | string;".to_string());
+    code.push("".to_string());
+    for (bus_id, fields) in buses.iter().enumerate() {
+        for value_type in ["FieldElement", "bigint"] {
+            let interface = if value_type == "bigint" {
+                format!("{}Value", type_names[bus_id])
+            } else {
+                type_names[bus_id].clone()
+            };
+            code.push(format!("/** Bus {} */", bus_names[bus_id]));
+            code.push(format!("export interface {} {{", interface));
+            for field in fields {
+                let field_type = typescript_of_type(field.bus_id, value_type, &type_names);
+                code.push(format!(
+                    "    {}: {};",
+                    field.name,
+                    typescript_of_array(field_type, &field.dimensions)
+                ));
+            }
+            code.push("}".to_string());
+            code.push("".to_string());
+        }
+    }
+    for (is_input, interface, value_type) in
+        [(true, "CircuitInput", "FieldElement"), (false, "CircuitOutput", "bigint")]
+    {
+        code.push(format!("export interface {} {{", interface));
+        for signal in signals.iter().filter(|s| s.is_input == is_input) {
+            code.push(format!("    /** {} */", signal_description(signal)));
+            let signal_type = typescript_of_type(signal.bus_id, value_type, &type_names);
+            code.push(format!(
+                "    {}: {};",
+                signal.name,
+                typescript_of_array(signal_type, &signal.dimensions)
+            ));
+        }
+        code.push("}".to_string());
+        code.push("".to_string());
+    }
+    code.join("\n")
+}
+
+// Buses instantiated with different parameters share the same name,
+// in that case the bus id is used to distinguish them. The dots of the
+// buses of namespaces, like lib.Point, are not allowed in the types.
+fn bus_type_names(bus_names: &BusNameList) -> Vec<String> {
+    let mut type_names = vec![];
+    for (bus_id, name) in bus_names.iter().enumerate() {
+        let instances = bus_names.iter().filter(|n| *n == name).count();
+        let type_name = name.replace('.', "__");
+        if instances == 1 {
+            type_names.push(type_name);
+        } else {
+            type_names.push(format!("{}_{}", type_name, bus_id));
+        }
+    }
+    type_names
+}
+
+fn signal_description(signal: &MainSignalInfo) -> String {
+    let role = if !signal.is_input {
+        "public output"
+    } else if signal.is_public {
+        "public input"
+    } else {
+        "private input"
+    };
+    let mut description = role.to_string();
+    if !signal.dimensions.is_empty() {
+        let dimensions: Vec<String> = signal.dimensions.iter().map(|d| d.to_string()).collect();
+        description = format!("{} [{}]", description, dimensions.join("]["));
+    }
+    if !signal.tags.is_empty() {
+        description = format!("{} {{{}}}", description, signal.tags.join(", "));
+    }
+    description
+}
+
+fn schema_of_signals(
+    signals: &MainSignalList,
+    is_input: bool,
+    type_names: &[String],
+) -> (Map<String, Value>, Vec<Value>) {
+    let mut properties = Map::new();
+    let mut names = vec![];
+    for signal in signals.iter().filter(|s| s.is_input == is_input) {
+        let mut schema = schema_of_array(schema_of_type(signal.bus_id, type_names), &signal.dimensions);
+        if let Value::Object(entries) = &mut schema {
+            entries.insert("description".to_string(), Value::String(signal_description(signal)));
+            entries.insert(
+                "x-circom".to_string(),
+                json!({
+                    "role": if !is_input { "output" } else if signal.is_public { "public" } else { "private" },
+                    "dimensions": signal.dimensions,
+                    "tags": signal.tags
+                }),
+            );
+        }
+        properties.insert(signal.name.clone(), schema);
+        names.push(Value::String(signal.name.clone()));
+    }
+    (properties, names)
+}
+
+fn schema_of_type(bus_id: Option<usize>, type_names: &[String]) -> Value {
+    match bus_id {
+        Some(id) => json!({ "$ref": format!("#/$defs/{}", type_names[id]) }),
+        None => json!({ "$ref": "#/$defs/FieldElement" }),
+    }
+}
+
+fn schema_of_array(element: Value, dimensions: &[usize]) -> Value {
+    let mut schema = element;
+    for length in dimensions.iter().rev() {
+        schema = json!({
+            "type": "array",
+            "items": schema,
+            "minItems": length,
+            "maxItems": length
+        });
+    }
+    schema
+}
+
+fn typescript_of_type(bus_id: Option<usize>, value_type: &str, type_names: &[String]) -> String {
+    match (bus_id, value_type) {
+        (Some(id), "bigint") => format!("{}Value", type_names[id]),
+        (Some(id), _) => type_names[id].clone(),
+        (None, _) => value_type.to_string(),
+    }
+}
+
+fn typescript_of_array(element: String, dimensions: &[usize]) -> String {
+    format!("{}{}", element, "[]".repeat(dimensions.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> (MainSignalList, FieldMap, BusNameList) {
+        let point = vec![
+            FieldData { dimensions: vec![], size: 1, offset: 0, bus_id: None, name: "x".to_string() },
+            FieldData { dimensions: vec![], size: 1, offset: 1, bus_id: None, name: "y".to_string() },
+        ];
+        let signals = vec![
+            MainSignalInfo {
+                name: "p".to_string(),
+                dimensions: vec![2],
+                bus_id: Some(0),
+                is_input: true,
+                is_public: true,
+                tags: vec![],
+            },
+            MainSignalInfo {
+                name: "k".to_string(),
+                dimensions: vec![],
+                bus_id: None,
+                is_input: true,
+                is_public: false,
+                tags: vec!["binary".to_string()],
+            },
+            MainSignalInfo {
+                name: "out".to_string(),
+                dimensions: vec![3, 2],
+                bus_id: None,
+                is_input: false,
+                is_public: true,
+                tags: vec![],
+            },
+        ];
+        (signals, vec![point], vec!["Point".to_string()])
+    }
+
+    #[test]
+    fn input_schema_describes_buses_and_dimensions() {
+        let (signals, buses, names) = example();
+        let schema: Value =
+            serde_json::from_str(&generate_input_schema("c", &signals, &buses, &names)).unwrap();
+        assert_eq!(schema["required"], json!(["p", "k"]));
+        assert_eq!(schema["properties"]["p"]["maxItems"], json!(2));
+        assert_eq!(schema["properties"]["p"]["items"]["$ref"], json!("#/$defs/Point"));
+        assert_eq!(schema["properties"]["k"]["x-circom"]["role"], json!("private"));
+        assert_eq!(schema["$defs"]["Point"]["required"], json!(["x", "y"]));
+        assert_eq!(schema["$defs"]["Output"]["properties"]["out"]["items"]["maxItems"], json!(2));
+    }
+
+    #[test]
+    fn typescript_declarations_describe_inputs_and_outputs() {
+        let (signals, buses, names) = example();
+        let code = generate_typescript_declarations("c", &signals, &buses, &names);
+        assert!(code.contains("export interface Point {\n    x: FieldElement;"));
+        assert!(code.contains("export interface PointValue {\n    x: bigint;"));
+        assert!(code.contains("    /** public input [2] */\n    p: Point[];"));
+        assert!(code.contains("    /** private input {binary} */\n    k: FieldElement;"));
+        assert!(code.contains("export interface CircuitOutput {\n    /** public output [3][2] */\n    out: bigint[][];"));
+    }
+}
@@ -1,3 +1,5 @@
+pub mod io_description;
+
 use lz_fnv::Fnv1a;
 use std::collections::BTreeMap;
 
@@ -30,6 +32,20 @@ pub struct FieldData{
 
 pub type FieldMap = Vec<Vec<FieldData>>;
 
+// Signal of the main component as declared in the program (buses are not unfolded)
+#[derive(Clone)]
+pub struct MainSignalInfo{
+    pub name: String,
+    pub dimensions: Vec<usize>,
+    pub bus_id: Option<usize>,
+    pub is_input: bool,
+    pub is_public: bool,
+    pub tags: Vec<String>
+}
+
+pub type MainSignalList = Vec<MainSignalInfo>;
+pub type BusNameList = Vec<String>;
+
 pub type InputList = Vec<InputInfo>;
 pub type TemplateList = Vec<String>;
 pub struct InfoParallel{
@@ -75,7 +75,13 @@ module.exports = async function builder(code, options) {
 //        );
 
     
-    wc = new WitnessCalculator(instance, sanityCheck);
+    let outputList = [];
+    const outputSections = WebAssembly.Module.customSections(wasmModule, "circom_outputs");
+    if (outputSections.length > 0) {
+	outputList = JSON.parse(new TextDecoder().decode(outputSections[0]));
+    }
+
+    wc = new WitnessCalculator(instance, sanityCheck, outputList);
     return wc;
 
     function getMessage() {
@@ -106,8 +112,9 @@ module.exports = async function builder(code, options) {
 };
 
 class WitnessCalculator {
-    constructor(instance, sanityCheck) {
+    constructor(instance, sanityCheck, outputList) {
         this.instance = instance;
+	this.outputList = outputList || [];
 
 	this.version = this.instance.exports.getVersion();
         this.n32 = this.instance.exports.getFieldNumLen32();
@@ -191,6 +198,45 @@ class WitnessCalculator {
     }
     
 
+    // Outputs of the main component of the last computed witness, structured by
+    // name, dimensions and bus fields, e.g. { out: [ { x: 1n, y: 2n }, ... ] }
+    getOutputs() {
+	const outputs = {};
+	for (const info of this.outputList) {
+	    const values = [];
+	    for (let i = 0; i < info.size; i++) {
+		values.push(this._getWitnessValue(info.witness + i));
+	    }
+	    setQualified(outputs, parseQualifiedName(info.name), reshape(values, info.dimensions));
+	}
+	return outputs;
+    }
+
+    // Value of a main output given its qualified name, e.g. "out", "out[3]" or "out[3].x"
+    getOutput(name) {
+	let value = this.getOutputs();
+	for (const key of parseQualifiedName(name)) {
+	    if (value === undefined || value === null || typeof value != "object") {
+		value = undefined;
+		break;
+	    }
+	    value = value[key];
+	}
+	if (value === undefined) {
+	    throw new Error(`Output signal ${name} not found\n`);
+	}
+	return value;
+    }
+
+    _getWitnessValue(i) {
+	this.instance.exports.getWitness(i);
+	const arr = new Uint32Array(this.n32);
+	for (let j=0; j<this.n32; j++) {
+	    arr[this.n32-1-j] = this.instance.exports.readSharedRWMemory(j);
+	}
+	return fromArray32(arr);
+    }
+
     async calculateBinWitness(input, sanityCheck) {
 
         const buff32 = new Uint32Array(this.witnessSize*this.n32);
@@ -318,6 +364,40 @@ function qualify_input(prefix,input,input1) {
     }
 }
 
+// "out[3].x" -> ["out", 3, "x"]
+function parseQualifiedName(name) {
+    const keys = [];
+    const re = /([^.\[\]]+)|\[(\d+)\]/g;
+    let m;
+    while ((m = re.exec(name)) !== null) {
+	keys.push(m[1] !== undefined ? m[1] : parseInt(m[2]));
+    }
+    return keys;
+}
+
+function setQualified(obj, keys, value) {
+    let current = obj;
+    for (let i = 0; i < keys.length - 1; i++) {
+	if (current[keys[i]] === undefined) {
+	    current[keys[i]] = typeof keys[i+1] == "number" ? [] : {};
+	}
+	current = current[keys[i]];
+    }
+    current[keys[keys.length - 1]] = value;
+}
+
+function reshape(values, dimensions) {
+    if (dimensions.length == 0) {
+	return values[0];
+    }
+    const size = values.length / dimensions[0];
+    const res = [];
+    for (let i = 0; i < dimensions[0]; i++) {
+	res.push(reshape(values.slice(i*size, (i+1)*size), dimensions.slice(1)));
+    }
+    return res;
+}
+
 function toArray32(rem,size) {
     const res = []; //new Uint32Array(size); //has no unshift
     const radix = BigInt(0x100000000);
@@ -27,6 +27,15 @@ pub fn merge_code(instructions: Vec<String>) -> String {
     code
 }
 
+// Assembles the text of a module into its binary encoding
+pub fn encode_wasm(wat_code: &str) -> Result<Vec<u8>, String> {
+    use wast::parser::{self, ParseBuffer};
+    use wast::Wat;
+    let buf = ParseBuffer::new(wat_code).map_err(|error| format!("Exception encountered when lexing WAT: {}", error))?;
+    let mut wat = parser::parse::<Wat>(&buf).map_err(|error| format!("Exception encountered when parsing WAT: {}", error))?;
+    wat.module.encode().map_err(|error| format!("Exception encountered when encoding WASM: {}", error))
+}
+
 pub fn set_constant(value: &str) -> WasmInstruction {
     format!("i32.const {}", value)
 }
@@ -787,6 +796,34 @@ pub fn generate_data_list(producer: &WASMProducer) -> Vec<WasmInstruction> {
     wdata
 }
 
+// Custom section with the names and witness positions of the main outputs,
+// read by the witness calculator to return the outputs structured by name
+pub fn generate_main_outputs_section(producer: &WASMProducer) -> Vec<WasmInstruction> {
+    use serde_json::json;
+    let witness_list = producer.get_witness_to_signal_list();
+    let mut outputs = vec![];
+    for output in producer.get_main_output_list() {
+        if let Some(witness) = witness_list.iter().position(|signal| *signal == output.start) {
+            outputs.push(json!({
+                "name": output.name,
+                "dimensions": output.dimensions,
+                "witness": witness,
+                "size": output.size
+            }));
+        }
+    }
+    let info = serde_json::to_string(&outputs).unwrap();
+    let mut escaped = String::new();
+    for byte in info.bytes() {
+        if byte == b'"' || byte == b'\\' || !(0x20..0x7f).contains(&byte) {
+            escaped.push_str(&format!("\\{:02x}", byte));
+        } else {
+            escaped.push(byte as char);
+        }
+    }
+    vec![format!("(@custom \"circom_outputs\" \"{}\")", escaped)]
+}
+
 // ------ stack handling operations
 
 pub fn reserve_stack_fr(producer: &WASMProducer, nbytes: usize) -> Vec<WasmInstruction> {
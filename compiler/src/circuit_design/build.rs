@@ -3,10 +3,12 @@ use crate::circuit_design::function::FunctionCodeInfo;
 use crate::circuit_design::template::TemplateCodeInfo;
 use crate::hir::very_concrete_program::*;
 use crate::intermediate_representation::translate;
-use crate::intermediate_representation::translate::{CodeInfo, FieldTracker, TemplateDB, ParallelClusters};
+use crate::intermediate_representation::translate::{CodeInfo, FieldTracker, TemplateDB, ParallelClusters, TagMessages};
 use code_producers::c_elements::*;
 use code_producers::wasm_elements::*;
+use program_structure::ast::SignalType;
 use program_structure::file_definition::FileLibrary;
+use program_structure::progress::{self, ProgressEvent};
 use std::collections::{BTreeMap, HashMap};
 
 #[cfg(debug_assertions)]
@@ -25,7 +27,8 @@ fn build_template_instances(
     c_info: &CircuitInfo,
     ti: Vec<TemplateInstance>,
     mut field_tracker: FieldTracker,
-) -> (FieldTracker, HashMap<String,usize>) {
+    mut tag_messages: TagMessages,
+) -> (FieldTracker, HashMap<String,usize>, TagMessages) {
 
     fn compute_jump(lengths: &Vec<usize>, indexes: &[usize]) -> usize {
         let mut jump = 0;
@@ -42,6 +45,7 @@ fn build_template_instances(
     let mut cmp_id = 0;
     let mut tmp_id = 0;
     let mut string_table = HashMap::new();
+    let no_templates = ti.len();
     for template in ti {
         let header = template.template_header;
         let name = template.template_name;
@@ -99,6 +103,9 @@ fn build_template_instances(
             template_database: &c_info.template_database,
             string_table : string_table,
             signals_to_tags: template.signals_to_tags,
+            check_tags: c_info.check_tags,
+            is_main: tmp_id == c_info.main_id,
+            tag_messages,
         };
         let mut template_info = TemplateCodeInfo {
             name,
@@ -121,12 +128,18 @@ fn build_template_instances(
         template_info.expression_stack_depth = out.expression_depth;
         template_info.var_stack_depth = out.stack_depth;
         template_info.signal_stack_depth = out.signal_depth;
+        template_info.debug_info = out.debug_info;
         string_table = out.string_table;
+        tag_messages = out.tag_messages;
         cmp_id = out.next_cmp_id;
+        if progress::is_reported() {
+            let template = template_info.header.clone();
+            progress::report(ProgressEvent::TemplateGenerated { template, index: tmp_id + 1, templates: no_templates });
+        }
         circuit.add_template_code(template_info);
         tmp_id += 1;
     }
-    (field_tracker, string_table)
+    (field_tracker, string_table, tag_messages)
 }
 
 fn build_function_instances(
@@ -163,7 +176,10 @@ fn build_function_instances(
             template_database: &c_info.template_database,
             string_table : string_table,
             signals_to_tags: BTreeMap::new(),
-            buses: &c_info.buses
+            buses: &c_info.buses,
+            check_tags: false,
+            is_main: false,
+            tag_messages: TagMessages::default(),
         };
         let mut function_info = FunctionCodeInfo {
             name,
@@ -179,6 +195,7 @@ fn build_function_instances(
         function_info.body = out.code;
         function_info.max_number_of_ops_in_expression = out.expression_depth;
         function_info.max_number_of_vars = out.stack_depth;
+        function_info.debug_info = out.debug_info;
         function_to_arena_size.insert(header, function_info.max_number_of_vars);
         circuit.add_function_code(function_info);
     }
@@ -230,7 +247,11 @@ fn initialize_wasm_producer(vcp: &VCP, database: &TemplateDB, wat_flag:bool, ver
         producer.busid_field_info
     ) = get_info_buses(&vcp.buses); 
 
+    producer.bus_name_list = get_bus_names(&vcp.buses);
+
     producer.main_input_list = main_input_list(&vcp.templates[initial_node],&producer.busid_field_info);
+    producer.main_output_list = main_output_list(&vcp.templates[initial_node],&producer.busid_field_info);
+    producer.main_signal_list = main_signal_list(&vcp.templates[initial_node], &vcp.public_inputs);
     producer.io_map = build_io_map(vcp, database);
     producer.template_instance_list = build_template_list(vcp);
     producer.field_tracking.clear();
@@ -271,7 +292,11 @@ fn initialize_c_producer(vcp: &VCP, database: &TemplateDB, version: &str) -> CPr
         producer.busid_field_info
     ) = get_info_buses(&vcp.buses); 
     
+    producer.bus_name_list = get_bus_names(&vcp.buses);
+
     producer.main_input_list = main_input_list(&vcp.templates[initial_node],&producer.busid_field_info);   
+    producer.main_output_list = main_output_list(&vcp.templates[initial_node],&producer.busid_field_info);
+    producer.main_signal_list = main_signal_list(&vcp.templates[initial_node], &vcp.public_inputs);
     producer.io_map = build_io_map(vcp, database);
     producer.template_instance_list = build_template_list_parallel(vcp);
     producer.field_tracking.clear();
@@ -282,6 +307,16 @@ fn initialize_c_producer(vcp: &VCP, database: &TemplateDB, version: &str) -> CPr
 
 fn main_input_list(main: &TemplateInstance, buses: &FieldMap) -> InputList {
     use program_structure::ast::SignalType::*;
+    main_io_list(main, buses, Input)
+}
+
+// Only the signals (and bus fields) that are not buses themselves
+fn main_output_list(main: &TemplateInstance, buses: &FieldMap) -> InputList {
+    use program_structure::ast::SignalType::*;
+    main_io_list(main, buses, Output).into_iter().filter(|io| io.bus_id.is_none()).collect()
+}
+
+fn main_io_list(main: &TemplateInstance, buses: &FieldMap, xtype: SignalType) -> InputList {
     use crate::hir::very_concrete_program::Wire::*;
     fn build_info_wire(wire: &Wire) -> InputInfo{
         match wire{
@@ -387,7 +422,7 @@ fn main_input_list(main: &TemplateInstance, buses: &FieldMap) -> InputList {
     }
     let mut input_list = vec![];
     for s in &main.wires {
-        if s.xtype() == Input {         
+        if s.xtype() == xtype {
             input_list.push(build_info_wire(s));
         }
     }
@@ -411,6 +446,30 @@ fn main_input_list(main: &TemplateInstance, buses: &FieldMap) -> InputList {
     input_list_with_qualifiers
 }
 
+fn main_signal_list(main: &TemplateInstance, public_inputs: &[String]) -> MainSignalList {
+    use program_structure::ast::SignalType::*;
+    let mut signal_list = vec![];
+    for wire in &main.wires {
+        if wire.xtype() == Intermediate {
+            continue;
+        }
+        let tags = if let Some(tags) = main.signals_to_tags.get(wire.name()) {
+            tags.keys().cloned().collect()
+        } else {
+            vec![]
+        };
+        signal_list.push(MainSignalInfo {
+            name: wire.name().clone(),
+            dimensions: wire.lengths().clone(),
+            bus_id: wire.bus_id(),
+            is_input: wire.xtype() == Input,
+            is_public: wire.xtype() == Output || public_inputs.contains(wire.name()),
+            tags,
+        });
+    }
+    signal_list
+}
+
 fn build_template_list(vcp: &VCP) -> TemplateList {
     let mut tmp_list = MessageList::new();
     for instance in &vcp.templates {
@@ -570,11 +629,17 @@ fn get_info_buses(buses: &Vec<BusInstance>)->(usize, FieldMap){
     (n_buses, bus_to_fields_data)
 }
 
+fn get_bus_names(buses: &[BusInstance]) -> BusNameList {
+    buses.iter().map(|bus| bus.name.clone()).collect()
+}
+
 struct CircuitInfo {
     file_library: FileLibrary,
     functions: HashMap<String, Vec<usize>>,
     template_database: TemplateDB,
-    buses: Vec<BusInstance>
+    buses: Vec<BusInstance>,
+    check_tags: bool,
+    main_id: usize,
 }
 
 pub fn build_circuit(vcp: VCP, flag: CompilationFlags, version: &str) -> Circuit {
@@ -594,13 +659,19 @@ pub fn build_circuit(vcp: VCP, flag: CompilationFlags, version: &str) -> Circuit
         template_database,
         file_library: vcp.file_library,
         functions: vcp.quick_knowledge,
-        buses: vcp.buses
+        buses: vcp.buses,
+        check_tags: flag.check_tags,
+        main_id: vcp.main_id,
     };
+    // the messages of the tag checks go after the ones of the templates and functions
+    let tag_messages = TagMessages { first_id: vcp.templates.len() + vcp.functions.len(), messages: vec![] };
 
-    let (field_tracker, string_table) =
-        build_template_instances(&mut circuit, &circuit_info, vcp.templates, field_tracker);
+    let (field_tracker, string_table, mut tag_messages) =
+        build_template_instances(&mut circuit, &circuit_info, vcp.templates, field_tracker, tag_messages);
     let (field_tracker, function_to_arena_size, table_string_to_usize) =
         build_function_instances(&mut circuit, &circuit_info, vcp.functions, field_tracker,string_table);
+    circuit.wasm_producer.message_list.append(&mut tag_messages.messages);
+    circuit.c_producer.message_list = circuit.wasm_producer.message_list.clone();
 
     let table_usize_to_string = create_table_usize_to_string(table_string_to_usize);
     circuit.wasm_producer.set_string_table(table_usize_to_string.clone());
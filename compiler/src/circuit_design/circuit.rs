@@ -10,6 +10,7 @@ use std::io::Write;
 pub struct CompilationFlags {
     pub main_inputs_log: bool,
     pub wat_flag:bool,
+    pub check_tags: bool,
 }
 
 pub struct Circuit {
@@ -127,170 +128,74 @@ impl WriteWasm for Circuit {
         code_aux = generate_data_list(&producer);
         code.append(&mut code_aux);
 
+        code_aux = generate_main_outputs_section(&producer);
+        code.append(&mut code_aux);
+
         code.push(")".to_string());
         code
     }
 
     fn write_wasm<T: Write>(&self, writer: &mut T, producer: &WASMProducer) -> Result<(), ()> {
-        use code_producers::wasm_elements::wasm_code_generator::*;
-
+        use code_producers::wasm_elements::wasm_code_generator::merge_code;
         writer.write_all("(module".as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        let mut code_aux = generate_imports_list();
-        let mut code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        code_aux = generate_memory_def_list(&producer);
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        code_aux = fr_types(&producer.prime_str);
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        code_aux = generate_types_list();
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        code_aux = generate_exports_list();
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        code_aux = fr_code(&producer.prime_str);
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        code_aux = desp_io_subcomponent_generator(&producer);
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        code_aux = get_version_generator(&producer);
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        code_aux = get_shared_rw_memory_start_generator(&producer);
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        code_aux = read_shared_rw_memory_generator(&producer);
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        code_aux = write_shared_rw_memory_generator(&producer);
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        code_aux = reserve_stack_fr_function_generator();
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        code_aux = init_generator(&producer);
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        code_aux = set_input_signal_generator(&producer);
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        code_aux = get_input_signal_size_generator(&producer);
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        code_aux = get_raw_prime_generator(&producer);
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        code_aux = get_field_num_len32_generator(&producer);
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        code_aux = get_input_size_generator(&producer);
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-	
-        code_aux = get_witness_size_generator(&producer);
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        code_aux = get_witness_generator(&producer);
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        code_aux = copy_32_in_shared_rw_memory_generator(&producer);
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        code_aux = copy_fr_in_shared_rw_memory_generator(&producer);
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        code_aux = get_message_char_generator(&producer);
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        code_aux = build_buffer_message_generator(&producer);
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
+        self.generate_wasm_module(producer, &mut |code| {
+            writer.write_all(merge_code(code).as_bytes()).map_err(|err| err.to_string())
+        })
+        .map_err(|_| {})?;
+        writer.write_all(")".as_bytes()).map_err(|_| {})?;
+        writer.flush().map_err(|_| {})
+    }
+}
 
-        code_aux = build_log_message_generator(&producer);
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
+impl Circuit {
+    // Generates the fields of the wasm module in order and gives them one by one to emit,
+    // so the code of every template is merged before the next one is generated
+    fn generate_wasm_module(
+        &self,
+        producer: &WASMProducer,
+        emit: &mut dyn FnMut(Vec<String>) -> Result<(), String>,
+    ) -> Result<(), String> {
+        use code_producers::wasm_elements::wasm_code_generator::*;
+        emit(generate_imports_list())?;
+        emit(generate_memory_def_list(producer))?;
+        emit(fr_types(&producer.prime_str))?;
+        emit(generate_types_list())?;
+        emit(generate_exports_list())?;
+        emit(fr_code(&producer.prime_str))?;
+        emit(desp_io_subcomponent_generator(producer))?;
+        emit(get_version_generator(producer))?;
+        emit(get_shared_rw_memory_start_generator(producer))?;
+        emit(read_shared_rw_memory_generator(producer))?;
+        emit(write_shared_rw_memory_generator(producer))?;
+        emit(reserve_stack_fr_function_generator())?;
+        emit(init_generator(producer))?;
+        emit(set_input_signal_generator(producer))?;
+        emit(get_input_signal_size_generator(producer))?;
+        emit(get_raw_prime_generator(producer))?;
+        emit(get_field_num_len32_generator(producer))?;
+        emit(get_input_size_generator(producer))?;
+        emit(get_witness_size_generator(producer))?;
+        emit(get_witness_generator(producer))?;
+        emit(copy_32_in_shared_rw_memory_generator(producer))?;
+        emit(copy_fr_in_shared_rw_memory_generator(producer))?;
+        emit(get_message_char_generator(producer))?;
+        emit(build_buffer_message_generator(producer))?;
+        emit(build_log_message_generator(producer))?;
 
         // Actual code from the program
 
         for f in &self.functions {
-            f.write_wasm(writer, producer)?;
-            //writer.flush().map_err(|_| {})?;
+            emit(f.produce_wasm(producer))?;
         }
 
         for t in &self.templates {
-            t.write_wasm(writer, producer)?;
-            //writer.flush().map_err(|_| {})?;
+            emit(t.produce_wasm(producer))?;
         }
 
-        code_aux = generate_table_of_template_runs(&producer);
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        code_aux = fr_data(&producer.prime_str);
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        code_aux = generate_data_list(&producer);
-        code = merge_code(code_aux);
-        writer.write_all(code.as_bytes()).map_err(|_| {})?;
-        //writer.flush().map_err(|_| {})?;
-
-        writer.write_all(")".as_bytes()).map_err(|_| {})?;
-        writer.flush().map_err(|_| {})
+        emit(generate_table_of_template_runs(producer))?;
+        emit(fr_data(&producer.prime_str))?;
+        emit(generate_data_list(producer))?;
+        emit(generate_main_outputs_section(producer))
     }
 }
 
@@ -364,6 +269,7 @@ impl WriteC for Circuit {
             "uint get_size_of_bus_field_map() {{return {};}}\n", 
             producer.get_busid_field_info().len()
         ));
+        code.append(&mut generate_main_output_list(producer));
         //code.append(&mut generate_message_list_def(producer, producer.get_message_list()));
         
         // Functions to release the memory
@@ -487,6 +393,7 @@ impl WriteC for Circuit {
             "uint get_size_of_bus_field_map() {{return {};}}\n", 
             producer.get_busid_field_info().len()
         ));
+        code.append(&mut generate_main_output_list(producer));
         //code.append(&mut generate_message_list_def(producer, producer.get_message_list()));
         
         // Functions to release the memory
@@ -601,13 +508,42 @@ impl Circuit {
         c_code_generator::generate_fr_asm_file(&c_folder_path, &self.c_producer.prime_str).map_err(|_err| {})?;
         c_code_generator::generate_make_file(&c_folder_path,run_name,&self.c_producer).map_err(|_err| {})?;
         c_code_generator::generate_dat_file(c_dat, &self.c_producer).map_err(|_err| {})?;
+        io_description::generate_io_description_files(
+            &c_folder_path,
+            run_name,
+            self.c_producer.get_main_signal_list(),
+            self.c_producer.get_busid_field_info(),
+            self.c_producer.get_bus_name_list(),
+        ).map_err(|_err| {})?;
         self.write_c(c_circuit, &self.c_producer)
     }
-    pub fn produce_wasm<W: Write>(&self, js_folder: &str, _wasm_name: &str, writer: &mut W) -> Result<(), ()> {
-	use std::path::Path;
-	let js_folder_path = Path::new(js_folder).to_path_buf();
-        wasm_code_generator::generate_generate_witness_js_file(&js_folder_path).map_err(|_err| {})?;
-        wasm_code_generator::generate_witness_calculator_js_file(&js_folder_path).map_err(|_err| {})?;
-        self.write_wasm(writer, &self.wasm_producer)
+    // Writes the js files of the witness calculator and the module in the given writers: the
+    // wat text is generated in memory, written when there is a writer for it and assembled
+    // with wast when there is a writer for the binary
+    pub fn produce_wasm<W: Write>(&self, js_folder: &str, wasm_name: &str, wasm_writer: Option<&mut W>, wat_writer: Option<&mut W>) -> Result<(), String> {
+        use std::path::Path;
+        let js_folder_path = Path::new(js_folder).to_path_buf();
+        wasm_code_generator::generate_generate_witness_js_file(&js_folder_path).map_err(|err| err.to_string())?;
+        wasm_code_generator::generate_witness_calculator_js_file(&js_folder_path).map_err(|err| err.to_string())?;
+        io_description::generate_io_description_files(
+            &js_folder_path,
+            wasm_name,
+            self.wasm_producer.get_main_signal_list(),
+            self.wasm_producer.get_busid_field_info(),
+            self.wasm_producer.get_bus_name_list(),
+        ).map_err(|err| err.to_string())?;
+        let mut wat_code = vec![];
+        self.write_wasm(&mut wat_code, &self.wasm_producer).map_err(|_| "Error generating the wat code".to_string())?;
+        if let Some(wat_writer) = wat_writer {
+            wat_writer.write_all(&wat_code).map_err(|err| err.to_string())?;
+            wat_writer.flush().map_err(|err| err.to_string())?;
+        }
+        if let Some(wasm_writer) = wasm_writer {
+            let wat_code = String::from_utf8(wat_code).map_err(|err| err.to_string())?;
+            let wasm_code = wasm_code_generator::encode_wasm(&wat_code)?;
+            wasm_writer.write_all(&wasm_code).map_err(|err| err.to_string())?;
+            wasm_writer.flush().map_err(|err| err.to_string())?;
+        }
+        Result::Ok(())
     }
 }
@@ -0,0 +1,27 @@
+use super::types::*;
+
+// Source symbols of a template or function together with the memory they are stored in.
+// The code producers ignore it, it is used to show the state of an execution.
+#[derive(Clone, Default)]
+pub struct DebugInfo {
+    pub variables: Vec<SymbolDebugInfo>,
+    pub signals: Vec<SymbolDebugInfo>,
+    pub components: Vec<SymbolDebugInfo>,
+}
+
+#[derive(Clone)]
+pub struct SymbolDebugInfo {
+    pub name: String,
+    // position in the variables, signals or subcomponents of the instance
+    pub address: Address,
+    pub dimensions: Vec<Dimension>,
+    pub size: Size,
+    // first and last line in which the symbol is visible
+    pub scope: (usize, usize),
+}
+
+impl SymbolDebugInfo {
+    pub fn is_visible_at(&self, line: usize) -> bool {
+        self.scope.0 <= line && line <= self.scope.1
+    }
+}
@@ -1,3 +1,4 @@
+use super::debug_info::DebugInfo;
 use super::types::*;
 use crate::hir::very_concrete_program::Param;
 use crate::intermediate_representation::InstructionList;
@@ -16,6 +17,7 @@ pub struct FunctionCodeInfo {
     pub body: InstructionList,
     pub max_number_of_vars: usize,
     pub max_number_of_ops_in_expression: usize,
+    pub debug_info: DebugInfo,
 }
 
 impl ToString for FunctionCodeInfo {
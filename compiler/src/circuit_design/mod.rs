@@ -1,5 +1,6 @@
 mod build;
 pub mod circuit;
+pub mod debug_info;
 pub mod function;
 pub mod template;
 pub mod types;
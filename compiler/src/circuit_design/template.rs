@@ -1,3 +1,4 @@
+use super::debug_info::DebugInfo;
 use crate::intermediate_representation::InstructionList;
 use crate::translating_traits::*;
 use code_producers::c_elements::*;
@@ -23,6 +24,7 @@ pub struct TemplateCodeInfo {
     pub expression_stack_depth: usize,
     pub signal_stack_depth: usize, // Not used now
     pub number_of_components: usize,
+    pub debug_info: DebugInfo,
 }
 impl ToString for TemplateCodeInfo {
     fn to_string(&self) -> String {
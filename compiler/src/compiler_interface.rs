@@ -7,10 +7,15 @@ pub struct Config {
     pub debug_output: bool,
     pub produce_input_log: bool,
     pub wat_flag: bool,
+    pub check_tags: bool,
 }
 
 pub fn run_compiler(vcp: VCP, config: Config, version: &str) -> Result<Circuit, ()> {
-    let flags = CompilationFlags { main_inputs_log: config.produce_input_log, wat_flag: config.wat_flag };
+    let flags = CompilationFlags {
+        main_inputs_log: config.produce_input_log,
+        wat_flag: config.wat_flag,
+        check_tags: config.check_tags,
+    };
     let circuit = Circuit::build(vcp, flags, version);
     if config.debug_output {
         produce_debug_output(&circuit)?;
@@ -18,15 +23,20 @@ pub fn run_compiler(vcp: VCP, config: Config, version: &str) -> Result<Circuit,
     Ok(circuit)
 }
 
-pub fn write_wasm(circuit: &Circuit, js_folder: &str, wasm_name: &str, file: &str) -> Result<(), ()> {
+// The errors are given as messages, so the caller can report them as failed translations
+pub fn write_wasm(circuit: &Circuit, js_folder: &str, wasm_name: &str, wasm_file: Option<&str>, wat_file: Option<&str>) -> Result<(), String> {
     use std::path::Path;
     if Path::new(js_folder).is_dir() {
-        std::fs::remove_dir_all(js_folder).map_err(|_err| {})?;
+        std::fs::remove_dir_all(js_folder).map_err(|err| err.to_string())?;
     }
-    std::fs::create_dir(js_folder).map_err(|_err| {})?;
-    let file = File::create(file).map_err(|_err| {})?;
-    let mut writer = BufWriter::new(file);
-    circuit.produce_wasm(js_folder, wasm_name, &mut writer)
+    std::fs::create_dir(js_folder).map_err(|err| err.to_string())?;
+    let create = |file: Option<&str>| match file {
+        Some(file) => File::create(file).map(|file| Some(BufWriter::new(file))).map_err(|err| err.to_string()),
+        None => Result::Ok(None),
+    };
+    let mut wasm_writer = create(wasm_file)?;
+    let mut wat_writer = create(wat_file)?;
+    circuit.produce_wasm(js_folder, wasm_name, wasm_writer.as_mut(), wat_writer.as_mut())
 }
 
 pub fn write_c(circuit: &Circuit, c_folder: &str, c_run_name: &str, c_file: &str, dat_file: &str) -> Result<(), ()> {
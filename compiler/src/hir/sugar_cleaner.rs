@@ -451,6 +451,7 @@ fn rmv_sugar(fresh_variable: &str, expr: Expression, buffer: &mut Vec<Statement>
     let declaration = Declaration {
         meta: declaration_meta,
         is_constant: true,
+        is_kept: false,
         xtype: VariableType::Var,
         name: fresh_variable.to_string(),
         dimensions: vec![],
@@ -562,6 +563,7 @@ fn split_return(stmt: Statement, id: usize) -> ReturnSplit {
             name: id.to_string(),
             dimensions: expr_lengths,
             is_constant: false,
+            is_kept: false,
         };
         let substitution = Substitution {
             meta: substitution_meta,
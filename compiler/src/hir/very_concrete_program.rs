@@ -308,10 +308,11 @@ pub struct VCP {
     pub templates_in_mixed: Vec<usize>,
     pub prime: String,
     pub buses: Vec<BusInstance>,
-
+    pub public_inputs: Vec<String>,
 }
 impl VCP {
     pub fn new(config: VCPConfig) -> VCP {
+        let public_inputs = config.program.get_public_inputs_main_component().clone();
         let mut vcp = VCP {
             stats: config.stats,
             main_id: config.main_id,
@@ -322,7 +323,8 @@ impl VCP {
             functions: vec![],
             quick_knowledge: HashMap::new(),
             prime: config.prime,
-            buses: config.buses
+            buses: config.buses,
+            public_inputs,
         };
         super::merger::run_preprocessing(&mut vcp, config.program);
         vcp
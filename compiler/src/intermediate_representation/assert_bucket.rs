@@ -8,6 +8,8 @@ pub struct AssertBucket {
     pub line: usize,
     pub message_id: usize,
     pub evaluate: InstructionPointer,
+    // the failure is reported with the message instead of the name of the template
+    pub with_message: bool,
 }
 
 impl IntoInstruction for AssertBucket {
@@ -71,7 +73,12 @@ impl WriteC for AssertBucket {
         use c_code_generator::*;
         let (prologue, value) = self.evaluate.produce_c(producer, parallel);
         let is_true = build_call("Fr_isTrue".to_string(), vec![value]);
-        let if_condition = format!("if (!{}) {};", is_true, build_failed_assert_message(self.line));    
+        let failed_message = if self.with_message {
+            build_failed_check_message(&producer.get_message_list()[self.message_id], self.line)
+        } else {
+            build_failed_assert_message(self.line)
+        };
+        let if_condition = format!("if (!{}) {};", is_true, failed_message);
         let assertion = format!("{};", build_call("assert".to_string(), vec![is_true]));
         let mut assert_c = prologue;
         assert_c.push(if_condition);
@@ -2,12 +2,13 @@ use super::ir_interface::*;
 use crate::hir::very_concrete_program::*;
 use crate::intermediate_representation::log_bucket::LogBucketArg;
 use crate::intermediate_representation::types::SizeOption;
+use crate::circuit_design::debug_info::{DebugInfo, SymbolDebugInfo};
 use constant_tracking::ConstantTracker;
 use num_bigint_dig::BigInt;
 use program_structure::ast::*;
 use program_structure::file_definition::FileLibrary;
 use program_structure::utils::environment::VarEnvironment;
-use std::collections::{HashMap, BTreeMap, HashSet};
+use std::collections::{HashMap, BTreeMap, BTreeSet};
 use std::mem;
 
 type Length = usize;
@@ -36,6 +37,25 @@ pub struct WireInfo{
 
 
 
+// Messages of the checks of the tags, placed after the ones of the templates and functions
+#[derive(Default)]
+pub struct TagMessages {
+    pub first_id: usize,
+    pub messages: Vec<String>,
+}
+impl TagMessages {
+    fn message_id(&mut self, message: String) -> usize {
+        let index = match self.messages.iter().position(|m| *m == message) {
+            Some(index) => index,
+            None => {
+                self.messages.push(message);
+                self.messages.len() - 1
+            }
+        };
+        self.first_id + index
+    }
+}
+
 #[derive(Clone)]
 pub struct TemplateDB {
     // one per template instance
@@ -122,7 +142,8 @@ struct State {
     field_tracker: FieldTracker,
     environment: E,
     component_to_parallel:  HashMap<String, ParallelClusters>,
-    component_to_instance: HashMap<String, HashSet<usize>>,
+    // ordered, the sizes of the possible instances are written in this order
+    component_to_instance: HashMap<String, BTreeSet<usize>>,
     signal_to_type: HashMap<String, SignalType>,
     signal_to_tags: BTreeMap<String, TagInfo>,
     message_id: usize,
@@ -134,6 +155,12 @@ struct State {
     code: InstructionList,
     // string_table
     string_table: HashMap<String, usize>,
+    debug_info: DebugInfo,
+    // last line of the block being translated
+    scope_end: usize,
+    // runtime checks of the tags, generated with --check-tags
+    check_tags: bool,
+    tag_messages: TagMessages,
 }
 
 impl State {
@@ -159,6 +186,10 @@ impl State {
             max_stack_depth: 0,
             code: vec![],
             string_table : HashMap::new(),
+            debug_info: DebugInfo::default(),
+            scope_end: usize::MAX,
+            check_tags: false,
+            tag_messages: TagMessages::default(),
         }
     }
     fn reserve(fresh: &mut usize, size: usize) -> usize {
@@ -175,6 +206,16 @@ impl State {
         ret
     }
 
+    fn debug_symbol(&self, name: &str, address: usize, dimensions: &[Length], starts_at: usize) -> SymbolDebugInfo {
+        SymbolDebugInfo {
+            name: name.to_string(),
+            address,
+            dimensions: dimensions.to_vec(),
+            size: dimensions.iter().product(),
+            scope: (starts_at, self.scope_end),
+        }
+    }
+
     fn reserve_component_address(&mut self, size: usize) -> usize {
         State::reserve(&mut self.component_address_stack, size)
     }
@@ -185,7 +226,7 @@ impl State {
 }
 
 struct Context<'a> {
-    _translating: String,
+    translating: String,
     files: &'a FileLibrary,
     tmp_database: &'a TemplateDB,
     _functions: &'a HashMap<String, Vec<Length>>,
@@ -198,6 +239,8 @@ fn initialize_parameters(state: &mut State, params: Vec<Param>) {
         let lengths = p.length;
         let full_size = lengths.iter().fold(1, |p, s| p * (*s));
         let address = state.reserve_variable(full_size);
+        let debug_symbol = state.debug_symbol(&p.name, address, &lengths, 0);
+        state.debug_info.variables.push(debug_symbol);
         let address_instruction = ValueBucket {
             line: 0,
             message_id: 0,
@@ -224,6 +267,8 @@ fn initialize_constants(state: &mut State, constants: Vec<Argument>) {
         let dimensions = arg.lengths;
         let size = dimensions.iter().fold(1, |p, c| p * (*c));
         let address = state.reserve_variable(size);
+        let debug_symbol = state.debug_symbol(&arg.name, address, &dimensions, 0);
+        state.debug_info.variables.push(debug_symbol);
         let address_instruction = ValueBucket {
             line: 0,
             message_id: 0,
@@ -294,6 +339,9 @@ fn initialize_signals(state: &mut State, wires: Vec<Wire>) {
         let address = state.reserve_signal(size);
         let dimensions =  wire.lengths().clone();
         let name = wire.name().clone();
+        let mut debug_symbol = state.debug_symbol(&name, address, &dimensions, 0);
+        debug_symbol.size = size;
+        state.debug_info.signals.push(debug_symbol);
         let xtype = wire.xtype();
 
         let (is_bus, bus_id) = match wire{
@@ -331,6 +379,8 @@ fn initialize_components(state: &mut State, components: Vec<Component>) {
     for component in components {
         let size = component.size();
         let address = state.reserve_component_address(size);
+        let debug_symbol = state.debug_symbol(&component.name, address, &component.lengths, 0);
+        state.debug_info.components.push(debug_symbol);
         let instruction = ValueBucket {
             line: 0,
             message_id: state.message_id,
@@ -361,7 +411,7 @@ fn create_components(state: &mut State, triggers: &[Trigger], clusters: Vec<Trig
                 info.insert(trigger.template_id);
             }
             None =>{
-                let mut new_info = HashSet::new();
+                let mut new_info = BTreeSet::new();
                 new_info.insert(trigger.template_id);
                 state.component_to_instance.insert(trigger.component_name.clone(), new_info);
             }
@@ -572,8 +622,13 @@ fn translate_while(stmt: Statement, state: &mut State, context: &Context) {
 
 fn translate_substitution(stmt: Statement, state: &mut State, context: &Context) {
     use Statement::Substitution;
-    if let Substitution { meta, var, access, rhe, .. } = stmt {
+    if let Substitution { meta, var, access, op, rhe } = stmt {
         debug_assert!(!meta.get_type_knowledge().is_component());
+        let checked = if op == AssignOp::AssignSignal && has_tag_checks(&var, &access, state) {
+            Some(SymbolDef { meta: meta.clone(), symbol: var.clone(), acc: access.clone() })
+        } else {
+            None
+        };
         let def = SymbolDef { meta: meta.clone(), symbol: var, acc: access };
         let str_info =
             StoreInfo { prc_symbol: ProcessedSymbol::new(def, state, context), src: rhe };
@@ -583,11 +638,158 @@ fn translate_substitution(stmt: Statement, state: &mut State, context: &Context)
             translate_standard_case(str_info, state, context)
         };
         state.code.push(store_instruction);
+        // values assigned with <-- are not constrained to satisfy the tags of the signal
+        if let Some(def) = checked {
+            let name = def.symbol.clone();
+            let symbol = ProcessedSymbol::new(def, state, context);
+            let line = symbol.line;
+            let loads = symbol.into_signal_loads(state);
+            translate_tag_checks(&name, line, loads, state, context);
+        }
     } else {
         unreachable!();
     }
 }
 
+// Start of tag checks utils
+fn is_checked_tag(tag: &str, value: &Option<BigInt>) -> bool {
+    match tag {
+        "binary" => true,
+        "maxbit" | "max" | "min" => value.is_some(),
+        _ => false,
+    }
+}
+
+fn has_tag_checks(name: &str, access: &[Access], state: &State) -> bool {
+    let is_signal = match state.environment.get_variable(name) {
+        Some(info) => !info.is_component && !info.is_bus && state.signal_to_type.contains_key(name),
+        None => false,
+    };
+    let is_tagged = match state.signal_to_tags.get(name) {
+        Some(tags) => tags.iter().any(|(tag, value)| is_checked_tag(tag, value)),
+        None => false,
+    };
+    state.check_tags
+        && is_signal
+        && is_tagged
+        && access.iter().all(|a| matches!(a, Access::ArrayAccess(_)))
+}
+
+// Condition that the value of a signal satisfies to carry the tag, max and min compare the
+// values as circom does
+fn tag_condition(
+    tag: &str,
+    value: &Option<BigInt>,
+    signal: &InstructionPointer,
+    line: usize,
+    state: &mut State,
+) -> InstructionPointer {
+    let message_id = state.message_id;
+    let mut constant = |value: &BigInt| {
+        let cid = bigint_to_cid(&mut state.field_tracker, value);
+        ValueBucket { line, message_id, op_aux_no: 0, parse_as: ValueType::BigInt, value: cid }.allocate()
+    };
+    let compute = |op: OperatorType, stack: Vec<InstructionPointer>| {
+        ComputeBucket { line, message_id, op_aux_no: 0, op, stack }.allocate()
+    };
+    let zero = constant(&BigInt::from(0));
+    match (tag, value) {
+        ("binary", _) => {
+            let one = constant(&BigInt::from(1));
+            let is_zero = compute(OperatorType::Eq(1), vec![signal.clone(), zero]);
+            let is_one = compute(OperatorType::Eq(1), vec![signal.clone(), one]);
+            compute(OperatorType::BoolOr, vec![is_zero, is_one])
+        }
+        ("maxbit", Some(bits)) => {
+            let bits = constant(bits);
+            let shifted = compute(OperatorType::ShiftR, vec![signal.clone(), bits]);
+            compute(OperatorType::Eq(1), vec![shifted, zero])
+        }
+        ("max", Some(max)) => {
+            let max = constant(max);
+            let lower = compute(OperatorType::GreaterEq, vec![signal.clone(), zero]);
+            let upper = compute(OperatorType::LesserEq, vec![signal.clone(), max]);
+            compute(OperatorType::BoolAnd, vec![lower, upper])
+        }
+        ("min", Some(min)) => {
+            let min = constant(min);
+            compute(OperatorType::GreaterEq, vec![signal.clone(), min])
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn translate_tag_checks(
+    name: &str,
+    line: usize,
+    loads: Vec<InstructionPointer>,
+    state: &mut State,
+    context: &Context,
+) {
+    let tags = state.signal_to_tags.get(name).cloned().unwrap_or_default();
+    for (tag, value) in tags.iter().filter(|(tag, value)| is_checked_tag(tag, value)) {
+        let tag_description = match value {
+            Some(value) => format!("{} = {}", tag, value),
+            None => tag.clone(),
+        };
+        let message = format!(
+            "Error in template {}: signal {} does not satisfy tag {}",
+            context.translating, name, tag_description
+        );
+        let message_id = state.tag_messages.message_id(message);
+        for load in &loads {
+            let evaluate = tag_condition(tag, value, load, line, state);
+            let check = AssertBucket { line, message_id, evaluate, with_message: true }.allocate();
+            state.code.push(check);
+        }
+    }
+}
+
+// Checks of the tagged inputs of the main component, whose values are not given by a template
+fn translate_main_input_tag_checks(
+    body: &Statement,
+    inputs: Vec<String>,
+    state: &mut State,
+    context: &Context,
+) {
+    // the declarations of the signals are not in the body, the checks point to the template
+    let meta = body.get_meta();
+    let line = context.files.get_line(meta.start, meta.get_file_id()).unwrap_or(0);
+    for name in inputs {
+        if !has_tag_checks(&name, &[], state) {
+            continue;
+        }
+        let symbol = state.environment.get_variable(&name).unwrap().clone();
+        let loads = signal_loads(symbol.access_instruction, symbol.size, line, state.message_id);
+        translate_tag_checks(&name, line, loads, state, context);
+    }
+}
+
+// Loads of each one of the signals stored from the given address
+fn signal_loads(address: InstructionPointer, size: usize, line: usize, message_id: usize) -> Vec<InstructionPointer> {
+    let mut loads = vec![];
+    for offset in 0..size {
+        let location = if offset == 0 {
+            address.clone()
+        } else {
+            let offset = ValueBucket { line, message_id, parse_as: ValueType::U32, op_aux_no: 0, value: offset }.allocate();
+            ComputeBucket { line, message_id, op_aux_no: 0, op: OperatorType::AddAddress, stack: vec![address.clone(), offset] }
+                .allocate()
+        };
+        let load = LoadBucket {
+            line,
+            message_id,
+            address_type: AddressType::Signal,
+            src: LocationRule::Indexed { location, template_header: None },
+            context: InstrContext { size: SizeOption::Single(1) },
+        }
+        .allocate();
+        loads.push(load);
+    }
+    loads
+}
+// End of tag checks utils
+
 // Start of substitution utils
 struct StoreInfo {
     prc_symbol: ProcessedSymbol,
@@ -626,6 +828,8 @@ fn translate_declaration(stmt: Statement, state: &mut State, context: &Context)
         let dimensions = meta.get_memory_knowledge().get_concrete_dimensions().to_vec();
         let size = dimensions.iter().fold(1, |p, c| p * (*c));
         let address = state.reserve_variable(size);
+        let debug_symbol = state.debug_symbol(&name, address, &dimensions, starts_at);
+        state.debug_info.variables.push(debug_symbol);
         let instruction = ValueBucket {
             line: starts_at,
             message_id: state.message_id,
@@ -650,14 +854,17 @@ fn translate_declaration(stmt: Statement, state: &mut State, context: &Context)
 
 fn translate_block(stmt: Statement, state: &mut State, context: &Context) {
     use Statement::Block;
-    if let Block { stmts, .. } = stmt {
+    if let Block { stmts, meta } = stmt {
         let save_variable_address = state.variable_stack;
+        let save_scope_end = state.scope_end;
+        state.scope_end = context.files.get_line(meta.end, meta.get_file_id()).unwrap_or(usize::MAX);
         state.environment.add_variable_block();
         for s in stmts {
             translate_statement(s, state, context);
         }
         state.environment.remove_variable_block();
         state.variable_stack = save_variable_address;
+        state.scope_end = save_scope_end;
     } else {
         unreachable!()
     }
@@ -690,7 +897,7 @@ fn translate_constraint_equality(stmt: Statement, state: &mut State, context: &C
         }
         .allocate();
         let assert_instruction =
-            AssertBucket { line: starts_at, message_id: state.message_id, evaluate: equality }
+            AssertBucket { line: starts_at, message_id: state.message_id, evaluate: equality, with_message: false }
                 .allocate();
         state.code.push(assert_instruction);
     } else {
@@ -703,7 +910,7 @@ fn translate_assert(stmt: Statement, state: &mut State, context: &Context) {
     if let Assert { meta, arg, .. } = stmt {
         let line = context.files.get_line(meta.start, meta.get_file_id()).unwrap();
         let code = translate_expression(arg, state, context);
-        let assert = AssertBucket { line, message_id: state.message_id, evaluate: code }.allocate();
+        let assert = AssertBucket { line, message_id: state.message_id, evaluate: code, with_message: false }.allocate();
         state.code.push(assert);
     }
 }
@@ -1436,6 +1643,23 @@ impl ProcessedSymbol {
         }
     }
 
+    // Loads of each one of the signals of a local symbol
+    fn into_signal_loads(self, state: &State) -> Vec<InstructionPointer> {
+        let size = match self.length {
+            SizeOption::Single(size) => size,
+            SizeOption::Multiple(_) => unreachable!("Local signals have a single size"),
+        };
+        let address = compute_full_address(
+            state,
+            self.symbol.access_instruction,
+            self.symbol_dimensions,
+            self.symbol_size,
+            self.bus_accesses,
+            self.before_signal,
+        );
+        signal_loads(address, size, self.line, self.message_id)
+    }
+
     fn into_load(self, state: &State) -> InstructionPointer {
         if let Option::Some(signal) = self.signal {
             let dest_type = AddressType::SubcmpSignal {
@@ -1862,7 +2086,10 @@ pub struct CodeInfo<'a> {
     pub component_to_parallel: HashMap<String, ParallelClusters>,
     pub string_table: HashMap<String, usize>,
     pub signals_to_tags: BTreeMap<String, TagInfo>,
-    pub buses: &'a Vec<BusInstance>
+    pub buses: &'a Vec<BusInstance>,
+    pub check_tags: bool,
+    pub is_main: bool,
+    pub tag_messages: TagMessages,
 }
 
 pub struct CodeOutput {
@@ -1873,6 +2100,8 @@ pub struct CodeOutput {
     pub code: InstructionList,
     pub constant_tracker: FieldTracker,
     pub string_table: HashMap<String, usize>,
+    pub debug_info: DebugInfo,
+    pub tag_messages: TagMessages,
 }
 
 pub fn translate_code(body: Statement, code_info: CodeInfo) -> CodeOutput {
@@ -1885,6 +2114,13 @@ pub fn translate_code(body: Statement, code_info: CodeInfo) -> CodeOutput {
         code_info.signals_to_tags,
     );
     state.string_table = code_info.string_table;
+    state.check_tags = code_info.check_tags;
+    state.tag_messages = code_info.tag_messages;
+    let main_inputs: Vec<String> = if code_info.is_main {
+        code_info.wires.iter().filter(|w| w.xtype() == SignalType::Input).map(|w| w.name().clone()).collect()
+    } else {
+        vec![]
+    };
     initialize_components(&mut state, code_info.components);
     initialize_signals(&mut state, code_info.wires);
     initialize_constants(&mut state, code_info.constants);
@@ -1892,13 +2128,14 @@ pub fn translate_code(body: Statement, code_info: CodeInfo) -> CodeOutput {
 
     let context = Context {
         files: code_info.files,
-        _translating: code_info.header,
+        translating: code_info.header,
         _functions: code_info.functions,
         cmp_to_type: code_info.cmp_to_type,
         tmp_database: code_info.template_database,
         buses: code_info.buses
     };
 
+    translate_main_input_tag_checks(&body, main_inputs, &mut state, &context);
     create_components(&mut state, &code_info.triggers, code_info.clusters);
     translate_statement(body, &mut state, &context);
 
@@ -1915,7 +2152,9 @@ pub fn translate_code(body: Statement, code_info: CodeInfo) -> CodeOutput {
         stack_depth: state.max_stack_depth,
         signal_depth: state.signal_stack,
         constant_tracker: state.field_tracker,
-        string_table : state.string_table
+        string_table : state.string_table,
+        debug_info: state.debug_info,
+        tag_messages: state.tag_messages,
     }
 }
 
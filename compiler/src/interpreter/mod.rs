@@ -0,0 +1,998 @@
+// Interpreter of the intermediate representation of a circuit. It computes the
+// witness following the same memory model as the C and wasm witness calculators:
+// every component has a block of signals starting at its signal start, the
+// subcomponents are stored by their position in the father and the inputs of a
+// subcomponent are counted down until it can be run.
+use crate::circuit_design::circuit::Circuit;
+use crate::circuit_design::debug_info::DebugInfo;
+use crate::circuit_design::function::FunctionCodeInfo;
+use crate::circuit_design::template::TemplateCodeInfo;
+use crate::intermediate_representation::ir_interface::*;
+use circom_algebra::modular_arithmetic::{self, ArithmeticError};
+use code_producers::components::{FieldMap, InputList, TemplateInstanceIOMap};
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use std::collections::HashMap;
+
+pub enum ErrorCause {
+    FailedAssert,
+    // checks added by the compiler, like the ones of the tags, fail with their own message
+    FailedCheck(String),
+    FailedEquality { left: Vec<BigInt>, right: Vec<BigInt> },
+    UnassignedSignal(usize),
+    ComponentNotCreated,
+    DivisionByZero,
+    ShiftOverflow,
+    NonAddressValue(BigInt),
+    Interrupted,
+}
+
+pub struct ExecutionError {
+    pub cause: ErrorCause,
+    pub line: usize,
+    // name in the source of the template or function being executed
+    pub symbol: String,
+    // "Error in template X" or "Error in function Y" as in the message list of the circuit
+    pub context: String,
+    // names of the components from the main component to the failing one
+    pub trace: String,
+}
+
+impl ExecutionError {
+    pub fn message(&self) -> String {
+        format!("{}. {} line {}. Followed trace of components: {}", self.cause_message(), self.context, self.line, self.trace)
+    }
+
+    pub fn cause_message(&self) -> String {
+        use ErrorCause::*;
+        match &self.cause {
+            FailedAssert => "Assert failed".to_string(),
+            FailedCheck(message) => message.clone(),
+            FailedEquality { left, right } => format!(
+                "Constraint does not hold: {} != {}",
+                values_to_string(left),
+                values_to_string(right)
+            ),
+            UnassignedSignal(signal) => format!("Reading signal {} before it is assigned", signal),
+            ComponentNotCreated => "Accessing a component that has not been created".to_string(),
+            DivisionByZero => "Division by zero".to_string(),
+            ShiftOverflow => "Bit overflow in shift".to_string(),
+            NonAddressValue(value) => format!("The value {} cannot be used as an index", value),
+            Interrupted => "The execution was interrupted".to_string(),
+        }
+    }
+}
+
+fn values_to_string(values: &[BigInt]) -> String {
+    if values.len() == 1 {
+        values[0].to_string()
+    } else {
+        let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+        format!("[{}]", values.join(", "))
+    }
+}
+
+// Notified before the execution of each statement, it can inspect the state of the interpreter
+pub trait ExecutionObserver {
+    // returns false to interrupt the execution
+    fn on_step(&mut self, interpreter: &Interpreter) -> bool;
+    // the state of the interpreter is kept when the execution fails
+    fn on_finish(&mut self, _interpreter: &Interpreter, _error: Option<&ExecutionError>) {}
+}
+
+pub struct StackFrame {
+    // name in the source of the template or function
+    pub symbol: String,
+    pub line: usize,
+    pub is_function: bool,
+    pub component: usize,
+}
+
+pub struct SymbolValue {
+    pub name: String,
+    pub dimensions: Vec<usize>,
+    // unassigned signals have no value
+    pub values: Vec<Option<BigInt>>,
+}
+
+impl SymbolValue {
+    // Nests the values following the dimensions of the symbol, the elements of a bus are written between braces
+    pub fn values_to_string(&self) -> String {
+        fn nest(values: &[Option<BigInt>], dimensions: &[usize]) -> String {
+            if let Some((length, rest)) = dimensions.split_first() {
+                let chunk = values.len() / (*length).max(1);
+                let elements: Vec<String> = values.chunks(chunk.max(1)).map(|c| nest(c, rest)).collect();
+                return format!("[{}]", elements.join(", "));
+            }
+            let elements: Vec<String> = values
+                .iter()
+                .map(|v| v.as_ref().map_or("?".to_string(), |v| v.to_string()))
+                .collect();
+            if elements.len() == 1 {
+                elements[0].clone()
+            } else {
+                format!("{{{}}}", elements.join(", "))
+            }
+        }
+        nest(&self.values, &self.dimensions)
+    }
+}
+
+pub struct SubcomponentValue {
+    pub name: String,
+    // id of the component, none if it has not been created yet
+    pub component: Option<usize>,
+}
+
+struct Component {
+    template_id: usize,
+    signal_start: usize,
+    input_counter: usize,
+    subcomponents: Vec<Option<usize>>,
+    name: String,
+    father: Option<usize>,
+}
+
+struct Frame {
+    component: usize,
+    message_id: usize,
+    lvars: Vec<BigInt>,
+    // line of the statement being executed
+    line: usize,
+}
+
+#[derive(Clone, Copy)]
+enum Place {
+    Variable(usize),
+    Signal(usize),
+    Constant(usize),
+}
+
+enum Value {
+    Address(usize),
+    At(Place),
+    Element(BigInt),
+}
+
+enum Flow {
+    Continue,
+    Return(Vec<BigInt>),
+}
+
+type ExecutionResult<T> = Result<T, Box<ExecutionError>>;
+
+pub struct Interpreter<'a> {
+    circuit: &'a Circuit,
+    field: BigInt,
+    constants: Vec<BigInt>,
+    // functions by header, with the id of their error message
+    functions: HashMap<&'a str, (&'a FunctionCodeInfo, usize)>,
+    io_map: &'a TemplateInstanceIOMap,
+    buses: &'a FieldMap,
+    messages: &'a [String],
+    strings: &'a [String],
+    main_inputs: &'a InputList,
+    signals: Vec<Option<BigInt>>,
+    components: Vec<Option<Component>>,
+    // operands of the last comparison, used to explain failed constraints
+    last_comparison: Option<(Vec<BigInt>, Vec<BigInt>)>,
+    logs: Vec<String>,
+    frames: Vec<Frame>,
+    observer: Option<Box<dyn ExecutionObserver + 'a>>,
+}
+
+impl<'a> Interpreter<'a> {
+    pub fn new(circuit: &'a Circuit) -> Interpreter<'a> {
+        let producer = &circuit.c_producer;
+        let field = BigInt::parse_bytes(producer.get_prime().as_bytes(), 10).unwrap();
+        let constants = producer
+            .get_field_constant_list()
+            .iter()
+            .map(|c| BigInt::parse_bytes(c.as_bytes(), 10).unwrap())
+            .collect();
+        let mut functions = HashMap::new();
+        // the messages of the templates are followed by the ones of the functions
+        for (i, function) in circuit.functions.iter().enumerate() {
+            functions.insert(function.header.as_str(), (function.as_ref(), circuit.templates.len() + i));
+        }
+        let mut signals = vec![None; producer.get_total_number_of_signals()];
+        signals[0] = Some(BigInt::from(1));
+        let mut components = Vec::with_capacity(producer.get_number_of_components());
+        components.resize_with(producer.get_number_of_components(), || None);
+        Interpreter {
+            circuit,
+            field,
+            constants,
+            functions,
+            io_map: producer.get_io_map(),
+            buses: producer.get_busid_field_info(),
+            messages: circuit.wasm_producer.get_message_list(),
+            strings: producer.get_string_table(),
+            main_inputs: producer.get_main_input_list(),
+            signals,
+            components,
+            last_comparison: None,
+            logs: vec![],
+            frames: vec![],
+            observer: None,
+        }
+    }
+
+    pub fn get_field(&self) -> &BigInt {
+        &self.field
+    }
+
+    // Sets the values of an input of the main component, the name is the one used in the input json
+    pub fn set_input(&mut self, name: &str, values: &[BigInt]) -> Result<(), String> {
+        let input = self
+            .main_inputs
+            .iter()
+            .find(|i| i.name == name)
+            .ok_or_else(|| format!("The main component has no input named {}", name))?;
+        if input.size != values.len() {
+            return Err(format!(
+                "The input {} expects {} values but {} were given",
+                name,
+                input.size,
+                values.len()
+            ));
+        }
+        for (i, value) in values.iter().enumerate() {
+            let value = modular_arithmetic::add(value, &BigInt::from(0), &self.field);
+            self.signals[input.start + i] = Some(value);
+        }
+        Ok(())
+    }
+
+    pub fn run(&mut self) -> ExecutionResult<()> {
+        let main_header = self.circuit.c_producer.get_main_header();
+        let main = self.circuit.templates.iter().find(|t| t.header == main_header).unwrap();
+        let main_start = self.circuit.c_producer.get_main_signal_offset();
+        self.create_component(main.id, main_start, 0, "main".to_string(), None);
+        let result = self.run_component(0);
+        if let Some(mut observer) = self.observer.take() {
+            observer.on_finish(self, result.as_ref().err().map(|error| error.as_ref()));
+            self.observer = Some(observer);
+        }
+        result
+    }
+
+    pub fn signal_values(&self) -> &[Option<BigInt>] {
+        &self.signals
+    }
+
+    pub fn logs(&self) -> &[String] {
+        &self.logs
+    }
+
+    pub fn set_observer(&mut self, observer: Box<dyn ExecutionObserver + 'a>) {
+        self.observer = Some(observer);
+    }
+
+    // Frames being executed, the innermost one is the last
+    pub fn call_stack(&self) -> Vec<StackFrame> {
+        let templates = self.circuit.templates.len();
+        self.frames
+            .iter()
+            .map(|frame| StackFrame {
+                symbol: self.symbol(frame.message_id).to_string(),
+                line: frame.line,
+                is_function: frame.message_id >= templates,
+                component: frame.component,
+            })
+            .collect()
+    }
+
+    // Variables of a frame of the call stack that are visible in its current line
+    pub fn variables(&self, frame: usize) -> Vec<SymbolValue> {
+        let frame = &self.frames[frame];
+        let mut variables: Vec<SymbolValue> = vec![];
+        for symbol in &self.debug_info(frame.message_id).variables {
+            if !symbol.is_visible_at(frame.line) {
+                continue;
+            }
+            let values = frame.lvars[symbol.address..symbol.address + symbol.size].iter().cloned().map(Some).collect();
+            let value = SymbolValue { name: symbol.name.clone(), dimensions: symbol.dimensions.clone(), values };
+            // inner declarations shadow the outer ones
+            match variables.iter_mut().find(|v| v.name == symbol.name) {
+                Some(shadowed) => *shadowed = value,
+                None => variables.push(value),
+            }
+        }
+        variables
+    }
+
+    pub fn signals(&self, component: usize) -> Vec<SymbolValue> {
+        let component = match self.components.get(component).and_then(Option::as_ref) {
+            Some(component) => component,
+            None => return vec![],
+        };
+        let template = self.template(component.template_id);
+        template
+            .debug_info
+            .signals
+            .iter()
+            .map(|symbol| {
+                let start = component.signal_start + symbol.address;
+                SymbolValue {
+                    name: symbol.name.clone(),
+                    dimensions: symbol.dimensions.clone(),
+                    values: self.signals[start..start + symbol.size].to_vec(),
+                }
+            })
+            .collect()
+    }
+
+    pub fn subcomponents(&self, component: usize) -> Vec<SubcomponentValue> {
+        let component = match self.components.get(component).and_then(Option::as_ref) {
+            Some(component) => component,
+            None => return vec![],
+        };
+        let mut subcomponents = vec![];
+        for symbol in &self.template(component.template_id).debug_info.components {
+            for position in 0..symbol.size {
+                let mut name = symbol.name.clone();
+                name.push_str(&array_position(&symbol.dimensions, position));
+                let component = component.subcomponents.get(symbol.address + position).cloned().flatten();
+                subcomponents.push(SubcomponentValue { name, component });
+            }
+        }
+        subcomponents
+    }
+
+    // Name of the template of a component and the number of its inputs that have not been set
+    pub fn component_state(&self, component: usize) -> Option<(&str, usize)> {
+        let component = self.components.get(component)?.as_ref()?;
+        Some((&self.template(component.template_id).name, component.input_counter))
+    }
+
+    fn template(&self, id: usize) -> &'a TemplateCodeInfo {
+        self.circuit.templates[id].as_ref()
+    }
+
+    fn create_component(
+        &mut self,
+        template_id: usize,
+        signal_start: usize,
+        id: usize,
+        name: String,
+        father: Option<usize>,
+    ) {
+        let template = self.template(template_id);
+        self.components[id] = Some(Component {
+            template_id,
+            signal_start,
+            input_counter: template.number_of_inputs,
+            subcomponents: vec![None; template.number_of_components],
+            name,
+            father,
+        });
+    }
+
+    fn frame(&self) -> &Frame {
+        self.frames.last().unwrap()
+    }
+
+    fn frame_mut(&mut self) -> &mut Frame {
+        self.frames.last_mut().unwrap()
+    }
+
+    fn symbol(&self, message_id: usize) -> &str {
+        let templates = &self.circuit.templates;
+        match templates.get(message_id) {
+            Some(template) => &template.name,
+            None => &self.circuit.functions[message_id - templates.len()].name,
+        }
+    }
+
+    fn debug_info(&self, message_id: usize) -> &'a DebugInfo {
+        let templates = &self.circuit.templates;
+        match templates.get(message_id) {
+            Some(template) => &template.debug_info,
+            None => &self.circuit.functions[message_id - templates.len()].debug_info,
+        }
+    }
+
+    fn component(&self, id: usize) -> &Component {
+        self.components[id].as_ref().unwrap()
+    }
+
+    fn run_component(&mut self, id: usize) -> ExecutionResult<()> {
+        let template = self.template(self.component(id).template_id);
+        self.frames.push(Frame {
+            component: id,
+            message_id: template.id,
+            lvars: vec![BigInt::from(0); template.var_stack_depth],
+            line: 0,
+        });
+        self.execute_list(&template.body)?;
+        // the frames are kept on errors so the state of the failure can be inspected
+        self.frames.pop();
+        Ok(())
+    }
+
+    // Names of the components from the main component to the given one
+    pub fn component_path(&self, id: usize) -> String {
+        let mut names = vec![];
+        let mut current = Some(id);
+        while let Some(id) = current {
+            let component = self.component(id);
+            names.push(component.name.clone());
+            current = component.father;
+        }
+        names.reverse();
+        names.join(".")
+    }
+
+    fn error(&self, line: usize, cause: ErrorCause) -> Box<ExecutionError> {
+        let message_id = self.frame().message_id;
+        Box::new(ExecutionError {
+            cause,
+            line,
+            symbol: self.symbol(message_id).to_string(),
+            context: self.messages.get(message_id).cloned().unwrap_or_default(),
+            trace: self.component_path(self.frame().component),
+        })
+    }
+
+    fn execute_list(&mut self, list: &'a InstructionList) -> ExecutionResult<Flow> {
+        for instruction in list {
+            if let Flow::Return(values) = self.execute(instruction)? {
+                return Ok(Flow::Return(values));
+            }
+        }
+        Ok(Flow::Continue)
+    }
+
+    fn step(&mut self, line: usize) -> ExecutionResult<()> {
+        self.frame_mut().line = line;
+        // the instructions added by the compiler have no line
+        if line == 0 {
+            return Ok(());
+        }
+        if let Some(mut observer) = self.observer.take() {
+            let proceed = observer.on_step(self);
+            self.observer = Some(observer);
+            if !proceed {
+                return Err(self.error(line, ErrorCause::Interrupted));
+            }
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, instruction: &'a Instruction) -> ExecutionResult<Flow> {
+        use Instruction::*;
+        self.step(instruction.get_line())?;
+        match instruction {
+            Store(bucket) => self.execute_store(bucket)?,
+            Call(bucket) => {
+                self.execute_call(bucket)?;
+            }
+            CreateCmp(bucket) => self.execute_create_cmp(bucket)?,
+            Branch(bucket) => {
+                let condition = self.evaluate_condition(&bucket.cond)?;
+                let branch = if condition { &bucket.if_branch } else { &bucket.else_branch };
+                return self.execute_list(branch);
+            }
+            Loop(bucket) => {
+                while self.evaluate_condition(&bucket.continue_condition)? {
+                    if let Flow::Return(values) = self.execute_list(&bucket.body)? {
+                        return Ok(Flow::Return(values));
+                    }
+                }
+            }
+            Return(bucket) => {
+                let value = self.evaluate(&bucket.value)?;
+                let values = self.read(&value, bucket.with_size.max(1), bucket.line)?;
+                return Ok(Flow::Return(values));
+            }
+            Assert(bucket) => {
+                self.last_comparison = None;
+                if !self.evaluate_condition(&bucket.evaluate)? {
+                    let cause = match self.last_comparison.take() {
+                        _ if bucket.with_message => {
+                            ErrorCause::FailedCheck(self.messages[bucket.message_id].clone())
+                        }
+                        Some((left, right)) if is_comparison(&bucket.evaluate) => {
+                            ErrorCause::FailedEquality { left, right }
+                        }
+                        _ => ErrorCause::FailedAssert,
+                    };
+                    return Err(self.error(bucket.line, cause));
+                }
+            }
+            Log(bucket) => {
+                let mut printed = vec![];
+                for argument in &bucket.argsprint {
+                    match argument {
+                        LogBucketArg::LogExp(expression) => {
+                            let value = self.evaluate(expression)?;
+                            printed.push(self.read(&value, 1, bucket.line)?[0].to_string());
+                        }
+                        LogBucketArg::LogStr(id) => printed.push(self.strings[*id].clone()),
+                    }
+                }
+                self.logs.push(printed.join(" "));
+            }
+            Value(_) | Load(_) | Compute(_) => {
+                self.evaluate(instruction)?;
+            }
+        }
+        Ok(Flow::Continue)
+    }
+
+    fn evaluate(&mut self, instruction: &'a Instruction) -> ExecutionResult<Value> {
+        use Instruction::*;
+        match instruction {
+            Value(bucket) => match bucket.parse_as {
+                ValueType::U32 => Ok(self::Value::Address(bucket.value)),
+                ValueType::BigInt => Ok(self::Value::At(Place::Constant(bucket.value))),
+            },
+            Load(bucket) => {
+                let (place, _) = self.place(&bucket.address_type, &bucket.src, bucket.line)?;
+                Ok(self::Value::At(place))
+            }
+            Compute(bucket) => self.evaluate_compute(bucket),
+            Call(bucket) => {
+                let values = self.execute_call(bucket)?;
+                Ok(self::Value::Element(values.into_iter().next().unwrap_or_default()))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn evaluate_condition(&mut self, instruction: &'a Instruction) -> ExecutionResult<bool> {
+        let value = self.evaluate(instruction)?;
+        let value = self.read(&value, 1, instruction.get_line())?;
+        Ok(modular_arithmetic::as_bool(&value[0], &self.field))
+    }
+
+    fn evaluate_address(&mut self, instruction: &'a Instruction) -> ExecutionResult<usize> {
+        match self.evaluate(instruction)? {
+            Value::Address(address) => Ok(address),
+            value => {
+                let value = self.read(&value, 1, instruction.get_line())?.remove(0);
+                self.to_address(value, instruction.get_line())
+            }
+        }
+    }
+
+    fn to_address(&self, value: BigInt, line: usize) -> ExecutionResult<usize> {
+        value.to_usize().ok_or_else(|| self.error(line, ErrorCause::NonAddressValue(value)))
+    }
+
+    fn evaluate_compute(&mut self, bucket: &'a ComputeBucket) -> ExecutionResult<Value> {
+        use modular_arithmetic as ma;
+        use OperatorType::*;
+        let mut operands = vec![];
+        for instruction in &bucket.stack {
+            operands.push(self.evaluate(instruction)?);
+        }
+        if bucket.op.is_address_op() {
+            let mut addresses = vec![];
+            for operand in operands {
+                let address = match operand {
+                    Value::Address(address) => address,
+                    value => {
+                        let value = self.read(&value, 1, bucket.line)?.remove(0);
+                        self.to_address(value, bucket.line)?
+                    }
+                };
+                addresses.push(address);
+            }
+            let result = match bucket.op {
+                AddAddress => addresses[0] + addresses[1],
+                MulAddress => addresses[0] * addresses[1],
+                _ => addresses[0],
+            };
+            return Ok(Value::Address(result));
+        }
+        if let Eq(n) = bucket.op {
+            let left = self.read(&operands[0], n.max(1), bucket.line)?;
+            let right = self.read(&operands[1], n.max(1), bucket.line)?;
+            let equal = left.iter().zip(right.iter()).all(|(l, r)| ma::eq(l, r, &self.field) == BigInt::from(1));
+            self.last_comparison = Some((left, right));
+            return Ok(Value::Element(BigInt::from(equal as u8)));
+        }
+        let mut values = vec![];
+        for operand in &operands {
+            values.push(self.read(operand, 1, bucket.line)?.remove(0));
+        }
+        let field = &self.field;
+        let result = match bucket.op {
+            Mul => Ok(ma::mul(&values[0], &values[1], field)),
+            Div => ma::div(&values[0], &values[1], field),
+            Add => Ok(ma::add(&values[0], &values[1], field)),
+            Sub => Ok(ma::sub(&values[0], &values[1], field)),
+            Pow => Ok(ma::pow(&values[0], &values[1], field)),
+            IntDiv => ma::idiv(&values[0], &values[1], field),
+            Mod => ma::mod_op(&values[0], &values[1], field),
+            ShiftL => ma::shift_l(&values[0], &values[1], field),
+            ShiftR => ma::shift_r(&values[0], &values[1], field),
+            LesserEq => Ok(ma::lesser_eq(&values[0], &values[1], field)),
+            GreaterEq => Ok(ma::greater_eq(&values[0], &values[1], field)),
+            Lesser => Ok(ma::lesser(&values[0], &values[1], field)),
+            Greater => Ok(ma::greater(&values[0], &values[1], field)),
+            NotEq => Ok(ma::not_eq(&values[0], &values[1], field)),
+            BoolOr => Ok(ma::bool_or(&values[0], &values[1], field)),
+            BoolAnd => Ok(ma::bool_and(&values[0], &values[1], field)),
+            BitOr => Ok(ma::bit_or(&values[0], &values[1], field)),
+            BitAnd => Ok(ma::bit_and(&values[0], &values[1], field)),
+            BitXor => Ok(ma::bit_xor(&values[0], &values[1], field)),
+            PrefixSub => Ok(ma::prefix_sub(&values[0], field)),
+            BoolNot => Ok(ma::not(&values[0], field)),
+            Complement => Ok(ma::complement(&values[0], field)),
+            Eq(_) | ToAddress | MulAddress | AddAddress => unreachable!(),
+        };
+        match result {
+            Ok(value) => Ok(Value::Element(value)),
+            Err(ArithmeticError::DivisionByZero) => {
+                Err(self.error(bucket.line, ErrorCause::DivisionByZero))
+            }
+            Err(ArithmeticError::BitOverFlowInShift) => {
+                Err(self.error(bucket.line, ErrorCause::ShiftOverflow))
+            }
+        }
+    }
+
+    // Position of the subcomponent stored in the given index of the current component
+    fn subcomponent(&mut self, cmp_address: &'a Instruction, line: usize) -> ExecutionResult<usize> {
+        let index = self.evaluate_address(cmp_address)?;
+        match self.component(self.frame().component).subcomponents.get(index) {
+            Some(Some(id)) => Ok(*id),
+            _ => Err(self.error(line, ErrorCause::ComponentNotCreated)),
+        }
+    }
+
+    // Returns the place together with the subcomponent it belongs to, if any
+    fn place(
+        &mut self,
+        address_type: &'a AddressType,
+        location: &'a LocationRule,
+        line: usize,
+    ) -> ExecutionResult<(Place, Option<usize>)> {
+        match address_type {
+            AddressType::Variable => Ok((Place::Variable(self.location_index(location, None)?), None)),
+            AddressType::Signal => {
+                let index = self.location_index(location, None)?;
+                Ok((Place::Signal(self.component(self.frame().component).signal_start + index), None))
+            }
+            AddressType::SubcmpSignal { cmp_address, .. } => {
+                let id = self.subcomponent(cmp_address, line)?;
+                let index = self.location_index(location, Some(id))?;
+                Ok((Place::Signal(self.component(id).signal_start + index), Some(id)))
+            }
+        }
+    }
+
+    // Index of a location relative to the start of the memory it belongs to. Mapped
+    // locations are resolved with the io map of the template of the subcomponent
+    fn location_index(
+        &mut self,
+        location: &'a LocationRule,
+        subcomponent: Option<usize>,
+    ) -> ExecutionResult<usize> {
+        match location {
+            LocationRule::Indexed { location, .. } => self.evaluate_address(location),
+            LocationRule::Mapped { signal_code, indexes } => {
+                let template_id = self.component(subcomponent.unwrap()).template_id;
+                let definition = self.io_map[&template_id].iter().find(|d| d.code == *signal_code).unwrap();
+                let mut offset = definition.offset;
+                let mut lengths = &definition.lengths;
+                let mut size = definition.size;
+                let mut bus_id = definition.bus_id;
+                for access in indexes {
+                    match access {
+                        AccessType::Indexed(info) => {
+                            let mut index = 0;
+                            for (i, instruction) in info.indexes.iter().enumerate() {
+                                let value = self.evaluate_address(instruction)?;
+                                index = if i == 0 { value } else { index * lengths[i] + value };
+                            }
+                            for length in lengths.iter().take(info.symbol_dim).skip(info.indexes.len()) {
+                                index *= length;
+                            }
+                            offset += index * size;
+                        }
+                        AccessType::Qualified(field) => {
+                            let field = &self.buses[bus_id.unwrap()][*field];
+                            offset += field.offset;
+                            lengths = &field.dimensions;
+                            size = field.size;
+                            bus_id = field.bus_id;
+                        }
+                    }
+                }
+                Ok(offset)
+            }
+        }
+    }
+
+    fn read(&self, value: &Value, size: usize, line: usize) -> ExecutionResult<Vec<BigInt>> {
+        match value {
+            Value::Address(address) => Ok(vec![BigInt::from(*address)]),
+            Value::Element(element) => Ok(vec![element.clone()]),
+            Value::At(Place::Constant(index)) => Ok(self.constants[*index..*index + size].to_vec()),
+            Value::At(Place::Variable(index)) => Ok(self.frame().lvars[*index..*index + size].to_vec()),
+            Value::At(Place::Signal(index)) => {
+                let mut values = Vec::with_capacity(size);
+                for signal in *index..*index + size {
+                    match &self.signals[signal] {
+                        Some(value) => values.push(value.clone()),
+                        None => return Err(self.error(line, ErrorCause::UnassignedSignal(signal))),
+                    }
+                }
+                Ok(values)
+            }
+        }
+    }
+
+    fn write(&mut self, place: Place, values: Vec<BigInt>) {
+        for (i, value) in values.into_iter().enumerate() {
+            match place {
+                Place::Variable(index) => self.frame_mut().lvars[index + i] = value,
+                Place::Signal(index) => self.signals[index + i] = Some(value),
+                Place::Constant(_) => unreachable!(),
+            }
+        }
+    }
+
+    fn size(&self, size: &SizeOption, subcomponent: Option<usize>) -> usize {
+        match size {
+            SizeOption::Single(value) => *value,
+            SizeOption::Multiple(values) => {
+                let template_id = self.component(subcomponent.unwrap()).template_id;
+                values.iter().find(|(id, _)| *id == template_id).map_or(0, |(_, size)| *size)
+            }
+        }
+    }
+
+    // Writes the values in the destination, running the subcomponent if all its inputs are set
+    fn store_values(
+        &mut self,
+        address_type: &'a AddressType,
+        (place, subcomponent): (Place, Option<usize>),
+        values: Vec<BigInt>,
+    ) -> ExecutionResult<()> {
+        let size = values.len();
+        self.write(place, values);
+        if let AddressType::SubcmpSignal { input_information: InputInformation::Input { status }, .. } = address_type {
+            let id = subcomponent.unwrap();
+            let component = self.components[id].as_mut().unwrap();
+            component.input_counter = component.input_counter.saturating_sub(size);
+            let run = match status {
+                StatusInput::NoLast => false,
+                StatusInput::Last => true,
+                StatusInput::Unknown => component.input_counter == 0,
+            };
+            if run {
+                self.run_component(id)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn execute_store(&mut self, bucket: &'a StoreBucket) -> ExecutionResult<()> {
+        let dest = self.place(&bucket.dest_address_type, &bucket.dest, bucket.line)?;
+        let src_component = match &bucket.src_address_type {
+            Some(cmp_address) => Some(self.subcomponent(cmp_address, bucket.line)?),
+            None => None,
+        };
+        let size = std::cmp::min(
+            self.size(&bucket.context.size, dest.1),
+            self.size(&bucket.src_context.size, src_component),
+        );
+        let src = self.evaluate(&bucket.src)?;
+        let values = self.read(&src, size.max(1), bucket.line)?;
+        self.store_values(&bucket.dest_address_type, dest, values)
+    }
+
+    fn execute_call(&mut self, bucket: &'a CallBucket) -> ExecutionResult<Vec<BigInt>> {
+        let (function, message_id) = self.functions[bucket.symbol.as_str()];
+        let mut lvars = Vec::with_capacity(bucket.arena_size);
+        for (argument, argument_type) in bucket.arguments.iter().zip(bucket.argument_types.iter()) {
+            let size = self.size(&argument_type.size, None);
+            let value = self.evaluate(argument)?;
+            lvars.append(&mut self.read(&value, size.max(1), bucket.line)?);
+        }
+        lvars.resize(bucket.arena_size.max(lvars.len()), BigInt::from(0));
+        self.frames.push(Frame { component: self.frame().component, message_id, lvars, line: bucket.line });
+        let values = match self.execute_list(&function.body)? {
+            Flow::Return(values) => values,
+            Flow::Continue => vec![],
+        };
+        self.frames.pop();
+        if let ReturnType::Final(data) = &bucket.return_info {
+            let dest = self.place(&data.dest_address_type, &data.dest, bucket.line)?;
+            let size = self.size(&data.context.size, dest.1).max(1).min(values.len());
+            let values = values[..size].to_vec();
+            self.store_values(&data.dest_address_type, dest, values)?;
+            return Ok(vec![]);
+        }
+        Ok(values)
+    }
+
+    fn execute_create_cmp(&mut self, bucket: &'a CreateCmpBucket) -> ExecutionResult<()> {
+        let first = self.evaluate_address(&bucket.sub_cmp_id)?;
+        let positions: Vec<usize> = if bucket.defined_positions.len() == bucket.number_of_cmp {
+            (0..bucket.number_of_cmp).collect()
+        } else {
+            bucket.defined_positions.iter().map(|(position, _)| *position).collect()
+        };
+        let father = self.frame().component;
+        let mut id = bucket.component_offset + father + 1;
+        let mut signal_start = self.component(father).signal_start + bucket.signal_offset;
+        for position in positions {
+            let mut name = bucket.name_subcomponent.clone();
+            if bucket.number_of_cmp > 1 {
+                name.push_str(&array_position(&bucket.dimensions, position));
+            }
+            self.create_component(bucket.template_id, signal_start, id, name, Some(father));
+            self.components[father].as_mut().unwrap().subcomponents[first + position] = Some(id);
+            if !bucket.has_inputs {
+                self.run_component(id)?;
+            }
+            signal_start += bucket.signal_offset_jump;
+            id += bucket.component_offset_jump;
+        }
+        Ok(())
+    }
+}
+
+fn is_comparison(instruction: &Instruction) -> bool {
+    matches!(instruction, Instruction::Compute(ComputeBucket { op: OperatorType::Eq(_), .. }))
+}
+
+// Writes the position of an element of a flattened array as [i][j]..
+fn array_position(dimensions: &[usize], mut index: usize) -> String {
+    let mut position = String::new();
+    for length in dimensions.iter().rev() {
+        position = format!("[{}]{}", index % length, position);
+        index /= length;
+    }
+    position
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use code_producers::components::InputInfo;
+
+    // Offsets of the signals of the only component of the circuits below, which starts at signal 1
+    const OUT: usize = 0;
+    const IN: usize = 1;
+
+    fn constant(index: usize) -> InstructionPointer {
+        ValueBucket { line: 0, message_id: 0, parse_as: ValueType::BigInt, op_aux_no: 0, value: index }.allocate()
+    }
+
+    fn address(value: usize) -> InstructionPointer {
+        ValueBucket { line: 0, message_id: 0, parse_as: ValueType::U32, op_aux_no: 0, value }.allocate()
+    }
+
+    fn signal(offset: usize) -> InstructionPointer {
+        LoadBucket {
+            line: 0,
+            message_id: 0,
+            address_type: AddressType::Signal,
+            src: LocationRule::Indexed { location: address(offset), template_header: None },
+            context: InstrContext { size: SizeOption::Single(1) },
+        }
+        .allocate()
+    }
+
+    fn compute(op: OperatorType, stack: Vec<InstructionPointer>) -> InstructionPointer {
+        ComputeBucket { line: 0, message_id: 0, op, op_aux_no: 0, stack }.allocate()
+    }
+
+    fn store(line: usize, offset: usize, src: InstructionPointer) -> InstructionPointer {
+        StoreBucket {
+            line,
+            message_id: 0,
+            context: InstrContext { size: SizeOption::Single(1) },
+            src_context: InstrContext { size: SizeOption::Single(1) },
+            dest_is_output: offset == OUT,
+            dest_address_type: AddressType::Signal,
+            src_address_type: None,
+            dest: LocationRule::Indexed { location: address(offset), template_header: None },
+            src,
+        }
+        .allocate()
+    }
+
+    fn assert_equal(line: usize, left: InstructionPointer, right: InstructionPointer) -> InstructionPointer {
+        let evaluate = compute(OperatorType::Eq(1), vec![left, right]);
+        AssertBucket { line, message_id: 0, evaluate, with_message: false }.allocate()
+    }
+
+    // A main component with an input, an output and the given body and constants
+    fn circuit(body: InstructionList, constants: &[&str]) -> Circuit {
+        let mut circuit = Circuit::default();
+        let producer = &mut circuit.c_producer;
+        producer.main_header = "Main_0".to_string();
+        producer.main_signal_offset = 1;
+        producer.total_number_of_signals = 3;
+        producer.number_of_components = 1;
+        producer.field_tracking = constants.iter().map(|c| c.to_string()).collect();
+        producer.main_input_list = vec![InputInfo { name: "in".to_string(), dimensions: vec![], start: 2, size: 1, bus_id: None }];
+        let template = TemplateCodeInfo {
+            header: "Main_0".to_string(),
+            name: "Main".to_string(),
+            number_of_inputs: 1,
+            number_of_outputs: 1,
+            body,
+            ..TemplateCodeInfo::default()
+        };
+        circuit.templates.push(Box::new(template));
+        circuit
+    }
+
+    fn run(circuit: &Circuit, input: i64) -> (Vec<Option<BigInt>>, Option<Box<ExecutionError>>) {
+        let mut interpreter = Interpreter::new(circuit);
+        interpreter.set_input("in", &[BigInt::from(input)]).unwrap();
+        let error = interpreter.run().err();
+        (interpreter.signal_values().to_vec(), error)
+    }
+
+    #[test]
+    fn arithmetic_is_computed_in_the_field() {
+        // out <== (in - 5) / in
+        let difference = compute(OperatorType::Sub, vec![signal(IN), constant(0)]);
+        let body = vec![store(1, OUT, compute(OperatorType::Div, vec![difference, signal(IN)]))];
+        let circuit = circuit(body, &["5"]);
+        let (signals, error) = run(&circuit, 2);
+        assert!(error.is_none());
+        let field = BigInt::parse_bytes(circuit.c_producer.get_prime().as_bytes(), 10).unwrap();
+        let out = signals[1].clone().unwrap();
+        assert!(out >= BigInt::from(0) && out < field);
+        assert_eq!((out * 2 + 3) % &field, BigInt::from(0));
+    }
+
+    #[test]
+    fn inputs_are_reduced_and_signals_are_assigned() {
+        // out <== in * in
+        let body = vec![store(1, OUT, compute(OperatorType::Mul, vec![signal(IN), signal(IN)]))];
+        let circuit = circuit(body, &[]);
+        let (signals, error) = run(&circuit, -3);
+        assert!(error.is_none());
+        let field = BigInt::parse_bytes(circuit.c_producer.get_prime().as_bytes(), 10).unwrap();
+        assert_eq!(signals, vec![Some(BigInt::from(1)), Some(BigInt::from(9)), Some(field - 3)]);
+    }
+
+    #[test]
+    fn failed_asserts_report_the_values_compared() {
+        // out <== in + 1; out === 7
+        let body = vec![
+            store(1, OUT, compute(OperatorType::Add, vec![signal(IN), constant(0)])),
+            assert_equal(2, signal(OUT), constant(1)),
+        ];
+        let circuit = circuit(body, &["1", "7"]);
+        assert!(run(&circuit, 6).1.is_none());
+        let error = run(&circuit, 4).1.unwrap();
+        assert_eq!(error.line, 2);
+        assert_eq!(error.symbol, "Main");
+        assert_eq!(error.trace, "main");
+        assert_eq!(error.cause_message(), "Constraint does not hold: 5 != 7");
+    }
+
+    #[test]
+    fn execution_errors_stop_the_witness() {
+        // out <== 1 / (in - in)
+        let zero = compute(OperatorType::Sub, vec![signal(IN), signal(IN)]);
+        let circuit = circuit(vec![store(3, OUT, compute(OperatorType::Div, vec![constant(0), zero]))], &["1"]);
+        let (signals, error) = run(&circuit, 2);
+        assert!(matches!(error.unwrap().cause, ErrorCause::DivisionByZero));
+        assert!(signals[1].is_none());
+        // out <== out
+        let circuit = self::circuit(vec![store(4, OUT, signal(OUT))], &[]);
+        let error = run(&circuit, 2).1.unwrap();
+        assert_eq!((error.line, error.cause_message()), (4, "Reading signal 1 before it is assigned".to_string()));
+    }
+
+    #[test]
+    fn array_positions_follow_row_major_order() {
+        assert_eq!(array_position(&[3], 2), "[2]");
+        assert_eq!(array_position(&[2, 3], 4), "[1][1]");
+        assert_eq!(array_position(&[2, 2, 2], 5), "[1][0][1]");
+    }
+}
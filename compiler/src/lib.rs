@@ -7,4 +7,5 @@ pub extern crate num_traits;
 
 pub mod compiler_interface;
 pub mod hir;
+pub mod interpreter;
 mod translating_traits;
@@ -1,7 +1,7 @@
 use super::slice_types::{FoldedResult, FoldedArgument, BusSlice, MemoryError, SignalSlice, SliceCapacity, TagInfo, TypeAssignmentError, TypeInvalidAccess};
 use crate::execution_data::type_definitions::AccessingInformationBus;
 use crate::{environment_utils::slice_types::BusRepresentation, execution_data::type_definitions::NodePointer};
-use crate::execution_data::ExecutedProgram;
+use crate::execution_data::{ExecutedProgram, InstanceInterface};
 use std::collections::{BTreeMap,HashMap, HashSet};
 use crate::ast::Meta;
 
@@ -133,7 +133,18 @@ impl ComponentRepresentation {
     ) -> Result<(), MemoryError> {
         let possible_node = ExecutedProgram::get_node(scheme, node_pointer);
         assert!(possible_node.is_some());
-        let node = possible_node.unwrap();
+        ComponentRepresentation::initialize_with_interface(component, possible_node.unwrap(), scheme)?;
+        component.node_pointer = Option::Some(node_pointer);
+        Result::Ok(())
+    }
+
+    // Initializes the signals of a component whose instance may not be in the scheme yet, its
+    // node_pointer is still the one of the prenode
+    pub fn initialize_with_interface(
+        component: &mut ComponentRepresentation,
+        node: &impl InstanceInterface,
+        scheme: &ExecutedProgram,
+    ) -> Result<(), MemoryError> {
         component.is_initialized = true;
 
         for info_wire in node.inputs() {
@@ -149,10 +160,9 @@ impl ComponentRepresentation {
                 component.inputs.insert(symbol.clone(), signal_slice);
             } else{
                 let mut initial_value_bus = BusRepresentation::default();
-                let bus_node = node.bus_connexions.get(symbol).unwrap().inspect.goes_to;
                 BusRepresentation::initialize_bus(
                     &mut initial_value_bus,
-                    bus_node,
+                    node.bus_node(symbol),
                     scheme,
                     false // it is not initialized at the begining
                 )?;
@@ -167,8 +177,8 @@ impl ComponentRepresentation {
         }
 
 
-        fn insert_tags_output(node: &crate::execution_data::ExecutedTemplate, symbol: &String, component: &mut ComponentRepresentation) {
-            let tags_output = node.signal_to_tags.get(symbol);
+        fn insert_tags_output(node: &impl InstanceInterface, symbol: &String, component: &mut ComponentRepresentation) {
+            let tags_output = node.signal_to_tags().get(symbol);
             let component_tags_output = component.outputs_tags.get_mut(symbol);
             if tags_output.is_some() && component_tags_output.is_some(){
                 let result_tags_output = tags_output.unwrap();
@@ -189,10 +199,9 @@ impl ComponentRepresentation {
                 component.outputs.insert(symbol.clone(), SignalSlice::new_with_route(route, &true));
             } else{
                 let mut initial_value_bus = BusRepresentation::default();
-                let bus_node = node.bus_connexions.get(symbol).unwrap().inspect.goes_to;
                 BusRepresentation::initialize_bus(
                     &mut initial_value_bus,
-                    bus_node,
+                    node.bus_node(symbol),
                     scheme,
                     true // the outputs of the component are initialized at the begining
                 )?;
@@ -202,8 +211,6 @@ impl ComponentRepresentation {
             }
             insert_tags_output(node, symbol, component);
         }
-        
-        component.node_pointer = Option::Some(node_pointer);
 
         let to_assign = std::mem::replace(&mut component.to_assign_inputs, vec![]);
 
@@ -19,55 +19,233 @@ use crate::assignment_utils::*;
 
 
 use program_structure::constants::UsefulConstants;
+use program_structure::file_definition::FileLibrary;
 
 use super::execution_data::analysis::Analysis;
-use super::execution_data::{ExecutedBus, ExecutedProgram, ExecutedTemplate, PreExecutedTemplate, NodePointer};
-use super::execution_data::type_definitions::{AccessingInformationBus, AccessingInformation};
+use super::execution_data::{ExecutedBus, ExecutedProgram, ExecutedTemplate, PreExecutedTemplate, NodePointer, TemplateInterface};
+use super::execution_data::type_definitions::{AccessingInformationBus, AccessingInformation, Constraint, SubComponentData};
 
 use super::{
     ast::*, ArithmeticError, FileID, ProgramArchive, Report, ReportCode, ReportCollection
 };
 use circom_algebra::num_bigint::BigInt;
 use std::collections::{HashMap, BTreeMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread::{self, Scope};
 use crate::FlagsExecution;
 type AExpr = ArithmeticExpressionGen<String>;
 type AnonymousComponentsInfo = BTreeMap<String, (Meta, Vec<Expression>)>;
 
+// Function and template calls run on a new segment of the stack when less than
+// STACK_RED_ZONE bytes are left, so the recursions allowed by --max-recursion-depth do not
+// overflow the native stack, and the depth budget bounds the segments that are added
+const STACK_RED_ZONE: usize = 1024 * 1024;
+const STACK_SEGMENT_SIZE: usize = 16 * 1024 * 1024;
+// Number of calls shown at each end of a long call trace
+const CALL_TRACE_ENDS: usize = 10;
+
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
 enum BlockType {
     Known,
     Unknown,
 }
 
-struct RuntimeInformation {
+struct RuntimeInformation<'scope, 'env> {
     pub block_type: BlockType,
     pub analysis: Analysis,
     pub public_inputs: Vec<String>,
     pub constants: UsefulConstants,
-    pub call_trace: Vec<String>,
+    pub call_trace: CallTrace<'env>,
     pub current_file: FileID,
     pub runtime_errors: ReportCollection,
     pub environment: ExecutionEnvironment,
     pub exec_program: ExecutedProgram,
     pub anonymous_components: AnonymousComponentsInfo,
+    pub number_of_instances: usize,
+    pub number_of_constraints: usize,
+    pub workers: Option<Workers<'scope, 'env>>,
+    pub pending_components: Vec<PendingComponent>,
+    // instance executed apart by this runtime, its interface is set once the body of its
+    // template is executed
+    pub apart_instance: Option<Arc<SharedInstance>>,
+    // position in exec_program of the instances executed apart, in the runtime of the main component
+    pub apart_nodes: HashMap<usize, NodePointer>,
+    // instances started apart by this runtime, after how many of its reports they were started
+    pub started_instances: Vec<(usize, usize)>,
+    // warnings of the instances executed apart whose nodes were added, in the runtime of the main component
+    pub apart_warnings: HashMap<usize, ApartWarnings>,
 }
-impl RuntimeInformation {
-    pub fn new(current_file: FileID, id_max: usize, prime: &String) -> RuntimeInformation {
+impl<'scope, 'env> RuntimeInformation<'scope, 'env> {
+    pub fn new(program_archive: &'env ProgramArchive, current_file: FileID, prime: &String) -> RuntimeInformation<'scope, 'env> {
+        let id_max = program_archive.id_max;
         RuntimeInformation {
             current_file,
             block_type: BlockType::Known,
             analysis: Analysis::new(id_max),
             public_inputs: vec![],
             constants: UsefulConstants::new(prime),
-            call_trace: Vec::new(),
+            call_trace: CallTrace { frames: Vec::new(), file_library: program_archive.get_file_library() },
             runtime_errors: ReportCollection::new(),
             environment: ExecutionEnvironment::new(),
             exec_program: ExecutedProgram::new(prime),
             anonymous_components: AnonymousComponentsInfo::new(),
+            number_of_instances: 0,
+            number_of_constraints: 0,
+            workers: Option::None,
+            pending_components: Vec::new(),
+            apart_instance: Option::None,
+            apart_nodes: HashMap::new(),
+            started_instances: Vec::new(),
+            apart_warnings: HashMap::new(),
         }
     }
 }
 
+// Calls being executed, written in the reports of the errors. The frames keep the values of the
+// arguments and the location of each call, and they are only written when an error is reported.
+#[derive(Clone)]
+struct CallTrace<'env> {
+    frames: Vec<CallFrame>,
+    file_library: &'env FileLibrary,
+}
+
+#[derive(Clone)]
+enum CallFrame {
+    Function { id: String, arguments: Arc<Vec<AExpressionSlice>>, site: CallSite },
+    // instantiation of a template as the given component
+    Template { component: String, id: String, arguments: Arc<Vec<AExpressionSlice>>, site: CallSite },
+    Bus(String),
+}
+
+#[derive(Clone, Copy)]
+struct CallSite {
+    file_id: Option<FileID>,
+    start: usize,
+}
+
+impl CallSite {
+    fn new(meta: &Meta) -> CallSite {
+        CallSite { file_id: meta.file_id, start: meta.get_start() }
+    }
+}
+
+// Threads that execute the template instances while the template that creates them goes on.
+// Each instance is executed once, in its own ExecutedProgram, and its component is initialized
+// with the interface of the instance when one of its signals is read. The nodes point to the
+// instances of their subcomponents by their index in ApartInstances, and the main component
+// adds them to its program at its end, in the order of a sequential execution.
+#[derive(Copy, Clone)]
+struct Workers<'scope, 'env> {
+    scope: &'scope Scope<'scope, 'env>,
+    available: &'env AtomicUsize,
+    program_archive: &'env ProgramArchive,
+    prime: &'env String,
+    instances: &'env Mutex<ApartInstances>,
+}
+
+type InstanceCall = (String, Vec<AExpressionSlice>, BTreeMap<String, TagInfo>);
+
+#[derive(Default)]
+struct ApartInstances {
+    // index of the instance of every call, by template name
+    calls: HashMap<String, Vec<(InstanceCall, usize)>>,
+    instances: Vec<Arc<SharedInstance>>,
+}
+
+impl ApartInstances {
+    fn get(workers: Workers, index: usize) -> Arc<SharedInstance> {
+        let instances = workers.instances.lock().unwrap_or_else(PoisonError::into_inner);
+        Arc::clone(&instances.instances[index])
+    }
+}
+
+// Value set once by the thread that executes an instance and waited by the ones that use it,
+// an error if the execution failed
+struct Outcome<T> {
+    value: Mutex<Option<Result<T, ()>>>,
+    ready: Condvar,
+}
+
+impl<T> Outcome<T> {
+    fn new() -> Outcome<T> {
+        Outcome { value: Mutex::new(Option::None), ready: Condvar::new() }
+    }
+
+    // only the first value is kept
+    fn set(&self, value: Result<T, ()>) {
+        let mut slot = self.value.lock().unwrap_or_else(PoisonError::into_inner);
+        if slot.is_none() {
+            *slot = Option::Some(value);
+            self.ready.notify_all();
+        }
+    }
+
+    fn wait(&self) -> MutexGuard<'_, Option<Result<T, ()>>> {
+        let mut slot = self.value.lock().unwrap_or_else(PoisonError::into_inner);
+        while slot.is_none() {
+            slot = self.ready.wait(slot).unwrap_or_else(PoisonError::into_inner);
+        }
+        slot
+    }
+
+    // the value can only be taken once, the next calls fail
+    fn take(&self) -> Result<T, ()> {
+        self.wait().replace(Result::Err(())).unwrap()
+    }
+}
+
+impl<T: Clone> Outcome<T> {
+    fn get(&self) -> Result<T, ()> {
+        self.wait().clone().unwrap()
+    }
+}
+
+// Template instance executed apart. The interface is set once the body of its template is
+// executed and the node once its arrows are added.
+struct SharedInstance {
+    // index in ApartInstances
+    index: usize,
+    // the instance that started it, none for the main component, and the position of the start
+    // in its started_instances
+    started_by: (Option<usize>, usize),
+    interface: Outcome<Arc<TemplateInterface>>,
+    node: Outcome<(ExecutedTemplate, ApartWarnings)>,
+}
+
+// Warnings reported by an instance executed apart, with the instances it started after each of
+// them. Their call traces go through the component that started the instance.
+struct ApartWarnings {
+    warnings: ReportCollection,
+    started_instances: Vec<(usize, usize)>,
+    started_by: (Option<usize>, usize),
+}
+
+// Sets the outcomes that were not set when the execution ends, also when it panics, so that
+// the threads that wait for them fail instead of waiting forever
+struct Completion(Arc<SharedInstance>);
+
+impl Drop for Completion {
+    fn drop(&mut self) {
+        self.0.interface.set(Result::Err(()));
+        self.0.node.set(Result::Err(()));
+    }
+}
+
+// Component whose template instance is executed apart, its arrow is added to the node at the
+// end of the template
+struct PendingComponent {
+    symbol: String,
+    // position of the component in the environment, empty for anonymous components
+    access: Vec<usize>,
+    arrow: SubComponentData,
+    arrow_name: String,
+    meta: Meta,
+    // index in ApartInstances
+    instance: usize,
+    is_initialized: bool,
+}
+
 struct FoldedValue {
     pub arithmetic_slice: Option<AExpressionSlice>,
     pub bus_slice: Option<(String, BusSlice)>, // stores the name of the bus and the value
@@ -120,7 +298,12 @@ enum ExecutionError {
     UnknownTemplate,
     NonValidTagAssignment,
     FalseAssert,
-    ArraySizeTooBig
+    ArraySizeTooBig,
+    IterationBudgetExceeded(usize),
+    RecursionBudgetExceeded(usize),
+    InstanceBudgetExceeded(usize),
+    ConstraintBudgetExceeded(usize),
+    ConstraintNotSpilled(std::io::Error),
 }
 
 enum ExecutionWarning {
@@ -130,13 +313,83 @@ enum ExecutionWarning {
 
 
 
+// With more than one thread the independent template instances are executed at the same
+// time. The result is the same as the one of a sequential execution: the instances are added
+// to the program in the same order, the warnings are reported in the same order, and an
+// execution that reports an error is repeated sequentially so that the errors are also the
+// same. The buses, the budgets of instances and constraints and the logs printed with
+// --verbose and the temporary file of --stream are only supported by the sequential
+// execution, which is warned about.
 pub fn constraint_execution(
     program_archive: &ProgramArchive,
+    flags: FlagsExecution,
+    prime: &String,
+) -> Result<(ExecutedProgram, ReportCollection), ReportCollection> {
+    if flags.threads <= 1 {
+        return execute_main_component(program_archive, flags, prime, Option::None);
+    }
+    if let Option::Some(reason) = sequential_execution_reason(program_archive, flags) {
+        let warning = Report::warning(
+            format!("--threads is ignored because {}, the template instances are executed one after the other", reason),
+            ReportCode::RuntimeWarning,
+        );
+        return match execute_main_component(program_archive, flags, prime, Option::None) {
+            Result::Ok((exec_program, mut reports)) => {
+                reports.insert(0, warning);
+                Result::Ok((exec_program, reports))
+            }
+            Result::Err(mut reports) => {
+                reports.insert(0, warning);
+                Result::Err(reports)
+            }
+        };
+    }
+    let available = AtomicUsize::new(flags.threads - 1);
+    let instances = Mutex::new(ApartInstances::default());
+    let result = thread::scope(|scope| {
+        let workers = Workers { scope, available: &available, program_archive, prime, instances: &instances };
+        execute_main_component(program_archive, flags, prime, Option::Some(workers))
+    });
+    match result {
+        Result::Ok((exec_program, reports)) if reports.iter().all(Report::is_warning) => {
+            Result::Ok((exec_program, reports))
+        }
+        _ => execute_main_component(program_archive, flags, prime, Option::None),
+    }
+}
+
+// The option that makes the execution sequential, if any
+fn sequential_execution_reason(program_archive: &ProgramArchive, flags: FlagsExecution) -> Option<&'static str> {
+    if !program_archive.get_bus_names().is_empty() {
+        Option::Some("the circuit uses buses")
+    } else if flags.inspect {
+        Option::Some("of --inspect")
+    } else if flags.verbose {
+        Option::Some("of --verbose")
+    } else if flags.stream {
+        Option::Some("of --stream")
+    } else if flags.budgets.max_instances.is_some() {
+        Option::Some("of --max-instances")
+    } else if flags.budgets.max_constraints.is_some() {
+        Option::Some("of --max-constraints")
+    } else {
+        Option::None
+    }
+}
+
+fn execute_main_component<'scope, 'env>(
+    program_archive: &'env ProgramArchive,
     flags: FlagsExecution, 
     prime: &String,
+    workers: Option<Workers<'scope, 'env>>,
 ) -> Result<(ExecutedProgram, ReportCollection), ReportCollection> {    
     let main_file_id = program_archive.get_file_id_main();
-    let mut runtime_information = RuntimeInformation::new(*main_file_id, program_archive.id_max, prime);
+    let mut runtime_information = RuntimeInformation::new(program_archive, *main_file_id, prime);
+    runtime_information.workers = workers;
+    runtime_information.exec_program.report_progress = true;
+    if flags.stream {
+        runtime_information.exec_program.spill_constraints();
+    }
     use Expression::Call;
 
     runtime_information.public_inputs = program_archive.get_public_inputs_main_component().clone();
@@ -150,9 +403,9 @@ pub fn constraint_execution(
                 // improve
             }
             execute_template_call_complete(
-                id,
-                arg_values,
-                BTreeMap::new(),
+                (id.to_string(), arg_values, BTreeMap::new()),
+                "main",
+                program_archive.get_main_expression().get_meta(),
                 program_archive,
                 &mut runtime_information,
                 flags,
@@ -166,7 +419,18 @@ pub fn constraint_execution(
         Result::Err(_) => Result::Err(runtime_information.runtime_errors),
         Result::Ok(folded_value) => {
             debug_assert!(FoldedValue::valid_node_pointer(&folded_value));
-            Result::Ok((runtime_information.exec_program, runtime_information.runtime_errors))
+            let mut reports = ReportCollection::new();
+            let started = (Option::None, runtime_information.started_instances.as_slice());
+            let merged = merge_apart_warnings(
+                runtime_information.runtime_errors,
+                started,
+                &mut runtime_information.apart_warnings,
+                &mut reports,
+            );
+            // the warnings that cannot be reported as in a sequential execution make it fail,
+            // so that it is repeated sequentially
+            let exec_program = runtime_information.exec_program;
+            merged.map(|()| (exec_program, reports)).map_err(|()| ReportCollection::new())
         }
     }
 }
@@ -179,7 +443,7 @@ pub fn execute_constant_expression(
     prime: &String,
 ) -> Result<BigInt, ReportCollection> {
     let current_file = expression.get_meta().get_file_id();
-    let mut runtime_information = RuntimeInformation::new(current_file, program_archive.id_max, prime);
+    let mut runtime_information = RuntimeInformation::new(program_archive, current_file, prime);
     runtime_information.environment = environment;
     let folded_value_result =
         execute_expression(expression, program_archive, &mut runtime_information, flags);
@@ -223,7 +487,7 @@ fn execute_statement(
             debug_assert!(possible_fold.is_none());
             possible_fold
         }
-        Declaration { meta, xtype, name, dimensions, .. } => {
+        Declaration { meta, xtype, name, dimensions, is_kept, .. } => {
             match xtype {
                 VariableType::AnonymousComponent => {
                     if runtime.block_type == BlockType::Unknown{
@@ -312,7 +576,10 @@ fn execute_statement(
                                 *signal_type,
                                 &mut runtime.environment,
                                 actual_node,
-                            )
+                            );
+                            if let (true, Option::Some(node)) = (*is_kept, actual_node.as_mut()) {
+                                node.add_kept_signal(name);
+                            }
                         },
                         VariableType::Bus(_id, signal_type, tag_list) => {
                             if runtime.block_type == BlockType::Unknown{
@@ -428,7 +695,8 @@ fn execute_statement(
                                 let symbol = signal_left;
                                 let expr = AExpr::sub(&symbol, &value_right, &p);
                                 let ctr = AExpr::transform_expression_to_constraint_form(expr, &p).unwrap();
-                                node.add_constraint(ctr);
+                                check_constraint_budget(meta, runtime, flags)?;
+                                add_constraint(node, ctr, meta, runtime)?;
                             }
                         } else if let AssignOp::AssignSignal = op {// needs fix, check case arrays
                             //debug_assert!(possible_constraint.is_some());
@@ -616,7 +884,8 @@ fn execute_statement(
                 )
                 .unwrap();
                 if let Option::Some(node) = actual_node {
-                    node.add_constraint(constraint_expression);
+                    check_constraint_budget(meta, runtime, flags)?;
+                    add_constraint(node, constraint_expression, meta, runtime)?;
                 }    
             }
             Option::None
@@ -645,32 +914,44 @@ fn execute_statement(
             can_be_simplified = can_simplify;
             possible_return
         }
-        While { cond, stmt, .. } => loop {
-            let (returned, can_simplify, condition_result) = execute_conditional_statement(
-                cond,
-                stmt,
-                Option::None,
-                program_archive,
-                runtime,
-                actual_node,
-                flags
-            )?;
-            can_be_simplified &= can_simplify;
-            if returned.is_some() {
-                break returned;
-            } else if condition_result.is_none() {
-                let (returned, _, _) = execute_conditional_statement(
+        While { meta, cond, stmt, .. } => {
+            let mut iterations = 0;
+            loop {
+                if matches!(flags.budgets.max_iterations, Some(max) if iterations >= max) {
+                    treat_result_with_execution_error(
+                        Result::Err(ExecutionError::IterationBudgetExceeded(iterations)),
+                        meta,
+                        &mut runtime.runtime_errors,
+                        &runtime.call_trace,
+                    )?;
+                }
+                iterations += 1;
+                let (returned, can_simplify, condition_result) = execute_conditional_statement(
                     cond,
                     stmt,
-                    None,
+                    Option::None,
                     program_archive,
                     runtime,
                     actual_node,
                     flags
                 )?;
-                break returned;
-            } else if !condition_result.unwrap() {
-                break returned;
+                can_be_simplified &= can_simplify;
+                if returned.is_some() {
+                    break returned;
+                } else if condition_result.is_none() {
+                    let (returned, _, _) = execute_conditional_statement(
+                        cond,
+                        stmt,
+                        None,
+                        program_archive,
+                        runtime,
+                        actual_node,
+                        flags
+                    )?;
+                    break returned;
+                } else if !condition_result.unwrap() {
+                    break returned;
+                }
             }
         },
         Block { stmts, .. } => {
@@ -1076,7 +1357,7 @@ fn execute_expression(
 //************************************************* Statement execution support *************************************************
 
 fn execute_call(
-    id: &String,
+    id: &str,
     meta: &Meta,
     args: &Vec<Expression>,
     program_archive: &ProgramArchive,
@@ -1113,14 +1394,18 @@ fn execute_call(
         let new_file_id = program_archive.get_function_data(id).get_file_id();
         let previous_id = std::mem::replace(&mut runtime.current_file, new_file_id);
 
-        runtime.call_trace.push(id.clone());
-        let folded_result = execute_function_call(id, program_archive, runtime, flags)?;
+        check_recursion_budget(meta, runtime, flags)?;
+        let call = CallFrame::Function { id: id.to_string(), arguments: Arc::new(arg_values), site: CallSite::new(meta) };
+        runtime.call_trace.frames.push(call);
+        let folded_result = stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT_SIZE, || {
+            execute_function_call(id, program_archive, runtime, flags)
+        })?;
 
         runtime.environment = previous_environment;
         runtime.current_file = previous_id;
         runtime.block_type = previous_block_type;
         runtime.anonymous_components = previous_anonymous_components;
-        runtime.call_trace.pop();
+        runtime.call_trace.frames.pop();
         Ok(folded_result)
     } else { // in this case we preexecute and check if it needs tags
         let folded_result = preexecute_template_call(id, &arg_values, program_archive, runtime)?;
@@ -1129,36 +1414,288 @@ fn execute_call(
 }
 
 fn execute_template_call_complete(
-    id: &String,
-    arg_values: Vec<AExpressionSlice>,
-    tags: BTreeMap<String, TagInfo>,
+    call: InstanceCall,
+    component_name: &str,
+    meta: &Meta,
     program_archive: &ProgramArchive,
     runtime: &mut RuntimeInformation,
     flags: FlagsExecution,
 ) -> Result<FoldedValue, ()> {
-    if program_archive.contains_template(id){ // in this case we execute
-        let new_environment = prepare_environment_for_call(id, &arg_values, program_archive);
+    let (id, arg_values, tags) = call;
+    if program_archive.contains_template(&id){ // in this case we execute
+        let new_environment = prepare_environment_for_call(&id, &arg_values, program_archive);
         let previous_environment = std::mem::replace(&mut runtime.environment, new_environment);
         let previous_block_type = std::mem::replace(&mut runtime.block_type, BlockType::Known);
         let previous_anonymous_components = std::mem::replace(&mut runtime.anonymous_components, AnonymousComponentsInfo::new());
 
-        let new_file_id = program_archive.get_template_data(id).get_file_id();
+        let new_file_id = program_archive.get_template_data(&id).get_file_id();
         let previous_id = std::mem::replace(&mut runtime.current_file, new_file_id);
 
-        runtime.call_trace.push(id.clone());
-        let folded_result = execute_template_call(id, arg_values, tags, program_archive, runtime, flags)?;
+        check_recursion_budget(meta, runtime, flags)?;
+        let arguments = Arc::new(arg_values);
+        let instantiation = CallFrame::Template {
+            component: component_name.to_string(),
+            id: id.clone(),
+            arguments: Arc::clone(&arguments),
+            site: CallSite::new(meta),
+        };
+        runtime.call_trace.frames.push(instantiation);
+        let folded_result = stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT_SIZE, || {
+            execute_template_call(&id, &arguments, tags, meta, program_archive, runtime, flags)
+        })?;
 
         runtime.environment = previous_environment;
         runtime.current_file = previous_id;
         runtime.block_type = previous_block_type;
         runtime.anonymous_components = previous_anonymous_components;
-        runtime.call_trace.pop();
+        runtime.call_trace.frames.pop();
         Ok(folded_result)
     } else { 
        unreachable!();
     }
 }
 
+// Starts the execution of the template instance of a component whose input tags are known,
+// unless it was already started by another component. The component is initialized later by
+// initialize_pending_components or link_pending_components.
+fn defer_component_execution(
+    symbol: &str,
+    access: Vec<usize>,
+    (arrow_name, arrow): (String, SubComponentData),
+    call: InstanceCall,
+    meta: &Meta,
+    runtime: &mut RuntimeInformation,
+    flags: FlagsExecution,
+) -> Result<(), ()> {
+    let workers = runtime.workers.unwrap();
+    let started_by = (runtime.apart_instance.as_ref().map(|instance| instance.index), runtime.started_instances.len());
+    let mut instances = workers.instances.lock().unwrap_or_else(PoisonError::into_inner);
+    let known = instances.calls.get(&call.0).and_then(|calls| calls.iter().find(|(c, _)| *c == call));
+    let instance = if let Option::Some((_, index)) = known {
+        *index
+    } else {
+        let index = instances.instances.len();
+        let instance =
+            Arc::new(SharedInstance { index, started_by, interface: Outcome::new(), node: Outcome::new() });
+        instances.instances.push(Arc::clone(&instance));
+        instances.calls.entry(call.0.clone()).or_default().push((call.clone(), index));
+        drop(instances);
+        let completion = Completion(instance);
+        let component_name = component_access_name(symbol, &arrow.indexed_with);
+        let call_trace = runtime.call_trace.clone();
+        let meta_call = meta.clone();
+        let execute = move || {
+            let node = execute_instance(workers, call, &component_name, &meta_call, call_trace, Arc::clone(&completion.0), flags);
+            completion.0.node.set(node);
+        };
+        let has_worker = workers.available.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1)).is_ok();
+        if has_worker {
+            let spawned = thread::Builder::new().stack_size(STACK_SEGMENT_SIZE).spawn_scoped(workers.scope, move || {
+                execute();
+                workers.available.fetch_add(1, Ordering::SeqCst);
+            });
+            spawned.map_err(|_| ())?;
+        } else {
+            execute();
+        }
+        index
+    };
+    runtime.started_instances.push((runtime.runtime_errors.len(), instance));
+    runtime.pending_components.push(PendingComponent {
+        symbol: symbol.to_string(),
+        access,
+        arrow,
+        arrow_name,
+        meta: meta.clone(),
+        instance,
+        is_initialized: false,
+    });
+    Result::Ok(())
+}
+
+// Executes a template instance in a new program and returns its node with its warnings, any
+// error makes the parallel execution fail
+fn execute_instance<'scope, 'env>(
+    workers: Workers<'scope, 'env>,
+    call: InstanceCall,
+    component_name: &str,
+    meta: &Meta,
+    call_trace: CallTrace<'env>,
+    instance: Arc<SharedInstance>,
+    flags: FlagsExecution,
+) -> Result<(ExecutedTemplate, ApartWarnings), ()> {
+    let program_archive = workers.program_archive;
+    let mut runtime = RuntimeInformation::new(program_archive, meta.get_file_id(), workers.prime);
+    runtime.call_trace = call_trace;
+    runtime.workers = Option::Some(workers);
+    let started_by = instance.started_by;
+    runtime.apart_instance = Option::Some(instance);
+    let folded_result =
+        execute_template_call_complete(call, component_name, meta, program_archive, &mut runtime, flags)?;
+    if runtime.runtime_errors.iter().any(Report::is_error) {
+        return Result::Err(());
+    }
+    let (node_pointer, _) = safe_unwrap_to_valid_node_pointer(folded_result, line!());
+    let warnings =
+        ApartWarnings { warnings: runtime.runtime_errors, started_instances: runtime.started_instances, started_by };
+    Result::Ok((runtime.exec_program.model.swap_remove(node_pointer), warnings))
+}
+
+// Initializes the pending components of symbol at the given position when one of their
+// signals is read. Their instances may still be executed, only their interface is needed.
+fn initialize_pending_components(symbol: &str, access: &[usize], runtime: &mut RuntimeInformation) -> Result<(), ()> {
+    for index in 0..runtime.pending_components.len() {
+        let pending = &runtime.pending_components[index];
+        if !pending.is_initialized && pending.symbol == symbol && pending.access == access {
+            initialize_pending_component(index, runtime)?;
+        }
+    }
+    Result::Ok(())
+}
+
+fn initialize_pending_component(index: usize, runtime: &mut RuntimeInformation) -> Result<(), ()> {
+    let instance = ApartInstances::get(runtime.workers.unwrap(), runtime.pending_components[index].instance);
+    let interface = instance.interface.get()?;
+    let pending = &mut runtime.pending_components[index];
+    pending.is_initialized = true;
+    let component_slice = treat_result_with_environment_error(
+        ExecutionEnvironment::get_mut_component_res(&mut runtime.environment, &pending.symbol),
+        &pending.meta,
+        &mut runtime.runtime_errors,
+        &runtime.call_trace,
+    )?;
+    let component = treat_result_with_memory_error(
+        ComponentSlice::get_mut_reference_to_single_value(component_slice, &pending.access),
+        &pending.meta,
+        &mut runtime.runtime_errors,
+        &runtime.call_trace,
+    )?;
+    let init_result =
+        ComponentRepresentation::initialize_with_interface(component, interface.as_ref(), &runtime.exec_program);
+    treat_result_with_memory_error_void(init_result, &pending.meta, &mut runtime.runtime_errors, &runtime.call_trace)
+}
+
+// Initializes the pending components that were not initialized yet and points their arrows
+// to their instances. In the main component the arrows point to the nodes, which are added
+// to the program once all the instances are executed.
+fn link_pending_components(runtime: &mut RuntimeInformation) -> Result<(), ()> {
+    for index in 0..runtime.pending_components.len() {
+        if !runtime.pending_components[index].is_initialized {
+            initialize_pending_component(index, runtime)?;
+        }
+    }
+    let workers = runtime.workers.unwrap();
+    for pending in &mut runtime.pending_components {
+        pending.arrow.goes_to = if runtime.apart_instance.is_some() {
+            pending.instance
+        } else {
+            let nodes = (&mut runtime.apart_nodes, &mut runtime.apart_warnings);
+            add_apart_node(pending.instance, workers, &mut runtime.exec_program, nodes)?
+        };
+    }
+    Result::Ok(())
+}
+
+// Adds the node of an instance executed apart after the nodes of its subcomponents, the same
+// order in which a sequential execution adds them. nodes keeps the position of every instance
+// already added, together with its warnings.
+fn add_apart_node(
+    instance: usize,
+    workers: Workers,
+    exec_program: &mut ExecutedProgram,
+    nodes: (&mut HashMap<usize, NodePointer>, &mut HashMap<usize, ApartWarnings>),
+) -> Result<NodePointer, ()> {
+    let (positions, warnings) = nodes;
+    if let Option::Some(node_pointer) = positions.get(&instance) {
+        return Result::Ok(*node_pointer);
+    }
+    let (mut node, node_warnings) = ApartInstances::get(workers, instance).node.take()?;
+    for subcomponent in node.subcomponent_nodes() {
+        stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT_SIZE, || {
+            add_apart_node(subcomponent, workers, exec_program, (&mut *positions, &mut *warnings))
+        })?;
+    }
+    node.relocate_subcomponents(|subcomponent| positions[&subcomponent]);
+    let node_pointer = exec_program.add_executed_node(node);
+    positions.insert(instance, node_pointer);
+    warnings.insert(instance, node_warnings);
+    Result::Ok(node_pointer)
+}
+
+// Adds the reports of the runtime of an instance, none for the main component, to merged with
+// the warnings of the instances it started apart where a sequential execution reports them: an
+// instance is executed the first time it is started. Fails if the warnings of an instance were
+// found by the execution started by another component, since their call traces are different.
+fn merge_apart_warnings(
+    reports: ReportCollection,
+    (runtime_instance, started_instances): (Option<usize>, &[(usize, usize)]),
+    apart_warnings: &mut HashMap<usize, ApartWarnings>,
+    merged: &mut ReportCollection,
+) -> Result<(), ()> {
+    let mut reports = reports.into_iter();
+    let mut added = 0;
+    for (start, &(position, instance)) in started_instances.iter().enumerate() {
+        merged.extend(reports.by_ref().take(position - added));
+        added = position;
+        if let Option::Some(apart) = apart_warnings.remove(&instance) {
+            let ApartWarnings { warnings, started_instances, started_by } = apart;
+            let mut instance_reports = ReportCollection::new();
+            stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT_SIZE, || {
+                let started = (Option::Some(instance), started_instances.as_slice());
+                merge_apart_warnings(warnings, started, apart_warnings, &mut instance_reports)
+            })?;
+            if !instance_reports.is_empty() && started_by != (runtime_instance, start) {
+                return Result::Err(());
+            }
+            merged.append(&mut instance_reports);
+        }
+    }
+    merged.extend(reports);
+    Result::Ok(())
+}
+
+// Entries of the call trace: the name of the accessed component with its indexes,
+// the values of the arguments and the location of the call
+fn component_access_name(symbol: &str, indexes: &[usize]) -> String {
+    let mut name = symbol.to_string();
+    for index in indexes {
+        name.push_str(&format!("[{}]", index));
+    }
+    name
+}
+
+fn arguments_to_string(arg_values: &[AExpressionSlice]) -> String {
+    arg_values.iter().map(|value| value.to_string()).collect::<Vec<_>>().join(",")
+}
+
+fn call_site(site: CallSite, file_library: &FileLibrary) -> String {
+    let file_id = match site.file_id {
+        Option::Some(file_id) => file_id,
+        Option::None => return "unknown location".to_string(),
+    };
+    let path = file_library.get_path(file_id).unwrap_or_default();
+    match file_library.get_line(site.start, file_id) {
+        Option::Some(line) => format!("{}:{}", path, line),
+        Option::None => path.to_string(),
+    }
+}
+
+fn call_to_string(call: &CallFrame, file_library: &FileLibrary) -> String {
+    match call {
+        CallFrame::Function { id, arguments, site } => {
+            format!("{}({}) at {}", id, arguments_to_string(arguments), call_site(*site, file_library))
+        }
+        CallFrame::Template { component, id, arguments, site } => format!(
+            "{} = {}({}) at {}",
+            component,
+            id,
+            arguments_to_string(arguments),
+            call_site(*site, file_library)
+        ),
+        CallFrame::Bus(id) => id.clone(),
+    }
+}
+
 fn execute_component_declaration(
     component_name: &str,
     dimensions: &[SliceCapacity],
@@ -1185,7 +1722,7 @@ fn execute_anonymous_component_declaration(
 }
 
 fn execute_bus_call_complete(
-    id: &String,
+    id: &str,
     args: &Vec<Expression>,
     program_archive: &ProgramArchive,
     runtime: &mut RuntimeInformation,
@@ -1207,14 +1744,14 @@ fn execute_bus_call_complete(
         let new_file_id = program_archive.get_bus_data(id).get_file_id();
         let previous_id = std::mem::replace(&mut runtime.current_file, new_file_id);
 
-        runtime.call_trace.push(id.clone());
+        runtime.call_trace.frames.push(CallFrame::Bus(id.to_string()));
         let folded_result = execute_bus_call(id, arg_values, program_archive, runtime, flags)?;
 
         runtime.environment = previous_environment;
         runtime.current_file = previous_id;
         runtime.block_type = previous_block_type;
         runtime.anonymous_components = previous_anonymous_components;
-        runtime.call_trace.pop();
+        runtime.call_trace.frames.pop();
         Ok(folded_result)
     } else{
         unreachable!()
@@ -1240,7 +1777,11 @@ fn execute_signal_declaration(
                 if let Some(tags_input) = node.tag_instances().get(signal_name){
                     environment_shortcut_add_input(environment, signal_name, dimensions, &tags_input);
                 } else{
+                    // inputs of the main component, their tags have no value
                     environment_shortcut_add_input(environment, signal_name, dimensions, &tags);
+                    for t in list_tags {
+                        node.add_tag_signal(signal_name, t, None);
+                    }
                 }
                 node.add_input(signal_name, dimensions, false);
             }
@@ -1353,7 +1894,7 @@ fn perform_assign(
     program_archive: &ProgramArchive,
     flags: FlagsExecution
 ) -> Result<Option<Constrained>, ()> {
-    use super::execution_data::type_definitions::{SubComponentData, BusData};
+    use super::execution_data::type_definitions::BusData;
 
     let full_symbol = if accessing_information.bus_access.is_some(){
         create_symbol_bus(symbol, &accessing_information.bus_access.as_ref().unwrap())
@@ -1598,6 +2139,10 @@ fn perform_assign(
     else if ExecutionEnvironment::has_component(&runtime.environment, symbol) {
         
         let accessing_information = accessing_information.bus_access.as_ref().unwrap();
+        // the anonymous components of a loop are assigned again in each iteration
+        if accessing_information.field_access.is_none() && runtime.anonymous_components.contains_key(symbol) {
+            initialize_pending_components(symbol, &[], runtime)?;
+        }
         
         let environment_response = ExecutionEnvironment::get_mut_component_res(&mut runtime.environment, symbol);
         let component_slice = treat_result_with_environment_error(
@@ -1652,14 +2197,33 @@ fn perform_assign(
                 &mut runtime.runtime_errors,
                 &runtime.call_trace,
             )?;
-            if component.is_ready_initialize() {  
+            if component.is_ready_initialize() && runtime.workers.is_some() {
+                let pretemplate_info = runtime.exec_program.get_prenode_value(prenode_pointer).unwrap();
+                let call = (
+                    pretemplate_info.template_name().clone(),
+                    pretemplate_info.parameter_instances().clone(),
+                    component.inputs_tags.clone(),
+                );
+                let arrow = SubComponentData {
+                    name: symbol.to_string(),
+                    is_parallel: component.is_parallel,
+                    goes_to: 0,
+                    indexed_with: accessing_information.array_access.clone(),
+                };
+                let access = if is_anonymous_component { Vec::new() } else { accessing_information.array_access.clone() };
+                defer_component_execution(symbol, access, (full_symbol.clone(), arrow), call, meta, runtime, flags)?;
+            } else if component.is_ready_initialize() {  
                 // calls to execute and initialize the component              
                 let pretemplate_info = runtime.exec_program.get_prenode_value(prenode_pointer).unwrap();
                 let inputs_tags = component.inputs_tags.clone();
                 let result = execute_template_call_complete(
-                    pretemplate_info.template_name(),
-                    pretemplate_info.parameter_instances().clone(),
-                    inputs_tags,
+                    (
+                        pretemplate_info.template_name().to_string(),
+                        pretemplate_info.parameter_instances().clone(),
+                        inputs_tags,
+                    ),
+                    &component_access_name(symbol, &accessing_information.array_access),
+                    meta,
                     program_archive,
                     runtime,
                     flags,
@@ -1876,7 +2440,28 @@ fn perform_assign(
                     unreachable!();
                 };
                 
-                if !component.is_initialized && component.is_ready_initialize() {  
+                let access = if is_anonymous_component { Vec::new() } else { accessing_information.array_access.clone() };
+                let is_pending = runtime.pending_components.iter().any(|p| p.symbol == symbol && p.access == access && !p.is_initialized);
+                if !component.is_initialized && component.is_ready_initialize() && runtime.workers.is_some() {
+                    if !is_pending {
+                        let pretemplate_info = runtime.exec_program.get_prenode_value(
+                            component.node_pointer.unwrap()
+                        ).unwrap();
+                        let call = (
+                            pretemplate_info.template_name().clone(),
+                            pretemplate_info.parameter_instances().clone(),
+                            component.inputs_tags.clone(),
+                        );
+                        let arrow = SubComponentData {
+                            name: symbol.to_string(),
+                            is_parallel: component.is_parallel,
+                            goes_to: 0,
+                            indexed_with: accessing_information.array_access.clone(),
+                        };
+                        let arrow_name = create_component_symbol(symbol, &accessing_information.array_access);
+                                                defer_component_execution(symbol, access, (arrow_name, arrow), call, meta, runtime, flags)?;
+                    }
+                } else if !component.is_initialized && component.is_ready_initialize() {  
                     // calls to execute and initialize the component              
                     let pretemplate_info = runtime.exec_program.get_prenode_value(
                         component.node_pointer.unwrap()
@@ -1884,9 +2469,13 @@ fn perform_assign(
                     let inputs_tags = component.inputs_tags.clone();
     
                     let folded_result = execute_template_call_complete(
-                        pretemplate_info.template_name(),
-                        pretemplate_info.parameter_instances().clone(),
-                        inputs_tags,
+                        (
+                            pretemplate_info.template_name().to_string(),
+                            pretemplate_info.parameter_instances().clone(),
+                            inputs_tags,
+                        ),
+                        &component_access_name(symbol, &accessing_information.array_access),
+                        meta,
                         program_archive,
                         runtime,
                         flags,
@@ -2928,6 +3517,15 @@ fn execute_component(
         return Result::Ok(FoldedValue { arithmetic_slice, ..FoldedValue::default() });
     }
 
+    if access_information.field_access.is_some() {
+        let is_anonymous = runtime.anonymous_components.contains_key(symbol);
+        let access = if is_anonymous { &[][..] } else { &access_information.array_access[..] };
+        initialize_pending_components(symbol, access, runtime)?;
+    } else if runtime.workers.is_some() {
+        // the node of the component is only known once the instances are added to the
+        // program, so the execution is repeated sequentially
+        return Result::Err(());
+    }
     let environment_response =
         ExecutionEnvironment::get_component_res(&runtime.environment, symbol);
     let component_slice = treat_result_with_environment_error(
@@ -3079,8 +3677,9 @@ fn execute_function_call(
 
 fn execute_template_call(
     id: &str,
-    parameter_values: Vec<AExpressionSlice>,
+    parameter_values: &[AExpressionSlice],
     tag_values: BTreeMap<String, TagInfo>,
+    meta: &Meta,
     program_archive: &ProgramArchive,
     runtime: &mut RuntimeInformation,
     flags: FlagsExecution
@@ -3121,8 +3720,19 @@ fn execute_template_call(
     let node_pointer = if let Option::Some(pointer) = existent_node {
         pointer
     } else {
+        if matches!(flags.budgets.max_instances, Some(max) if runtime.number_of_instances >= max) {
+            treat_result_with_execution_error(
+                Result::Err(ExecutionError::InstanceBudgetExceeded(runtime.number_of_instances)),
+                meta,
+                &mut runtime.runtime_errors,
+                &runtime.call_trace,
+            )?;
+        }
+        runtime.number_of_instances += 1;
         let analysis =
             std::mem::replace(&mut runtime.analysis, Analysis::new(program_archive.id_max));
+        let apart_instance = runtime.apart_instance.clone();
+        let outer_pending_components = std::mem::take(&mut runtime.pending_components);
         let code = program_archive.get_template_data(id).get_body().clone();
         let mut node_wrap = Option::Some(ExecutedTemplate::new(
             is_main,
@@ -3144,6 +3754,19 @@ fn execute_template_call(
         )?;
         debug_assert!(ret.is_none());
 
+        // the parent can read the signals of the instance while its subcomponents are executed
+        if let Option::Some(instance) = &apart_instance {
+            instance.interface.set(Result::Ok(Arc::new(node_wrap.as_ref().unwrap().interface())));
+        }
+        if runtime.workers.is_some() {
+            link_pending_components(runtime)?;
+        }
+        let node = node_wrap.as_mut().unwrap();
+        let pending_components = std::mem::replace(&mut runtime.pending_components, outer_pending_components);
+        for pending in pending_components {
+            node.add_arrow(pending.arrow_name, pending.arrow);
+        }
+
         let result_check_components = environment_check_all_components_assigned(&runtime.environment);
         match result_check_components{
             Err((error, meta)) =>{
@@ -3537,7 +4160,7 @@ fn treat_result_with_arithmetic_error<C>(
     arithmetic_error: Result<C, ArithmeticError>,
     meta: &Meta,
     runtime_errors: &mut ReportCollection,
-    call_trace: &[String],
+    call_trace: &CallTrace,
 ) -> Result<C, ()> {
     use ReportCode::RuntimeError;
     match arithmetic_error {
@@ -3561,7 +4184,7 @@ fn treat_result_with_memory_error_void(
     memory_error: Result<(), MemoryError>,
     meta: &Meta,
     runtime_errors: &mut ReportCollection,
-    call_trace: &[String],
+    call_trace: &CallTrace,
 ) -> Result<(), ()> {
     use ReportCode::RuntimeError;
     match memory_error {
@@ -3707,11 +4330,11 @@ fn treat_result_with_memory_error_void(
     }
 }
 
-pub fn treat_result_with_memory_error<C>(
+fn treat_result_with_memory_error<C>(
     memory_error: Result<C, MemoryError>,
     meta: &Meta,
     runtime_errors: &mut ReportCollection,
-    call_trace: &[String],
+    call_trace: &CallTrace,
 ) -> Result<C, ()> {
     use ReportCode::RuntimeError;
     match memory_error {
@@ -3844,7 +4467,7 @@ fn treat_result_with_environment_error<C>(
     environment_error: Result<C, ExecutionEnvironmentError>,
     meta: &Meta,
     runtime_errors: &mut ReportCollection,
-    call_trace: &[String],
+    call_trace: &CallTrace,
 ) -> Result<C, ()> {
     use ReportCode::*;
     match environment_error {
@@ -3861,11 +4484,60 @@ fn treat_result_with_environment_error<C>(
     }
 }
 
+fn check_recursion_budget(
+    meta: &Meta,
+    runtime: &mut RuntimeInformation,
+    flags: FlagsExecution,
+) -> Result<(), ()> {
+    if matches!(flags.budgets.max_recursion_depth, Some(max) if runtime.call_trace.frames.len() >= max) {
+        treat_result_with_execution_error(
+            Result::Err(ExecutionError::RecursionBudgetExceeded(runtime.call_trace.frames.len())),
+            meta,
+            &mut runtime.runtime_errors,
+            &runtime.call_trace,
+        )?;
+    }
+    Result::Ok(())
+}
+
+fn check_constraint_budget(
+    meta: &Meta,
+    runtime: &mut RuntimeInformation,
+    flags: FlagsExecution,
+) -> Result<(), ()> {
+    if matches!(flags.budgets.max_constraints, Some(max) if runtime.number_of_constraints >= max) {
+        treat_result_with_execution_error(
+            Result::Err(ExecutionError::ConstraintBudgetExceeded(runtime.number_of_constraints)),
+            meta,
+            &mut runtime.runtime_errors,
+            &runtime.call_trace,
+        )?;
+    }
+    runtime.number_of_constraints += 1;
+    Result::Ok(())
+}
+
+// With --stream the constraint is written to the temporary file of the program
+fn add_constraint(
+    node: &mut ExecutedTemplate,
+    constraint: Constraint,
+    meta: &Meta,
+    runtime: &mut RuntimeInformation,
+) -> Result<(), ()> {
+    let added = node.add_constraint(constraint, meta, runtime.exec_program.spill.as_mut());
+    treat_result_with_execution_error(
+        added.map_err(ExecutionError::ConstraintNotSpilled),
+        meta,
+        &mut runtime.runtime_errors,
+        &runtime.call_trace,
+    )
+}
+
 fn treat_result_with_execution_error<C>(
     execution_error: Result<C, ExecutionError>,
     meta: &Meta,
     runtime_errors: &mut ReportCollection,
-    call_trace: &[String],
+    call_trace: &CallTrace,
 ) -> Result<C, ()> {
     use ExecutionError::*;
     match execution_error {
@@ -3902,7 +4574,27 @@ fn treat_result_with_execution_error<C>(
                 TagAssignmentInUnknown => Report::error(
                     "There are tag assignments depending on the value of a condition that can be unknown during the constraint generation phase".to_string(),
                     ReportCode::RuntimeError,
-                )
+                ),
+                IterationBudgetExceeded(max) => Report::error(
+                    format!("The loop has exceeded the maximum number of iterations ({}) of the constraint generation phase. Use the flag --max-iterations to change this limit", max),
+                    ReportCode::RuntimeError,
+                ),
+                RecursionBudgetExceeded(max) => Report::error(
+                    format!("The call has exceeded the maximum depth of nested calls ({}) of the constraint generation phase. Use the flag --max-recursion-depth to change this limit", max),
+                    ReportCode::RuntimeError,
+                ),
+                InstanceBudgetExceeded(max) => Report::error(
+                    format!("The component instantiation has exceeded the maximum number of template instances ({}). Use the flag --max-instances to change this limit", max),
+                    ReportCode::RuntimeError,
+                ),
+                ConstraintBudgetExceeded(max) => Report::error(
+                    format!("The constraint has exceeded the maximum number of constraints ({}) of the constraint generation phase. Use the flag --max-constraints to change this limit", max),
+                    ReportCode::RuntimeError,
+                ),
+                ConstraintNotSpilled(error) => Report::error(
+                    format!("The constraint could not be written to the temporary file of --stream: {}", error),
+                    ReportCode::RuntimeError,
+                ),
             };
             add_report_to_runtime(report, meta, runtime_errors, call_trace);
             Result::Err(())
@@ -3914,7 +4606,7 @@ fn treat_result_with_execution_warning<C>(
     execution_error: Result<C, ExecutionWarning>,
     meta: &Meta,
     runtime_errors: &mut ReportCollection,
-    call_trace: &[String],
+    call_trace: &CallTrace,
 ) -> Result<(), ()> {
     use ExecutionWarning::*;
     match execution_error {
@@ -3958,18 +4650,318 @@ fn add_report_to_runtime(
     report: Report,
     meta: &Meta,
     runtime_errors: &mut ReportCollection,
-    call_trace: &[String],
+    call_trace: &CallTrace,
 ) {
     let mut report = report;
     report.add_primary(meta.location.clone(), meta.get_file_id(), "found here".to_string());
 
     let mut trace = "call trace:\n".to_string();
     let mut spacing = "".to_string();
-    for call in call_trace.iter() {
-        let msg = format!("{}->{}\n", spacing, call);
+    let calls = &call_trace.frames;
+    for (i, call) in calls.iter().enumerate() {
+        // the calls in the middle of a long trace are omitted
+        if calls.len() > 2 * CALL_TRACE_ENDS && i >= CALL_TRACE_ENDS && i < calls.len() - CALL_TRACE_ENDS {
+            if i == CALL_TRACE_ENDS {
+                let omitted = calls.len() - 2 * CALL_TRACE_ENDS;
+                trace.push_str(&format!("{}... {} calls omitted\n", spacing, omitted));
+            }
+            continue;
+        }
+        let msg = format!("{}->{}\n", spacing, call_to_string(call, call_trace.file_library));
         trace.push_str(msg.as_str());
         spacing.push_str(" ");
     }
     report.add_note(trace);
     runtime_errors.push(report);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExecutionBudgets;
+
+    fn parse(name: &str, source: &str) -> ProgramArchive {
+        let folder = std::env::temp_dir().join(format!("circom_execution_{}_{}", std::process::id(), name));
+        std::fs::create_dir_all(&folder).unwrap();
+        let file = folder.join("main.circom");
+        std::fs::write(&file, source).unwrap();
+        let prime = UsefulConstants::new(&"bn128".to_string()).get_p().clone();
+        let result = parser::run_parser(file.display().to_string(), "2.2.0", vec![], &prime);
+        std::fs::remove_dir_all(&folder).unwrap();
+        match result {
+            Result::Ok((program, _)) => program,
+            Result::Err(_) => panic!("the test program {} does not parse", name),
+        }
+    }
+
+    fn describe(reports: ReportCollection) -> Vec<String> {
+        reports.iter().map(|report| format!("{} {}", report.get_message(), report.get_notes().join(" "))).collect()
+    }
+
+    // Messages and notes of the warnings of executing the source with the given flags, or of
+    // its errors if it fails
+    fn execution_reports(name: &str, source: &str, flags: FlagsExecution) -> Result<Vec<String>, Vec<String>> {
+        let program = parse(name, source);
+        match constraint_execution(&program, flags, &"bn128".to_string()) {
+            Result::Ok((_, warnings)) => Result::Ok(describe(warnings)),
+            Result::Err(errors) => Result::Err(describe(errors)),
+        }
+    }
+
+    // Messages of the errors of executing the source with the given budgets
+    fn execution_errors(name: &str, source: &str, budgets: ExecutionBudgets) -> Vec<String> {
+        let flags = FlagsExecution { verbose: false, inspect: false, budgets, threads: 1, stream: false };
+        execution_reports(name, source, flags).err().unwrap_or_default()
+    }
+
+    const UNLIMITED: ExecutionBudgets =
+        ExecutionBudgets { max_iterations: None, max_recursion_depth: None, max_instances: None, max_constraints: None };
+
+    #[test]
+    fn errors_show_the_instantiations_and_calls_that_led_to_them() {
+        let source = "pragma circom 2.0.0;
+function check(x) {
+    assert(x < 3);
+    return x;
+}
+function descend(n) {
+    return n == 0 ? check(3) : descend(n - 1);
+}
+template Leaf(n, depth) {
+    signal input in;
+    var y = depth == 0 ? check(n) : descend(depth);
+}
+template Mid(n, depth) {
+    component l[2];
+    for (var i = 0; i < 2; i++) {
+        l[i] = Leaf(n + i, depth);
+        l[i].in <== 1;
+    }
+}
+template Main(depth) {
+    component m = Mid(2, depth);
+}
+component main = Main(DEPTH);
+";
+        // the trace names the files by their path
+        let trace = |name: &str, depth: &str| {
+            let file = std::env::temp_dir().join(format!("circom_execution_{}_{}", std::process::id(), name)).join("main.circom");
+            let errors = execution_errors(name, &source.replace("DEPTH", depth), UNLIMITED);
+            errors.iter().map(|error| error.replace(&file.display().to_string(), "main.circom")).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            trace("call_trace", "0"),
+            ["False assert reached call trace:
+->main = Main(0) at main.circom:23
+ ->m = Mid(2,0) at main.circom:21
+  ->l[1] = Leaf(3,0) at main.circom:16
+   ->check(3) at main.circom:11
+"]
+        );
+        // 4 frames before the 31 calls of descend and check, the middle ones are omitted
+        let long_trace = trace("long_call_trace", "30");
+        let lines: Vec<&str> = long_trace[0].lines().map(str::trim).collect();
+        assert_eq!(lines.len(), 1 + 2 * CALL_TRACE_ENDS + 1);
+        assert_eq!(
+            lines[1..5],
+            [
+                "->main = Main(30) at main.circom:23",
+                "->m = Mid(2,30) at main.circom:21",
+                "->l[0] = Leaf(2,30) at main.circom:16",
+                "->descend(30) at main.circom:11"
+            ]
+        );
+        assert_eq!(lines[CALL_TRACE_ENDS + 1], "... 15 calls omitted");
+        assert_eq!(lines[CALL_TRACE_ENDS + 2], "->descend(8) at main.circom:7");
+        assert_eq!(lines.last(), Some(&"->check(3) at main.circom:7"));
+    }
+
+    #[test]
+    fn loops_and_calls_are_limited_by_default() {
+        let budgets = ExecutionBudgets::default();
+        assert!(budgets.max_iterations.is_some() && budgets.max_recursion_depth.is_some());
+    }
+
+    #[test]
+    fn iteration_budget_is_reported() {
+        let source = "pragma circom 2.0.0;
+template A() {
+    signal output out;
+    var x = 0;
+    while (x >= 0) {
+        x++;
+    }
+    out <== x;
+}
+component main = A();
+";
+        let budgets = ExecutionBudgets { max_iterations: Some(100), ..UNLIMITED };
+        let errors = execution_errors("iterations", source, budgets);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("maximum number of iterations (100)") && errors[0].contains("--max-iterations"));
+    }
+
+    #[test]
+    fn recursion_budget_is_reported() {
+        let source = "pragma circom 2.0.0;
+function f(n) {
+    return f(n + 1);
+}
+template A() {
+    signal output out;
+    out <== f(0);
+}
+component main = A();
+";
+        let budgets = ExecutionBudgets { max_recursion_depth: Some(50), ..UNLIMITED };
+        let errors = execution_errors("recursion", source, budgets);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("maximum depth of nested calls (50)") && errors[0].contains("--max-recursion-depth"));
+    }
+
+    #[test]
+    fn instance_budget_is_reported() {
+        let source = "pragma circom 2.0.0;
+template B(n) {
+    signal input in;
+    signal output out;
+    out <== in * n;
+}
+template A() {
+    signal input in;
+    signal output out[10];
+    component b[10];
+    for (var i = 0; i < 10; i++) {
+        b[i] = B(i);
+        b[i].in <== in;
+        out[i] <== b[i].out;
+    }
+}
+component main = A();
+";
+        let budgets = ExecutionBudgets { max_instances: Some(5), ..UNLIMITED };
+        let errors = execution_errors("instances", source, budgets);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("maximum number of template instances (5)") && errors[0].contains("--max-instances"));
+    }
+
+    #[test]
+    fn constraint_budget_is_reported() {
+        let source = "pragma circom 2.0.0;
+template A() {
+    signal input in[10];
+    signal output out[10];
+    for (var i = 0; i < 10; i++) {
+        out[i] <== in[i] * in[i];
+    }
+}
+component main = A();
+";
+        let budgets = ExecutionBudgets { max_constraints: Some(5), ..UNLIMITED };
+        let errors = execution_errors("constraints", source, budgets);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("maximum number of constraints (5)") && errors[0].contains("--max-constraints"));
+        assert!(execution_errors("no_budgets", source, UNLIMITED).is_empty());
+    }
+
+    // The instance of Node(1) is started twice, its warnings are reported by the first component
+    const WARNINGS_CIRCUIT: &str = "pragma circom 2.0.0;
+template Leaf(n) {
+    signal input in;
+    signal output out;
+    var a[n + 3] = [n, n];
+    out <== in * a[0];
+}
+template Node(n) {
+    signal input in;
+    signal output out;
+    var b[n + 5] = [1, 1];
+    component leaves[2];
+    leaves[0] = Leaf(n);
+    leaves[1] = Leaf(n + 10);
+    leaves[0].in <== in;
+    leaves[1].in <== leaves[0].out;
+    var c[n + 9] = [2, 2];
+    out <== leaves[1].out * c[0];
+}
+template Main() {
+    signal input in;
+    signal output out;
+    var d[20] = [3, 3];
+    component first = Node(1);
+    component second = Node(2);
+    component third = Node(1);
+    first.in <== in;
+    second.in <== first.out;
+    third.in <== second.out;
+    out <== third.out;
+}
+component main = Main();
+";
+
+    #[test]
+    fn threads_keep_the_warnings_of_a_sequential_execution() {
+        let flags = |threads| FlagsExecution { verbose: false, inspect: false, budgets: UNLIMITED, threads, stream: false };
+        let program = parse("warnings", WARNINGS_CIRCUIT);
+        let prime = "bn128".to_string();
+        let (_, sequential) = execute_main_component(&program, flags(1), &prime, Option::None).ok().unwrap();
+        // the parallel execution is not repeated sequentially
+        let available = AtomicUsize::new(3);
+        let instances = Mutex::new(ApartInstances::default());
+        let (_, parallel) = thread::scope(|scope| {
+            let workers = Workers { scope, available: &available, program_archive: &program, prime: &prime, instances: &instances };
+            execute_main_component(&program, flags(4), &prime, Option::Some(workers))
+        })
+        .ok()
+        .unwrap();
+        // the main component, the two instances of Node and the four of Leaf
+        assert_eq!(sequential.len(), 1 + 2 * 2 + 4);
+        assert_eq!(describe(parallel), describe(sequential));
+    }
+
+    #[test]
+    fn warnings_of_instances_are_placed_where_they_are_started() {
+        let report = |message: &str| Report::warning(message.to_string(), ReportCode::RuntimeWarning);
+        let apart = |messages: &[&str], started_instances: Vec<(usize, usize)>, started_by| ApartWarnings {
+            warnings: messages.iter().map(|m| report(m)).collect(),
+            started_instances,
+            started_by,
+        };
+        // the main component starts 0, 1 and 0 again, and 0 starts 2 before 1 does
+        let apart_warnings = || {
+            let mut apart_warnings = HashMap::new();
+            apart_warnings.insert(0, apart(&["a1", "a2"], vec![(1, 2)], (None, 0)));
+            apart_warnings.insert(1, apart(&["b1"], vec![(0, 2)], (None, 1)));
+            apart_warnings.insert(2, apart(&["c1"], vec![], (Some(0), 0)));
+            apart_warnings
+        };
+        let main = || vec![report("m1"), report("m2")];
+        let started = [(0, 0), (1, 1), (2, 0)];
+        let mut merged = ReportCollection::new();
+        assert!(merge_apart_warnings(main(), (None, &started), &mut apart_warnings(), &mut merged).is_ok());
+        let messages: Vec<&String> = merged.iter().map(|report| report.get_message()).collect();
+        assert_eq!(messages, vec!["a1", "c1", "a2", "m1", "b1", "m2"]);
+        // 2 was executed by the start of 1, its call traces are not the sequential ones
+        let mut apart_warnings = apart_warnings();
+        apart_warnings.get_mut(&2).unwrap().started_by = (Some(1), 0);
+        assert!(merge_apart_warnings(main(), (None, &started), &mut apart_warnings, &mut ReportCollection::new()).is_err());
+    }
+
+    #[test]
+    fn ignored_threads_are_warned_about() {
+        let source = "pragma circom 2.0.0;
+template A() {
+    signal input in;
+    signal output out;
+    out <== in * in;
+}
+component main = A();
+";
+        let flags = FlagsExecution { verbose: false, inspect: false, budgets: UNLIMITED, threads: 2, stream: false };
+        assert_eq!(execution_reports("threads", source, flags), Result::Ok(vec![]));
+        let budgets = ExecutionBudgets { max_instances: Some(10), ..UNLIMITED };
+        let warnings = execution_reports("ignored_threads", source, FlagsExecution { budgets, ..flags }).ok().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("--threads is ignored because of --max-instances"));
+    }
+}
@@ -4,10 +4,13 @@ use super::executed_template::{ExecutedTemplate, PreExecutedTemplate};
 use super::executed_bus::ExecutedBus;
 
 use super::type_definitions::*;
+use circom_algebra::spill::Spill;
 use compiler::hir::very_concrete_program::{Stats, VCPConfig, VCP};
 use dag::DAG;
+use program_structure::error_code::ReportCode;
 use program_structure::program_archive::ProgramArchive;
-use program_structure::program_library::error_definition::ReportCollection;
+use program_structure::program_library::error_definition::{Report, ReportCollection};
+use program_structure::progress::{self, ProgressEvent};
 use std::collections::HashMap;
 
 pub type ExportResult = Result<(DAG, VCP, ReportCollection), ReportCollection>;
@@ -20,6 +23,11 @@ pub struct ExecutedProgram {
     pub template_to_nodes: HashMap<String, Vec<NodePointer>>,
     pub bus_to_nodes: HashMap<String, Vec<NodePointer>>,
     pub prime: String,
+    // only the program of the main component reports its new instances, not the ones of the
+    // instances executed apart, which are reported once they are added to it
+    pub report_progress: bool,
+    // temporary file where the constraints of the templates are written with --stream
+    pub spill: Option<Spill<String>>,
 }
 
 impl ExecutedProgram {
@@ -31,6 +39,16 @@ impl ExecutedProgram {
             model_pretemplates: Vec::new(),
             model_buses: Vec::new(),
             bus_to_nodes: HashMap::new(),
+            report_progress: false,
+            spill: Option::None,
+        }
+    }
+
+    // Without a temporary file the constraints are kept in memory, as without --stream
+    pub fn spill_constraints(&mut self) {
+        match Spill::new() {
+            Result::Ok(spill) => self.spill = Option::Some(spill),
+            Result::Err(error) => eprintln!("Could not create a temporary file for the constraints, they are kept in memory: {}", error),
         }
     }
 
@@ -124,9 +142,36 @@ impl ExecutedProgram {
         let node_index = self.model.len();
         self.model.push(node);
         nodes_for_template.push(node_index);
+        self.report_instance(node_index);
         node_index
     }
 
+    fn report_instance(&self, node_index: NodePointer) {
+        if self.report_progress {
+            let node = &self.model[node_index];
+            progress::report(ProgressEvent::InstanceExecuted {
+                instance: node.report_name.clone(),
+                constraints: node.number_of_constraints(),
+                instances: self.model.len(),
+            });
+        }
+    }
+
+
+    // Adds the node of a template instance executed apart, once the nodes of its
+    // subcomponents are added and it points to them
+    pub fn add_executed_node(&mut self, node: ExecutedTemplate) -> NodePointer {
+        debug_assert!(self.model_buses.is_empty());
+        let existent_node = self.identify_node(node.template_name(), node.parameter_instances(), node.tag_instances());
+        if let Option::Some(index) = existent_node {
+            return index;
+        }
+        let index = self.model.len();
+        self.template_to_nodes.entry(node.template_name().clone()).or_default().push(index);
+        self.model.push(node);
+        self.report_instance(index);
+        index
+    }
 
     pub fn add_bus_node_to_scheme(
         &mut self,
@@ -167,6 +212,12 @@ impl ExecutedProgram {
 
         let mut warnings = vec![];
         let mut dag = DAG::new(&self.prime);
+        // the analysis of --inspect goes through the constraints of the dag in memory
+        if flags.stream && !flags.inspect {
+            if let Result::Err(error) = dag.spill_constraints() {
+                eprintln!("Could not create a temporary file for the constraints, they are kept in memory: {}", error);
+            }
+        }
         let mut temp_instances = Vec::with_capacity(self.model.len());
         let mut mixed_instances = vec![false; self.model.len()];
 
@@ -176,8 +227,13 @@ impl ExecutedProgram {
         }
 
         for exe in &mut self.model {
-            exe.insert_in_dag(&mut dag, &self.model_buses);
+            exe.insert_in_dag(&mut dag, &self.model_buses, self.spill.as_ref()).map_err(|error| {
+                let message = format!("The constraints could not be read from or written to the temporary file: {}", error);
+                vec![Report::error(message, ReportCode::RuntimeError)]
+            })?;
         }
+        // the constraints are in the dag now
+        self.spill = Option::None;
 
         let mut wrapped_buses_table = vec![None; self.model_buses.len()];
         let mut index = 0;
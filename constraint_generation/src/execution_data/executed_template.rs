@@ -2,11 +2,13 @@ use super::executed_bus::BusConnexion;
 use super::type_definitions::*;
 use super::ExecutedBus;
 use circom_algebra::algebra::ArithmeticExpression;
+use circom_algebra::spill::{Spill, SpillRange};
 use compiler::hir::very_concrete_program::*;
-use dag::DAG;
+use dag::{ConstraintLocation, DAG};
 use num_bigint::BigInt;
-use program_structure::ast::{SignalType, Statement};
+use program_structure::ast::{Meta, SignalType, Statement};
 use std::collections::{HashMap, HashSet};
+use std::io;
 use crate::execution_data::AExpressionSlice;
 use crate::execution_data::TagInfo;
 
@@ -62,6 +64,53 @@ impl PreExecutedTemplate {
 
 
 
+// What the components of a template instance need from it to be initialized, the signals
+// of the instance and the tags of its outputs. The thread that executes an instance apart
+// sends it as soon as the body of the template is executed.
+pub trait InstanceInterface {
+    fn inputs(&self) -> &WireCollector;
+    fn outputs(&self) -> &WireCollector;
+    fn signal_to_tags(&self) -> &TagContext;
+    fn bus_node(&self, symbol: &str) -> NodePointer;
+}
+
+pub struct TemplateInterface {
+    inputs: WireCollector,
+    outputs: WireCollector,
+    signal_to_tags: TagContext,
+    bus_nodes: HashMap<String, NodePointer>,
+}
+
+impl InstanceInterface for TemplateInterface {
+    fn inputs(&self) -> &WireCollector {
+        &self.inputs
+    }
+    fn outputs(&self) -> &WireCollector {
+        &self.outputs
+    }
+    fn signal_to_tags(&self) -> &TagContext {
+        &self.signal_to_tags
+    }
+    fn bus_node(&self, symbol: &str) -> NodePointer {
+        self.bus_nodes[symbol]
+    }
+}
+
+impl InstanceInterface for ExecutedTemplate {
+    fn inputs(&self) -> &WireCollector {
+        &self.inputs
+    }
+    fn outputs(&self) -> &WireCollector {
+        &self.outputs
+    }
+    fn signal_to_tags(&self) -> &TagContext {
+        &self.signal_to_tags
+    }
+    fn bus_node(&self, symbol: &str) -> NodePointer {
+        self.bus_connexions[symbol].inspect.goes_to
+    }
+}
+
 pub struct ExecutedTemplate {
     pub code: Statement,
     pub template_name: String,
@@ -71,6 +120,9 @@ pub struct ExecutedTemplate {
     pub intermediates: WireCollector,
     pub ordered_signals: WireCollector,
     pub constraints: Vec<Constraint>,
+    // constraints written to the spill of the program, their locations are kept in memory
+    pub spilled_constraints: Vec<SpillRange>,
+    pub constraint_locations: Vec<ConstraintLocation>,
     pub components: ComponentCollector,
     pub number_of_components: usize,
     pub public_inputs: HashSet<String>,
@@ -81,6 +133,8 @@ pub struct ExecutedTemplate {
     pub has_parallel_sub_cmp: bool,
     pub is_custom_gate: bool,
     pub underscored_signals: Vec<String>,
+    // signals declared with keep, the simplification does not remove them
+    pub kept_signals: Vec<String>,
     connexions: Vec<Connexion>,
     pub bus_connexions: HashMap<String, BusConnexion>,
 }
@@ -113,11 +167,14 @@ impl ExecutedTemplate {
             intermediates: WireCollector::new(),
             ordered_signals: WireCollector::new(),
             constraints: Vec::new(),
+            spilled_constraints: Vec::new(),
+            constraint_locations: Vec::new(),
             components: ComponentCollector::new(),
             number_of_components: 0,
             connexions: Vec::new(),
             bus_connexions: HashMap::new(),
             underscored_signals: Vec::new(),
+            kept_signals: Vec::new(),
         }
     }
 
@@ -133,6 +190,26 @@ impl ExecutedTemplate {
             self.connexions.push(cnn);
     }
 
+    pub fn subcomponent_nodes(&self) -> Vec<NodePointer> {
+        self.connexions.iter().map(|connexion| connexion.inspect.goes_to).collect()
+    }
+
+    // The subcomponents point to the nodes of another program, node_of gives their position
+    pub fn relocate_subcomponents(&mut self, node_of: impl Fn(NodePointer) -> NodePointer) {
+        for connexion in &mut self.connexions {
+            connexion.inspect.goes_to = node_of(connexion.inspect.goes_to);
+        }
+    }
+
+    pub fn interface(&self) -> TemplateInterface {
+        TemplateInterface {
+            inputs: self.inputs.clone(),
+            outputs: self.outputs.clone(),
+            signal_to_tags: self.signal_to_tags.clone(),
+            bus_nodes: self.bus_connexions.iter().map(|(name, c)| (name.clone(), c.inspect.goes_to)).collect(),
+        }
+    }
+
     pub fn add_bus_arrow(&mut self, bus_name: String, data: BusData){
         let cnn =
             BusConnexion { full_name:bus_name.clone(), inspect: data, dag_offset: 0, dag_jump: 0};
@@ -185,14 +262,27 @@ impl ExecutedTemplate {
         self.number_of_components += dimensions.iter().fold(1, |p, c| p * (*c));
     }
 
-    pub fn add_constraint(&mut self, constraint: Constraint) {
-        self.constraints.push(constraint);
+    pub fn add_constraint(&mut self, constraint: Constraint, meta: &Meta, spill: Option<&mut Spill<String>>) -> io::Result<()> {
+        match spill {
+            Option::Some(spill) => spill.write(&constraint, &mut self.spilled_constraints)?,
+            Option::None => self.constraints.push(constraint),
+        }
+        self.constraint_locations.push((meta.get_file_id(), meta.file_location()));
+        Result::Ok(())
+    }
+
+    pub fn number_of_constraints(&self) -> usize {
+        self.constraint_locations.len()
     }
 
     pub fn add_underscored_signal(&mut self, signal: &str) {
         self.underscored_signals.push(signal.to_string());
     }
 
+    pub fn add_kept_signal(&mut self, signal: &str) {
+        self.kept_signals.push(signal.to_string());
+    }
+
     pub fn template_name(&self) -> &String {
         &self.template_name
     }
@@ -217,7 +307,7 @@ impl ExecutedTemplate {
         &self.intermediates
     }
 
-    pub fn insert_in_dag(&mut self, dag: &mut DAG, buses_info : &Vec<ExecutedBus>) {
+    pub fn insert_in_dag(&mut self, dag: &mut DAG, buses_info : &Vec<ExecutedBus>, spill: Option<&Spill<String>>) -> io::Result<()> {
         let parameters = {
             let mut parameters = vec![];
             for (_, data) in self.parameter_instances.clone() {
@@ -238,7 +328,7 @@ impl ExecutedTemplate {
         self.build_wires(dag, buses_info);
         self.build_ordered_signals(dag, buses_info);
         self.build_connexions(dag);
-        self.build_constraints(dag);
+        self.build_constraints(dag, spill)
     }
 
     fn build_wires(&self, dag: &mut DAG, buses_info : &Vec<ExecutedBus>) {
@@ -320,18 +410,35 @@ impl ExecutedTemplate {
         self.has_parallel_sub_cmp = dag.nodes[dag.main_id()].has_parallel_sub_cmp();
         dag.set_number_of_subcomponents_indexes(self.number_of_components);
     }
-    fn build_constraints(&self, dag: &mut DAG) {
-        
-        for c in &self.constraints {
+    // The constraints are moved to the dag, the template does not need them afterwards
+    fn build_constraints(&mut self, dag: &mut DAG, spill: Option<&Spill<String>>) -> io::Result<()> {
+        let constraints = std::mem::take(&mut self.constraints);
+        let spilled = std::mem::take(&mut self.spilled_constraints);
+        let locations = std::mem::take(&mut self.constraint_locations);
+        let spilled = spill.into_iter().flat_map(|spill| spill.read(&spilled));
+        for (c, location) in constraints.into_iter().map(Result::Ok).chain(spilled).zip(locations) {
             let correspondence = dag.get_main().unwrap().correspondence();
-            let cc = Constraint::apply_correspondence(c, correspondence);
-            dag.add_constraint(cc);
+            let cc = Constraint::apply_correspondence(&c?, correspondence);
+            dag.add_constraint(cc, location)?;
         }
         for s in &self.underscored_signals{
             let correspondence = dag.get_main().unwrap().correspondence();
             let new_s = correspondence.get(s).unwrap().clone();
             dag.add_underscored_signal(new_s);
         }
+        // every position of a kept array is kept
+        for s in &self.kept_signals {
+            let correspondence = dag.get_main().unwrap().correspondence();
+            let kept: Vec<_> = correspondence
+                .iter()
+                .filter(|(name, _)| matches!(name.strip_prefix(s.as_str()), Some(rest) if rest.is_empty() || rest.starts_with('[')))
+                .map(|(_, id)| *id)
+                .collect();
+            for signal in kept {
+                dag.add_kept_signal(signal);
+            }
+        }
+        Result::Ok(())
     }
 
     pub fn export_to_circuit(self, instances: &mut [TemplateInstance], buses_info : &Vec<BusInstance>) -> TemplateInstance {
@@ -387,7 +494,8 @@ impl ExecutedTemplate {
             arguments
         }
 
-        let header = format!("{}_{}", self.template_name, instances.len());
+        // the names of templates in namespaces contain dots, not allowed in the generated code
+        let header = format!("{}_{}", self.template_name.replace('.', "__"), instances.len());
         let clusters = build_clusters(&self, instances);
         let triggers = build_triggers(instances, self.connexions);
         let components = build_components(self.components);
@@ -1,7 +1,7 @@
 use super::environment_utils::slice_types::{AExpressionSlice, TagInfo};
 use circom_algebra::algebra::Constraint;
 pub use executed_program::ExecutedProgram;
-pub use executed_template::{PreExecutedTemplate, ExecutedTemplate};
+pub use executed_template::{PreExecutedTemplate, ExecutedTemplate, InstanceInterface, TemplateInterface};
 pub use executed_bus::ExecutedBus;
 pub use type_definitions::NodePointer;
 
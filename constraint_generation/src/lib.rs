@@ -20,6 +20,7 @@ use program_structure::error_code::ReportCode;
 use program_structure::error_definition::{Report, ReportCollection};
 use program_structure::file_definition::FileID;
 use program_structure::program_archive::ProgramArchive;
+use program_structure::progress::{self, Phase, PhaseTimer, ProgressEvent};
 use std::rc::Rc;
 
 pub struct BuildConfig {
@@ -32,13 +33,47 @@ pub struct BuildConfig {
     pub flag_verbose: bool,
     pub flag_old_heuristics: bool,
     pub inspect_constraints: bool,
+    pub stream_flag: bool,
+    pub keep_substitutions: bool,
     pub prime: String,
+    pub budgets: ExecutionBudgets,
+    pub threads: usize,
 }
 
 #[derive(Debug, Copy, Clone)]
 pub struct FlagsExecution{
     pub verbose: bool,
     pub inspect: bool,
+    pub budgets: ExecutionBudgets,
+    // template instances executed at the same time, see execute::constraint_execution
+    pub threads: usize,
+    // the generated constraints are written to temporary files instead of kept in memory
+    pub stream: bool,
+}
+
+// Limits of the work done during the constraint generation, the execution is aborted with an
+// error when one of them is exceeded. None means that there is no limit. By default the loops
+// and the nested calls are limited, so a loop that does not end or an unbounded recursion is
+// reported instead of running forever or growing the stack until the memory is exhausted.
+#[derive(Debug, Copy, Clone)]
+pub struct ExecutionBudgets {
+    // iterations of a single execution of a loop
+    pub max_iterations: Option<usize>,
+    // nested calls to functions and templates
+    pub max_recursion_depth: Option<usize>,
+    pub max_instances: Option<usize>,
+    pub max_constraints: Option<usize>,
+}
+
+impl Default for ExecutionBudgets {
+    fn default() -> Self {
+        ExecutionBudgets {
+            max_iterations: Some(10_000_000),
+            max_recursion_depth: Some(10_000),
+            max_instances: None,
+            max_constraints: None,
+        }
+    }
 }
 
 pub type ConstraintWriter = Box<dyn ConstraintExporter>;
@@ -48,7 +83,11 @@ pub fn build_circuit(program: ProgramArchive, config: BuildConfig) -> BuildRespo
     let flags = FlagsExecution{
         verbose: config.flag_verbose,
         inspect: config.inspect_constraints,
+        budgets: config.budgets,
+        threads: config.threads,
+        stream: config.stream_flag,
     };
+    let timer = PhaseTimer::start(Phase::ConstraintGeneration);
     let (exe, warnings) = instantiation(&program, flags, &config.prime).map_err(|r| {
         Report::print_reports(&r, &files);
     })?;
@@ -56,6 +95,10 @@ pub fn build_circuit(program: ProgramArchive, config: BuildConfig) -> BuildRespo
     let (mut dag, mut vcp, warnings) = export(exe, program, flags).map_err(|r| {
         Report::print_reports(&r, &files);
     })?;
+    if progress::is_reported() {
+        progress::report(ProgressEvent::ConstraintsGenerated { constraints: dag.number_of_constraints() });
+    }
+    timer.finish();
     if config.inspect_constraints {
         Report::print_reports(&warnings, &files);
     }
@@ -70,7 +113,9 @@ pub fn build_circuit(program: ProgramArchive, config: BuildConfig) -> BuildRespo
 
         Result::Ok((Box::new(dag), vcp))
     } else {
-        let list = simplification_process(&mut vcp, dag, &config);
+        let timer = PhaseTimer::start(Phase::Simplification);
+        let list = simplification_process(&mut vcp, dag, &config)?;
+        timer.finish();
         if config.flag_json_sub { 
             println!("{} {}", Colour::Green.paint("Written successfully:"), config.json_substitutions);
         };
@@ -78,6 +123,16 @@ pub fn build_circuit(program: ProgramArchive, config: BuildConfig) -> BuildRespo
     }
 }
 
+// Builds the circuit without simplifying its constraints, so that every constraint keeps
+// the location of the statement that generated it. The reports are returned instead of printed.
+pub fn build_unsimplified_circuit(program: ProgramArchive, prime: &String) -> Result<(DAG, VCP), ReportCollection> {
+    let flags = FlagsExecution { verbose: false, inspect: false, budgets: ExecutionBudgets::default(), threads: 1, stream: false };
+    let (exe, _) = execute::constraint_execution(&program, flags, prime)?;
+    let (mut dag, mut vcp, _) = export(exe, program, flags)?;
+    sync_dag_and_vcp(&mut vcp, &mut dag);
+    Result::Ok((dag, vcp))
+}
+
 type InstantiationResponse = Result<(ExecutedProgram, ReportCollection), ReportCollection>;
 fn instantiation(program: &ProgramArchive, flags: FlagsExecution, prime: &String) -> InstantiationResponse {
     let execution_result = execute::constraint_execution(&program, flags, prime);
@@ -103,18 +158,23 @@ fn sync_dag_and_vcp(vcp: &mut VCP, dag: &mut DAG) {
     VCP::add_witness_list(vcp, Rc::clone(&witness));
 }
 
-fn simplification_process(vcp: &mut VCP, dag: DAG, config: &BuildConfig) -> ConstraintList {
+fn simplification_process(vcp: &mut VCP, dag: DAG, config: &BuildConfig) -> Result<ConstraintList, ()> {
     use dag::SimplificationFlags;
     let flags = SimplificationFlags {
         flag_s: config.flag_s,
         parallel_flag: config.flag_p,
         port_substitution: config.flag_json_sub,
         json_substitutions: config.json_substitutions.clone(),
+        keep_substitutions: config.keep_substitutions,
+        stream_flag: config.stream_flag,
         no_rounds: config.no_rounds,
         flag_old_heuristics: config.flag_old_heuristics,
         prime : config.prime.clone(),
     };
-    let list = DAG::map_to_list(dag, flags);
+    let list = DAG::map_to_list(dag, flags).map_err(|error| {
+        let message = format!("The constraints could not be read from the temporary file: {}", error);
+        eprintln!("{}", Colour::Red.paint(message));
+    })?;
     VCP::add_witness_list(vcp, Rc::new(list.get_witness_as_vec()));
-    list
+    Result::Ok(list)
 }
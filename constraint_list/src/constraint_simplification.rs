@@ -1,19 +1,33 @@
 // Uncomment lines 163, 165, 336 and 338 to print cluster information
-use super::{ConstraintStorage, EncodingIterator, SEncoded, Simplifier, A, C, S};
+use super::{AppliedSubstitution, ConstraintStorage, EncodingIterator, SEncoded, SFrames, SimplificationRule, Simplifier, A, C, S};
 use crate::SignalMap;
 use circom_algebra::num_bigint::BigInt;
 use constraint_writers::json_writer::SubstitutionJSON;
+use constraint_writers::simplification_writer::LinearRound;
+use program_structure::progress::{self, ProgressEvent};
 use std::collections::{HashMap, HashSet, LinkedList, BTreeSet};
+use std::convert::Infallible;
 use std::sync::Arc;
 
-fn log_substitutions(substitutions: &LinkedList<S>, writer: &mut Option<SubstitutionJSON>) {
+// Substitutions found by the simplification, written in the json file and kept in memory on demand
+// together with the rule that found them. Every linear simplification is a new round.
+struct SubstitutionLog {
+    writer: Option<SubstitutionJSON>,
+    kept: Option<LinkedList<AppliedSubstitution>>,
+    linear_rounds: Vec<LinearRound>,
+}
+
+fn log_substitutions(substitutions: &LinkedList<S>, rule: SimplificationRule, log: &mut SubstitutionLog) {
     use super::json_porting::port_substitution;
-    if let Some(w) = writer {
+    if let Some(w) = &mut log.writer {
         for s in substitutions {
             let (from, to) = port_substitution(s);
             w.write_substitution(&from, &to).unwrap();
         }
     }
+    if let Some(kept) = &mut log.kept {
+        kept.extend(substitutions.iter().map(|s| AppliedSubstitution { substitution: s.clone(), rule }));
+    }
 }
 
 #[derive(Default, Clone)]
@@ -74,7 +88,8 @@ fn build_clusters(linear: LinkedList<C>, no_vars: usize) -> Vec<Cluster> {
     let mut signal_to_cluster = vec![no_linear; no_vars];
     for constraint in linear {
         if !constraint.is_empty(){
-            let signals = C::take_cloned_signals(&constraint);
+            // the order of the merges gives the order of the constraints of the cluster
+            let signals = C::take_cloned_signals_ordered(&constraint);
             let dest = ClusterArena::len(&arena);
             ClusterArena::push(&mut arena, Some(Cluster::new(constraint, signals.len())));
             Vec::push(&mut cluster_to_current, dest);
@@ -102,7 +117,7 @@ fn rebuild_witness(
     max_signal: usize, 
     deleted: &mut HashSet<usize>, 
     forbidden: &HashSet<usize>, 
-    non_linear_map: SignalToConstraints, 
+    is_used: &dyn Fn(usize) -> bool,
     remove_unused: bool,
 ) -> SignalMap {
     let mut map = SignalMap::with_capacity(max_signal);
@@ -110,7 +125,7 @@ fn rebuild_witness(
     for signal in 0..max_signal {
         if deleted.contains(&signal) {
             free.push_back(signal);
-        } else if remove_unused && !forbidden.contains(&signal) && !non_linear_map.contains_key(&signal){
+        } else if remove_unused && !forbidden.contains(&signal) && !is_used(signal){
             deleted.insert(signal);
             free.push_back(signal);
         } else if let Some(new_pos) = free.pop_front() {
@@ -156,7 +171,7 @@ fn eq_cluster_simplification(
         let mut cons = LinkedList::new();
         let mut subs = LinkedList::new();
         let (mut remains, mut min_remains) = (BTreeSet::new(), None);
-        let (mut remove, mut min_remove) = (HashSet::new(), None);
+        let (mut remove, mut min_remove) = (BTreeSet::new(), None);
         for c in cluster.constraints {
             for signal in C::take_cloned_signals_ordered(&c) {
                 if HashSet::contains(&forbidden, &signal) {
@@ -164,7 +179,7 @@ fn eq_cluster_simplification(
                     min_remains = Some(min_remains.map_or(signal, |s| std::cmp::min(s, signal)));
                 } else {
                     min_remove = Some(min_remove.map_or(signal, |s| std::cmp::min(s, signal)));
-                    HashSet::insert(&mut remove, signal);
+                    BTreeSet::insert(&mut remove, signal);
                 }
             }
         }
@@ -174,7 +189,7 @@ fn eq_cluster_simplification(
             signal
         } else {
             let signal = min_remove.unwrap();
-            HashSet::remove(&mut remove, &signal);
+            BTreeSet::remove(&mut remove, &signal);
             signal
         };
 
@@ -200,7 +215,7 @@ fn eq_simplification(
     forbidden: Arc<HashSet<usize>>,
     no_vars: usize,
     field: &BigInt,
-    substitution_log: &mut Option<SubstitutionJSON>,
+    substitution_log: &mut SubstitutionLog,
 ) -> (LinkedList<S>, LinkedList<C>) {
     use std::sync::mpsc;
     use threadpool::ThreadPool;
@@ -214,12 +229,14 @@ fn eq_simplification(
     // println!("Clusters: {}", no_clusters);
     let mut single_clusters = 0;
     let mut id = 0;
+    // the results are gathered by cluster, so they do not depend on the order the jobs end
     let mut aux_constraints = vec![LinkedList::new(); clusters.len()];
+    let mut aux_substitutions = vec![LinkedList::new(); clusters.len()];
     for cluster in clusters {
         if Cluster::size(&cluster) == 1 {
-            let (mut subs, cons) = eq_cluster_simplification(cluster, &forbidden, &field);
+            let (subs, cons) = eq_cluster_simplification(cluster, &forbidden, &field);
             aux_constraints[id] = cons;
-            LinkedList::append(&mut substitutions, &mut subs);
+            aux_substitutions[id] = subs;
             single_clusters += 1;
         } else {
             let cluster_tx = cluster_tx.clone();
@@ -239,14 +256,15 @@ fn eq_simplification(
     // println!("{} clusters were of size 1", single_clusters);
     ThreadPool::join(&pool);
     for _ in 0..(no_clusters - single_clusters) {
-        let (id, (mut subs, cons)) = simplified_rx.recv().unwrap();
+        let (id, (subs, cons)) = simplified_rx.recv().unwrap();
         aux_constraints[id] = cons;
-        LinkedList::append(&mut substitutions, &mut subs);
+        aux_substitutions[id] = subs;
     }
     for id in 0..no_clusters {
         LinkedList::append(&mut constraints, &mut aux_constraints[id]);
+        LinkedList::append(&mut substitutions, &mut aux_substitutions[id]);
     }
-    log_substitutions(&substitutions, substitution_log);
+    log_substitutions(&substitutions, SimplificationRule::Equality, substitution_log);
     (substitutions, constraints)
 }
 
@@ -254,7 +272,7 @@ fn constant_eq_simplification(
     c_eq: LinkedList<C>,
     forbidden: &HashSet<usize>,
     field: &BigInt,
-    substitution_log: &mut Option<SubstitutionJSON>,
+    substitution_log: &mut SubstitutionLog,
 ) -> (LinkedList<S>, LinkedList<C>) {
     let mut cons = LinkedList::new();
     let mut subs = LinkedList::new();
@@ -268,12 +286,12 @@ fn constant_eq_simplification(
             LinkedList::push_back(&mut subs, sub);
         }
     }
-    log_substitutions(&subs, substitution_log);
+    log_substitutions(&subs, SimplificationRule::ConstantEquality, substitution_log);
     (subs, cons)
 }
 
 fn linear_simplification(
-    log: &mut Option<SubstitutionJSON>,
+    log: &mut SubstitutionLog,
     linear: LinkedList<C>,
     forbidden: Arc<HashSet<usize>>,
     no_labels: usize,
@@ -283,9 +301,14 @@ fn linear_simplification(
     use circom_algebra::simplification_utils::full_simplification;
     use circom_algebra::simplification_utils::Config;
     use std::sync::mpsc;
+    use std::time::SystemTime;
     use threadpool::ThreadPool;
 
     // println!("Cluster simplification");
+    let now = SystemTime::now();
+    let round = log.linear_rounds.len() + 1;
+    let rule = SimplificationRule::Linear(round);
+    let no_constraints = linear.len();
     let mut cons = LinkedList::new();
     let mut substitutions = LinkedList::new();
     let clusters = build_clusters(linear, no_labels);
@@ -307,7 +330,7 @@ fn linear_simplification(
             // println!("cluster: {}", id);
             let result = full_simplification(config);
             // println!("End of cluster: {}", id);
-            cluster_tx.send(result).unwrap();
+            cluster_tx.send((id, result)).unwrap();
         };
         ThreadPool::execute(&pool, job);
         let _ = id;
@@ -315,12 +338,30 @@ fn linear_simplification(
     }
     ThreadPool::join(&pool);
 
+    // the results are taken in the order of the clusters, as in eq_simplification
+    let mut results: Vec<_> = std::iter::repeat_with(|| None).take(no_clusters).collect();
     for _ in 0..no_clusters {
-        let mut result = simplified_rx.recv().unwrap();
-        log_substitutions(&result.substitutions, log);
+        let (id, result) = simplified_rx.recv().unwrap();
+        results[id] = Some(result);
+    }
+    let mut rank = 0;
+    for result in results {
+        let mut result = result.unwrap();
+        rank += result.rank;
+        log_substitutions(&result.substitutions, rule, log);
         LinkedList::append(&mut cons, &mut result.constraints);
         LinkedList::append(&mut substitutions, &mut result.substitutions);
     }
+    // the progress of the round is reported with its entry in the simplification report, so
+    // both have the same rounds
+    log.linear_rounds.push(LinearRound { round, constraints: no_constraints, rank });
+    progress::report(ProgressEvent::SimplificationRound {
+        round,
+        linear: no_constraints,
+        substitutions: substitutions.len(),
+        remaining: cons.len(),
+        millis: now.elapsed().unwrap().as_millis(),
+    });
     (substitutions, cons)
 }
 
@@ -396,7 +437,7 @@ fn apply_substitution_to_map(
 }
 
 fn build_relevant_set(
-    mut iter: EncodingIterator,
+    iter: EncodingIterator,
     relevant: &mut HashSet<usize>,
     renames: &SEncoded,
     deletes: &SEncoded,
@@ -412,9 +453,9 @@ fn build_relevant_set(
         SEncoded::get(map, &signal).map_or(None, f)
     }
 
-    let (_, non_linear) = EncodingIterator::take(&mut iter);
-    for c in non_linear {
-        for signal in C::take_cloned_signals(&c) {
+    for c in &EncodingIterator::node(&iter).non_linear {
+        for signal in C::take_signals(c) {
+            let signal = signal + iter.offset;
             let signal = unwrapped_signal(renames, signal).unwrap_or(signal);
             if !SEncoded::contains_key(deletes, &signal) {
                 HashSet::insert(relevant, signal);
@@ -438,19 +479,25 @@ fn remove_not_relevant(substitutions: &mut SEncoded, relevant: &HashSet<usize>)
 }
 
 
-// returns the constraints, the assignment of the witness and the number of inputs in the witness
-pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap, usize) {
-    use super::non_linear_utils::obtain_and_simplify_non_linear;
+// returns the constraints, the assignment of the witness, the number of inputs in the witness,
+// the substitutions applied if they are kept and the rank of every linear round
+pub fn simplification(
+    smp: &mut Simplifier,
+) -> (ConstraintStorage, SignalMap, usize, LinkedList<AppliedSubstitution>, Vec<LinearRound>, Option<SFrames>) {
+    use super::non_linear_utils::{obtain_and_simplify_non_linear, stream_non_linear};
     use circom_algebra::simplification_utils::build_encoded_fast_substitutions;
     use circom_algebra::simplification_utils::fast_encoded_constraint_substitution;
     use std::time::SystemTime;
 
-    let mut substitution_log =
-        if smp.port_substitution { 
-            Some(SubstitutionJSON::new(&smp.json_substitutions).unwrap()) 
+    let mut substitution_log = SubstitutionLog {
+        writer: if smp.port_substitution {
+            Some(SubstitutionJSON::new(&smp.json_substitutions).unwrap())
         } else {
-             None 
-        };
+            None
+        },
+        kept: if smp.keep_substitutions { Some(LinkedList::new()) } else { None },
+        linear_rounds: Vec::new(),
+    };
     let apply_linear = !smp.flag_s;
     let use_old_heuristics = smp.flag_old_heuristics;
     let field = smp.field.clone();
@@ -577,7 +624,9 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap, us
         HashMap::with_capacity(0)
     };
 
-    let (with_linear, mut constraint_storage) = {
+    // When streaming, the non-linear constraints are not stored, only their signals are marked
+    // as used. They are simplified again when they are written.
+    let (with_linear, mut constraint_storage, streamed) = {
         // println!("Building constraint storage");
         let now = SystemTime::now();
         let mut frames = LinkedList::new();
@@ -586,16 +635,27 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap, us
         LinkedList::push_back(&mut frames, linear_substitutions);
         let iter = EncodingIterator::new(&smp.dag_encoding);
         let mut storage = ConstraintStorage::new();
-        let with_linear = obtain_and_simplify_non_linear(iter, &mut storage, &frames, &field);
-        crate::state_utils::empty_encoding_constraints(&mut smp.dag_encoding);
+        let (with_linear, streamed) = if smp.stream_flag && !apply_linear {
+            let mut used = vec![false; max_signal];
+            let mut mark = |constraint: C| {
+                for signal in C::take_cloned_signals(&constraint) {
+                    used[signal] = true;
+                }
+                Result::<(), Infallible>::Ok(())
+            };
+            let Result::Ok(with_linear) = stream_non_linear(iter, &frames, &field, &mut mark);
+            (with_linear, Option::Some((used, frames)))
+        } else {
+            let with_linear = obtain_and_simplify_non_linear(iter, &mut storage, &frames, &field);
+            crate::state_utils::empty_encoding_constraints(&mut smp.dag_encoding);
+            (with_linear, Option::None)
+        };
         let _dur = now.elapsed().unwrap().as_millis();
         // println!("Storages built in {} ms", dur);
         no_rounds -= 1;
-        (with_linear, storage)
+        (with_linear, storage, streamed)
     };
 
-    let mut round_id = 0;
-    let _ = round_id;
     let mut linear = with_linear;
     let mut apply_round = apply_linear && no_rounds > 0 && !linear.is_empty();
     let mut non_linear_map = if apply_round || remove_unused {
@@ -609,8 +669,6 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap, us
         SignalToConstraints::with_capacity(0)
     };
     while apply_round {
-        let now = SystemTime::now();
-        // println!("Number of linear constraints: {}", linear.len());
         let (substitutions, mut constants) = linear_simplification(
             &mut substitution_log,
             linear,
@@ -636,11 +694,8 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap, us
             &substitutions,
             &field,
         );
-        round_id += 1;
         no_rounds -= 1;
         apply_round = !linear.is_empty() && no_rounds > 0;
-        let _dur = now.elapsed().unwrap().as_millis();
-        // println!("Iteration no {} took {} ms", round_id, dur);
     }
 
     for constraint in linear {
@@ -698,11 +753,14 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap, us
     let signal_map = {
         // println!("Rebuild witness");
         let now = SystemTime::now();
+        let is_used = |signal: usize| {
+            non_linear_map.contains_key(&signal) || matches!(&streamed, Some((used, _)) if used[signal])
+        };
         let signal_map= rebuild_witness(
             max_signal, 
             &mut deleted, 
             &forbidden, 
-            non_linear_map, 
+            &is_used,
             remove_unused
         );
         let _dur = now.elapsed().unwrap().as_millis();
@@ -720,11 +778,14 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap, us
     }
 
 
-    if let Some(w) = substitution_log {
+    if let Some(w) = substitution_log.writer {
         w.end().unwrap();
     }
     // println!("NO CONSTANTS: {}", constraint_storage.no_constants());
-    (constraint_storage, signal_map, smp.no_private_inputs - deleted_inputs)
+    let substitutions = substitution_log.kept.unwrap_or_default();
+    let no_private_inputs_witness = smp.no_private_inputs - deleted_inputs;
+    let streamed = streamed.map(|(_, frames)| frames);
+    (constraint_storage, signal_map, no_private_inputs_witness, substitutions, substitution_log.linear_rounds, streamed)
 }
 
 
@@ -0,0 +1,68 @@
+use super::{ConstraintList, DAGEncoding};
+use constraint_writers::hierarchy_writer::ComponentInstance;
+use std::convert::Infallible;
+
+// Every constraint of the simplified system is counted in the deepest instance that contains
+// all its signals, since the substitutions move constraints between instances.
+pub fn port_hierarchy(list: &ConstraintList) -> ComponentInstance {
+    let encoding = &list.dag_encoding;
+    let mut root = build_instance(encoding, encoding.init, "main".to_string(), 0, list.no_labels() - 1);
+    let Result::Ok(()) = list.for_each_constraint(|constraint| {
+        let signals = constraint.take_signals();
+        let first = signals.iter().map(|s| **s).filter(|s| *s != 0).min();
+        let last = signals.iter().map(|s| **s).max();
+        match (first, last) {
+            (Some(first), Some(last)) => add_constraint(&mut root, first, last),
+            _ => root.constraints += 1,
+        }
+        Result::<(), Infallible>::Ok(())
+    });
+    root
+}
+
+fn build_instance(
+    encoding: &DAGEncoding,
+    node_id: usize,
+    path: String,
+    offset: usize,
+    number_of_signals: usize,
+) -> ComponentInstance {
+    let node = &encoding.nodes[node_id];
+    let subcomponents = encoding.adjacency[node_id]
+        .iter()
+        .map(|edge| {
+            let path = format!("{}.{}", path, edge.path);
+            build_instance(encoding, edge.goes_to, path, offset + edge.offset, edge.number_of_signals)
+        })
+        .collect();
+    ComponentInstance {
+        path,
+        // the name of the node includes the parameters, like Num2Bits(8)
+        template_name: node.name.split('(').next().unwrap().to_string(),
+        parameters: node.parameters.iter().map(|p| p.to_string()).collect(),
+        signal_offset: offset,
+        number_of_signals,
+        constraints: 0,
+        is_custom_gate: node.is_custom_gate,
+        subcomponents,
+    }
+}
+
+fn add_constraint(root: &mut ComponentInstance, first: usize, last: usize) {
+    let mut current = root;
+    loop {
+        // the subcomponents are sorted by their signals, which do not overlap
+        let subcomponents = &current.subcomponents;
+        let index = subcomponents.partition_point(|s| s.signal_offset + s.number_of_signals < first);
+        let contains = matches!(
+            subcomponents.get(index),
+            Some(s) if s.signal_offset < first && last <= s.signal_offset + s.number_of_signals
+        );
+        if !contains {
+            break;
+        }
+        let parent = current;
+        current = &mut parent.subcomponents[index];
+    }
+    current.constraints += 1;
+}
@@ -1,5 +1,4 @@
-use super::{ConstraintStorage, C, S};
-use crate::SignalMap;
+use super::{ConstraintList, C, S};
 use circom_algebra::num_bigint::BigInt;
 use constraint_writers::debug_writer::DebugWriter;
 use json::JsonValue;
@@ -32,17 +31,12 @@ pub fn port_substitution(sub: &S) -> (String, String) {
     (from, to)
 }
 
-pub fn port_constraints(
-    storage: &ConstraintStorage,
-    map: &SignalMap,
-    debug: &DebugWriter,
-) -> Result<(), ()> {
+pub fn port_constraints(list: &ConstraintList, debug: &DebugWriter) -> Result<(), ()> {
     let mut writer = debug.build_constraints_file()?;
-    for c_id in storage.get_ids() {
-        let constraint = storage.read_constraint(c_id).unwrap();
-        let constraint = C::apply_correspondence(&constraint, map);
+    list.for_each_constraint(|constraint| {
+        let constraint = C::apply_correspondence(&constraint, &list.signal_map);
         let json_value = transform_constraint_to_json(&constraint);
-        writer.write_constraint(&json_value.to_string())?;
-    }
+        writer.write_constraint(&json_value.to_string())
+    })?;
     writer.end()
 }
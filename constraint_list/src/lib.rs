@@ -3,13 +3,19 @@ use std::collections::{HashMap, HashSet, LinkedList};
 use circom_algebra::constraint_storage::ConstraintStorage;
 use circom_algebra::num_bigint::BigInt;
 use constraint_writers::debug_writer::DebugWriter;
+use constraint_writers::hierarchy_writer::ComponentInstance;
+use constraint_writers::simplification_writer::{LinearRound, SimplificationReport};
+use constraint_writers::substitution_map_writer::SubstitutionMap;
 use constraint_writers::ConstraintExporter;
 
 mod constraint_simplification;
+mod hierarchy_porting;
 mod json_porting;
 mod non_linear_utils;
 mod r1cs_porting;
+mod report_porting;
 mod state_utils;
+mod substitution_map_porting;
 mod sym_porting;
 mod non_linear_simplification;
 
@@ -20,6 +26,20 @@ type SignalMap = HashMap<usize, usize>;
 type SEncoded = HashMap<usize, A>;
 type SFrames = LinkedList<SEncoded>;
 
+// Rule of the simplification that removed a signal
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SimplificationRule {
+    ConstantEquality,
+    Equality,
+    // round of the linear simplification, starting at 1
+    Linear(usize),
+}
+
+pub struct AppliedSubstitution {
+    pub substitution: S,
+    pub rule: SimplificationRule,
+}
+
 pub struct SignalInfo {
     pub name: String,
     pub id: usize,
@@ -38,6 +58,8 @@ pub struct EncodingEdge {
     pub goes_to: usize,
     pub path: String,
     pub offset: usize,
+    // signals of the subcomponent and of its subcomponents
+    pub number_of_signals: usize,
 }
 
 pub struct DAGEncoding {
@@ -62,48 +84,44 @@ impl IteratorSignal {
     }
 }
 
+// Instance of a node of the encoding, its signals and constraints are moved by offset
+// only when they are asked for, so the instances of a node share its data
 pub struct EncodingIterator<'a> {
     encoding: &'a DAGEncoding,
     pub node_id: usize,
     pub path: String,
     pub offset: usize,
-    pub signals: Vec<SignalInfo>,
-    pub non_linear: LinkedList<C>,
 }
 
 impl<'a> EncodingIterator<'a> {
     pub fn new(encoding: &'a DAGEncoding) -> EncodingIterator<'a> {
-        let iter = EncodingIterator {
-            encoding,
-            offset: 0,
-            non_linear: LinkedList::new(),
-            path: "main".to_string(),
-            signals: Vec::new(),
-            node_id: encoding.init,
-        };
-        state_utils::build_encoding_iterator(iter)
+        EncodingIterator { encoding, offset: 0, path: "main".to_string(), node_id: encoding.init }
     }
 
     pub fn next(iterator: &'a EncodingIterator, edge: &EncodingEdge) -> EncodingIterator<'a> {
-        let iter = EncodingIterator {
+        EncodingIterator {
             encoding: iterator.encoding,
             offset: iterator.offset + edge.offset,
             node_id: edge.goes_to,
             path: format!("{}.{}", iterator.path, edge.path),
-            non_linear: LinkedList::new(),
-            signals: Vec::new(),
-        };
-        state_utils::build_encoding_iterator(iter)
+        }
     }
 
     pub fn edges(iterator: &'a EncodingIterator) -> &'a Vec<EncodingEdge> {
         &iterator.encoding.adjacency[iterator.node_id]
     }
 
-    pub fn take(iter: &mut EncodingIterator) -> (Vec<SignalInfo>, LinkedList<C>) {
-        let ret = (std::mem::take(&mut iter.signals), std::mem::take(&mut iter.non_linear));
-        state_utils::clear_encoding_iterator(iter);
-        ret
+    pub fn node(iterator: &EncodingIterator<'a>) -> &'a EncodingNode {
+        &iterator.encoding.nodes[iterator.node_id]
+    }
+
+    pub fn signals(iterator: &EncodingIterator) -> Vec<SignalInfo> {
+        state_utils::instance_signals(iterator)
+    }
+
+    pub fn non_linear(iterator: &EncodingIterator<'a>) -> impl Iterator<Item = C> + 'a {
+        let offset = iterator.offset;
+        EncodingIterator::node(iterator).non_linear.iter().map(move |c| C::apply_offset(c, offset))
     }
 }
 
@@ -126,10 +144,14 @@ pub struct Simplifier {
     pub flag_old_heuristics: bool,
     pub port_substitution: bool,
     pub json_substitutions: String,
+    pub keep_substitutions: bool,
+    // the simplified constraints are spilled to a temporary file, only without linear rounds
+    pub stream_flag: bool,
 }
 impl Simplifier {
     pub fn simplify_constraints(mut self) -> ConstraintList {
-        let (portable, map, private_inputs_witness) = constraint_simplification::simplification(&mut self);
+        let (portable, map, private_inputs_witness, substitutions, linear_rounds, streamed) =
+            constraint_simplification::simplification(&mut self);
         ConstraintList {
             field: self.field,
             dag_encoding: self.dag_encoding,
@@ -140,6 +162,9 @@ impl Simplifier {
             no_labels: self.max_signal,
             constraints: portable,
             signal_map: map,
+            substitutions,
+            linear_rounds,
+            streamed,
         }
     }
 
@@ -163,6 +188,13 @@ pub struct ConstraintList {
     pub no_labels: usize,
     //  Signals in [witness_len, Vec::len(&signal_map)) are the ones deleted
     pub signal_map: SignalMap,
+    // substitutions applied by the simplification, only kept if the simplifier is asked to
+    pub substitutions: LinkedList<AppliedSubstitution>,
+    // constraints and rank of every round of the linear simplification
+    pub linear_rounds: Vec<LinearRound>,
+    // substitutions of the non-linear constraints when they are streamed, they are not stored
+    // and the encoding keeps them to simplify them again each time they are written
+    streamed: Option<SFrames>,
 }
 
 impl ConstraintExporter for ConstraintList {
@@ -171,15 +203,40 @@ impl ConstraintExporter for ConstraintList {
     }
 
     fn json_constraints(&self, writer: &DebugWriter) -> Result<(), ()> {
-        json_porting::port_constraints(&self.constraints, &self.signal_map, writer)
+        json_porting::port_constraints(self, writer)
     }
 
     fn sym(&self, out: &str) -> Result<(), ()> {
         sym_porting::port_sym(self, out)
     }
+
+    fn hierarchy(&self) -> ComponentInstance {
+        hierarchy_porting::port_hierarchy(self)
+    }
+
+    fn simplification_report(&self) -> SimplificationReport {
+        report_porting::port_report(self)
+    }
+
+    fn substitution_map(&self) -> SubstitutionMap {
+        substitution_map_porting::port_substitution_map(self)
+    }
 }
 
 impl ConstraintList {
+    // The constraints in the order of their ids, the streamed ones are given as they are
+    // simplified again and then the stored ones
+    pub fn for_each_constraint<E>(&self, mut f: impl FnMut(C) -> Result<(), E>) -> Result<(), E> {
+        if let Option::Some(frames) = &self.streamed {
+            let iter = EncodingIterator::new(&self.dag_encoding);
+            non_linear_utils::stream_non_linear(iter, frames, &self.field, &mut f)?;
+        }
+        for constraint in self.constraints.constraints() {
+            f(constraint)?;
+        }
+        Result::Ok(())
+    }
+
     pub fn get_witness(&self) -> &SignalMap {
         &self.signal_map
     }
@@ -1,31 +1,148 @@
-use super::{ConstraintStorage, EncodingIterator, SFrames, C};
+use super::{ConstraintStorage, EncodingIterator, EncodingNode, SFrames, A, C};
+use circom_algebra::constraint_storage::TemplateID;
 use circom_algebra::num_bigint::BigInt;
 use circom_algebra::simplification_utils::fast_encoded_constraint_substitution;
-use std::collections::LinkedList;
+use std::collections::{HashMap, HashSet, LinkedList};
+use std::convert::Infallible;
+
+// Template of the non-linear constraints of a node once fixed, and whether some of them
+// became linear. It is shared by the instances of the node whose signals are at most renamed
+// by the substitutions.
+type SharedTemplate = (TemplateID, bool);
 
 pub fn obtain_and_simplify_non_linear(
-    mut iter: EncodingIterator,
+    iter: EncodingIterator,
     storage: &mut ConstraintStorage,
     frames: &SFrames,
     field: &BigInt,
 ) -> LinkedList<C> {
+    let mut templates = HashMap::new();
+    let mut linear = LinkedList::new();
+    let mut keep = |_: &mut ConstraintStorage| Result::<(), Infallible>::Ok(());
+    let Result::Ok(()) = visit_instance(iter, storage, frames, field, &mut templates, &mut linear, &mut keep);
+    linear
+}
+
+// Simplifies the non-linear constraints like obtain_and_simplify_non_linear, but they are given
+// to write after each instance instead of stored. Only the templates of the instances that share
+// the constraints of their nodes are kept.
+pub fn stream_non_linear<E>(
+    iter: EncodingIterator,
+    frames: &SFrames,
+    field: &BigInt,
+    write: &mut dyn FnMut(C) -> Result<(), E>,
+) -> Result<LinkedList<C>, E> {
+    let mut storage = ConstraintStorage::new();
+    let mut templates = HashMap::new();
     let mut linear = LinkedList::new();
-    let (_, non_linear) = EncodingIterator::take(&mut iter);
-    for mut constraint in non_linear {
-        for frame in frames {
-            fast_encoded_constraint_substitution(&mut constraint, frame, &field);
+    let mut drain = |storage: &mut ConstraintStorage| {
+        for constraint in storage.drain() {
+            write(constraint)?;
         }
-        C::fix_constraint(&mut constraint, &field);
-        if C::is_linear(&constraint) {
-            linear.push_back(constraint);
-        } else {
-            storage.add_constraint(constraint);
+        Result::Ok(())
+    };
+    visit_instance(iter, &mut storage, frames, field, &mut templates, &mut linear, &mut drain)?;
+    Result::Ok(linear)
+}
+
+// The constraints of each instance are added to the storage before visiting its subcomponents,
+// and then the storage is given to end_instance
+fn visit_instance<E>(
+    iter: EncodingIterator,
+    storage: &mut ConstraintStorage,
+    frames: &SFrames,
+    field: &BigInt,
+    templates: &mut HashMap<usize, SharedTemplate>,
+    linear: &mut LinkedList<C>,
+    end_instance: &mut dyn FnMut(&mut ConstraintStorage) -> Result<(), E>,
+) -> Result<(), E> {
+    let node = EncodingIterator::node(&iter);
+    if let Option::Some(renames) = instance_renames(node, iter.offset, frames) {
+        let (template, has_linear) = *templates
+            .entry(iter.node_id)
+            .or_insert_with(|| build_template(node, storage, field));
+        if has_linear {
+            let non_linear = EncodingIterator::non_linear(&iter);
+            for constraint in non_linear.filter(|c| is_linear_once_fixed(c, field)) {
+                linear.push_back(simplify_constraint(constraint, frames, field));
+            }
+        }
+        storage.add_instance(template, iter.offset, renames);
+    } else {
+        for constraint in EncodingIterator::non_linear(&iter) {
+            let constraint = simplify_constraint(constraint, frames, field);
+            if C::is_linear(&constraint) {
+                linear.push_back(constraint);
+            } else {
+                storage.add_constraint(constraint);
+            }
         }
     }
+    end_instance(storage)?;
     for edge in EncodingIterator::edges(&iter) {
         let next = EncodingIterator::next(&iter, edge);
-        let mut linear_in_next = obtain_and_simplify_non_linear(next, storage, frames, field);
-        linear.append(&mut linear_in_next);
+        visit_instance(next, storage, frames, field, templates, linear, end_instance)?;
     }
-    linear
+    Result::Ok(())
+}
+
+fn simplify_constraint(mut constraint: C, frames: &SFrames, field: &BigInt) -> C {
+    for frame in frames {
+        fast_encoded_constraint_substitution(&mut constraint, frame, field);
+    }
+    C::fix_constraint(&mut constraint, field);
+    constraint
+}
+
+fn is_linear_once_fixed(constraint: &C, field: &BigInt) -> bool {
+    let mut constraint = constraint.clone();
+    C::fix_constraint(&mut constraint, field);
+    C::is_linear(&constraint)
+}
+
+// Signals of the node whose substitutions in the instance are renames, as long as two
+// signals are not renamed to the same one. Otherwise the instance does not share the
+// constraints of the node.
+fn instance_renames(node: &EncodingNode, offset: usize, frames: &SFrames) -> Option<Vec<(usize, usize)>> {
+    let constant = C::constant_coefficient();
+    let mut renames = Vec::new();
+    let mut visited = HashSet::new();
+    let mut targets = HashSet::new();
+    for c in &node.non_linear {
+        for signal in c.a().keys().chain(c.b().keys()).chain(c.c().keys()) {
+            if *signal == constant || !visited.insert(*signal) {
+                continue;
+            }
+            let mut target = *signal + offset;
+            for frame in frames {
+                match frame.get(&target) {
+                    Option::None => {}
+                    Option::Some(A::Signal { symbol }) => target = *symbol,
+                    Option::Some(_) => return Option::None,
+                }
+            }
+            if !targets.insert(target) {
+                return Option::None;
+            }
+            if target != *signal + offset {
+                renames.push((*signal, target));
+            }
+        }
+    }
+    Option::Some(renames)
+}
+
+fn build_template(node: &EncodingNode, storage: &mut ConstraintStorage, field: &BigInt) -> SharedTemplate {
+    let mut shared = Vec::new();
+    let mut has_linear = false;
+    for constraint in &node.non_linear {
+        let mut constraint = constraint.clone();
+        C::fix_constraint(&mut constraint, field);
+        if C::is_linear(&constraint) {
+            has_linear = true;
+        } else {
+            shared.push(constraint);
+        }
+    }
+    (storage.add_template(shared), has_linear)
 }
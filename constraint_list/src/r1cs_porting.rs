@@ -20,8 +20,8 @@ pub fn port_r1cs(list: &ConstraintList, output: &str, custom_gates: bool) -> Res
     let mut constraint_section = R1CSWriter::start_constraints_section(r1cs)?;
     let mut written = 0;
 
-    for c_id in list.constraints.get_ids() {
-        let c = list.constraints.read_constraint(c_id).unwrap();
+    // the header is written after the constraints, once they are counted
+    list.for_each_constraint(|c| {
         let c = C::apply_correspondence(&c, &list.signal_map);
         ConstraintSection::write_constraint_usize(&mut constraint_section, c.a(), c.b(), c.c())?;
         if C::is_linear(&c) {
@@ -30,7 +30,8 @@ pub fn port_r1cs(list: &ConstraintList, output: &str, custom_gates: bool) -> Res
             log.no_non_linear += 1;
         }
         written += 1;
-    }
+        Result::Ok(())
+    })?;
 
     let r1cs = constraint_section.end_section()?;
     let mut header_section = R1CSWriter::start_header_section(r1cs)?;
@@ -0,0 +1,88 @@
+use super::{AppliedSubstitution, ConstraintList, EncodingIterator, EncodingNode, SimplificationRule};
+use circom_algebra::num_bigint::BigInt;
+use constraint_writers::simplification_writer::{EliminatedSignal, SimplificationReport};
+use std::collections::{BTreeMap, HashMap};
+
+// Name of a signal and the node of the instance it belongs to
+pub type Names = Vec<Option<(String, usize)>>;
+
+// The signals out of the witness were removed by the substitution kept for them, or for not
+// being in any constraint when there is none
+pub fn port_report(list: &ConstraintList) -> SimplificationReport {
+    let mut names = vec![None; list.no_labels()];
+    let mut template_signals = BTreeMap::new();
+    collect(EncodingIterator::new(&list.dag_encoding), &mut names, &mut template_signals);
+    let substitutions: HashMap<_, _> =
+        list.substitutions.iter().map(|s| (*s.substitution.from(), s)).collect();
+    let mut eliminated = Vec::new();
+    for (signal, info) in names.iter().enumerate() {
+        if let (Some((name, node_id)), false) = (info, list.signal_map.contains_key(&signal)) {
+            let applied = substitutions.get(&signal);
+            let (rule, round) = match applied.map(|s| s.rule) {
+                Some(SimplificationRule::ConstantEquality) => ("constant equality", None),
+                Some(SimplificationRule::Equality) => ("equality", None),
+                Some(SimplificationRule::Linear(round)) => ("linear", Some(round)),
+                None => ("unused", None),
+            };
+            eliminated.push(EliminatedSignal {
+                name: name.clone(),
+                template: template_name(&list.dag_encoding.nodes[*node_id]).to_string(),
+                rule,
+                round,
+                replaced_by: applied.map(|s| expression(s, &names, &list.field)),
+            });
+        }
+    }
+    SimplificationReport { eliminated, template_signals, linear_rounds: list.linear_rounds.clone() }
+}
+
+fn template_name(node: &EncodingNode) -> &str {
+    // the name of the node includes the parameters, like Num2Bits(8)
+    node.name.split('(').next().unwrap()
+}
+
+pub fn collect(iter: EncodingIterator, names: &mut Names, template_signals: &mut BTreeMap<String, usize>) {
+    let signals = EncodingIterator::signals(&iter);
+    let template = template_name(EncodingIterator::node(&iter)).to_string();
+    *template_signals.entry(template).or_insert(0) += signals.len();
+    for signal in signals {
+        names[signal.id] = Some((signal.name, iter.node_id));
+    }
+    for edge in EncodingIterator::edges(&iter) {
+        let next = EncodingIterator::next(&iter, edge);
+        collect(next, names, template_signals);
+    }
+}
+
+// The coefficients greater than p/2 are written as negative numbers and the constant at the end
+fn expression(applied: &AppliedSubstitution, names: &Names, field: &BigInt) -> String {
+    let mut terms: Vec<_> = applied.substitution.to().iter().filter(|(_, v)| **v != BigInt::from(0)).collect();
+    terms.sort_by_key(|(s, _)| (**s == 0, **s));
+    let mut out = String::new();
+    for (signal, value) in terms {
+        let value = if value * 2 > *field { value - field } else { value.clone() };
+        let negative = value < BigInt::from(0);
+        let magnitude = if negative { -value } else { value };
+        if out.is_empty() {
+            out.push_str(if negative { "-" } else { "" });
+        } else {
+            out.push_str(if negative { " - " } else { " + " });
+        }
+        let name = match names.get(*signal) {
+            Some(Some((name, _))) => name.clone(),
+            _ => format!("signal_{}", signal),
+        };
+        if *signal == 0 {
+            out.push_str(&magnitude.to_string());
+        } else if magnitude == BigInt::from(1) {
+            out.push_str(&name);
+        } else {
+            out.push_str(&format!("{}*{}", magnitude, name));
+        }
+    }
+    if out.is_empty() {
+        "0".to_string()
+    } else {
+        out
+    }
+}
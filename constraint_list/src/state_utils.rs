@@ -6,30 +6,14 @@ pub fn empty_encoding_constraints(encoding: &mut DAGEncoding) {
     }
 }
 
-pub fn clear_encoding_iterator(iterator: &mut EncodingIterator) {
-    iterator.signals = Vec::with_capacity(0);
-    iterator.non_linear.clear();
-}
-
-pub fn build_encoding_iterator(mut iterator: EncodingIterator) -> EncodingIterator {
-    let encoding = iterator.encoding;
+pub fn instance_signals(iterator: &EncodingIterator) -> Vec<SignalInfo> {
     let offset = iterator.offset;
-    let node_id = iterator.node_id;
-    let path = iterator.path;
-    let mut non_linear = LinkedList::new();
+    let path = &iterator.path;
     let mut signals = Vec::new();
-    for signal in &encoding.nodes[node_id].signals {
+    for signal in &EncodingIterator::node(iterator).signals {
         let new_signal =
             SignalInfo { id: signal.id + offset, name: format!("{}.{}", path, signal.name) };
         Vec::push(&mut signals, new_signal);
     }
-
-    for constraint in &encoding.nodes[node_id].non_linear {
-        let constraint = C::apply_offset(constraint, offset);
-        LinkedList::push_back(&mut non_linear, constraint);
-    }
-    iterator.path = path;
-    iterator.non_linear = non_linear;
-    iterator.signals = signals;
-    iterator
+    signals
 }
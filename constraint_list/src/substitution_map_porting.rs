@@ -0,0 +1,63 @@
+use super::report_porting::{collect, Names};
+use super::{ConstraintList, EncodingIterator};
+use circom_algebra::modular_arithmetic;
+use circom_algebra::num_bigint::BigInt;
+use constraint_writers::substitution_map_writer::{RemovedSignal, SubstitutionMap};
+use std::collections::{BTreeMap, HashMap};
+
+// Coefficient of every wire of the witness
+type Terms = BTreeMap<usize, BigInt>;
+
+// The substitutions are kept in the order they were applied, so the signals of the expression
+// of one of them are in the witness or removed by a later substitution. Going backwards every
+// removed signal is written over the witness. The ones that depend on a signal removed for
+// being unused can not be computed and are left out, as the ones removed without substitution.
+pub fn port_substitution_map(list: &ConstraintList) -> SubstitutionMap {
+    let mut names = vec![None; list.no_labels()];
+    collect(EncodingIterator::new(&list.dag_encoding), &mut names, &mut BTreeMap::new());
+    let mut resolved: HashMap<usize, Option<Terms>> = HashMap::new();
+    for applied in list.substitutions.iter().rev() {
+        let from = *applied.substitution.from();
+        if !list.signal_map.contains_key(&from) {
+            let terms = over_witness(applied.substitution.to(), list, &resolved);
+            resolved.insert(from, terms);
+        }
+    }
+    let mut signals: Vec<_> = resolved
+        .into_iter()
+        .filter_map(|(label, terms)| terms.map(|terms| RemovedSignal { label, name: name(&names, label), terms }))
+        .collect();
+    signals.sort_by_key(|signal| signal.label);
+    SubstitutionMap { field: list.field.clone(), witness_wires: list.no_wires(), signals }
+}
+
+fn over_witness(expression: &HashMap<usize, BigInt>, list: &ConstraintList, resolved: &HashMap<usize, Option<Terms>>) -> Option<Terms> {
+    let mut terms = Terms::new();
+    for (signal, coefficient) in expression {
+        if let Some(wire) = list.signal_map.get(signal) {
+            add_term(&mut terms, *wire, coefficient.clone(), &list.field);
+        } else {
+            for (wire, value) in resolved.get(signal)?.as_ref()? {
+                add_term(&mut terms, *wire, modular_arithmetic::mul(coefficient, value, &list.field), &list.field);
+            }
+        }
+    }
+    Some(terms)
+}
+
+fn add_term(terms: &mut Terms, wire: usize, value: BigInt, field: &BigInt) {
+    let sum = match terms.remove(&wire) {
+        Some(previous) => modular_arithmetic::add(&previous, &value, field),
+        None => value,
+    };
+    if sum != BigInt::from(0) {
+        terms.insert(wire, sum);
+    }
+}
+
+fn name(names: &Names, label: usize) -> String {
+    match names.get(label) {
+        Some(Some((name, _))) => name.clone(),
+        _ => format!("signal_{}", label),
+    }
+}
@@ -12,11 +12,11 @@ pub fn port_sym(list: &ConstraintList, file_name: &str) -> Result<(), ()> {
 }
 
 pub fn signal_iteration(
-    mut iter: EncodingIterator,
+    iter: EncodingIterator,
     map: &SignalMap,
     dot_sym: &mut SymFile,
 ) -> Result<(), ()> {
-    let (signals, _) = EncodingIterator::take(&mut iter);
+    let signals = EncodingIterator::signals(&iter);
 
     for signal in signals {
         let signal = IteratorSignal::new(signal, map);
@@ -0,0 +1,107 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+// Instance of a template in the component tree of the circuit
+pub struct ComponentInstance {
+    pub path: String,
+    pub template_name: String,
+    pub parameters: Vec<String>,
+    // the signals of the instance and of its subcomponents are the ones whose labels
+    // are in [signal_offset + 1, signal_offset + number_of_signals], as in the .sym file
+    pub signal_offset: usize,
+    pub number_of_signals: usize,
+    // constraints of the final system that only involve signals of the instance and of its
+    // subcomponents, without being in any of its subcomponents
+    pub constraints: usize,
+    pub is_custom_gate: bool,
+    pub subcomponents: Vec<ComponentInstance>,
+}
+
+impl ComponentInstance {
+    pub fn total_constraints(&self) -> usize {
+        self.constraints + self.subcomponents.iter().map(|s| s.total_constraints()).sum::<usize>()
+    }
+
+    pub fn number_of_instances(&self) -> usize {
+        1 + self.subcomponents.iter().map(|s| s.number_of_instances()).sum::<usize>()
+    }
+
+    fn signature(&self) -> String {
+        format!("{}({})", self.template_name, self.parameters.join(","))
+    }
+}
+
+pub fn write_json(root: &ComponentInstance, file: &str) -> std::io::Result<()> {
+    let file = File::create(file)?;
+    let mut writer = BufWriter::new(file);
+    write_json_instance(&mut writer, root, 0)?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
+
+fn write_json_instance(writer: &mut BufWriter<File>, instance: &ComponentInstance, depth: usize) -> std::io::Result<()> {
+    let indent = "  ".repeat(depth);
+    let parameters: Vec<_> = instance.parameters.iter().map(|p| json::stringify(p.as_str())).collect();
+    let fields = format!(
+        "{{\"path\": {}, \"template\": {}, \"parameters\": [{}], \"signal_offset\": {}, \"signal_range\": [{}, {}], \"constraints\": {}, \"total_constraints\": {}, \"custom_gate\": {}, \"subcomponents\": [",
+        json::stringify(instance.path.as_str()),
+        json::stringify(instance.template_name.as_str()),
+        parameters.join(", "),
+        instance.signal_offset,
+        instance.signal_offset + 1,
+        instance.signal_offset + instance.number_of_signals,
+        instance.constraints,
+        instance.total_constraints(),
+        instance.is_custom_gate
+    );
+    writer.write_all(indent.as_bytes())?;
+    writer.write_all(fields.as_bytes())?;
+    for (i, subcomponent) in instance.subcomponents.iter().enumerate() {
+        writer.write_all(if i == 0 { b"\n" } else { b",\n" })?;
+        write_json_instance(writer, subcomponent, depth + 1)?;
+    }
+    if !instance.subcomponents.is_empty() {
+        writer.write_all(format!("\n{}", indent).as_bytes())?;
+    }
+    writer.write_all(b"]}")
+}
+
+// Graph of the instances up to the given depth, the main component is at depth 0.
+// The subcomponents of the deepest instances are summarized in their nodes.
+pub fn write_dot(root: &ComponentInstance, file: &str, max_depth: usize) -> std::io::Result<()> {
+    let file = File::create(file)?;
+    let mut writer = BufWriter::new(file);
+    writer.write_all(b"digraph circuit {\n  node [shape=box, fontname=\"monospace\"];\n")?;
+    let mut next_id = 0;
+    write_dot_instance(&mut writer, root, 0, max_depth, &mut next_id)?;
+    writer.write_all(b"}\n")?;
+    writer.flush()
+}
+
+fn write_dot_instance(
+    writer: &mut BufWriter<File>,
+    instance: &ComponentInstance,
+    depth: usize,
+    max_depth: usize,
+    next_id: &mut usize,
+) -> std::io::Result<()> {
+    let id = *next_id;
+    *next_id += 1;
+    let name = instance.path.rsplit('.').next().unwrap_or(&instance.path);
+    let mut label = format!("{}\\n{}\\n{} constraints", name, instance.signature(), instance.total_constraints());
+    if depth == max_depth && !instance.subcomponents.is_empty() {
+        let hidden = instance.number_of_instances() - 1;
+        label.push_str(&format!("\\n+{} subcomponents", hidden));
+    }
+    let style = if instance.is_custom_gate { ", style=filled, fillcolor=lightgrey" } else { "" };
+    let node = format!("  n{} [label=\"{}\"{}];\n", id, label.replace('"', "\\\""), style);
+    writer.write_all(node.as_bytes())?;
+    if depth < max_depth {
+        for subcomponent in &instance.subcomponents {
+            let child = *next_id;
+            write_dot_instance(writer, subcomponent, depth + 1, max_depth, next_id)?;
+            writer.write_all(format!("  n{} -> n{};\n", id, child).as_bytes())?;
+        }
+    }
+    Ok(())
+}
@@ -1,11 +1,19 @@
 pub mod debug_writer;
+pub mod hierarchy_writer;
 pub mod json_writer;
 pub mod log_writer;
+pub mod r1cs_reader;
 pub mod r1cs_writer;
+pub mod simplification_writer;
+pub mod substitution_map_writer;
 pub mod sym_writer;
+pub mod wtns_reader;
 
 pub trait ConstraintExporter {
     fn r1cs(&self, out: &str, custom_gates: bool) -> Result<(), ()>;
     fn json_constraints(&self, writer: &debug_writer::DebugWriter) -> Result<(), ()>;
     fn sym(&self, out: &str) -> Result<(), ()>;
+    fn hierarchy(&self) -> hierarchy_writer::ComponentInstance;
+    fn simplification_report(&self) -> simplification_writer::SimplificationReport;
+    fn substitution_map(&self) -> substitution_map_writer::SubstitutionMap;
 }
@@ -0,0 +1,175 @@
+use circom_algebra::num_bigint::{BigInt, Sign};
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::io::{Error, ErrorKind};
+
+type LinearCombination = HashMap<usize, BigInt>;
+
+// Contents of a .r1cs file, the signals of the constraints are wires. The custom gates
+// sections are not read, the metadata of the build is read if the file has it.
+pub struct R1CSData {
+    pub field: BigInt,
+    pub total_wires: usize,
+    pub public_outputs: usize,
+    pub public_inputs: usize,
+    pub private_inputs: usize,
+    pub number_of_labels: usize,
+    pub constraints: Vec<(LinearCombination, LinearCombination, LinearCombination)>,
+    pub wire_to_label: Vec<usize>,
+    pub metadata: Option<String>,
+}
+
+// Reader of the little endian numbers of the binary files
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Reader<'a> {
+        Reader { bytes, position: 0 }
+    }
+
+    pub(crate) fn take(&mut self, size: usize) -> Result<&'a [u8], ()> {
+        let end = self.position.checked_add(size).ok_or(())?;
+        let slice = self.bytes.get(self.position..end).ok_or(())?;
+        self.position = end;
+        Result::Ok(slice)
+    }
+
+    pub(crate) fn read_u32(&mut self) -> Result<usize, ()> {
+        let bytes = self.take(4)?;
+        Result::Ok(u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+    }
+
+    pub(crate) fn read_u64(&mut self) -> Result<usize, ()> {
+        let bytes = self.take(8)?;
+        Result::Ok(u64::from_le_bytes(bytes.try_into().unwrap()) as usize)
+    }
+
+    pub(crate) fn read_number(&mut self, size: usize) -> Result<BigInt, ()> {
+        Result::Ok(BigInt::from_bytes_le(Sign::Plus, self.take(size)?))
+    }
+
+    fn read_linear_combination(&mut self, field_size: usize) -> Result<LinearCombination, ()> {
+        let factors = self.read_u32()?;
+        let mut linear_combination = HashMap::new();
+        for _ in 0..factors {
+            let wire = self.read_u32()?;
+            linear_combination.insert(wire, self.read_number(field_size)?);
+        }
+        Result::Ok(linear_combination)
+    }
+}
+
+// Sections of a binary file with the given magic and version, by type
+pub(crate) fn read_sections<'a>(bytes: &'a [u8], magic: &[u8], version: usize) -> Result<HashMap<usize, &'a [u8]>, ()> {
+    let mut reader = Reader::new(bytes);
+    if reader.take(4)? != magic || reader.read_u32()? != version {
+        return Result::Err(());
+    }
+    let number_of_sections = reader.read_u32()?;
+    let mut sections = HashMap::new();
+    for _ in 0..number_of_sections {
+        let section_type = reader.read_u32()?;
+        let size = reader.read_u64()?;
+        sections.insert(section_type, reader.take(size)?);
+    }
+    Result::Ok(sections)
+}
+
+pub fn read_r1cs(file: &str) -> std::io::Result<R1CSData> {
+    let bytes = std::fs::read(file)?;
+    parse_r1cs(&bytes).map_err(|_| Error::new(ErrorKind::InvalidData, "invalid r1cs file"))
+}
+
+fn parse_r1cs(bytes: &[u8]) -> Result<R1CSData, ()> {
+    // the sections can be in any order, the constraints are read once the header is known
+    let sections = read_sections(bytes, b"r1cs", 1)?;
+    let mut reader = Reader { bytes: sections.get(&1).ok_or(())?, position: 0 };
+    let field_size = reader.read_u32()?;
+    let field = reader.read_number(field_size)?;
+    let total_wires = reader.read_u32()?;
+    let public_outputs = reader.read_u32()?;
+    let public_inputs = reader.read_u32()?;
+    let private_inputs = reader.read_u32()?;
+    let number_of_labels = reader.read_u64()?;
+    let number_of_constraints = reader.read_u32()?;
+
+    let mut reader = Reader { bytes: sections.get(&2).ok_or(())?, position: 0 };
+    let mut constraints = Vec::with_capacity(number_of_constraints);
+    for _ in 0..number_of_constraints {
+        let a = reader.read_linear_combination(field_size)?;
+        let b = reader.read_linear_combination(field_size)?;
+        let c = reader.read_linear_combination(field_size)?;
+        constraints.push((a, b, c));
+    }
+    let mut reader = Reader { bytes: sections.get(&3).ok_or(())?, position: 0 };
+    let mut wire_to_label = Vec::with_capacity(total_wires);
+    for _ in 0..total_wires {
+        wire_to_label.push(reader.read_u64()?);
+    }
+    let metadata = match sections.get(&6) {
+        Some(bytes) => Some(String::from_utf8(bytes.to_vec()).map_err(|_err| {})?),
+        None => None,
+    };
+    Result::Ok(R1CSData {
+        field,
+        total_wires,
+        public_outputs,
+        public_inputs,
+        private_inputs,
+        number_of_labels,
+        constraints,
+        wire_to_label,
+        metadata,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::r1cs_writer::{add_metadata_section, HeaderData, R1CSWriter};
+
+    #[test]
+    fn read_what_is_written() {
+        let file = std::env::temp_dir().join(format!("circom_r1cs_reader_{}.r1cs", std::process::id()));
+        let file = file.to_str().unwrap().to_string();
+        let field = BigInt::from(101);
+        let a: LinearCombination = vec![(1, BigInt::from(1))].into_iter().collect();
+        let b: LinearCombination = vec![(2, BigInt::from(100))].into_iter().collect();
+        let c: LinearCombination = vec![(0, BigInt::from(3)), (3, BigInt::from(1))].into_iter().collect();
+        let r1cs = R1CSWriter::new(file.clone(), 8, false).unwrap();
+        let mut constraint_section = R1CSWriter::start_constraints_section(r1cs).unwrap();
+        constraint_section.write_constraint_usize(&a, &b, &c).unwrap();
+        let r1cs = constraint_section.end_section().unwrap();
+        let mut header_section = R1CSWriter::start_header_section(r1cs).unwrap();
+        let header = HeaderData {
+            field: field.clone(),
+            total_wires: 4,
+            public_outputs: 1,
+            public_inputs: 1,
+            private_inputs: 1,
+            number_of_labels: 5,
+            number_of_constraints: 1,
+        };
+        header_section.write_section(header).unwrap();
+        let r1cs = header_section.end_section().unwrap();
+        let mut signal_section = R1CSWriter::start_signal_section(r1cs).unwrap();
+        for label in [0, 1, 2, 4] {
+            signal_section.write_signal_usize(label).unwrap();
+        }
+        R1CSWriter::finish_writing(signal_section.end_section().unwrap()).unwrap();
+        assert_eq!(read_r1cs(&file).unwrap().metadata, None);
+        add_metadata_section(&file, "{\"compiler\": \"circom\"}").unwrap();
+
+        let data = read_r1cs(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(data.field, field);
+        assert_eq!((data.public_outputs, data.public_inputs, data.private_inputs), (1, 1, 1));
+        assert_eq!(data.number_of_labels, 5);
+        assert_eq!(data.constraints, vec![(a, b, c)]);
+        assert_eq!(data.wire_to_label, vec![0, 1, 2, 4]);
+        assert_eq!(data.metadata.as_deref(), Some("{\"compiler\": \"circom\"}"));
+    }
+}
@@ -1,7 +1,7 @@
 use circom_algebra::num_bigint::BigInt;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
 
 const SECTIONS: u8 = 5;
 const MAGIC: &[u8] = b"r1cs";
@@ -11,6 +11,7 @@ const CONSTRAINT_TYPE: &[u8] = &[2, 0, 0, 0];
 const WIRE2LABEL_TYPE: &[u8] = &[3, 0, 0, 0];
 const CUSTOM_GATES_USED_TYPE: &[u8] = &[4, 0, 0, 0];
 const CUSTOM_GATES_APPLIED_TYPE: &[u8] = &[5, 0, 0, 0];
+const METADATA_TYPE: &[u8] = &[6, 0, 0, 0];
 const PLACE_HOLDER: &[u8] = &[3, 3, 3, 3, 3, 3, 3, 3];
 
 fn into_format(number: &[u8], with_bytes: usize) -> (Vec<u8>, usize) {
@@ -452,3 +453,21 @@ impl CustomGatesAppliedSection {
         })
     }
 }
+
+// Appends to a written file a section with the metadata of the build, a json object in utf-8,
+// and adds it to the number of sections of the file
+pub fn add_metadata_section(output_file: &str, metadata: &str) -> std::io::Result<()> {
+    let mut file = OpenOptions::new().read(true).write(true).open(output_file)?;
+    let mut num_sections = [0; 4];
+    file.seek(SeekFrom::Start((MAGIC.len() + VERSION.len()) as u64))?;
+    file.read_exact(&mut num_sections)?;
+    let num_sections = u32::from_le_bytes(num_sections) + 1;
+    file.seek(SeekFrom::Start((MAGIC.len() + VERSION.len()) as u64))?;
+    file.write_all(&num_sections.to_le_bytes())?;
+    file.seek(SeekFrom::End(0))?;
+    let mut writer = BufWriter::new(file);
+    writer.write_all(METADATA_TYPE)?;
+    writer.write_all(&(metadata.len() as u64).to_le_bytes())?;
+    writer.write_all(metadata.as_bytes())?;
+    writer.flush()
+}
@@ -0,0 +1,87 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+// Signal of the circuit removed by the simplification
+pub struct EliminatedSignal {
+    pub name: String,
+    pub template: String,
+    // constant equality, equality, linear or unused
+    pub rule: &'static str,
+    // round of the linear simplification that found the substitution
+    pub round: Option<usize>,
+    // the signal is replaced by this expression of other signals, unless it is unused
+    pub replaced_by: Option<String>,
+}
+
+// Round of the linear simplification, the constraints that do not add to the rank
+// depend on the rest and were dropped
+#[derive(Clone)]
+pub struct LinearRound {
+    pub round: usize,
+    pub constraints: usize,
+    pub rank: usize,
+}
+
+// Signals removed by the simplification, with the number of signals of every template
+// to compute the totals
+pub struct SimplificationReport {
+    pub eliminated: Vec<EliminatedSignal>,
+    pub template_signals: BTreeMap<String, usize>,
+    pub linear_rounds: Vec<LinearRound>,
+}
+
+const RULES: [&str; 4] = ["constant equality", "equality", "linear", "unused"];
+
+pub fn write_json(report: &SimplificationReport, file: &str) -> std::io::Result<()> {
+    let file = File::create(file)?;
+    let mut writer = BufWriter::new(file);
+    writer.write_all(b"{\n \"eliminated\": [")?;
+    for (i, signal) in report.eliminated.iter().enumerate() {
+        let round = signal.round.map_or("null".to_string(), |r| r.to_string());
+        let replaced_by = signal.replaced_by.as_ref().map_or("null".to_string(), |e| json::stringify(e.as_str()));
+        let entry = format!(
+            "{}\n  {{\"signal\": {}, \"template\": {}, \"rule\": {}, \"round\": {}, \"replaced_by\": {}}}",
+            if i == 0 { "" } else { "," },
+            json::stringify(signal.name.as_str()),
+            json::stringify(signal.template.as_str()),
+            json::stringify(signal.rule),
+            round,
+            replaced_by
+        );
+        writer.write_all(entry.as_bytes())?;
+    }
+    let mut totals: BTreeMap<&str, [usize; 4]> = BTreeMap::new();
+    for signal in &report.eliminated {
+        let rule = RULES.iter().position(|r| *r == signal.rule).unwrap();
+        totals.entry(signal.template.as_str()).or_default()[rule] += 1;
+    }
+    writer.write_all(b"\n ],\n \"templates\": [")?;
+    for (i, (template, signals)) in report.template_signals.iter().enumerate() {
+        let by_rule = totals.get(template.as_str()).cloned().unwrap_or_default();
+        let rules: Vec<_> = RULES.iter().zip(&by_rule).map(|(r, n)| format!("{}: {}", json::stringify(*r), n)).collect();
+        let entry = format!(
+            "{}\n  {{\"template\": {}, \"signals\": {}, \"eliminated\": {}, \"rules\": {{{}}}}}",
+            if i == 0 { "" } else { "," },
+            json::stringify(template.as_str()),
+            signals,
+            by_rule.iter().sum::<usize>(),
+            rules.join(", ")
+        );
+        writer.write_all(entry.as_bytes())?;
+    }
+    writer.write_all(b"\n ],\n \"linear_rounds\": [")?;
+    for (i, round) in report.linear_rounds.iter().enumerate() {
+        let entry = format!(
+            "{}\n  {{\"round\": {}, \"constraints\": {}, \"rank\": {}, \"redundant\": {}}}",
+            if i == 0 { "" } else { "," },
+            round.round,
+            round.constraints,
+            round.rank,
+            round.constraints - round.rank
+        );
+        writer.write_all(entry.as_bytes())?;
+    }
+    writer.write_all(b"\n ]\n}\n")?;
+    writer.flush()
+}
@@ -0,0 +1,153 @@
+use crate::r1cs_reader::{read_sections, Reader};
+use circom_algebra::num_bigint::BigInt;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Error, ErrorKind, Write};
+
+const MAGIC: &[u8] = b"smap";
+const VERSION: u32 = 1;
+const HEADER_TYPE: u32 = 1;
+const SIGNALS_TYPE: u32 = 2;
+
+// Signal removed by the simplification, given by a linear combination of the wires of the
+// witness, where the wire 0 is the constant one
+pub struct RemovedSignal {
+    pub label: usize,
+    pub name: String,
+    pub terms: BTreeMap<usize, BigInt>,
+}
+
+// Removed signals whose value can be computed from the witness of the simplified circuit
+pub struct SubstitutionMap {
+    pub field: BigInt,
+    pub witness_wires: usize,
+    pub signals: Vec<RemovedSignal>,
+}
+
+// As in the .r1cs and .wtns files, the numbers take a multiple of 8 bytes
+fn field_size(field: &BigInt) -> usize {
+    field.bits().div_ceil(64) * 8
+}
+
+fn number_as_bytes(number: &BigInt, size: usize) -> Vec<u8> {
+    let (_, mut bytes) = number.to_bytes_le();
+    bytes.resize(size, 0);
+    bytes
+}
+
+fn write_section(writer: &mut BufWriter<File>, section_type: u32, contents: &[u8]) -> std::io::Result<()> {
+    writer.write_all(&section_type.to_le_bytes())?;
+    writer.write_all(&(contents.len() as u64).to_le_bytes())?;
+    writer.write_all(contents)
+}
+
+// The header has the size of the numbers, the prime, the wires of the witness and the number
+// of signals. Every signal is written with its label, its name and its terms, as the linear
+// combinations of the .r1cs file.
+pub fn write_binary(map: &SubstitutionMap, file: &str) -> std::io::Result<()> {
+    let size = field_size(&map.field);
+    let mut header = Vec::new();
+    header.extend_from_slice(&(size as u32).to_le_bytes());
+    header.extend(number_as_bytes(&map.field, size));
+    header.extend_from_slice(&(map.witness_wires as u32).to_le_bytes());
+    header.extend_from_slice(&(map.signals.len() as u32).to_le_bytes());
+    let mut signals = Vec::new();
+    for signal in &map.signals {
+        signals.extend_from_slice(&(signal.label as u64).to_le_bytes());
+        signals.extend_from_slice(&(signal.name.len() as u32).to_le_bytes());
+        signals.extend_from_slice(signal.name.as_bytes());
+        signals.extend_from_slice(&(signal.terms.len() as u32).to_le_bytes());
+        for (wire, coefficient) in &signal.terms {
+            signals.extend_from_slice(&(*wire as u32).to_le_bytes());
+            signals.extend(number_as_bytes(coefficient, size));
+        }
+    }
+    let mut writer = BufWriter::new(File::create(file)?);
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&2u32.to_le_bytes())?;
+    write_section(&mut writer, HEADER_TYPE, &header)?;
+    write_section(&mut writer, SIGNALS_TYPE, &signals)?;
+    writer.flush()
+}
+
+pub fn write_json(map: &SubstitutionMap, file: &str) -> std::io::Result<()> {
+    let file = File::create(file)?;
+    let mut writer = BufWriter::new(file);
+    let header = format!(
+        "{{\n \"prime\": {},\n \"witness_wires\": {},\n \"signals\": [",
+        json::stringify(map.field.to_string()),
+        map.witness_wires
+    );
+    writer.write_all(header.as_bytes())?;
+    for (i, signal) in map.signals.iter().enumerate() {
+        let terms: Vec<_> = signal
+            .terms
+            .iter()
+            .map(|(wire, coefficient)| format!("\"{}\": {}", wire, json::stringify(coefficient.to_string())))
+            .collect();
+        let entry = format!(
+            "{}\n  {{\"label\": {}, \"signal\": {}, \"terms\": {{{}}}}}",
+            if i == 0 { "" } else { "," },
+            signal.label,
+            json::stringify(signal.name.as_str()),
+            terms.join(", ")
+        );
+        writer.write_all(entry.as_bytes())?;
+    }
+    writer.write_all(b"\n ]\n}\n")?;
+    writer.flush()
+}
+
+pub fn read_substitution_map(file: &str) -> std::io::Result<SubstitutionMap> {
+    let bytes = std::fs::read(file)?;
+    parse_substitution_map(&bytes).map_err(|_| Error::new(ErrorKind::InvalidData, "invalid substitution map"))
+}
+
+fn parse_substitution_map(bytes: &[u8]) -> Result<SubstitutionMap, ()> {
+    let sections = read_sections(bytes, MAGIC, VERSION as usize)?;
+    let mut reader = Reader::new(sections.get(&(HEADER_TYPE as usize)).ok_or(())?);
+    let size = reader.read_u32()?;
+    let field = reader.read_number(size)?;
+    let witness_wires = reader.read_u32()?;
+    let number_of_signals = reader.read_u32()?;
+    let mut reader = Reader::new(sections.get(&(SIGNALS_TYPE as usize)).ok_or(())?);
+    let mut signals = Vec::with_capacity(number_of_signals);
+    for _ in 0..number_of_signals {
+        let label = reader.read_u64()?;
+        let length = reader.read_u32()?;
+        let name = String::from_utf8(reader.take(length)?.to_vec()).map_err(|_err| {})?;
+        let mut terms = BTreeMap::new();
+        for _ in 0..reader.read_u32()? {
+            let wire = reader.read_u32()?;
+            terms.insert(wire, reader.read_number(size)?);
+        }
+        signals.push(RemovedSignal { label, name, terms });
+    }
+    Result::Ok(SubstitutionMap { field, witness_wires, signals })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_what_is_written() {
+        let file = std::env::temp_dir().join(format!("circom_substitution_map_{}.smap", std::process::id()));
+        let file = file.to_str().unwrap().to_string();
+        let terms = vec![(0, BigInt::from(5)), (2, BigInt::from(100))].into_iter().collect();
+        let signals = vec![
+            RemovedSignal { label: 3, name: "main.c.x".to_string(), terms },
+            RemovedSignal { label: 7, name: "main.y[1]".to_string(), terms: BTreeMap::new() },
+        ];
+        let map = SubstitutionMap { field: BigInt::from(101), witness_wires: 4, signals };
+        write_binary(&map, &file).unwrap();
+        let read = read_substitution_map(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!((read.field, read.witness_wires), (map.field, map.witness_wires));
+        assert_eq!(read.signals.len(), 2);
+        for (read, written) in read.signals.iter().zip(&map.signals) {
+            assert_eq!((read.label, &read.name, &read.terms), (written.label, &written.name, &written.terms));
+        }
+    }
+}
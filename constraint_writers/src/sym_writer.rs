@@ -1,5 +1,5 @@
 use std::fs::File;
-use std::io::{BufWriter, Write};
+use std::io::{BufWriter, Error, ErrorKind, Write};
 
 pub struct SymElem {
     pub original: i64,
@@ -36,3 +36,21 @@ impl SymFile {
 
     // pub fn close(_sym: SymFile) {}
 }
+
+// Reads the elements of a .sym file, the removed signals have witness -1
+pub fn read_sym_file(file: &str) -> std::io::Result<Vec<SymElem>> {
+    let contents = std::fs::read_to_string(file)?;
+    parse_sym_file(&contents).map_err(|_| Error::new(ErrorKind::InvalidData, "invalid sym file"))
+}
+
+fn parse_sym_file(contents: &str) -> Result<Vec<SymElem>, ()> {
+    let mut elems = Vec::new();
+    for line in contents.lines().filter(|line| !line.is_empty()) {
+        let mut fields = line.splitn(4, ',');
+        let mut number = || fields.next().and_then(|f| f.parse::<i64>().ok()).ok_or(());
+        let (original, witness, node_id) = (number()?, number()?, number()?);
+        let symbol = fields.next().ok_or(())?.to_string();
+        elems.push(SymElem { original, witness, node_id, symbol });
+    }
+    Result::Ok(elems)
+}
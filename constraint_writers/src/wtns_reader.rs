@@ -0,0 +1,28 @@
+use crate::r1cs_reader::{read_sections, Reader};
+use circom_algebra::num_bigint::BigInt;
+use std::io::{Error, ErrorKind};
+
+// Contents of a .wtns file, the values are in the order of the wires of the witness
+pub struct WitnessData {
+    pub field: BigInt,
+    pub values: Vec<BigInt>,
+}
+
+pub fn read_wtns(file: &str) -> std::io::Result<WitnessData> {
+    let bytes = std::fs::read(file)?;
+    parse_wtns(&bytes).map_err(|_| Error::new(ErrorKind::InvalidData, "invalid witness file"))
+}
+
+fn parse_wtns(bytes: &[u8]) -> Result<WitnessData, ()> {
+    let sections = read_sections(bytes, b"wtns", 2)?;
+    let mut reader = Reader::new(sections.get(&1).ok_or(())?);
+    let size = reader.read_u32()?;
+    let field = reader.read_number(size)?;
+    let number_of_values = reader.read_u32()?;
+    let mut reader = Reader::new(sections.get(&2).ok_or(())?);
+    let mut values = Vec::with_capacity(number_of_values);
+    for _ in 0..number_of_values {
+        values.push(reader.read_number(size)?);
+    }
+    Result::Ok(WitnessData { field, values })
+}
@@ -2,7 +2,7 @@ use super::Node;
 use circom_algebra::algebra::Constraint;
 use program_structure::error_code::ReportCode;
 use program_structure::error_definition::{Report, ReportCollection};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 type C = Constraint<usize>;
 const UNCONSTRAINED_SIGNAL_CODE: ReportCode = ReportCode::UnconstrainedSignal;
@@ -74,7 +74,8 @@ fn analysis_interpretation(analysis: Analysis, result: &mut AnalysisResult) {
     let tmp_name = analysis.template_name;
     let stats = analysis.signal_stats;
 
-    let mut signal2unconstrainedex: HashMap<String, (SignalType, Vec<String>)> = HashMap::new();
+    // the warnings are given in the order of the names of the signals
+    let mut signal2unconstrainedex: BTreeMap<String, (SignalType, Vec<String>)> = BTreeMap::new();
 
     for (name, xtype, no_appearances) in stats {
         if no_appearances == 0 {
@@ -145,12 +146,18 @@ pub struct AnalysisResult {
 }
 pub fn clean_constraints(nodes: &mut [Node]){
     for node in nodes{
+        // the spilled constraints were cleaned when they were written
+        if !node.spilled.is_empty() {
+            continue;
+        }
         let length_bound = Vec::len(&node.constraints);
         let work = std::mem::replace(&mut node.constraints, Vec::with_capacity(length_bound));
-        for mut constraint in work {
+        let locations = std::mem::replace(&mut node.constraint_locations, Vec::with_capacity(length_bound));
+        for (mut constraint, location) in work.into_iter().zip(locations) {
             C::remove_zero_value_coefficients(&mut constraint);
             if !C::is_empty(&constraint) {
                 Vec::push(&mut node.constraints, constraint);
+                Vec::push(&mut node.constraint_locations, location);
             }
         }
     }
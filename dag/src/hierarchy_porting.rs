@@ -0,0 +1,30 @@
+use super::DAG;
+use constraint_writers::hierarchy_writer::ComponentInstance;
+
+// Without simplification, every instance keeps the constraints generated by its template
+pub fn port_hierarchy(dag: &DAG) -> ComponentInstance {
+    let entry = dag.get_entry().unwrap();
+    build_instance(dag, dag.main_id(), entry.get_label().to_string(), entry.get_in())
+}
+
+fn build_instance(dag: &DAG, node_id: usize, path: String, offset: usize) -> ComponentInstance {
+    let node = &dag.nodes[node_id];
+    let subcomponents = dag.adjacency[node_id]
+        .iter()
+        .map(|edge| {
+            let path = format!("{}.{}", path, edge.get_label());
+            build_instance(dag, edge.get_goes_to(), path, offset + edge.get_in())
+        })
+        .collect();
+    ComponentInstance {
+        path,
+        // the name of the node includes the parameters, like Num2Bits(8)
+        template_name: node.template_name.split('(').next().unwrap().to_string(),
+        parameters: node.parameters.iter().map(|p| p.to_string()).collect(),
+        signal_offset: offset,
+        number_of_signals: node.number_of_signals,
+        constraints: node.number_of_constraints(),
+        is_custom_gate: node.is_custom_gate,
+        subcomponents,
+    }
+}
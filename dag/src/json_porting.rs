@@ -28,7 +28,8 @@ fn hashmap_as_json(values: &HashMap<usize, BigInt>) -> JsonValue {
 }
 
 fn visit_tree(tree: &Tree, writer: &mut ConstraintJSON) -> Result<(), ()> {
-    for constraint in &tree.constraints {
+    for constraint in tree.constraints() {
+        let constraint = constraint.map_err(|_| ())?;
         let json_value = transform_constraint_to_json(&constraint);
         writer.write_constraint(&json_value.to_string())?;
     }
@@ -1,20 +1,30 @@
 mod constraint_correctness_analysis;
+mod hierarchy_porting;
 mod json_porting;
 mod map_to_constraint_list;
 mod r1cs_porting;
+mod report_porting;
 mod sym_porting;
 mod witness_producer;
 use circom_algebra::num_bigint::BigInt;
+use circom_algebra::spill::{Spill, SpillRange};
 use constraint_list::ConstraintList;
 use constraint_writers::debug_writer::DebugWriter;
+use constraint_writers::hierarchy_writer::ComponentInstance;
+use constraint_writers::simplification_writer::SimplificationReport;
+use constraint_writers::substitution_map_writer::SubstitutionMap;
 use constraint_writers::ConstraintExporter;
 use program_structure::constants::UsefulConstants;
 use program_structure::error_definition::ReportCollection;
+use program_structure::file_definition::{FileID, FileLocation};
 use std::collections::{HashMap, HashSet};
+use std::io;
 type Signal = usize;
 type Constraint = circom_algebra::algebra::Constraint<usize>;
 type Substitution = circom_algebra::algebra::Substitution<usize>;
 type Range = std::ops::Range<usize>;
+// Statement of the program that generated a constraint
+pub type ConstraintLocation = (FileID, FileLocation);
 
 pub type FastSubAccess = HashMap<usize, Substitution>;
 
@@ -27,7 +37,6 @@ pub struct Tree<'a> {
     pub signals: Vec<usize>,
     pub forbidden: HashSet<usize>,
     pub id_to_name: HashMap<usize, String>,
-    pub constraints: Vec<Constraint>,
 }
 
 impl<'a> Tree<'a> {
@@ -38,7 +47,6 @@ impl<'a> Tree<'a> {
         let node_id = dag.main_id();
         let offset = dag.get_entry().unwrap().in_number;
         let path = dag.get_entry().unwrap().label.clone();
-        let constraints = root.constraints.clone();
         let mut id_to_name = HashMap::new();
         let mut signals: Vec<_> = Vec::new();
         let forbidden: HashSet<_> =
@@ -50,7 +58,7 @@ impl<'a> Tree<'a> {
             }
         }
         signals.sort();
-        Tree { field, dag, path, offset, node_id, signals, forbidden, id_to_name, constraints }
+        Tree { field, dag, path, offset, node_id, signals, forbidden, id_to_name }
     }
 
     pub fn go_to_subtree(current: &'a Tree, edge: &Edge) -> Tree<'a> {
@@ -70,13 +78,32 @@ impl<'a> Tree<'a> {
             }
         }
         signals.sort();
-        let constraints: Vec<_> = node
-            .constraints
+        Tree { field, dag, path, offset, node_id, signals, forbidden, id_to_name }
+    }
+
+    pub fn node(&self) -> &'a Node {
+        &self.dag.nodes[self.node_id]
+    }
+
+    // The constraints of the node are shared by all its instances, they are moved to the
+    // signals of this one as they are iterated
+    pub fn constraints(&self) -> impl Iterator<Item = io::Result<Constraint>> + 'a {
+        self.located_constraints().map(|c| c.map(|(c, _)| c))
+    }
+
+    pub fn located_constraints(&self) -> impl Iterator<Item = io::Result<(Constraint, &'a ConstraintLocation)>> + 'a {
+        let offset = self.offset;
+        let node = self.node();
+        let stored = node.constraints
             .iter()
-            .filter(|c| !c.is_empty())
-            .map(|c| Constraint::apply_offset(c, offset))
-            .collect();
-        Tree { field, dag, path, offset, node_id, signals, forbidden, id_to_name, constraints }
+            .zip(node.constraint_locations.iter())
+            .filter(|(c, _)| !c.is_empty())
+            .map(move |(c, l)| Result::Ok((Constraint::apply_offset(c, offset), l)));
+        let spilled = self.dag
+            .spilled_constraints(node)
+            .zip(node.constraint_locations.iter())
+            .map(move |(c, l)| c.map(|c| (Constraint::apply_offset(&c, offset), l)));
+        stored.chain(spilled)
     }
 
     pub fn get_edges(tree: &'a Tree) -> &'a Vec<Edge> {
@@ -149,7 +176,12 @@ pub struct Node {
     forbidden_if_main: HashSet<usize>,
     io_signals: Vec<usize>,
     constraints: Vec<Constraint>,
+    // constraints kept in the spill of the dag, their locations are kept in memory
+    spilled: Vec<SpillRange>,
+    constraint_locations: Vec<ConstraintLocation>,
     underscored_signals: Vec<usize>,
+    // local signals declared with keep, forbidden for the simplification in every instance
+    kept_signals: Vec<usize>,
     is_parallel: bool,
     has_parallel_sub_cmp: bool,
     is_custom_gate: bool,
@@ -217,18 +249,28 @@ impl Node {
         self.ordered_signals.push(name);
     }
 
-    fn add_constraint(&mut self, constraint: Constraint) {
-        self.constraints.push(constraint)
+    fn add_constraint(&mut self, constraint: Constraint, location: ConstraintLocation) {
+        self.constraints.push(constraint);
+        self.constraint_locations.push(location);
     }
 
     fn add_underscored_signal(&mut self, signal: usize) {
         self.underscored_signals.push(signal)
     }
 
+    fn add_kept_signal(&mut self, signal: usize) {
+        self.kept_signals.push(signal)
+    }
+
     fn set_number_of_subcomponents_indexes(&mut self, number_scmp: usize) {
         self.number_of_subcomponents_indexes = number_scmp
     }
 
+    // name of the template followed by the values of its parameters, like Num2Bits(3)
+    pub fn template_name(&self) -> &str {
+        &self.template_name
+    }
+
     pub fn parameters(&self) -> &Vec<BigInt> {
         &self.parameters
     }
@@ -249,8 +291,17 @@ impl Node {
         &self.signal_correspondence
     }
 
-    pub fn constraints(&self) -> &[Constraint] {
-        &self.constraints
+    // Number of constraints of the node, stored or spilled, but the empty ones
+    pub fn number_of_constraints(&self) -> usize {
+        if self.spilled.is_empty() {
+            self.constraints.iter().filter(|c| !c.is_empty()).count()
+        } else {
+            self.constraint_locations.len()
+        }
+    }
+
+    pub fn constraint_locations(&self) -> &[ConstraintLocation] {
+        &self.constraint_locations
     }
 
     pub fn io_signals(&self) -> &Vec<usize> {
@@ -299,6 +350,8 @@ pub struct DAG {
     pub nodes: Vec<Node>,
     pub adjacency: Vec<Vec<Edge>>,
     pub prime: String,
+    // temporary file where the constraints of the nodes are written, if any
+    spill: Option<Spill<usize>>,
 }
 
 impl ConstraintExporter for DAG {
@@ -313,6 +366,18 @@ impl ConstraintExporter for DAG {
     fn sym(&self, out: &str) -> Result<(), ()> {
         DAG::generate_sym_output(self, out)
     }
+
+    fn hierarchy(&self) -> ComponentInstance {
+        hierarchy_porting::port_hierarchy(self)
+    }
+
+    fn simplification_report(&self) -> SimplificationReport {
+        report_porting::port_report(self)
+    }
+
+    fn substitution_map(&self) -> SubstitutionMap {
+        report_porting::port_substitution_map(self)
+    }
 }
 
 impl DAG {
@@ -322,9 +387,16 @@ impl DAG {
             one_signal: 0,
             nodes: Vec::new(),
             adjacency: Vec::new(),
+            spill: Option::None,
         }
     }
 
+    // The constraints added from now on are cleaned and written to a temporary file
+    pub fn spill_constraints(&mut self) -> io::Result<()> {
+        self.spill = Option::Some(Spill::new()?);
+        Result::Ok(())
+    }
+
     pub fn add_edge(&mut self, to: usize, label: &str, is_parallel: bool) -> Option<&Edge> {
         if to < self.main_id() {
             // create arrow
@@ -409,10 +481,26 @@ impl DAG {
         }
     }
 
-    pub fn add_constraint(&mut self, constraint: Constraint) {
-        if let Option::Some(node) = self.get_mut_main() {
-            node.add_constraint(constraint);
+    pub fn add_constraint(&mut self, mut constraint: Constraint, location: ConstraintLocation) -> io::Result<()> {
+        let node = match self.nodes.last_mut() {
+            Option::Some(node) => node,
+            Option::None => return Result::Ok(()),
+        };
+        if let Option::Some(spill) = &mut self.spill {
+            Constraint::remove_zero_value_coefficients(&mut constraint);
+            if !constraint.is_empty() {
+                spill.write(&constraint, &mut node.spilled)?;
+                node.constraint_locations.push(location);
+            }
+        } else {
+            node.add_constraint(constraint, location);
         }
+        Result::Ok(())
+    }
+
+    // Constraints of the node written to the spill, with the signals of the node
+    fn spilled_constraints<'a>(&'a self, node: &'a Node) -> impl Iterator<Item = io::Result<Constraint>> + 'a {
+        self.spill.iter().flat_map(move |spill| spill.read(&node.spilled))
     }
 
     pub fn add_underscored_signal(&mut self, signal: usize) {
@@ -421,6 +509,12 @@ impl DAG {
         }
     }
 
+    pub fn add_kept_signal(&mut self, signal: usize) {
+        if let Option::Some(node) = self.get_mut_main() {
+            node.add_kept_signal(signal);
+        }
+    }
+
     pub fn set_number_of_subcomponents_indexes(&mut self, number_scmp: usize){
         if let Option::Some(node) = self.get_mut_main() {
             node.set_number_of_subcomponents_indexes(number_scmp);
@@ -510,6 +604,17 @@ impl DAG {
         self.nodes.len()
     }
 
+    // Constraints of the whole circuit, every instance of a node has the constraints of the
+    // node and the ones of its subcomponents, which are always created before it
+    pub fn number_of_constraints(&self) -> usize {
+        let mut totals = Vec::with_capacity(self.nodes.len());
+        for (node, edges) in self.nodes.iter().zip(&self.adjacency) {
+            let own = node.number_of_constraints();
+            totals.push(own + edges.iter().map(|edge| totals[edge.goes_to]).sum::<usize>());
+        }
+        totals.last().cloned().unwrap_or(0)
+    }
+
     pub fn get_entry(&self) -> Option<&Edge> {
         self.get_main().map(|v| v.get_entry())
     }
@@ -538,7 +643,7 @@ impl DAG {
         }
     }
 
-    pub fn map_to_list(self, flags: SimplificationFlags) -> ConstraintList {
+    pub fn map_to_list(self, flags: SimplificationFlags) -> io::Result<ConstraintList> {
         map_to_constraint_list::map(self, flags)
     }
 }
@@ -549,6 +654,8 @@ pub struct SimplificationFlags {
     pub parallel_flag: bool,
     pub port_substitution: bool,
     pub json_substitutions: String,
+    pub keep_substitutions: bool,
+    pub stream_flag: bool,
     pub flag_old_heuristics: bool,
     pub prime : String,
 }
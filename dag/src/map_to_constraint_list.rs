@@ -1,7 +1,9 @@
 use super::{Constraint, Edge, Node, SimplificationFlags, Tree, DAG};
+use circom_algebra::spill::Spill;
 use constraint_list::{ConstraintList, DAGEncoding, EncodingEdge, EncodingNode, SignalInfo, Simplifier};
 use program_structure::utils::constants::UsefulConstants;
 use std::collections::{HashSet, LinkedList};
+use std::io;
 #[derive(Default)]
 struct CHolder {
     linear: LinkedList<Constraint>,
@@ -14,7 +16,7 @@ fn map_tree(
     witness: &mut Vec<usize>,
     c_holder: &mut CHolder,
     forbidden: &mut HashSet<usize>
-) -> usize {
+) -> io::Result<usize> {
     let mut no_constraints = 0;
 
     for signal in &tree.signals {
@@ -23,24 +25,36 @@ fn map_tree(
             forbidden.insert(*signal);
         }
     }
+    for signal in &tree.node().kept_signals {
+        forbidden.insert(*signal + tree.offset);
+    }
 
-    for constraint in &tree.constraints {
+    // only the constraints used by the simplification are moved to the instance, the
+    // non-linear ones stay in the node until the encoding is iterated
+    let offset = tree.offset;
+    let mut classify = |constraint: &Constraint| {
         if Constraint::is_constant_equality(constraint) {
-            LinkedList::push_back(&mut c_holder.constant_equalities, constraint.clone());
+            LinkedList::push_back(&mut c_holder.constant_equalities, constraint.apply_offset(offset));
         } else if Constraint::is_equality(constraint, &tree.field) {
-            LinkedList::push_back(&mut c_holder.equalities, constraint.clone());
+            LinkedList::push_back(&mut c_holder.equalities, constraint.apply_offset(offset));
         } else if Constraint::is_linear(constraint) {
-            LinkedList::push_back(&mut c_holder.linear, constraint.clone());
+            LinkedList::push_back(&mut c_holder.linear, constraint.apply_offset(offset));
         } else {
             no_constraints += 1;
         }
+    };
+    for constraint in tree.node().constraints.iter().filter(|c| !c.is_empty()) {
+        classify(constraint);
+    }
+    for constraint in tree.dag.spilled_constraints(tree.node()) {
+        classify(&constraint?);
     }
 
     for edge in Tree::get_edges(tree) {
         let subtree = Tree::go_to_subtree(tree, edge);
-        no_constraints += map_tree(&subtree, witness, c_holder, forbidden);
+        no_constraints += map_tree(&subtree, witness, c_holder, forbidden)?;
     }
-    no_constraints
+    Result::Ok(no_constraints)
 }
 
 fn produce_encoding(
@@ -48,12 +62,13 @@ fn produce_encoding(
     init: usize,
     dag_nodes: Vec<Node>,
     dag_edges: Vec<Vec<Edge>>,
-) -> DAGEncoding {
+    spill: Option<&Spill<usize>>,
+) -> io::Result<DAGEncoding> {
     let mut adjacency = Vec::new();
     let mut nodes = Vec::new();
     let mut id = 0;
     for node in dag_nodes {
-        let encoded = map_node_to_encoding(id, node);
+        let encoded = map_node_to_encoding(id, node, spill)?;
         Vec::push(&mut nodes, encoded);
         id += 1;
     }
@@ -65,10 +80,12 @@ fn produce_encoding(
         }
         Vec::push(&mut adjacency, encoded);
     }
-    DAGEncoding { init, no_constraints, nodes, adjacency }
+    Result::Ok(DAGEncoding { init, no_constraints, nodes, adjacency })
 }
 
-fn map_node_to_encoding(id: usize, node: Node) -> EncodingNode {
+// The non-linear constraints of the node are kept in memory by the encoding, also the
+// spilled ones, since the simplification goes through them for every instance of the node
+fn map_node_to_encoding(id: usize, node: Node, spill: Option<&Spill<usize>>) -> io::Result<EncodingNode> {
     let mut signals = Vec::new();
     let mut ordered_signals = Vec::new();
     let locals = node.locals;
@@ -78,6 +95,13 @@ fn map_node_to_encoding(id: usize, node: Node) -> EncodingNode {
             LinkedList::push_back(&mut non_linear, c);
         }
     }
+    let spilled = node.spilled;
+    for c in spill.iter().flat_map(|spill| spill.read(&spilled)) {
+        let c = c?;
+        if !Constraint::is_linear(&c) {
+            LinkedList::push_back(&mut non_linear, c);
+        }
+    }
 
     for signal in node.ordered_signals {
         let signal_numbering = node.signal_correspondence.get(&signal).unwrap();
@@ -92,7 +116,7 @@ fn map_node_to_encoding(id: usize, node: Node) -> EncodingNode {
     }
     signals.sort_by(|a, b| a.id.cmp(&b.id));
 
-    EncodingNode {
+    Result::Ok(EncodingNode {
         id,
         name: node.template_name,
         parameters: node.parameters,
@@ -100,14 +124,19 @@ fn map_node_to_encoding(id: usize, node: Node) -> EncodingNode {
         ordered_signals,
         non_linear,
         is_custom_gate: node.is_custom_gate,
-    }
+    })
 }
 
 fn map_edge_to_encoding(edge: Edge) -> EncodingEdge {
-    EncodingEdge { goes_to: edge.goes_to, path: edge.label, offset: edge.in_number }
+    EncodingEdge {
+        goes_to: edge.goes_to,
+        path: edge.label,
+        offset: edge.in_number,
+        number_of_signals: edge.out_number - edge.in_number,
+    }
 }
 
-pub fn map(dag: DAG, flags: SimplificationFlags) -> ConstraintList {
+pub fn map(dag: DAG, flags: SimplificationFlags) -> io::Result<ConstraintList> {
     use std::time::SystemTime;
     // println!("Start of dag to list mapping");
     let now = SystemTime::now();
@@ -120,12 +149,12 @@ pub fn map(dag: DAG, flags: SimplificationFlags) -> ConstraintList {
     let mut forbidden = dag.get_main().unwrap().forbidden_if_main.clone();
     let mut c_holder = CHolder::default();
     let mut signal_map = vec![0];
-    let no_constraints = map_tree(&Tree::new(&dag), &mut signal_map, &mut c_holder, &mut forbidden);
+    let no_constraints = map_tree(&Tree::new(&dag), &mut signal_map, &mut c_holder, &mut forbidden)?;
     let max_signal = Vec::len(&signal_map);
-    let name_encoding = produce_encoding(no_constraints, init_id, dag.nodes, dag.adjacency);
+    let name_encoding = produce_encoding(no_constraints, init_id, dag.nodes, dag.adjacency, dag.spill.as_ref())?;
     let _dur = now.elapsed().unwrap().as_millis();
     // println!("End of dag to list mapping: {} ms", dur);
-    Simplifier {
+    let list = Simplifier {
         field,
         no_public_inputs,
         no_public_outputs,
@@ -142,6 +171,9 @@ pub fn map(dag: DAG, flags: SimplificationFlags) -> ConstraintList {
         flag_old_heuristics: flags.flag_old_heuristics,
         port_substitution: flags.port_substitution,
         json_substitutions: flags.json_substitutions,
+        keep_substitutions: flags.keep_substitutions,
+        stream_flag: flags.stream_flag,
     }
-    .simplify_constraints()
+    .simplify_constraints();
+    Result::Ok(list)
 }
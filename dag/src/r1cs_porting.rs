@@ -118,8 +118,9 @@ fn write_constraint_section(
     tree: &Tree,
 ) -> Result<usize, ()> {
     let mut no_signals = tree.signals.len();
-    for c in &tree.constraints {
-        if Constraint::is_linear(c) {
+    for c in tree.constraints() {
+        let c = c.map_err(|_| ())?;
+        if Constraint::is_linear(&c) {
             log.no_linear += 1;
         } else {
             log.no_non_linear += 1;
@@ -0,0 +1,25 @@
+use super::{Tree, DAG};
+use constraint_writers::simplification_writer::SimplificationReport;
+use constraint_writers::substitution_map_writer::SubstitutionMap;
+use std::collections::BTreeMap;
+
+// Without simplification no signal is removed, the report only has the signals of every template
+pub fn port_report(dag: &DAG) -> SimplificationReport {
+    let mut template_signals = BTreeMap::new();
+    count_signals(&Tree::new(dag), &mut template_signals);
+    SimplificationReport { eliminated: Vec::new(), template_signals, linear_rounds: Vec::new() }
+}
+
+pub fn port_substitution_map(dag: &DAG) -> SubstitutionMap {
+    let field = Tree::new(dag).field;
+    SubstitutionMap { field, witness_wires: dag.produce_witness().len(), signals: Vec::new() }
+}
+
+fn count_signals(tree: &Tree, template_signals: &mut BTreeMap<String, usize>) {
+    let template = tree.dag.nodes[tree.node_id].template_name.split('(').next().unwrap();
+    *template_signals.entry(template.to_string()).or_insert(0) += tree.signals.len();
+    for edge in Tree::get_edges(tree) {
+        let subtree = Tree::go_to_subtree(tree, edge);
+        count_signals(&subtree, template_signals);
+    }
+}
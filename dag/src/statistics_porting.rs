@@ -33,7 +33,7 @@ pub fn write_statistics(dag: &DAG,debug: &DebugWriter) -> Result<(),()>{
         node_statistics[i].number_of_outputs = dag.nodes[i].number_of_outputs();
         node_statistics[i].number_of_inputs = dag.nodes[i].number_of_inputs();
         node_statistics[i].number_of_intermediates = dag.nodes[i].number_of_intermediates();
-        node_statistics[i].number_of_constraints = dag.nodes[i].constraints().len();
+        node_statistics[i].number_of_constraints = dag.nodes[i].number_of_constraints();
         node_statistics[i].number_of_components = dag.adjacency[i].len();
         for arrow in &dag.adjacency[i] {
             let pointer = arrow.goes_to;
@@ -1,27 +1,45 @@
-use program_structure::ast::produce_report_with_message;
+use program_structure::ast::{produce_report_with_message, Include};
 use program_structure::error_code::ReportCode;
 use program_structure::error_definition::Report;
+use program_structure::file_definition::FileID;
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 
+// Files are identified by their path together with the namespace they are included in,
+// a file included in two namespaces is parsed once for each of them. Every file keeps the
+// chain of includes that led to it: including a file of the chain in another namespace
+// would nest the namespaces in themselves forever, so it is reported as an error.
 pub struct FileStack {
     current_location: PathBuf,
-    black_paths: HashSet<PathBuf>,
-    stack: Vec<PathBuf>,
+    current_chain: IncludeChain,
+    black_paths: HashSet<NamespacedFile>,
+    stack: Vec<(PathBuf, String, IncludeChain)>,
 }
 
+// Path of a file with the namespace it is included in
+type NamespacedFile = (PathBuf, String);
+type IncludeChain = Vec<NamespacedFile>;
+
 impl FileStack {
     pub fn new(src: PathBuf) -> FileStack {
         let mut location = src.clone();
         location.pop();
-        FileStack { current_location: location, black_paths: HashSet::new(), stack: vec![src] }
+        FileStack {
+            current_location: location,
+            current_chain: Vec::new(),
+            black_paths: HashSet::new(),
+            stack: vec![(src, String::new(), Vec::new())],
+        }
     }
 
     pub fn add_include(
         f_stack: &mut FileStack,
-        name: String,
+        include: &Include,
+        namespace: String,
+        file_id: FileID,
         libraries: &Vec<PathBuf>,
     ) -> Result<String, Report> {
+        let name = include.path.clone();
         let mut libraries2 = Vec::new();
         libraries2.push(f_stack.current_location.clone());
         libraries2.append(&mut libraries.clone());
@@ -34,8 +52,24 @@ impl FileStack {
                 Err(_) => {}
                 Ok(path) => {
                     if path.is_file() {
-                        if !f_stack.black_paths.contains(&path) {
-                            f_stack.stack.push(path.clone());
+                        let in_chain = f_stack.current_chain.iter().position(|(p, n)| *p == path && *n != namespace);
+                        if let Some(start) = in_chain {
+                            let mut cycle: Vec<_> = f_stack.current_chain[start..].iter().map(|(p, _)| p.clone()).collect();
+                            cycle.push(path);
+                            let mut report = Report::error(
+                                format!(
+                                    "The include of {} nests a namespace in itself because of the following cycle of includes {}",
+                                    name,
+                                    IncludesGraph::display_path(&cycle)
+                                ),
+                                ReportCode::CyclicNamespaceInclude,
+                            );
+                            report.add_primary(include.meta.location.clone(), file_id, "included here".to_string());
+                            return Result::Err(report);
+                        }
+                        let file = (path.clone(), namespace);
+                        if !f_stack.black_paths.contains(&file) {
+                            f_stack.stack.push((file.0, file.1, f_stack.current_chain.clone()));
                         }
                         return Result::Ok(path.to_str().unwrap().to_string());
                     }
@@ -45,19 +79,23 @@ impl FileStack {
         Result::Err(produce_report_with_message(ReportCode::IncludeNotFound, name))
     }
 
-    pub fn take_next(f_stack: &mut FileStack) -> Option<PathBuf> {
+    pub fn take_next(f_stack: &mut FileStack) -> Option<NamespacedFile> {
         loop {
             match f_stack.stack.pop() {
                 None => {
                     break None;
                 }
-                Some(file) if !f_stack.black_paths.contains(&file) => {
-                    f_stack.current_location = file.clone();
-                    f_stack.current_location.pop();
-                    f_stack.black_paths.insert(file.clone());
-                    break Some(file);
+                Some((path, namespace, chain)) => {
+                    let file = (path, namespace);
+                    if !f_stack.black_paths.contains(&file) {
+                        f_stack.current_location = file.0.clone();
+                        f_stack.current_location.pop();
+                        f_stack.current_chain = chain;
+                        f_stack.current_chain.push(file.clone());
+                        f_stack.black_paths.insert(file.clone());
+                        break Some(file);
+                    }
                 }
-                _ => {}
             }
         }
     }
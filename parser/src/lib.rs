@@ -8,17 +8,21 @@ extern crate lalrpop_util;
 lalrpop_mod!(pub lang);
 
 mod include_logic;
+mod namespace_logic;
 mod parser_logic;
 mod syntax_sugar_remover;
 
 use include_logic::{FileStack, IncludesGraph};
+use namespace_logic::Namespaces;
 use num_bigint::BigInt;
-use program_structure::ast::{produce_compiler_version_report, produce_report, produce_report_with_message, produce_version_warning_report, Expression};
+use program_structure::ast::{build_main_component, produce_compiler_version_report, produce_report, produce_report_with_message, produce_version_warning_report, Definition, Expression, MainComponent, Meta, TestCase};
+use program_structure::expression_builders::build_call;
 use program_structure::error_code::ReportCode;
 use program_structure::error_definition::ReportCollection;
 use program_structure::error_definition::Report;
-use program_structure::file_definition::{FileLibrary};
+use program_structure::file_definition::{FileID, FileLibrary};
 use program_structure::program_archive::ProgramArchive;
+use program_structure::progress::{self, Phase, PhaseTimer, ProgressEvent};
 use std::path::{PathBuf, Path};
 use syntax_sugar_remover::{apply_syntactic_sugar};
 
@@ -57,22 +61,36 @@ pub fn find_file(
     (found, path, src, crr_str_file, reports)
 }
 
-pub fn run_parser(
+// Files of a project together with the pieces of information gathered while parsing them
+struct ParsedProject {
+    file_library: FileLibrary,
+    definitions: Vec<(FileID, Vec<Definition>)>,
+    main_components: Vec<(FileID, MainComponent, bool)>,
+    includes_graph: IncludesGraph,
+    // tests and custom templates pragma of the initial file
+    tests: Vec<TestCase>,
+    custom_gates: bool,
+}
+
+fn parse_project_files(
     file: String,
     version: &str,
     link_libraries: Vec<PathBuf>,
-    field: &BigInt,     
-) -> Result<(ProgramArchive, ReportCollection), (FileLibrary, ReportCollection)> {
+    field: &BigInt,
+    warnings: &mut ReportCollection,
+) -> Result<ParsedProject, (FileLibrary, ReportCollection)> {
     let mut file_library = FileLibrary::new();
     let mut definitions = Vec::new();
     let mut main_components = Vec::new();
+    let mut tests = Vec::new();
+    let mut custom_gates = false;
     let mut file_stack = FileStack::new(PathBuf::from(file));
     let mut includes_graph = IncludesGraph::new();
-    let mut warnings = Vec::new();
+    let mut namespaces = Namespaces::new();
     let mut link_libraries2 = link_libraries.clone();
     let mut ext_link_libraries = vec![Path::new("").to_path_buf()];
     ext_link_libraries.append(&mut link_libraries2);
-    while let Some(crr_file) = FileStack::take_next(&mut file_stack) {
+    while let Some((crr_file, namespace)) = FileStack::take_next(&mut file_stack) {
         let (found, path, src, crr_str_file, reports) =
             find_file(crr_file, ext_link_libraries.clone());
         if !found {
@@ -81,15 +99,29 @@ pub fn run_parser(
         let file_id = file_library.add_file(path.clone(), src.clone());
         let program =
             parser_logic::parse_file(&src, file_id, field).map_err(|e| (file_library.clone(), e))?;
+        progress::report(ProgressEvent::FileParsed { file: crr_str_file.display().to_string() });
+        namespaces.add_file(file_id, &namespace, &program.includes);
         if let Some(main) = program.main_component {
             main_components.push((file_id, main, program.custom_gates));
         }
+        if file_id == 0 {
+            // the location of a test is reported when it does not fail as declared
+            tests = program.tests;
+            for test in &mut tests {
+                test.meta.set_file_id(file_id);
+            }
+            custom_gates = program.custom_gates;
+        }
         includes_graph.add_node(crr_str_file, program.custom_gates, program.custom_gates_declared);
         let includes = program.includes;
         definitions.push((file_id, program.definitions));
         for include in includes {
+            let include_namespace = match &include.alias {
+                Some(alias) => Namespaces::alias_prefix(&namespace, alias),
+                None => namespace.clone(),
+            };
             let path_include =
-                FileStack::add_include(&mut file_stack, include.clone(), &link_libraries.clone())
+                FileStack::add_include(&mut file_stack, &include, include_namespace, file_id, &link_libraries.clone())
                     .map_err(|e| (file_library.clone(), vec![e]))?;
             includes_graph.add_edge(path_include).map_err(|e| (file_library.clone(), vec![e]))?;
         }
@@ -110,7 +142,71 @@ pub fn run_parser(
             .map_err(|e| (file_library.clone(), vec![e]))?
         }
     }
+    let mut errors = Vec::new();
+    for (file_id, file_definitions) in &mut definitions {
+        namespaces.of_file(*file_id).resolve_definitions(file_definitions, &mut errors);
+    }
+    for (file_id, (_, main), _) in &mut main_components {
+        namespaces.of_file(*file_id).resolve_expression(main, &mut errors);
+    }
+    for test in &mut tests {
+        namespaces.of_file(0).resolve_definitions(std::slice::from_mut(&mut test.checks), &mut errors);
+    }
+    if !errors.is_empty() {
+        return Err((file_library, errors));
+    }
+    Ok(ParsedProject { file_library, definitions, main_components, includes_graph, tests, custom_gates })
+}
+
+fn check_custom_gates_pragmas(includes_graph: &IncludesGraph) -> ReportCollection {
+    includes_graph.get_problematic_paths().iter().map(|path|
+        Report::error(
+            format!(
+                "Missing custom templates pragma in file {} because of the following chain of includes {}",
+                path.last().unwrap().display(),
+                IncludesGraph::display_path(path)
+            ),
+            ReportCode::CustomGatesPragmaError
+        )
+    ).collect()
+}
 
+fn build_program_archive(
+    file_library: FileLibrary,
+    main_id: FileID,
+    main_component: MainComponent,
+    definitions: Vec<(FileID, Vec<Definition>)>,
+    custom_gates: bool,
+) -> Result<ProgramArchive, (FileLibrary, ReportCollection)> {
+    let mut program_archive =
+        ProgramArchive::new(file_library, main_id, main_component, definitions, custom_gates)?;
+    let lib = program_archive.get_file_library().clone();
+    apply_syntactic_sugar(&mut program_archive).map_err(|v| (lib, vec![v]))?;
+    Ok(program_archive)
+}
+
+pub fn run_parser(
+    file: String,
+    version: &str,
+    link_libraries: Vec<PathBuf>,
+    field: &BigInt,
+) -> Result<(ProgramArchive, ReportCollection), (FileLibrary, ReportCollection)> {
+    let timer = PhaseTimer::start(Phase::Parsing);
+    let result = parse_program(file, version, link_libraries, field);
+    timer.finish();
+    result
+}
+
+fn parse_program(
+    file: String,
+    version: &str,
+    link_libraries: Vec<PathBuf>,
+    field: &BigInt,
+) -> Result<(ProgramArchive, ReportCollection), (FileLibrary, ReportCollection)> {
+    let mut warnings = Vec::new();
+    let project = parse_project_files(file, version, link_libraries, field, &mut warnings)?;
+    let file_library = project.file_library;
+    let mut main_components = project.main_components;
     if main_components.len() == 0 {
         let report = produce_report(ReportCode::NoMainFoundInProject,0..0, 0);
         warnings.push(report);
@@ -120,48 +216,84 @@ pub fn run_parser(
         warnings.push(report);
         Err((file_library, warnings))
     } else {
-        let mut errors: ReportCollection = includes_graph.get_problematic_paths().iter().map(|path|
-            Report::error(
-                format!(
-                    "Missing custom templates pragma in file {} because of the following chain of includes {}",
-                    path.last().unwrap().display(),
-                    IncludesGraph::display_path(path)
-                ),
-                ReportCode::CustomGatesPragmaError
-            )
-        ).collect();
+        let mut errors = check_custom_gates_pragmas(&project.includes_graph);
         if errors.len() > 0 {
             warnings.append(& mut errors);
             Err((file_library, warnings))
         } else {
             let (main_id, main_component, custom_gates) = main_components.pop().unwrap();
-            let result_program_archive = ProgramArchive::new(
-                file_library,
-                main_id,
-                main_component,
-                definitions,
-                custom_gates,
-            );
-            match result_program_archive {
+            match build_program_archive(file_library, main_id, main_component, project.definitions, custom_gates) {
                 Err((lib, mut rep)) => {
                     warnings.append(&mut rep);
                     Err((lib, warnings))
                 }
-                Ok(mut program_archive) => {
-                    let lib = program_archive.get_file_library().clone();
-                    let program_archive_result = apply_syntactic_sugar( &mut program_archive);
-                    match program_archive_result {
-                        Result::Err(v) => {
-                            warnings.push(v);
-                            Result::Err((lib,warnings))},
-                        Result::Ok(_) => Ok((program_archive, warnings)),
-                    }
-                }
+                Ok(program_archive) => Ok((program_archive, warnings)),
             }
         }
     }
 }
 
+// Every test with the program that runs it
+pub type TestPrograms = Vec<(TestCase, ProgramArchive)>;
+
+// Parses a file with tests. Every test gets its own program whose main component
+// is the template with the checks of the test. The main component of the file, if any, is ignored.
+pub fn run_test_parser(
+    file: String,
+    version: &str,
+    link_libraries: Vec<PathBuf>,
+    field: &BigInt,
+) -> Result<(TestPrograms, ReportCollection), (FileLibrary, ReportCollection)> {
+    let mut warnings = Vec::new();
+    let project = parse_project_files(file, version, link_libraries, field, &mut warnings)?;
+    let mut errors = check_custom_gates_pragmas(&project.includes_graph);
+    if !errors.is_empty() {
+        warnings.append(&mut errors);
+        return Err((project.file_library, warnings));
+    }
+    let mut programs = Vec::new();
+    for test in project.tests {
+        let mut definitions = project.definitions.clone();
+        definitions.push((0, vec![test.checks.clone()]));
+        let call = build_call(test.meta.clone(), test.get_template_name().to_string(), Vec::new());
+        let main_component = build_main_component(Vec::new(), call);
+        match build_program_archive(project.file_library.clone(), 0, main_component, definitions, project.custom_gates) {
+            Err((lib, mut rep)) => {
+                warnings.append(&mut rep);
+                return Err((lib, warnings));
+            }
+            Ok(program_archive) => programs.push((test, program_archive)),
+        }
+    }
+    Ok((programs, warnings))
+}
+
+// Parses a file to document its definitions and the ones of the files it includes.
+// A main component is not needed, the one of the program is an empty call.
+pub fn run_doc_parser(
+    file: String,
+    version: &str,
+    link_libraries: Vec<PathBuf>,
+    field: &BigInt,
+) -> Result<(ProgramArchive, ReportCollection), (FileLibrary, ReportCollection)> {
+    let mut warnings = Vec::new();
+    let project = parse_project_files(file, version, link_libraries, field, &mut warnings)?;
+    let mut errors = check_custom_gates_pragmas(&project.includes_graph);
+    if !errors.is_empty() {
+        warnings.append(&mut errors);
+        return Err((project.file_library, warnings));
+    }
+    let call = build_call(Meta::new(0, 0), String::new(), Vec::new());
+    let main_component = build_main_component(Vec::new(), call);
+    match build_program_archive(project.file_library, 0, main_component, project.definitions, project.custom_gates) {
+        Err((lib, mut rep)) => {
+            warnings.append(&mut rep);
+            Err((lib, warnings))
+        }
+        Ok(program_archive) => Ok((program_archive, warnings)),
+    }
+}
+
 fn produce_report_with_main_components(main_components: Vec<(usize, (Vec<String>, Expression), bool)>) -> Report {
     let mut j = 0;
     let mut r = produce_report(ReportCode::MultipleMain, 0..0, 0);
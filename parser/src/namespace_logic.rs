@@ -0,0 +1,391 @@
+use program_structure::ast::*;
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use program_structure::file_definition::FileID;
+use std::collections::{HashMap, HashSet};
+
+// Namespaces of the files of a project: the prefix of the namespace of every file, and the
+// aliases declared by the files of every namespace. The names are resolved once all the
+// files are parsed, so lib.bits.Num2Bits can refer to an alias declared in the files of lib.
+#[derive(Default)]
+pub struct Namespaces {
+    files: HashMap<FileID, (String, HashSet<String>)>,
+    aliases: HashMap<String, HashSet<String>>,
+}
+
+impl Namespaces {
+    pub fn new() -> Namespaces {
+        Namespaces::default()
+    }
+
+    pub fn add_file(&mut self, file_id: FileID, prefix: &str, includes: &[Include]) {
+        let aliases: HashSet<_> = includes.iter().filter_map(|include| include.alias.clone()).collect();
+        self.aliases.entry(prefix.to_string()).or_default().extend(aliases.iter().cloned());
+        self.files.insert(file_id, (prefix.to_string(), aliases));
+    }
+
+    pub fn of_file(&self, file_id: FileID) -> Namespace<'_> {
+        let (prefix, aliases) = &self.files[&file_id];
+        Namespace { prefix, aliases, file_id, namespaces: self }
+    }
+
+    // Prefix of the namespace of an alias declared in a file of the namespace prefix
+    pub fn alias_prefix(prefix: &str, alias: &str) -> String {
+        format!("{}{}.", prefix, alias)
+    }
+}
+
+// The definitions of a file included with an alias live in the namespace of the alias:
+// with include "lib.circom" as lib; the template Num2Bits of lib.circom, and of the files
+// it includes without an alias, is renamed to lib.Num2Bits. Inside a namespace, the names
+// without qualifier refer to the definitions of the same namespace, and the qualified
+// ones to the definitions of the namespaces of the aliases declared in the file.
+pub struct Namespace<'a> {
+    prefix: &'a str,
+    aliases: &'a HashSet<String>,
+    file_id: FileID,
+    namespaces: &'a Namespaces,
+}
+
+impl Namespace<'_> {
+    pub fn resolve_definitions(&self, definitions: &mut [Definition], reports: &mut ReportCollection) {
+        for definition in definitions {
+            match definition {
+                Definition::Template { name, body, .. }
+                | Definition::Function { name, body, .. }
+                | Definition::Bus { name, body, .. } => {
+                    *name = format!("{}{}", self.prefix, name);
+                    self.resolve_statement(body, reports);
+                }
+            }
+        }
+    }
+
+    pub fn resolve_expression(&self, expression: &mut Expression, reports: &mut ReportCollection) {
+        use Expression::*;
+        match expression {
+            InfixOp { lhe, rhe, .. } => {
+                self.resolve_expression(lhe, reports);
+                self.resolve_expression(rhe, reports);
+            }
+            PrefixOp { rhe, .. } | ParallelOp { rhe, .. } => self.resolve_expression(rhe, reports),
+            InlineSwitchOp { cond, if_true, if_false, .. } => {
+                self.resolve_expression(cond, reports);
+                self.resolve_expression(if_true, reports);
+                self.resolve_expression(if_false, reports);
+            }
+            Variable { access, .. } => self.resolve_access(access, reports),
+            Number(..) => {}
+            Call { meta, id, args } | BusCall { meta, id, args } => {
+                self.resolve_name(id, meta, reports);
+                for arg in args {
+                    self.resolve_expression(arg, reports);
+                }
+            }
+            AnonymousComp { meta, id, params, signals, .. } => {
+                self.resolve_name(id, meta, reports);
+                for value in params.iter_mut().chain(signals.iter_mut()) {
+                    self.resolve_expression(value, reports);
+                }
+            }
+            ArrayInLine { values, .. } | Tuple { values, .. } => {
+                for value in values {
+                    self.resolve_expression(value, reports);
+                }
+            }
+            UniformArray { value, dimension, .. } => {
+                self.resolve_expression(value, reports);
+                self.resolve_expression(dimension, reports);
+            }
+        }
+    }
+
+    fn resolve_statement(&self, statement: &mut Statement, reports: &mut ReportCollection) {
+        use Statement::*;
+        match statement {
+            IfThenElse { cond, if_case, else_case, .. } => {
+                self.resolve_expression(cond, reports);
+                self.resolve_statement(if_case, reports);
+                if let Some(else_case) = else_case {
+                    self.resolve_statement(else_case, reports);
+                }
+            }
+            While { cond, stmt, .. } => {
+                self.resolve_expression(cond, reports);
+                self.resolve_statement(stmt, reports);
+            }
+            Return { value, .. } => self.resolve_expression(value, reports),
+            InitializationBlock { meta, xtype, initializations } => {
+                self.resolve_type(xtype, meta, reports);
+                for initialization in initializations {
+                    self.resolve_statement(initialization, reports);
+                }
+            }
+            Declaration { meta, xtype, dimensions, .. } => {
+                self.resolve_type(xtype, meta, reports);
+                for dimension in dimensions {
+                    self.resolve_expression(dimension, reports);
+                }
+            }
+            Substitution { access, rhe, .. } => {
+                self.resolve_access(access, reports);
+                self.resolve_expression(rhe, reports);
+            }
+            MultSubstitution { lhe, rhe, .. } | ConstraintEquality { lhe, rhe, .. } => {
+                self.resolve_expression(lhe, reports);
+                self.resolve_expression(rhe, reports);
+            }
+            UnderscoreSubstitution { rhe, .. } => self.resolve_expression(rhe, reports),
+            LogCall { args, .. } => {
+                for arg in args {
+                    if let LogArgument::LogExp(arg) = arg {
+                        self.resolve_expression(arg, reports);
+                    }
+                }
+            }
+            Block { stmts, .. } => {
+                for stmt in stmts {
+                    self.resolve_statement(stmt, reports);
+                }
+            }
+            Assert { arg, .. } => self.resolve_expression(arg, reports),
+        }
+    }
+
+    fn resolve_access(&self, access: &mut [Access], reports: &mut ReportCollection) {
+        for acc in access {
+            if let Access::ArrayAccess(index) = acc {
+                self.resolve_expression(index, reports);
+            }
+        }
+    }
+
+    fn resolve_type(&self, xtype: &mut VariableType, meta: &Meta, reports: &mut ReportCollection) {
+        if let VariableType::Bus(id, ..) = xtype {
+            self.resolve_name(id, meta, reports);
+        }
+    }
+
+    fn resolve_name(&self, name: &mut String, meta: &Meta, reports: &mut ReportCollection) {
+        let mut qualifiers: Vec<&str> = name.split('.').collect();
+        let id = qualifiers.pop().unwrap();
+        let mut prefix = self.prefix.to_string();
+        for (i, alias) in qualifiers.iter().enumerate() {
+            let declared = if i == 0 {
+                self.aliases.contains(*alias)
+            } else {
+                self.namespaces.aliases.get(&prefix).is_some_and(|aliases| aliases.contains(*alias))
+            };
+            if !declared {
+                let message = if i == 0 {
+                    format!("Unknown namespace {}, it is not the alias of any include of the file", alias)
+                } else {
+                    format!(
+                        "Unknown namespace {}, {} is not the alias of any include of the files of {}",
+                        qualifiers[..=i].join("."),
+                        alias,
+                        qualifiers[..i].join(".")
+                    )
+                };
+                let mut report = Report::error(message, ReportCode::UnknownNamespace);
+                report.add_primary(meta.location.clone(), self.file_id, "used here".to_string());
+                reports.push(report);
+                return;
+            }
+            prefix = Namespaces::alias_prefix(&prefix, alias);
+        }
+        *name = format!("{}{}", prefix, id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use program_structure::constants::UsefulConstants;
+    use program_structure::error_code::ReportCode;
+    use program_structure::error_definition::ReportCollection;
+    use program_structure::program_archive::ProgramArchive;
+
+    // Writes the files in a temporary folder and parses the first one
+    fn parse_files(name: &str, files: &[(&str, &str)]) -> Result<ProgramArchive, ReportCollection> {
+        let folder = std::env::temp_dir().join(format!("circom_namespaces_{}_{}", std::process::id(), name));
+        std::fs::create_dir_all(&folder).unwrap();
+        for (file, source) in files {
+            std::fs::write(folder.join(file), format!("pragma circom 2.2.0;\n{}", source)).unwrap();
+        }
+        let main = folder.join(files[0].0).display().to_string();
+        let prime = UsefulConstants::new(&"bn128".to_string()).get_p().clone();
+        let result = crate::run_parser(main, "2.2.0", vec![], &prime);
+        std::fs::remove_dir_all(&folder).unwrap();
+        result.map(|(program, _)| program).map_err(|(_, reports)| reports)
+    }
+
+    #[test]
+    fn names_are_qualified_by_nested_namespaces() {
+        let program = parse_files(
+            "nested",
+            &[
+                (
+                    "main.circom",
+                    "include \"a.circom\" as lib;
+                    template M() { signal input x; lib.bb.P() p; p.a <== lib.bb.B(lib.bb.f(3))(x); }
+                    component main = M();",
+                ),
+                ("a.circom", "include \"b.circom\" as bb; template A() { signal input x; signal y <== bb.B(bb.f(2))(x); }"),
+                (
+                    "b.circom",
+                    "include \"c.circom\"; template B(n) { signal input x; signal output y; signal c <== C()(x); y <== c * n; }
+                    bus P() { signal a; }
+                    function f(x) { return x + 1; }",
+                ),
+                ("c.circom", "template C() { signal input x; signal output y; y <== x; }"),
+            ],
+        )
+        .unwrap_or_else(|reports| panic!("the program does not parse: {}", reports[0].get_message()));
+        for template in ["M", "lib.A", "lib.bb.B", "lib.bb.C"] {
+            assert!(program.contains_template(template), "missing template {}", template);
+        }
+        assert!(program.contains_function("lib.bb.f"));
+        assert!(program.contains_bus("lib.bb.P"));
+        assert!(!program.contains_template("B"));
+    }
+
+    #[test]
+    fn a_file_is_included_once_for_each_alias() {
+        let program = parse_files(
+            "two_aliases",
+            &[
+                (
+                    "main.circom",
+                    "include \"b.circom\" as x; include \"b.circom\" as y;
+                    template M() { signal input a; component b[2]; b[0] = x.B(); b[1] = y.B(); }
+                    component main = M();",
+                ),
+                ("b.circom", "template B() { signal input x; }"),
+            ],
+        )
+        .unwrap_or_else(|reports| panic!("the program does not parse: {}", reports[0].get_message()));
+        assert!(program.contains_template("x.B") && program.contains_template("y.B"));
+    }
+
+    #[test]
+    fn include_cycles_without_aliases_are_parsed_once() {
+        let program = parse_files(
+            "plain_cycle",
+            &[
+                ("main.circom", "include \"a.circom\"; component main = A();"),
+                ("a.circom", "include \"b.circom\"; template A() { component b = B(); }"),
+                ("b.circom", "include \"a.circom\"; template B() { signal input x; }"),
+            ],
+        )
+        .unwrap_or_else(|reports| panic!("the program does not parse: {}", reports[0].get_message()));
+        assert!(program.contains_template("A") && program.contains_template("B"));
+    }
+
+    #[test]
+    fn include_cycles_through_aliases_are_reported() {
+        let files = [
+            ("main.circom", "include \"a.circom\" as lib; component main = lib.A();"),
+            ("a.circom", "include \"b.circom\" as bb; template A() { signal input x; }"),
+            ("b.circom", "include \"a.circom\" as aa; template B() { signal input x; }"),
+        ];
+        let reports = parse_files("alias_cycle", &files).err().unwrap();
+        assert!(matches!(reports[0].get_code(), ReportCode::CyclicNamespaceInclude));
+        assert!(reports[0].get_message().ends_with("a.circom -> b.circom -> a.circom"));
+
+        // going back to a.circom without an alias would also nest bb in itself
+        let files = [files[0], files[1], ("b.circom", "include \"a.circom\"; template B() { signal input x; }")];
+        let reports = parse_files("alias_cycle_back", &files).err().unwrap();
+        assert!(matches!(reports[0].get_code(), ReportCode::CyclicNamespaceInclude));
+    }
+
+    #[test]
+    fn unknown_namespaces_are_reported() {
+        let reports = parse_files(
+            "unknown",
+            &[
+                (
+                    "main.circom",
+                    "include \"a.circom\" as lib;
+                    template M() { component a = lib.cc.A(); component b = other.A(); }
+                    component main = M();",
+                ),
+                ("a.circom", "template A() { signal input x; }"),
+            ],
+        )
+        .err()
+        .unwrap();
+        let messages: Vec<_> = reports.iter().map(|report| report.get_message().as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "Unknown namespace lib.cc, cc is not the alias of any include of the files of lib",
+                "Unknown namespace other, it is not the alias of any include of the file",
+            ]
+        );
+    }
+
+    #[test]
+    fn names_only_collide_in_the_same_namespace() {
+        let files = [
+            (
+                "main.circom",
+                "include \"c.circom\" as lib;
+                template B() { signal input x; }
+                template M() { signal input x; component b = B(); b.x <== x; component c = lib.B(); c.x <== x; }
+                component main = M();",
+            ),
+            ("c.circom", "template B() { signal input x; }"),
+            ("b.circom", "template B() { signal input x; }"),
+        ];
+        let program = parse_files("collisions", &files[..2])
+            .unwrap_or_else(|reports| panic!("the program does not parse: {}", reports[0].get_message()));
+        assert!(program.contains_template("B") && program.contains_template("lib.B"));
+
+        // without an alias, the template of b.circom is in the namespace of main.circom
+        let main = files[0].1.replace("template B()", "include \"b.circom\"; template B()");
+        let reports = parse_files("collisions_without_alias", &[("main.circom", &main), files[1], files[2]]).err().unwrap();
+        assert_eq!(reports.len(), 1);
+        assert!(matches!(reports[0].get_code(), ReportCode::SameSymbolDeclaredTwice));
+        assert_eq!(reports[0].get_message(), "Duplicated callable symbol");
+    }
+
+    #[test]
+    fn qualified_calls_find_the_functions_and_templates_of_the_namespace() {
+        use program_structure::ast::{Expression, Statement};
+        fn calls(statement: &Statement, ids: &mut Vec<String>) {
+            match statement {
+                Statement::Block { stmts, .. } | Statement::InitializationBlock { initializations: stmts, .. } => {
+                    stmts.iter().for_each(|stmt| calls(stmt, ids))
+                }
+                Statement::Substitution { rhe, .. } => expression_calls(rhe, ids),
+                _ => {}
+            }
+        }
+        fn expression_calls(expression: &Expression, ids: &mut Vec<String>) {
+            if let Expression::Call { id, args, .. } = expression {
+                ids.push(id.clone());
+                args.iter().for_each(|arg| expression_calls(arg, ids));
+            }
+        }
+        let program = parse_files(
+            "functions_and_templates",
+            &[
+                (
+                    "main.circom",
+                    "include \"c.circom\" as lib;
+                    template g() { signal input x; }
+                    template M() { signal input x; component a = g(); a.x <== x; component t = lib.T(lib.g(2)); t.x <== x; }
+                    component main = M();",
+                ),
+                ("c.circom", "template T(n) { signal input x; } function g(n) { return n + 1; }"),
+            ],
+        )
+        .unwrap_or_else(|reports| panic!("the program does not parse: {}", reports[0].get_message()));
+        assert!(program.contains_template("g") && !program.contains_function("g"));
+        assert!(program.contains_function("lib.g") && !program.contains_template("lib.g"));
+        assert!(program.contains_template("lib.T"));
+        let mut ids = vec![];
+        calls(program.get_template_data("M").get_body(), &mut ids);
+        assert_eq!(ids, ["g", "lib.T", "lib.g"]);
+    }
+}
@@ -1,16 +1,23 @@
 use super::lang;
 use num_bigint::BigInt;
-use program_structure::ast::{AST};
+use program_structure::ast::{Definition, AST};
 use program_structure::ast::produce_report;
 use program_structure::error_code::ReportCode;
 use program_structure::error_definition::{ReportCollection, Report};
 use program_structure::file_definition::FileID;
 
-pub fn preprocess(expr: &str, file_id: FileID) -> Result<String, ReportCollection> {
+// Line of a doc comment (///): its start and end positions and its text
+type DocComment = (usize, usize, String);
+
+// The comments are replaced by spaces, so the positions of the preprocessed code are the
+// positions of the source. The doc comments are returned apart to document the definitions.
+pub fn preprocess(expr: &str, file_id: FileID) -> Result<(String, Vec<DocComment>), ReportCollection> {
     let mut pp = String::new();
+    let mut docs = Vec::new();
     let mut state = 0;
     let mut loc = 0;
     let mut block_start = 0;
+    let mut comment_start = 0;
 
     let mut it = expr.chars();
     while let Some(c0) = it.next() {
@@ -21,6 +28,7 @@ pub fn preprocess(expr: &str, file_id: FileID) -> Result<String, ReportCollectio
                 match it.next() {
                     Some('/') => {
                         state = 1;
+                        comment_start = pp.len();
                         pp.push(' ');
                         pp.push(' ');
                     }
@@ -42,6 +50,7 @@ pub fn preprocess(expr: &str, file_id: FileID) -> Result<String, ReportCollectio
             }
             (0, _) => pp.push(c0),
             (1, '\n') => {
+                add_doc_comment(&mut docs, expr, comment_start, pp.len());
                 pp.push(c0);
                 state = 0;
             }
@@ -75,12 +84,46 @@ pub fn preprocess(expr: &str, file_id: FileID) -> Result<String, ReportCollectio
             }
         }
     }
+    if state == 1 {
+        add_doc_comment(&mut docs, expr, comment_start, pp.len());
+    }
     if state == 2 {
         Err(vec![
             produce_report(ReportCode::UnclosedComment,  block_start..block_start, file_id)
         ])
     } else {
-        Ok(pp)
+        Ok((pp, docs))
+    }
+}
+
+fn add_doc_comment(docs: &mut Vec<DocComment>, expr: &str, start: usize, end: usize) {
+    let comment = &expr[start..end];
+    if let Some(text) = comment.strip_prefix("///") {
+        // a line of slashes is not a doc comment
+        if !text.starts_with('/') {
+            let text = text.strip_prefix(' ').unwrap_or(text).trim_end();
+            docs.push((start, end, text.to_string()));
+        }
+    }
+}
+
+// A definition is documented by the doc comments that precede it with nothing else in between
+fn attach_doc_comments(src: &str, docs: &[DocComment], definitions: &mut [Definition]) {
+    for definition in definitions {
+        let mut start = definition.get_meta().start;
+        let mut lines = Vec::new();
+        for (doc_start, doc_end, text) in docs.iter().rev() {
+            if *doc_end > start {
+                continue;
+            }
+            if !src[*doc_end..start].trim().is_empty() {
+                break;
+            }
+            lines.push(text.clone());
+            start = *doc_start;
+        }
+        lines.reverse();
+        definition.set_docs(lines);
     }
 }
 
@@ -88,9 +131,9 @@ pub fn parse_file(src: &str, file_id: FileID, field: &BigInt) -> Result<AST, Rep
     use lalrpop_util::ParseError::*;
 
     let mut errors = Vec::new();
-    let preprocess = preprocess(src, file_id)?;
+    let (preprocess, docs) = preprocess(src, file_id)?;
 
-    let ast = lang::ParseAstParser::new()
+    let mut ast = lang::ParseAstParser::new()
         .parse(file_id, &mut errors, field, &preprocess)
         // TODO: is this always fatal?
         .map_err(|parse_error| match parse_error {
@@ -118,6 +161,7 @@ pub fn parse_file(src: &str, file_id: FileID, field: &BigInt) -> Result<AST, Rep
     if !errors.is_empty() {
         return Err(errors.into_iter().collect());
     }
+    attach_doc_comments(src, &docs, &mut ast.definitions);
 
     Ok(ast)
 }
@@ -128,3 +172,73 @@ fn produce_generic_report(format: String, token: std::ops::Range<usize>, file_id
     report
 }
 
+
+#[cfg(test)]
+mod tests {
+    use program_structure::ast::{Definition, Statement};
+    use program_structure::constants::UsefulConstants;
+    use program_structure::error_definition::ReportCollection;
+
+    // The declarations of the template, with the signals declared with keep
+    fn parse_declarations(template: &str) -> Result<Vec<(String, bool)>, ReportCollection> {
+        fn visit(statement: &Statement, declarations: &mut Vec<(String, bool)>) {
+            match statement {
+                Statement::Declaration { name, is_kept, .. } => declarations.push((name.clone(), *is_kept)),
+                Statement::Block { stmts, .. } => stmts.iter().for_each(|s| visit(s, declarations)),
+                Statement::InitializationBlock { initializations, .. } => {
+                    initializations.iter().for_each(|s| visit(s, declarations))
+                }
+                _ => {}
+            }
+        }
+        let prime = UsefulConstants::new(&"bn128".to_string()).get_p().clone();
+        let ast = super::parse_file(template, 0, &prime)?;
+        let mut declarations = Vec::new();
+        if let Definition::Template { body, .. } = &ast.definitions[0] {
+            visit(body, &mut declarations);
+        }
+        Result::Ok(declarations)
+    }
+
+    #[test]
+    fn signals_declared_with_keep_are_kept() {
+        let declarations = parse_declarations(
+            "template T() {
+                keep signal a;
+                keep signal input {binary} b[2];
+                keep signal output c <== b[0];
+                keep signal (d, e) <== U()(a);
+                signal f;
+            }",
+        )
+        .unwrap_or_else(|reports| panic!("the template does not parse: {}", reports[0].get_message()));
+        let kept: Vec<_> = declarations.iter().map(|(name, is_kept)| (name.as_str(), *is_kept)).collect();
+        assert_eq!(kept, [("a", true), ("b", true), ("c", true), ("d", true), ("e", true), ("f", false)]);
+    }
+
+    #[test]
+    fn keep_is_still_an_identifier() {
+        let declarations = parse_declarations(
+            "template T() {
+                var keep = 1;
+                keep = keep + 1;
+                signal input {keep} in;
+                signal output keep2[keep];
+                keep2[0] <== in * keep;
+            }",
+        )
+        .unwrap_or_else(|reports| panic!("the template does not parse: {}", reports[0].get_message()));
+        assert!(declarations.iter().all(|(_, is_kept)| !is_kept));
+        assert!(declarations.iter().any(|(name, _)| name == "keep"));
+
+        let declarations = parse_declarations("template T() { signal keep; keep <== 1; }")
+            .unwrap_or_else(|reports| panic!("the template does not parse: {}", reports[0].get_message()));
+        assert_eq!(declarations, [("keep".to_string(), false)]);
+    }
+
+    #[test]
+    fn other_identifiers_before_signal_are_reported() {
+        let reports = parse_declarations("template T() { kept signal a; }").err().unwrap();
+        assert_eq!(reports[0].get_message(), "Unexpected identifier kept, expected keep or a statement");
+    }
+}
@@ -20,6 +20,17 @@ pub fn build_main_component(public: Vec<String>, call: Expression) -> MainCompon
 
 pub type Version = (usize, usize, usize);
 
+// Test declared as `test "name" Template(args) { ... }`. The inputs and expected
+// outputs of the test are kept in the template `checks`, which instantiates the
+// tested template and can be compiled as a main component.
+#[derive(Clone)]
+pub struct TestCase {
+    pub meta: Meta,
+    pub name: String,
+    pub should_fail: bool,
+    pub checks: Definition,
+}
+
 #[derive(Clone)]
 pub struct Meta {
     pub elem_id: usize,
@@ -81,24 +92,35 @@ impl Meta {
     }
 }
 
+// An include with an alias makes the definitions of the included file
+// accessible only through qualified names: include "lib.circom" as lib;
+#[derive(Clone)]
+pub struct Include {
+    pub meta: Meta,
+    pub path: String,
+    pub alias: Option<String>,
+}
+
 #[derive(Clone)]
 pub struct AST {
     pub meta: Meta,
     pub compiler_version: Option<Version>,
     pub custom_gates: bool,
     pub custom_gates_declared: bool,
-    pub includes: Vec<String>,
+    pub includes: Vec<Include>,
     pub definitions: Vec<Definition>,
     pub main_component: Option<MainComponent>,
+    pub tests: Vec<TestCase>,
 }
 
 impl AST {
     pub fn new(
         meta: Meta,
         pragmas: Vec<Pragma>,
-        includes: Vec<String>,
+        includes: Vec<Include>,
         definitions: Vec<Definition>,
         main_component: Option<MainComponent>,
+        tests: Vec<TestCase>,
     ) -> (AST,Vec<Report>) {
         let mut custom_gates = None;
         let mut compiler_version = None;
@@ -133,6 +155,7 @@ impl AST {
             includes,
             definitions,
             main_component,
+            tests,
         }, reports)
     }
 }
@@ -147,6 +170,8 @@ pub enum Definition {
         body: Statement,
         parallel: bool,
         is_custom_gate: bool,
+        // lines of the doc comments (///) written right before the definition
+        docs: Vec<String>,
     },
     Function {
         meta: Meta,
@@ -154,6 +179,7 @@ pub enum Definition {
         args: Vec<String>,
         arg_location: FileLocation,
         body: Statement,
+        docs: Vec<String>,
     },
     Bus {
         meta: Meta,
@@ -161,6 +187,7 @@ pub enum Definition {
         args: Vec<String>,
         arg_location: FileLocation,
         body: Statement,
+        docs: Vec<String>,
     },
 }
 pub fn build_template(
@@ -172,7 +199,7 @@ pub fn build_template(
     parallel: bool,
     is_custom_gate: bool,
 ) -> Definition {
-    Definition::Template { meta, name, args, arg_location, body, parallel, is_custom_gate }
+    Definition::Template { meta, name, args, arg_location, body, parallel, is_custom_gate, docs: Vec::new() }
 }
 
 pub fn build_function(
@@ -182,7 +209,7 @@ pub fn build_function(
     arg_location: FileLocation,
     body: Statement,
 ) -> Definition {
-    Definition::Function { meta, name, args, arg_location, body }
+    Definition::Function { meta, name, args, arg_location, body, docs: Vec::new() }
 }
 
 pub fn build_bus(
@@ -192,7 +219,7 @@ pub fn build_bus(
     arg_location: FileLocation,
     body: Statement,
 ) -> Definition {
-    Definition::Bus { meta, name, args, arg_location, body }
+    Definition::Bus { meta, name, args, arg_location, body, docs: Vec::new() }
 }
 
 #[derive(Clone)]
@@ -223,6 +250,8 @@ pub enum Statement {
         name: String,
         dimensions: Vec<Expression>,
         is_constant: bool,
+        // signals declared with keep are never removed by the simplification
+        is_kept: bool,
     },
     Substitution {
         meta: Meta,
@@ -1,7 +1,7 @@
 use super::ast::*;
 
 impl AST {
-    pub fn get_includes(&self) -> &Vec<String> {
+    pub fn get_includes(&self) -> &Vec<Include> {
         &self.includes
     }
 
@@ -12,7 +12,33 @@ impl AST {
     pub fn get_definitions(&self) -> &Vec<Definition> {
         &self.definitions
     }
-    pub fn decompose(self) -> (Meta, Option<Version>, Vec<String>, Vec<Definition>, Option<MainComponent>) {
+    pub fn decompose(self) -> (Meta, Option<Version>, Vec<Include>, Vec<Definition>, Option<MainComponent>) {
         (self.meta, self.compiler_version, self.includes, self.definitions, self.main_component)
     }
 }
+
+impl Definition {
+    pub fn get_meta(&self) -> &Meta {
+        match self {
+            Definition::Template { meta, .. }
+            | Definition::Function { meta, .. }
+            | Definition::Bus { meta, .. } => meta,
+        }
+    }
+    pub fn set_docs(&mut self, new_docs: Vec<String>) {
+        match self {
+            Definition::Template { docs, .. }
+            | Definition::Function { docs, .. }
+            | Definition::Bus { docs, .. } => *docs = new_docs,
+        }
+    }
+}
+
+impl TestCase {
+    pub fn get_template_name(&self) -> &str {
+        match &self.checks {
+            Definition::Template { name, .. } => name,
+            _ => unreachable!(),
+        }
+    }
+}
@@ -15,6 +15,17 @@ pub struct TupleInit{
     pub tuple_init : (AssignOp,Expression)
 }
 
+// Line of a test: `input a[0] = 3;` or `output out = 9;`
+pub struct TestCheck {
+    pub meta: Meta,
+    pub is_input: bool,
+    pub signal: (String, Vec<Access>),
+    pub value: Expression,
+}
+
+// Name of the component that holds the tested template inside the checks of a test
+pub const TESTED_COMPONENT: &str = "circuit";
+
 pub fn assign_with_op_shortcut(
     op: ExpressionInfixOpcode,
     meta: Meta,
@@ -206,3 +217,52 @@ pub fn split_bus_declaration_into_single_nodes_and_multisubstitution(
     }
     build_initialization_block(meta, xtype, initializations)
 }
+
+// Marks the signals declared in the block, written as `keep signal ...`, so that
+// the simplification never removes them
+pub fn keep_declared_signals(mut block: Statement) -> Statement {
+    if let Statement::InitializationBlock { initializations, .. } = &mut block {
+        for initialization in initializations {
+            if let Statement::Declaration { is_kept, .. } = initialization {
+                *is_kept = true;
+            }
+        }
+    }
+    block
+}
+
+// The test is turned into a template without signals:
+//     component circuit = Template(args);
+//     circuit.input <== value;   for every input
+//     circuit.output === value;  for every expected output
+pub fn build_test_case(
+    meta: Meta,
+    name: String,
+    should_fail: bool,
+    template_call: Expression,
+    checks: Vec<TestCheck>,
+) -> TestCase {
+    use crate::ast_shortcuts::AssignOp::{AssignConstraintSignal, AssignVar};
+    let call_meta = template_call.get_meta().clone();
+    let declaration = build_declaration(call_meta.clone(), VariableType::Component, TESTED_COMPONENT.to_string(), vec![]);
+    let instantiation = build_substitution(call_meta.clone(), TESTED_COMPONENT.to_string(), vec![], AssignVar, template_call);
+    let mut stmts = vec![build_initialization_block(call_meta, VariableType::Component, vec![declaration, instantiation])];
+    let (inputs, outputs): (Vec<_>, Vec<_>) = checks.into_iter().partition(|c| c.is_input);
+    for check in inputs {
+        let (signal, access) = check.signal;
+        let mut full_access = vec![build_component_access(signal)];
+        full_access.extend(access);
+        stmts.push(build_substitution(check.meta, TESTED_COMPONENT.to_string(), full_access, AssignConstraintSignal, check.value));
+    }
+    for check in outputs {
+        let (signal, access) = check.signal;
+        let mut full_access = vec![build_component_access(signal)];
+        full_access.extend(access);
+        let tested = build_variable(check.meta.clone(), TESTED_COMPONENT.to_string(), full_access);
+        stmts.push(build_constraint_equality(check.meta, tested, check.value));
+    }
+    let template_name = format!("test${}", meta.start);
+    let body = build_block(meta.clone(), stmts);
+    let checks = build_template(meta.clone(), template_name, vec![], meta.start..meta.start, body, false, false);
+    TestCase { meta, name, should_fail, checks }
+}
@@ -36,7 +36,8 @@ pub fn build_declaration(
     dimensions: Vec<Expression>,
 ) -> Statement {
     let is_constant = true;
-    Declaration { meta, xtype, name, dimensions, is_constant }
+    let is_kept = false;
+    Declaration { meta, xtype, name, dimensions, is_constant, is_kept }
 }
 
 pub fn build_substitution(
@@ -14,6 +14,7 @@ pub struct BusData {
     name_of_params: Vec<String>,
     param_location: FileLocation,
     fields: WireInfo,
+    docs: Vec<String>,
     /* Only used to know the order in which fields are declared.*/
     field_declarations: WireDeclarationOrder,
 }
@@ -40,6 +41,7 @@ impl BusData {
              name_of_params,
              param_location,
              fields,
+             docs: Vec::new(),
              field_declarations
         }
     }
@@ -61,6 +63,7 @@ impl BusData {
             name_of_params,
             param_location,
             fields,
+            docs: Vec::new(),
             field_declarations
         }
     }
@@ -82,6 +85,9 @@ impl BusData {
     pub fn set_body(&mut self, body: Statement){
         self.body = body;
     }
+    pub fn set_docs(&mut self, docs: Vec<String>) {
+        self.docs = docs;
+    }
     pub fn replace_body(&mut self, new: Statement) -> Statement {
         std::mem::replace(&mut self.body, new)
     }
@@ -100,6 +106,9 @@ impl BusData {
     pub fn get_name_of_params(&self) -> &Vec<String> {
         &self.name_of_params
     }
+    pub fn get_docs(&self) -> &Vec<String> {
+        &self.docs
+    }
     pub fn get_name(&self) -> &str {
         &self.name
     }
@@ -14,6 +14,8 @@ pub enum ReportCode {
     UnrecognizedPragma,
     ExpectedIdentifier,
     IncludeNotFound,
+    UnknownNamespace,
+    CyclicNamespaceInclude,
     IllegalExpression,
     MultiplePragma,
     NoCompilerVersionWarning, 
@@ -106,6 +108,8 @@ pub enum ReportCode {
     UnconstrainedIOSignal,
     UnusedInput,
     UnusedOutput,
+    UnreachableDefinition,
+    UnusedSymbol,
 
     ErrorWat2Wasm,
     CustomGateIntermediateSignalWarning,
@@ -122,6 +126,8 @@ pub enum ReportCode {
     InvalidSignalAccessInBus,
     MustBeSameBus,
     MustBeBus,
+    // Test codes
+    FailedTestConstraint,
 }
 
 impl fmt::Display for ReportCode {
@@ -142,6 +148,8 @@ impl fmt::Display for ReportCode {
             MultiplePragma => "P1013",
             IncludeNotFound => "P1014",
             ExpectedIdentifier => "P1015",
+            UnknownNamespace => "P1016",
+            CyclicNamespaceInclude => "P1017",
             UndefinedFunction => "T2001",
             UndefinedTemplate => "T2002",
             UninitializedSymbolInExpression => "T2003",
@@ -230,6 +238,8 @@ impl fmt::Display for ReportCode {
             UnconstrainedIOSignal => "CA02",
             UnusedInput => "CA03",
             UnusedOutput => "CA04",
+            UnreachableDefinition => "CA05",
+            UnusedSymbol => "CA06",
             ErrorWat2Wasm => "W01",
             CustomGateIntermediateSignalWarning => "CG01",
             CustomGateConstraintError => "CG02",
@@ -245,6 +255,7 @@ impl fmt::Display for ReportCode {
             InvalidSignalAccessInBus => "BU04",
             MustBeSameBus => "BU05",
             MustBeBus => "BU06",
+            FailedTestConstraint => "TST01",
         };
         f.write_str(string_format)
     }
@@ -31,6 +31,31 @@ impl FileLibrary {
             None => None,
         }
     }
+    // Location of the content of a line without surrounding whitespace, lines are numbered from 1
+    pub fn get_line_location(&self, line: usize, file_id: FileID) -> Option<FileLocation> {
+        let range = self.files.line_range(file_id, line.checked_sub(1)?)?;
+        let source = &self.files.source(file_id)?[range.clone()];
+        let start = range.start + (source.len() - source.trim_start().len());
+        let end = range.end - (source.len() - source.trim_end().len());
+        Some(start..end.max(start))
+    }
+    // The parser stores the paths between quotes
+    pub fn get_path(&self, file_id: FileID) -> Option<&str> {
+        self.files.get(file_id).map(|file| file.name().trim_matches('"'))
+    }
+    pub fn get_source(&self, file_id: FileID) -> Option<&str> {
+        self.files.source(file_id)
+    }
+    // Id of the file whose path is the given one, or ends with it
+    pub fn find_file(&self, path: &str) -> Option<FileID> {
+        use std::path::Path;
+        let canonical = Path::new(path).canonicalize().ok();
+        (0..).map_while(|file_id| Some((file_id, self.get_path(file_id)?))).find_map(|(file_id, name)| {
+            let name = Path::new(name);
+            let found = name.ends_with(path) || (canonical.is_some() && name.canonicalize().ok() == canonical);
+            if found { Some(file_id) } else { None }
+        })
+    }
     pub fn to_storage(&self) -> &FileStorage {
         &self.get_files()
     }
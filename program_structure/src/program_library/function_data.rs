@@ -13,6 +13,7 @@ pub struct FunctionData {
     name_of_params: Vec<String>,
     param_location: FileLocation,
     body: Statement,
+    docs: Vec<String>,
 }
 
 impl FunctionData {
@@ -26,7 +27,7 @@ impl FunctionData {
         elem_id: &mut usize,
     ) -> FunctionData {
         body.fill(file_id, elem_id);
-        FunctionData { name, file_id, body, name_of_params, param_location, num_of_params }
+        FunctionData { name, file_id, body, name_of_params, param_location, num_of_params, docs: Vec::new() }
     }
     pub fn get_file_id(&self) -> FileID {
         self.file_id
@@ -46,6 +47,9 @@ impl FunctionData {
     pub fn set_body(&mut self, body: Statement){
         self.body = body;
     }
+    pub fn set_docs(&mut self, docs: Vec<String>) {
+        self.docs = docs;
+    }
     pub fn replace_body(&mut self, new: Statement) -> Statement {
         std::mem::replace(&mut self.body, new)
     }
@@ -58,6 +62,9 @@ impl FunctionData {
     pub fn get_param_location(&self) -> FileLocation {
         self.param_location.clone()
     }
+    pub fn get_docs(&self) -> &Vec<String> {
+        &self.docs
+    }
     pub fn get_num_of_params(&self) -> usize {
         self.num_of_params
     }
@@ -32,11 +32,11 @@ impl Merger {
         let mut reports = vec![];
         for definition in definitions {
             let (name, meta) = match definition {
-                Definition::Template { name, args, arg_location, body, meta, parallel, is_custom_gate } => {
+                Definition::Template { name, args, arg_location, body, meta, parallel, is_custom_gate, docs } => {
                     if self.contains_function(&name) || self.contains_template(&name) || self.contains_bus(&name) {
                         (Option::Some(name), meta)
                     } else {
-                        let new_data = TemplateData::new(
+                        let mut new_data = TemplateData::new(
                             name.clone(),
                             file_id,
                             body,
@@ -47,15 +47,16 @@ impl Merger {
                             parallel,
                             is_custom_gate,
                         );
+                        new_data.set_docs(docs);
                         self.get_mut_template_info().insert(name.clone(), new_data);
                         (Option::None, meta)
                     }
                 }
-                Definition::Function { name, body, args, arg_location, meta } => {
+                Definition::Function { name, body, args, arg_location, meta, docs } => {
                     if self.contains_function(&name) || self.contains_template(&name) || self.contains_bus(&name) {
                         (Option::Some(name), meta)
                     } else {
-                        let new_data = FunctionData::new(
+                        let mut new_data = FunctionData::new(
                             name.clone(),
                             file_id,
                             body,
@@ -64,15 +65,16 @@ impl Merger {
                             arg_location,
                             &mut self.fresh_id,
                         );
+                        new_data.set_docs(docs);
                         self.get_mut_function_info().insert(name.clone(), new_data);
                         (Option::None, meta)
                     }
                 }
-                Definition::Bus { name, body, args, arg_location, meta  } => {
+                Definition::Bus { name, body, args, arg_location, meta, docs } => {
                     if self.contains_function(&name) || self.contains_template(&name) || self.contains_bus(&name) {
                         (Option::Some(name), meta)
                     } else {
-                        let new_data = BusData::new(
+                        let mut new_data = BusData::new(
                             file_id,
                             name.clone(),
                             body,
@@ -81,6 +83,7 @@ impl Merger {
                             arg_location,
                             &mut self.fresh_id,
                         );
+                        new_data.set_docs(docs);
                         self.get_mut_bus_info().insert(name.clone(), new_data);
                         (Option::None, meta)
                     }
@@ -18,6 +18,7 @@ pub struct TemplateData {
     output_wires: WireInfo,
     is_parallel: bool,
     is_custom_gate: bool,
+    docs: Vec<String>,
     /* Only used to know the order in which signals are declared.*/
     input_declarations: WireDeclarationOrder,
     output_declarations: WireDeclarationOrder,
@@ -52,6 +53,7 @@ impl TemplateData {
             output_wires,
             is_parallel,
             is_custom_gate,
+            docs: Vec::new(),
             input_declarations,
             output_declarations
         }
@@ -82,6 +84,7 @@ impl TemplateData {
             output_wires,
             is_parallel,
             is_custom_gate,
+            docs: Vec::new(),
             input_declarations,
             output_declarations
         }
@@ -111,6 +114,9 @@ impl TemplateData {
     pub fn set_body(&mut self, body: Statement){
         self.body = body;
     }
+    pub fn set_docs(&mut self, docs: Vec<String>) {
+        self.docs = docs;
+    }
     pub fn get_num_of_params(&self) -> usize {
         self.num_of_params
     }
@@ -120,6 +126,9 @@ impl TemplateData {
     pub fn get_name_of_params(&self) -> &Vec<String> {
         &self.name_of_params
     }
+    pub fn get_docs(&self) -> &Vec<String> {
+        &self.docs
+    }
     pub fn get_input_info(&self, name: &str) -> Option<&WireData> {
         self.input_wires.get(name)
     }
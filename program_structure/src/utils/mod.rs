@@ -1,3 +1,4 @@
 pub mod constants;
 pub mod environment;
 pub mod memory_slice;
+pub mod progress;
@@ -0,0 +1,97 @@
+use std::sync::RwLock;
+use std::time::Instant;
+
+// Phases of a compilation, in the order they are run
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Parsing,
+    TypeAnalysis,
+    ConstraintGeneration,
+    Simplification,
+    ConstraintOutputs,
+    CodeGeneration,
+}
+
+impl Phase {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phase::Parsing => "parsing",
+            Phase::TypeAnalysis => "type analysis",
+            Phase::ConstraintGeneration => "constraint generation",
+            Phase::Simplification => "simplification",
+            Phase::ConstraintOutputs => "constraint outputs",
+            Phase::CodeGeneration => "code generation",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum ProgressEvent {
+    PhaseStarted { phase: Phase },
+    // peak memory of the process in bytes at the end of the phase, when the system gives it
+    PhaseFinished { phase: Phase, millis: u128, peak_memory: Option<u64> },
+    FileParsed { file: String },
+    // a new template instance, like Num2Bits(8), with the constraints generated by its template
+    // and the number of instances executed so far
+    InstanceExecuted { instance: String, constraints: usize, instances: usize },
+    // constraints of the whole circuit before the simplification
+    ConstraintsGenerated { constraints: usize },
+    // round of the linear simplification of --O2, one for every linear round of the
+    // simplification report, the remaining linear constraints are the ones it kept
+    SimplificationRound { round: usize, linear: usize, substitutions: usize, remaining: usize, millis: u128 },
+    // code of a template instance translated, the index starts at 1
+    TemplateGenerated { template: String, index: usize, templates: usize },
+}
+
+type Reporter = Box<dyn Fn(&ProgressEvent) + Send + Sync>;
+
+// The events are sent to a single reporter installed by the tool that runs the compilation,
+// they are dropped when there is none
+static REPORTER: RwLock<Option<Reporter>> = RwLock::new(None);
+
+pub fn set_reporter(reporter: Reporter) {
+    *REPORTER.write().unwrap() = Some(reporter);
+}
+
+pub fn remove_reporter() {
+    *REPORTER.write().unwrap() = None;
+}
+
+// To skip the work of building an event that nobody receives
+pub fn is_reported() -> bool {
+    REPORTER.read().unwrap().is_some()
+}
+
+pub fn report(event: ProgressEvent) {
+    if let Some(reporter) = REPORTER.read().unwrap().as_ref() {
+        reporter(&event);
+    }
+}
+
+// Reports the start of a phase when it is created and its end when it is finished
+pub struct PhaseTimer {
+    phase: Phase,
+    start: Instant,
+}
+
+impl PhaseTimer {
+    pub fn start(phase: Phase) -> PhaseTimer {
+        report(ProgressEvent::PhaseStarted { phase });
+        PhaseTimer { phase, start: Instant::now() }
+    }
+
+    pub fn finish(self) {
+        if is_reported() {
+            let millis = self.start.elapsed().as_millis();
+            report(ProgressEvent::PhaseFinished { phase: self.phase, millis, peak_memory: peak_memory() });
+        }
+    }
+}
+
+// Maximum resident memory of the process, only known in Linux
+pub fn peak_memory() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kilobytes: u64 = line.trim_start_matches("VmHWM:").trim().trim_end_matches("kB").trim().parse().ok()?;
+    Some(kilobytes * 1024)
+}
@@ -0,0 +1,305 @@
+use program_structure::ast::{Access, Expression, LogArgument, Statement, VariableType};
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use program_structure::file_definition::{FileID, FileLocation};
+use program_structure::program_archive::ProgramArchive;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// Warnings about the code that does not take part in the circuit: the definitions that are
+// not reached from the main component and the variables, parameters and subcomponents of
+// the reached ones that are never used. Symbols whose name starts with _ are not reported.
+
+pub fn unreachable_definitions_analysis(
+    program_archive: &ProgramArchive,
+    reached: &HashSet<String>,
+) -> ReportCollection {
+    // definitions of each file, together with whether they are reached or not
+    let mut files: BTreeMap<FileID, Vec<(&str, &str, FileLocation, bool)>> = BTreeMap::new();
+    for (name, data) in program_archive.get_templates() {
+        let definition = ("template", name.as_str(), data.get_param_location(), reached.contains(name));
+        files.entry(data.get_file_id()).or_default().push(definition);
+    }
+    for (name, data) in program_archive.get_functions() {
+        let definition = ("function", name.as_str(), data.get_param_location(), reached.contains(name));
+        files.entry(data.get_file_id()).or_default().push(definition);
+    }
+    for (name, data) in program_archive.get_buses() {
+        let definition = ("bus", name.as_str(), data.get_param_location(), reached.contains(name));
+        files.entry(data.get_file_id()).or_default().push(definition);
+    }
+    let main_file = *program_archive.get_file_id_main();
+    let mut reports = ReportCollection::new();
+    for (file_id, mut definitions) in files {
+        definitions.sort_by_key(|(_, _, location, _)| location.start);
+        let no_definition_reached = definitions.iter().all(|(_, _, _, is_reached)| !is_reached);
+        if file_id != main_file && no_definition_reached {
+            let path = program_archive.get_file_library().get_path(file_id).unwrap_or_default();
+            reports.push(Report::warning(
+                format!("The file {} is included, but none of its definitions is used by the main component", path),
+                ReportCode::UnreachableDefinition,
+            ));
+            continue;
+        }
+        for (kind, name, location, is_reached) in definitions {
+            if !is_reached && !is_ignored(name) {
+                let mut report = Report::warning(
+                    format!("The {} {} is never used by the main component", kind, name),
+                    ReportCode::UnreachableDefinition,
+                );
+                report.add_primary(location, file_id, format!("{} {} defined here", kind, name));
+                reports.push(report);
+            }
+        }
+    }
+    reports
+}
+
+pub fn unused_symbols_analysis(
+    file_id: FileID,
+    param_location: FileLocation,
+    params_names: &[String],
+    body: &Statement,
+) -> ReportCollection {
+    let mut analysis = Analysis { file_id, environment: vec![HashMap::new()], reports: ReportCollection::new() };
+    for param in params_names {
+        analysis.declare(param, Some(SymbolKind::Parameter), param_location.clone());
+    }
+    analysis.statement(body);
+    analysis.close_block();
+    analysis.reports
+}
+
+fn is_ignored(name: &str) -> bool {
+    name.rsplit('.').next().is_some_and(|name| name.starts_with('_'))
+}
+
+#[derive(Copy, Clone)]
+enum SymbolKind {
+    Parameter,
+    Variable,
+    Component,
+}
+
+struct Symbol {
+    // signals and buses are declared to follow the scopes, but they are not reported
+    kind: Option<SymbolKind>,
+    location: FileLocation,
+    used: bool,
+}
+
+struct Analysis {
+    file_id: FileID,
+    environment: Vec<HashMap<String, Symbol>>,
+    reports: ReportCollection,
+}
+
+impl Analysis {
+    fn declare(&mut self, name: &str, kind: Option<SymbolKind>, location: FileLocation) {
+        let block = self.environment.last_mut().unwrap();
+        block.insert(name.to_string(), Symbol { kind, location, used: false });
+    }
+
+    fn symbol(&mut self, name: &str) -> Option<&mut Symbol> {
+        self.environment.iter_mut().rev().find_map(|block| block.get_mut(name))
+    }
+
+    fn mark_used(&mut self, name: &str) {
+        if let Some(symbol) = self.symbol(name) {
+            symbol.used = true;
+        }
+    }
+
+    fn is_component(&mut self, name: &str) -> bool {
+        matches!(self.symbol(name), Some(Symbol { kind: Some(SymbolKind::Component), .. }))
+    }
+
+    fn close_block(&mut self) {
+        let block = self.environment.pop().unwrap();
+        let mut unused: Vec<_> = block.into_iter().filter(|(name, symbol)| !symbol.used && !is_ignored(name)).collect();
+        unused.sort_by_key(|(_, symbol)| symbol.location.start);
+        for (name, symbol) in unused {
+            let message = match symbol.kind {
+                Some(SymbolKind::Parameter) => format!("The parameter {} is never used", name),
+                Some(SymbolKind::Variable) => format!("The variable {} is never read", name),
+                Some(SymbolKind::Component) => {
+                    format!("The subcomponent {} is never used, none of its signals is accessed", name)
+                }
+                None => continue,
+            };
+            let mut report = Report::warning(message, ReportCode::UnusedSymbol);
+            report.add_primary(symbol.location, self.file_id, "declared here".to_string());
+            self.reports.push(report);
+        }
+    }
+
+    fn statement(&mut self, stmt: &Statement) {
+        use Statement::*;
+        match stmt {
+            IfThenElse { cond, if_case, else_case, .. } => {
+                self.expression(cond);
+                self.statement(if_case);
+                if let Some(else_case) = else_case {
+                    self.statement(else_case);
+                }
+            }
+            While { cond, stmt, .. } => {
+                self.expression(cond);
+                self.statement(stmt);
+            }
+            Return { value, .. } => self.expression(value),
+            InitializationBlock { initializations, .. } => {
+                for initialization in initializations {
+                    self.statement(initialization);
+                }
+            }
+            Declaration { meta, xtype, name, dimensions, .. } => {
+                for dimension in dimensions {
+                    self.expression(dimension);
+                }
+                let kind = match xtype {
+                    VariableType::Var => Some(SymbolKind::Variable),
+                    VariableType::Component => Some(SymbolKind::Component),
+                    _ => None,
+                };
+                self.declare(name, kind, meta.location.clone());
+            }
+            Substitution { var, access, rhe, .. } => {
+                // the value of x in x = x + 1 only goes back to x, it is not a read of x
+                let used_before = self.symbol(var).is_none_or(|symbol| symbol.used);
+                self.expression(rhe);
+                self.access(access);
+                // assigning a variable is not using it, accessing the signals of a component is
+                let accesses_signal = access.iter().any(|acc| matches!(acc, Access::ComponentAccess(_)));
+                if accesses_signal && self.is_component(var) {
+                    self.mark_used(var);
+                } else if let Some(symbol @ Symbol { kind: Some(SymbolKind::Variable), .. }) = self.symbol(var) {
+                    symbol.used = used_before;
+                }
+            }
+            MultSubstitution { lhe, rhe, .. } | ConstraintEquality { lhe, rhe, .. } => {
+                self.expression(lhe);
+                self.expression(rhe);
+            }
+            UnderscoreSubstitution { rhe, .. } => self.expression(rhe),
+            LogCall { args, .. } => {
+                for arg in args {
+                    if let LogArgument::LogExp(arg) = arg {
+                        self.expression(arg);
+                    }
+                }
+            }
+            Block { stmts, .. } => {
+                self.environment.push(HashMap::new());
+                for stmt in stmts {
+                    self.statement(stmt);
+                }
+                self.close_block();
+            }
+            Assert { arg, .. } => self.expression(arg),
+        }
+    }
+
+    fn access(&mut self, access: &[Access]) {
+        for acc in access {
+            if let Access::ArrayAccess(index) = acc {
+                self.expression(index);
+            }
+        }
+    }
+
+    fn expression(&mut self, expr: &Expression) {
+        use Expression::*;
+        match expr {
+            InfixOp { lhe, rhe, .. } => {
+                self.expression(lhe);
+                self.expression(rhe);
+            }
+            PrefixOp { rhe, .. } | ParallelOp { rhe, .. } => self.expression(rhe),
+            InlineSwitchOp { cond, if_true, if_false, .. } => {
+                self.expression(cond);
+                self.expression(if_true);
+                self.expression(if_false);
+            }
+            Variable { name, access, .. } => {
+                self.mark_used(name);
+                self.access(access);
+            }
+            Number(..) => {}
+            Call { args, .. } | BusCall { args, .. } => {
+                for arg in args {
+                    self.expression(arg);
+                }
+            }
+            AnonymousComp { params, signals, .. } => {
+                for value in params.iter().chain(signals.iter()) {
+                    self.expression(value);
+                }
+            }
+            ArrayInLine { values, .. } | Tuple { values, .. } => {
+                for value in values {
+                    self.expression(value);
+                }
+            }
+            UniformArray { value, dimension, .. } => {
+                self.expression(value);
+                self.expression(dimension);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::check_types::check_types;
+    use crate::parse_test_program;
+
+    // Messages of the dead code warnings of the source, in the order they are reported
+    fn dead_code_warnings(name: &str, source: &str) -> Vec<String> {
+        let mut program = parse_test_program(name, &format!("pragma circom 2.1.0;\n{}", source));
+        let warnings = check_types(&mut program, false, true).unwrap_or_else(|_| panic!("{} has errors", name));
+        warnings.iter().map(|warning| warning.get_message().clone()).filter(|m| m.contains("never")).collect()
+    }
+
+    #[test]
+    fn warnings_are_sorted_by_location() {
+        let source = "template T1(unused1) { signal input in; }
+function f2(unused2) { return 0; }
+template T3() { var unused3 = 0; signal input in; }
+bus B4(unused4) { signal s; }
+template T5() { var unused5; component unused6 = T1(1); }
+function f6() { return 6; }
+template Main() { signal input in; B4(1) b; var x = f2(0); component t3 = T3(); t3.in <== in; component t5 = T5(); }
+component main = Main();
+";
+        // the unreachable definitions are reported first
+        let expected = [
+            "The function f6 is never used by the main component",
+            "The parameter unused1 is never used",
+            "The parameter unused2 is never used",
+            "The variable unused3 is never read",
+            "The parameter unused4 is never used",
+            "The variable unused5 is never read",
+            "The subcomponent unused6 is never used, none of its signals is accessed",
+            "The variable x is never read",
+            "The subcomponent t5 is never used, none of its signals is accessed",
+        ];
+        assert_eq!(dead_code_warnings("sorted", source), expected);
+    }
+
+    #[test]
+    fn assigning_a_variable_from_itself_is_not_a_read() {
+        let source = "template Main() {
+    signal input in;
+    signal output out;
+    var count = 0;
+    count = count + 1;
+    count += 2;
+    var total = 0;
+    total = total + 1;
+    out <== in * total;
+}
+component main = Main();
+";
+        assert_eq!(dead_code_warnings("self_assignment", source), ["The variable count is never read"]);
+    }
+}
@@ -398,7 +398,7 @@ fn analyze_expression(
                 report.add_primary(
                     file_definition::generate_file_location(meta.get_start(), meta.get_end()),
                     file_id.clone(),
-                    format!("Calling unknown symbol"),
+                    unknown_call_message(id),
                 );
                 reports.push(report);
                 return;
@@ -474,7 +474,7 @@ fn analyze_expression(
                 report.add_primary(
                     file_definition::generate_file_location(meta.get_start(), meta.get_end()),
                     file_id.clone(),
-                    format!("Calling unknown symbol"),
+                    unknown_call_message(id),
                 );
                 reports.push(report);
                 return;
@@ -507,4 +507,12 @@ fn analyze_expression(
         },
         _ => {}
     }
-}
\ No newline at end of file
+}
+
+// The names of the definitions of a namespace are qualified by its alias, lib.Num2Bits
+fn unknown_call_message(id: &str) -> String {
+    match id.rsplit_once('.') {
+        Some((namespace, name)) => format!("Calling unknown symbol {} of the namespace {}", name, namespace),
+        None => format!("Calling unknown symbol"),
+    }
+}
@@ -1,238 +1,475 @@
-// use program_structure::ast::{
-//     Access, AssignOp, Expression, ExpressionPrefixOpcode, Meta, SignalElementType, SignalType,
-//     Statement, VariableType,
-// };
-// use program_structure::environment::CircomEnvironment;
-// use program_structure::error_code::ReportCode;
-// use program_structure::error_definition::{Report, ReportCollection};
-// use program_structure::file_definition::{generate_file_location, FileID};
-// use program_structure::program_archive::ProgramArchive;
-// use program_structure::template_data::TemplateData;
-// use std::collections::HashMap;
-
-// type TemplateInfo = HashMap<String, TemplateData>;
-// type Environment = CircomEnvironment<Option<String>, SignalElementType, SignalElementType>;
-// enum ExpressionResult {
-//     ArithmeticExpression(SignalElementType),
-//     Template(Option<String>),
-// }
-
-// pub fn tag_analysis(
-//     template_name: &str,
-//     program_archive: &ProgramArchive,
-// ) -> Result<(), ReportCollection> {
-//     let template_body = program_archive.get_template_data(template_name).get_body_as_vec();
-//     let file_id = program_archive.get_template_data(template_name).get_file_id();
-//     let template_info = program_archive.get_templates();
-
-//     let mut environment = Environment::new();
-//     let args = program_archive.get_template_data(template_name).get_name_of_params().clone();
-//     for arg in args.iter() {
-//         environment.add_variable(arg, SignalElementType::FieldElement);
-//     }
-
-//     let mut reports = ReportCollection::new();
-//     treat_sequence_of_statements(
-//         template_body,
-//         file_id,
-//         template_info,
-//         &mut reports,
-//         &mut environment,
-//     );
-//     if reports.is_empty() {
-//         Result::Ok(())
-//     } else {
-//         Result::Err(reports)
-//     }
-// }
-
-// fn statement_inspection(
-//     stmt: &Statement,
-//     file_id: FileID,
-//     template_info: &TemplateInfo,
-//     reports: &mut ReportCollection,
-//     environment: &mut Environment,
-// ) {
-//     use Statement::*;
-//     match stmt {
-//         IfThenElse { if_case, else_case, .. } => {
-//             statement_inspection(if_case, file_id, template_info, reports, environment);
-//             if let Option::Some(else_stmt) = else_case {
-//                 statement_inspection(else_stmt, file_id, template_info, reports, environment);
-//             }
-//         }
-//         While { stmt, .. } => {
-//             statement_inspection(stmt, file_id, template_info, reports, environment);
-//         }
-//         Block { stmts, .. } => {
-//             environment.add_variable_block();
-//             treat_sequence_of_statements(stmts, file_id, template_info, reports, environment);
-//             environment.remove_variable_block();
-//         }
-//         InitializationBlock { initializations, .. } => {
-//             treat_sequence_of_statements(
-//                 initializations,
-//                 file_id,
-//                 template_info,
-//                 reports,
-//                 environment,
-//             );
-//         }
-//         Declaration { xtype, name, meta, .. } => {
-//             use SignalType::*;
-//             use VariableType::*;
-//             match xtype {
-//                 Var => environment.add_variable(name, SignalElementType::FieldElement),
-//                 Component => environment.add_component(name, meta.component_inference.clone()),
-//                 Signal(signal_type, signal_element) => match signal_type {
-//                     Output => environment.add_output(name, *signal_element),
-//                     Intermediate => environment.add_intermediate(name, *signal_element),
-//                     Input => environment.add_input(name, *signal_element),
-//                 },
-//             }
-//         }
-//         Substitution { meta, var, access, rhe, op, .. } => {
-//             use ExpressionResult::*;
-//             let var_info = variable_inspection(var, access, environment, template_info);
-//             let rhe_info = expression_inspection(rhe, template_info, reports, environment);
-//             match (var_info, rhe_info) {
-//                 (Template(_), Template(assign)) => {
-//                     *environment.get_mut_component_or_break(var, file!(), line!()) = assign;
-//                 }
-//                 (ArithmeticExpression(tag_0), ArithmeticExpression(tag_1))
-//                     if tag_0 == SignalElementType::Binary
-//                         && tag_1 == SignalElementType::FieldElement
-//                         && *op == AssignOp::AssignConstraintSignal =>
-//                 {
-//                     add_report(ReportCode::WrongSignalTags, meta, file_id, reports)
-//                 }
-//                 _ => {}
-//             }
-//         }
-//         _ => {}
-//     }
-// }
-
-      
-// fn expression_inspection(
-//     expr: &Expression,
-//     template_info: &TemplateInfo,
-//     reports: &mut ReportCollection,
-//     environment: &Environment,
-// ) -> ExpressionResult {
-//     use Expression::*;
-//     match expr {
-//         InfixOp { .. } => ExpressionResult::ArithmeticExpression(SignalElementType::FieldElement),
-//         PrefixOp { rhe, prefix_op, .. } => {
-//             let rhe_info = expression_inspection(rhe, template_info, reports, environment);
-//             match prefix_op {
-//                 ExpressionPrefixOpcode::BoolNot => rhe_info,
-//                 _ => ExpressionResult::ArithmeticExpression(SignalElementType::FieldElement),
-//             }
-//         }
-//         InlineSwitchOp { if_true, if_false, .. } => {
-//             let if_true_info = expression_inspection(if_true, template_info, reports, environment);
-//             let if_false_info =
-//                 expression_inspection(if_false, template_info, reports, environment);
-//             match (&if_true_info, &if_false_info) {
-//                 (
-//                     ExpressionResult::ArithmeticExpression(tag_0),
-//                     ExpressionResult::ArithmeticExpression(tag_1),
-//                 ) => {
-//                     if let SignalElementType::FieldElement = tag_0 {
-//                         if_true_info
-//                     } else if let SignalElementType::FieldElement = tag_1 {
-//                         if_false_info
-//                     } else {
-//                         if_true_info
-//                     }
-//                 }
-//                 _ => if_true_info,
-//             }
-//         }
-//         Variable { name, access, .. } => {
-//             variable_inspection(name, access, environment, template_info)
-//         }
-//         Number(..) => ExpressionResult::ArithmeticExpression(SignalElementType::FieldElement),
-//         Call { id, .. } => {
-//             if template_info.contains_key(id) {
-//                 ExpressionResult::Template(Option::Some(id.clone()))
-//             } else {
-//                 ExpressionResult::ArithmeticExpression(SignalElementType::FieldElement)
-//             }
-//         }
-//         ArrayInLine { .. } => {
-//             ExpressionResult::ArithmeticExpression(SignalElementType::FieldElement)
-//         }
-//         UniformArray { .. } => {
-//             ExpressionResult::ArithmeticExpression(SignalElementType::FieldElement)
-//         }
-//         _ => {unreachable!("Anonymous calls should not be reachable at this point."); }
-//     }
-// }
-
-// //************************************************* Statement support *************************************************
-// fn treat_sequence_of_statements(
-//     stmts: &[Statement],
-//     file_id: FileID,
-//     template_info: &TemplateInfo,
-//     reports: &mut ReportCollection,
-//     environment: &mut Environment,
-// ) {
-//     for stmt in stmts {
-//         statement_inspection(stmt, file_id, template_info, reports, environment);
-//     }
-// }
-// //************************************************* Expression support *************************************************
-
-// fn variable_inspection(
-//     symbol: &str,
-//     accesses: &[Access],
-//     environment: &Environment,
-//     template_info: &TemplateInfo,
-// ) -> ExpressionResult {
-//     use ExpressionResult::*;
-//     let mut result = if environment.has_component(symbol) {
-//         Template(environment.get_component_or_break(symbol, file!(), line!()).clone())
-//     } else if environment.has_signal(symbol) {
-//         ArithmeticExpression(*environment.get_signal_or_break(symbol, file!(), line!()))
-//     } else {
-//         ArithmeticExpression(SignalElementType::FieldElement)
-//     };
-
-//     for access in accesses {
-//         if let Access::ComponentAccess(signal) = access {
-//             let template =
-//                 environment.get_component_or_break(symbol, file!(), line!()).clone().unwrap();
-//             let input = template_info.get(&template).unwrap().get_input_info(signal);
-//             let output = template_info.get(&template).unwrap().get_output_info(signal);
-//             match (input, output) {
-//                 (Some((_, tag)), _) | (_, Some((_, tag))) => {
-//                     result = ArithmeticExpression(*tag);
-//                 }
-//                 _ => {
-//                     unreachable!()
-//                 }
-//             }
-//         }
-//     }
-//     result
-// }
-
-// //************************************************* Report support *************************************************
-// fn add_report(
-//     error_code: ReportCode,
-//     meta: &Meta,
-//     file_id: FileID,
-//     reports: &mut ReportCollection,
-// ) {
-//     use ReportCode::*;
-//     let mut report = Report::error("Typing error found".to_string(), error_code);
-//     let location = generate_file_location(meta.start, meta.end);
-//     let message = match error_code {
-//         WrongSignalTags => "Can not assign Field values to signals tagged as binary".to_string(),
-//         _ => panic!("Unimplemented error code"),
-//     };
-//     report.add_primary(location, file_id, message);
-//     reports.push(report);
-// }
+use program_structure::ast::{Access, AssignOp, Expression, Meta, SignalType, Statement, VariableType};
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use program_structure::file_definition::{generate_file_location, FileID};
+use program_structure::program_archive::ProgramArchive;
+use program_structure::wire_data::WireType;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+// Tags that a value may carry when the template is executed, none if they can not be known
+// before the execution. A tag required by an input is only reported as missing when it is
+// not in this set, so the analysis never rejects a program that the execution accepts.
+type Tags = Option<BTreeSet<String>>;
+// Signal or bus followed by the names of the accessed fields
+type Path = Vec<String>;
+
+struct SignalInfo {
+    signal_type: SignalType,
+    wire_type: WireType,
+    tags: BTreeSet<String>,
+}
+
+// A bus assigned as a whole receives the tags of the fields of the assigned bus
+enum BusSource {
+    Local(Path),
+    Unknown,
+}
+
+struct Analysis<'a> {
+    program_archive: &'a ProgramArchive,
+    file_id: FileID,
+    signals: HashMap<String, SignalInfo>,
+    // templates that may be instantiated by each component
+    components: HashMap<String, BTreeSet<String>>,
+    variables: HashMap<String, Tags>,
+    // tags received by signals and bus fields through <==
+    inherited: HashMap<Path, Tags>,
+    bus_sources: HashMap<Path, Vec<BusSource>>,
+    changed: bool,
+}
+
+pub fn tag_analysis(template_name: &str, program_archive: &ProgramArchive) -> Result<(), ReportCollection> {
+    let template_data = program_archive.get_template_data(template_name);
+    let mut analysis = Analysis {
+        program_archive,
+        file_id: template_data.get_file_id(),
+        signals: HashMap::new(),
+        components: HashMap::new(),
+        variables: HashMap::new(),
+        inherited: HashMap::new(),
+        bus_sources: HashMap::new(),
+        changed: true,
+    };
+    for param in template_data.get_name_of_params() {
+        analysis.variables.insert(param.clone(), Some(BTreeSet::new()));
+    }
+    // the propagation does not follow the order of the statements, it is repeated until the
+    // tags of every symbol include the ones of all the values assigned to it
+    while analysis.changed {
+        analysis.changed = false;
+        analysis.propagate(template_data.get_body());
+    }
+    let mut reports = ReportCollection::new();
+    analysis.check(template_data.get_body(), &mut reports);
+    if reports.is_empty() {
+        Result::Ok(())
+    } else {
+        Result::Err(reports)
+    }
+}
+
+fn union(tags: &Tags, other: &Tags) -> Tags {
+    match (tags, other) {
+        (Some(tags), Some(other)) => Some(tags.union(other).cloned().collect()),
+        _ => None,
+    }
+}
+
+impl<'a> Analysis<'a> {
+    fn propagate(&mut self, stmt: &Statement) {
+        use Statement::*;
+        match stmt {
+            IfThenElse { if_case, else_case, .. } => {
+                self.propagate(if_case);
+                if let Some(else_stmt) = else_case {
+                    self.propagate(else_stmt);
+                }
+            }
+            While { stmt, .. } => self.propagate(stmt),
+            Block { stmts, .. } => stmts.iter().for_each(|s| self.propagate(s)),
+            InitializationBlock { initializations, .. } => initializations.iter().for_each(|s| self.propagate(s)),
+            Declaration { xtype, name, .. } => self.declare(xtype, name),
+            Substitution { var, access, rhe, .. } if self.components.contains_key(var) => {
+                let is_creation = access.iter().all(|a| matches!(a, Access::ArrayAccess(_)));
+                if is_creation {
+                    let mut templates = BTreeSet::new();
+                    instantiated_templates(rhe, self.program_archive, &mut templates);
+                    let known = self.components.get_mut(var).unwrap();
+                    let previous = known.len();
+                    known.append(&mut templates);
+                    self.changed |= known.len() != previous;
+                }
+            }
+            Substitution { var, access, op, rhe, .. } if self.signals.contains_key(var) => {
+                let path = match self.signal_path(var, access) {
+                    Some(path) => path,
+                    // assignment of the value of a tag
+                    None => return,
+                };
+                if *op != AssignOp::AssignConstraintSignal {
+                    return;
+                }
+                let tags = self.expression_tags(rhe);
+                self.inherit(path.clone(), tags);
+                if let WireType::Bus(_) = self.path_type(&path) {
+                    let source = match rhe {
+                        Expression::Variable { name, access, .. } if self.signals.contains_key(name) => {
+                            self.signal_path(name, access).map_or(BusSource::Unknown, BusSource::Local)
+                        }
+                        Expression::Variable { .. } | Expression::InlineSwitchOp { .. } => BusSource::Unknown,
+                        _ => return,
+                    };
+                    self.add_bus_source(path, source);
+                }
+            }
+            // only complete assignments give their tags to a variable
+            Substitution { var, access, rhe, .. } if self.variables.contains_key(var) && access.is_empty() => {
+                let tags = self.expression_tags(rhe);
+                let current = self.variables.get(var).unwrap();
+                let updated = union(current, &tags);
+                if updated != *current {
+                    self.variables.insert(var.clone(), updated);
+                    self.changed = true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn declare(&mut self, xtype: &VariableType, name: &str) {
+        use VariableType::*;
+        let (signal_type, wire_type, tags) = match xtype {
+            Var => {
+                self.variables.entry(name.to_string()).or_insert_with(|| Some(BTreeSet::new()));
+                return;
+            }
+            Component | AnonymousComponent => {
+                self.components.entry(name.to_string()).or_default();
+                return;
+            }
+            Signal(signal_type, tags) => (signal_type, WireType::Signal, tags),
+            Bus(bus_name, signal_type, tags) => (signal_type, WireType::Bus(bus_name.clone()), tags),
+        };
+        let tags = tags.iter().cloned().collect();
+        self.signals.insert(name.to_string(), SignalInfo { signal_type: *signal_type, wire_type, tags });
+    }
+
+    fn inherit(&mut self, path: Path, tags: Tags) {
+        let current = self.inherited.entry(path).or_insert_with(|| Some(BTreeSet::new()));
+        let updated = union(current, &tags);
+        if updated != *current {
+            *current = updated;
+            self.changed = true;
+        }
+    }
+
+    fn add_bus_source(&mut self, path: Path, source: BusSource) {
+        let sources = self.bus_sources.entry(path).or_default();
+        let is_new = match &source {
+            BusSource::Local(new) => !sources.iter().any(|s| matches!(s, BusSource::Local(p) if p == new)),
+            BusSource::Unknown => !sources.iter().any(|s| matches!(s, BusSource::Unknown)),
+        };
+        if is_new {
+            sources.push(source);
+            self.changed = true;
+        }
+    }
+
+    // Path of the signal or bus field accessed, none if the access is the value of a tag
+    fn signal_path(&self, name: &str, access: &[Access]) -> Option<Path> {
+        let mut path = vec![name.to_string()];
+        for a in access {
+            if let Access::ComponentAccess(field) = a {
+                match self.path_type(&path) {
+                    WireType::Bus(bus_name) if self.bus_field(&bus_name, field).is_some() => path.push(field.clone()),
+                    _ => return None,
+                }
+            }
+        }
+        Some(path)
+    }
+
+    fn bus_field(&self, bus_name: &str, field: &str) -> Option<&'a program_structure::wire_data::WireData> {
+        if !self.program_archive.contains_bus(bus_name) {
+            return None;
+        }
+        self.program_archive.get_bus_data(bus_name).get_field_info(field)
+    }
+
+    fn path_type(&self, path: &[String]) -> WireType {
+        let mut wire_type = self.signals[&path[0]].wire_type.clone();
+        for field in &path[1..] {
+            wire_type = match &wire_type {
+                WireType::Bus(bus_name) => self.bus_field(bus_name, field).map_or(WireType::Signal, |f| f.get_type()),
+                WireType::Signal => WireType::Signal,
+            };
+        }
+        wire_type
+    }
+
+    fn declared_tags(&self, path: &[String]) -> BTreeSet<String> {
+        if path.len() == 1 {
+            return self.signals[&path[0]].tags.clone();
+        }
+        let parent = &path[..path.len() - 1];
+        match self.path_type(parent) {
+            WireType::Bus(bus_name) => match self.bus_field(&bus_name, &path[path.len() - 1]) {
+                Some(field) => field.get_tags().iter().cloned().collect(),
+                None => BTreeSet::new(),
+            },
+            WireType::Signal => BTreeSet::new(),
+        }
+    }
+
+    fn path_tags(&self, path: &[String], visited: &mut HashSet<Path>) -> Tags {
+        if !visited.insert(path.to_vec()) {
+            return Some(BTreeSet::new());
+        }
+        // the fields of an input bus keep the tags given by the father component
+        if path.len() > 1 && self.signals[&path[0]].signal_type == SignalType::Input {
+            return None;
+        }
+        let mut tags = Some(self.declared_tags(path));
+        if let Some(inherited) = self.inherited.get(path) {
+            tags = union(&tags, inherited);
+        }
+        for length in 1..path.len() {
+            for source in self.bus_sources.get(&path[..length]).into_iter().flatten() {
+                let source_tags = match source {
+                    BusSource::Local(source) => {
+                        let mut source = source.clone();
+                        source.extend_from_slice(&path[length..]);
+                        self.path_tags(&source, visited)
+                    }
+                    BusSource::Unknown => None,
+                };
+                tags = union(&tags, &source_tags);
+            }
+        }
+        tags
+    }
+
+    fn expression_tags(&self, expr: &Expression) -> Tags {
+        use Expression::*;
+        match expr {
+            Variable { name, access, .. } => {
+                if let Some(tags) = self.variables.get(name) {
+                    tags.clone()
+                } else if self.signals.contains_key(name) {
+                    match self.signal_path(name, access) {
+                        Some(path) => self.path_tags(&path, &mut HashSet::new()),
+                        None => Some(BTreeSet::new()),
+                    }
+                } else if let Some(templates) = self.components.get(name) {
+                    self.output_tags(templates, access)
+                } else {
+                    None
+                }
+            }
+            InlineSwitchOp { if_true, if_false, .. } => {
+                union(&self.expression_tags(if_true), &self.expression_tags(if_false))
+            }
+            InfixOp { .. } | PrefixOp { .. } | Number(..) | Call { .. } | ArrayInLine { .. } | UniformArray { .. } => {
+                Some(BTreeSet::new())
+            }
+            _ => None,
+        }
+    }
+
+    // The outputs of a component only carry the tags declared in its template
+    fn output_tags(&self, templates: &BTreeSet<String>, access: &[Access]) -> Tags {
+        let mut fields = access.iter().filter_map(|a| match a {
+            Access::ComponentAccess(field) => Some(field),
+            Access::ArrayAccess(_) => None,
+        });
+        let output = fields.next()?;
+        let rest = fields.next();
+        if templates.is_empty() {
+            return None;
+        }
+        let mut tags = Some(BTreeSet::new());
+        for template in templates {
+            let info = self.program_archive.get_template_data(template).get_output_info(output)?;
+            let output_tags = match (info.get_type(), rest) {
+                (_, None) => Some(info.get_tags().iter().cloned().collect()),
+                // value of a tag of the output
+                (WireType::Signal, Some(_)) => Some(BTreeSet::new()),
+                (WireType::Bus(_), Some(_)) => None,
+            };
+            tags = union(&tags, &output_tags);
+        }
+        tags
+    }
+
+    // Tags that every template that the component may instantiate requires in the input
+    fn required_tags(&self, templates: &BTreeSet<String>, input: &str) -> BTreeSet<String> {
+        let mut required: Option<BTreeSet<String>> = None;
+        for template in templates {
+            let tags: BTreeSet<String> = match self.program_archive.get_template_data(template).get_input_info(input) {
+                Some(info) => info.get_tags().iter().cloned().collect(),
+                None => BTreeSet::new(),
+            };
+            required = Some(match required {
+                Some(required) => required.intersection(&tags).cloned().collect(),
+                None => tags,
+            });
+        }
+        required.unwrap_or_default()
+    }
+
+    fn check(&self, stmt: &Statement, reports: &mut ReportCollection) {
+        use Statement::*;
+        match stmt {
+            IfThenElse { if_case, else_case, .. } => {
+                self.check(if_case, reports);
+                if let Some(else_stmt) = else_case {
+                    self.check(else_stmt, reports);
+                }
+            }
+            While { stmt, .. } => self.check(stmt, reports),
+            Block { stmts, .. } => stmts.iter().for_each(|s| self.check(s, reports)),
+            InitializationBlock { initializations, .. } => initializations.iter().for_each(|s| self.check(s, reports)),
+            Substitution { meta, var, access, rhe, .. } if self.components.contains_key(var) => {
+                let fields: Vec<&String> = access
+                    .iter()
+                    .filter_map(|a| if let Access::ComponentAccess(field) = a { Some(field) } else { None })
+                    .collect();
+                // only the tags of complete inputs are checked, not the ones of their fields
+                if fields.len() != 1 {
+                    return;
+                }
+                let input = fields[0];
+                let required = self.required_tags(&self.components[var], input);
+                if let Some(tags) = self.expression_tags(rhe) {
+                    for tag in required.difference(&tags) {
+                        add_report(meta, self.file_id, var, input, tag, reports);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn instantiated_templates(expr: &Expression, program_archive: &ProgramArchive, templates: &mut BTreeSet<String>) {
+    use Expression::*;
+    match expr {
+        InlineSwitchOp { if_true, if_false, .. } => {
+            instantiated_templates(if_true, program_archive, templates);
+            instantiated_templates(if_false, program_archive, templates);
+        }
+        Call { id, .. } if program_archive.contains_template(id) => {
+            templates.insert(id.clone());
+        }
+        ParallelOp { rhe, .. } => instantiated_templates(rhe, program_archive, templates),
+        _ => {}
+    }
+}
+
+fn add_report(meta: &Meta, file_id: FileID, component: &str, input: &str, tag: &str, reports: &mut ReportCollection) {
+    let mut report = Report::error(
+        format!("Invalid assignment: missing tags required by input signal. \n Missing tag: input signal {} requires tag {}", input, tag),
+        ReportCode::WrongSignalTags,
+    );
+    let location = generate_file_location(meta.start, meta.end);
+    report.add_primary(
+        location,
+        file_id,
+        format!("the value assigned to {}.{} does not have the tag {}", component, input, tag),
+    );
+    reports.push(report);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::tag_analysis;
+    use crate::parse_test_program;
+
+    const TEMPLATES: &str = "pragma circom 2.1.0;
+template Binary() {
+    signal input {binary} in;
+    signal output {binary} out;
+    out <== in;
+}
+template BinaryMax() {
+    signal input {binary, max} in;
+    signal output out;
+    out <== in;
+}
+";
+
+    // Missing tags reported for the template Main of the source
+    fn missing_tags(name: &str, main: &str) -> Vec<String> {
+        let program = parse_test_program(name, &format!("{}{}\ncomponent main = Main();\n", TEMPLATES, main));
+        match tag_analysis("Main", &program) {
+            Result::Ok(()) => vec![],
+            Result::Err(reports) => reports.iter().map(|r| r.get_primary()[0].message.clone()).collect(),
+        }
+    }
+
+    #[test]
+    fn tags_reach_the_uses_that_come_before_the_assignments() {
+        // every round of the propagation moves the tag one signal back
+        let main = "template Main() {
+    signal input {binary} x;
+    signal y;
+    signal z;
+    component c = Binary();
+    c.in <== z;
+    z <== y;
+    y <== x;
+}";
+        assert!(missing_tags("chain", main).is_empty());
+    }
+
+    #[test]
+    fn values_without_the_tag_are_reported() {
+        let main = "template Main() {
+    signal input x;
+    signal input {binary} b;
+    var v = 0;
+    component c = Binary();
+    c.in <== x;
+    component d = Binary();
+    d.in <== v;
+    component e = Binary();
+    e.in <== b * b;
+    component f = Binary();
+    f.in <== b;
+}";
+        let expected: Vec<String> = ["c", "d", "e"]
+            .iter()
+            .map(|c| format!("the value assigned to {}.in does not have the tag binary", c))
+            .collect();
+        assert_eq!(missing_tags("missing", main), expected);
+    }
+
+    #[test]
+    fn outputs_and_variables_carry_their_tags() {
+        let main = "template Main() {
+    signal input {binary} x;
+    component a = Binary();
+    a.in <== x;
+    var v = a.out;
+    component b = Binary();
+    b.in <== v;
+    component c = BinaryMax();
+    c.in <== a.out;
+}";
+        // the output of Binary does not have the tag max
+        let expected = vec!["the value assigned to c.in does not have the tag max".to_string()];
+        assert_eq!(missing_tags("outputs", main), expected);
+    }
+
+    #[test]
+    fn components_check_the_tags_required_by_every_template() {
+        let main = "template Main(n) {
+    signal input {binary} x;
+    component c = n == 0 ? Binary() : BinaryMax();
+    c.in <== x;
+}";
+        let program = parse_test_program("choice", &format!("{}{}\ncomponent main = Main(0);\n", TEMPLATES, main));
+        // max is only required by one of the templates, so it is not reported
+        assert!(tag_analysis("Main", &program).is_ok());
+    }
+}
@@ -78,7 +78,7 @@ pub struct OutInfo {
     pub reached: HashSet<String>,
 }
 
-pub fn type_check(program_archive: &ProgramArchive) -> Result<OutInfo, ReportCollection> {
+pub fn type_check(program_archive: &ProgramArchive, check_tags: bool) -> Result<OutInfo, ReportCollection> {
     let mut analysis_information = AnalysisInformation {
         reached: HashSet::new(),
         file_id: *program_archive.get_file_id_main(),
@@ -94,7 +94,9 @@ pub fn type_check(program_archive: &ProgramArchive) -> Result<OutInfo, ReportCol
         return Result::Err(analysis_information.reports);
     }
 
-    check_main_has_tags(initial_expression, program_archive, &mut analysis_information.reports);
+    if !check_tags {
+        check_main_has_tags(initial_expression, program_archive, &mut analysis_information.reports);
+    }
 
 
     if analysis_information.reports.is_empty() {
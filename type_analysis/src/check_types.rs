@@ -2,9 +2,26 @@ use super::analyzers::*;
 use super::decorators::*;
 use program_structure::error_definition::ReportCollection;
 use program_structure::program_archive::ProgramArchive;
+use program_structure::progress::{Phase, PhaseTimer};
 
+// When check_tags is set the tags of the inputs of the main component are checked when
+// the witness is computed, so they are accepted. When inspect is set the unused code is
+// reported with warnings
 pub fn check_types(
     program_archive: &mut ProgramArchive,
+    check_tags: bool,
+    inspect: bool,
+) -> Result<ReportCollection, ReportCollection> {
+    let timer = PhaseTimer::start(Phase::TypeAnalysis);
+    let result = run_analyses(program_archive, check_tags, inspect);
+    timer.finish();
+    result
+}
+
+fn run_analyses(
+    program_archive: &mut ProgramArchive,
+    check_tags: bool,
+    inspect: bool,
 ) -> Result<ReportCollection, ReportCollection> {
     let mut errors = ReportCollection::new();
     let mut warnings = ReportCollection::new();
@@ -47,13 +64,16 @@ pub fn check_types(
     }
 
     // Type analysis
-    let typing_result = type_check(program_archive);
+    let typing_result = type_check(program_archive, check_tags);
     match typing_result {
         Err(mut type_reports) => {
             errors.append(&mut type_reports);
             return Result::Err(errors);
         }
         Ok(info) => {
+            if inspect {
+                warnings.append(&mut unreachable_definitions_analysis(program_archive, &info.reached));
+            }
             for name in program_archive.get_function_names().clone() {
                 if !info.reached.contains(&name) {
                     program_archive.remove_function(&name)
@@ -74,6 +94,9 @@ pub fn check_types(
 
     // Semantics analyses
     semantic_analyses(program_archive, &mut errors, &mut warnings);
+    if inspect {
+        dead_code_analyses(program_archive, &mut warnings);
+    }
 
     if !errors.is_empty() {
         Result::Err(errors)
@@ -175,6 +198,9 @@ fn semantic_analyses(
             unknown_known_analysis(template_name, program_archive) {
                 errors.append(&mut unknown_known_report);
             }
+        if let Result::Err(mut tag_report) = tag_analysis(template_name, program_archive) {
+            errors.append(&mut tag_report);
+        }
         if program_archive.get_template_data(template_name).is_custom_gate() {
             let body = program_archive.get_template_data(template_name).get_body();
             match custom_gate_analysis(template_name, body) {
@@ -184,3 +210,22 @@ fn semantic_analyses(
         }
     }
 }
+
+// The definitions are kept in hash maps, so the warnings are sorted by file and location
+fn dead_code_analyses(program_archive: &ProgramArchive, warnings: &mut ReportCollection) {
+    let templates = program_archive.get_templates().values().map(|data| {
+        (data.get_file_id(), data.get_param_location(), data.get_name_of_params(), data.get_body())
+    });
+    let functions = program_archive.get_functions().values().map(|data| {
+        (data.get_file_id(), data.get_param_location(), data.get_name_of_params(), data.get_body())
+    });
+    let buses = program_archive.get_buses().values().map(|data| {
+        (data.get_file_id(), data.get_param_location(), data.get_name_of_params(), data.get_body())
+    });
+    let mut reports = ReportCollection::new();
+    for (file_id, param_location, params_names, body) in templates.chain(functions).chain(buses) {
+        reports.append(&mut unused_symbols_analysis(file_id, param_location, params_names, body));
+    }
+    reports.sort_by_key(|report| report.get_primary().first().map(|label| (label.file_id, label.range.start)));
+    warnings.append(&mut reports);
+}
@@ -4,3 +4,20 @@ extern crate num_traits;
 mod analyzers;
 pub mod check_types;
 mod decorators;
+
+// Program of a source written in a temporary file, for the tests of the analyses
+#[cfg(test)]
+fn parse_test_program(name: &str, source: &str) -> program_structure::program_archive::ProgramArchive {
+    use program_structure::constants::UsefulConstants;
+    let folder = std::env::temp_dir().join(format!("circom_type_analysis_{}_{}", std::process::id(), name));
+    std::fs::create_dir_all(&folder).unwrap();
+    let file = folder.join("main.circom");
+    std::fs::write(&file, source).unwrap();
+    let prime = UsefulConstants::new(&"bn128".to_string()).get_p().clone();
+    let result = parser::run_parser(file.display().to_string(), "2.2.0", vec![], &prime);
+    std::fs::remove_dir_all(&folder).unwrap();
+    match result {
+        Result::Ok((program, _)) => program,
+        Result::Err(_) => panic!("the test program {} does not parse", name),
+    }
+}